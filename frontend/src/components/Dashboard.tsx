@@ -4,6 +4,7 @@ import { StatsChart } from "./StatsChart";
 import { Activity, Cpu, HardDrive, LayoutDashboard } from "lucide-react";
 import type { SystemStats } from "@/types";
 import { apiFetch } from "@/api";
+import { formatBytes } from "@/lib/utils";
 import { useTranslation } from "react-i18next";
 
 async function fetchStats(): Promise<SystemStats> {
@@ -30,14 +31,6 @@ export default function Dashboard() {
         refetchInterval: 30000, // Poll every 30s
     });
 
-    const formatBytes = (bytes: number) => {
-        if (bytes === 0) return "0 B";
-        const k = 1024;
-        const sizes = ["B", "KB", "MB", "GB", "TB"];
-        const i = Math.floor(Math.log(bytes) / Math.log(k));
-        return parseFloat((bytes / Math.pow(k, i)).toFixed(2)) + " " + sizes[i];
-    };
-
     const cpuUsage = stats?.cpu_usage?.toFixed(1) || "0.0";
     const ramUsage = stats ? ((stats.memory_used / stats.memory_total) * 100).toFixed(1) : "0.0";
     const diskUsage = stats ? ((stats.disk_used / stats.disk_total) * 100).toFixed(1) : "0.0";
@@ -1,59 +1,259 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { Dialog, DialogContent, DialogHeader, DialogTitle, DialogTrigger } from "./ui/dialog";
 import { Button } from "./ui/button";
-import { HardDrive, Trash2, RotateCw } from "lucide-react";
+import { HardDrive, Trash2, RotateCw, ArrowUp, Folder, File } from "lucide-react";
 import { Card, CardContent, CardHeader, CardTitle } from "./ui/card";
 import { toast } from "sonner";
 import { useTranslation } from "react-i18next";
 import { apiFetch } from "@/api";
+import { formatBytes } from "@/lib/utils";
+
+interface DirUsage {
+    path: string;
+    bytes: number;
+    files: number;
+    children?: DirUsage[];
+}
 
 interface DiskUsage {
-    target_size: string;
-    node_modules_size: string;
-    docker_size: string;
-    logs_size: string;
+    target_bytes: number;
+    node_modules_bytes: number;
+    docker_bytes: number;
+    logs_bytes: number;
+    roots: DirUsage[];
+    scanned_at: number;
+    age_secs: number;
+    scan_duration_ms: number;
+    errors: number;
+    scanning: boolean;
+}
+
+interface FileEntry {
+    path: string;
+    bytes: number;
+    modified: number;
+    deletable: boolean;
+}
+
+interface LargeFiles {
+    largest: FileEntry[];
+    old_logs: FileEntry[];
+    older_than_days: number;
+    deletable_roots: string[];
+}
+
+interface DeleteResult {
+    path: string;
+    status: "deleted" | "refused" | "failed";
+    bytes: number;
+    error?: string;
+}
+
+interface CleanReport {
+    target: string;
+    dry_run: boolean;
+    items: { name: string; bytes: number }[];
+    reclaimable_bytes: number;
+    freed_bytes: number | null;
+    message: string;
+}
+
+interface CleanupRecord {
+    id: number;
+    timestamp: number;
+    target: string;
+    triggered_by: string;
+    status: string;
+    freed_bytes: number;
+}
+
+interface RetentionConfig {
+    journald: { max_age_days: number | null; max_size_bytes: number | null };
+    policies: {
+        name: string;
+        paths: string[];
+        action: "compress" | "truncate" | "delete";
+        max_age_days: number | null;
+        max_total_bytes: number | null;
+    }[];
+}
+
+interface DiskForecast {
+    mount: string;
+    used_bytes: number;
+    total_bytes: number;
+    growth_bytes_per_day: number | null;
+    full_at: number | null;
+    hours_until_full: number | null;
+}
+
+function parentPath(path: string) {
+    const parent = path.replace(/\/[^/]*$/, "");
+    return parent === "" ? "/" : parent;
 }
 
 export function DiskManager() {
     const { t } = useTranslation();
+    const [open, setOpen] = useState(false);
     const [usage, setUsage] = useState<DiskUsage | null>(null);
+    const [tree, setTree] = useState<DirUsage | null>(null);
+    const [loading, setLoading] = useState(false);
     const [cleaning, setCleaning] = useState<string | null>(null);
+    const [files, setFiles] = useState<LargeFiles | null>(null);
+    const [fileView, setFileView] = useState<"largest" | "old_logs">("largest");
+    const [selected, setSelected] = useState<Set<string>>(new Set());
+    const [deleting, setDeleting] = useState(false);
+    const [cleanups, setCleanups] = useState<CleanupRecord[]>([]);
+
+    const [forecasts, setForecasts] = useState<DiskForecast[]>([]);
 
-    const fetchUsage = async () => {
-        const res = await apiFetch("/disk/usage");
-        if (res.ok) setUsage(await res.json());
+    const fetchForecasts = async () => {
+        const res = await apiFetch("/disk/forecast");
+        if (res.ok) setForecasts(await res.json());
     };
 
-    const handleClean = async (target: string) => {
-        if (!confirm(t('disk.confirm_clean', { target }))) return;
+    const [retention, setRetention] = useState<RetentionConfig | null>(null);
 
-        setCleaning(target);
+    const fetchRetention = async () => {
+        const res = await apiFetch("/disk/retention");
+        if (res.ok) setRetention(await res.json());
+    };
+
+    const fetchCleanups = async () => {
+        const res = await apiFetch("/disk/cleanups?limit=5");
+        if (res.ok) setCleanups(await res.json());
+    };
+
+    const fetchFiles = async () => {
+        const res = await apiFetch("/disk/large-files?limit=50");
+        if (res.ok) setFiles(await res.json());
+    };
+
+    const fetchTree = async (path?: string) => {
+        const params = new URLSearchParams({ depth: "1", limit: "15" });
+        if (path) params.set("path", path);
+        const res = await apiFetch(`/disk/tree?${params}`);
+        if (res.ok) setTree(await res.json());
+    };
+
+    const fetchUsage = async (refresh = false) => {
+        setLoading(true);
         try {
-            const res = await apiFetch("/disk/clean", {
+            const res = await apiFetch(`/disk/usage${refresh ? "?refresh=true" : ""}`);
+            if (res.ok) setUsage(await res.json());
+            await Promise.all([fetchTree(tree?.path), fetchFiles(), fetchCleanups(), fetchRetention(), fetchForecasts()]);
+        } finally {
+            setLoading(false);
+        }
+    };
+
+    // A rescan runs in the background after a cleanup; pick up its results when done
+    useEffect(() => {
+        if (!open || !usage?.scanning) return;
+        const timer = setTimeout(() => fetchUsage(), 3000);
+        const toggleSelected = (path: string) => {
+        const next = new Set(selected);
+        if (next.has(path)) next.delete(path); else next.add(path);
+        setSelected(next);
+    };
+
+    const handleDelete = async () => {
+        if (!confirm(t('disk.confirm_delete', { count: selected.size }))) return;
+
+        setDeleting(true);
+        try {
+            const res = await apiFetch("/disk/delete", {
                 method: "POST",
-                body: JSON.stringify({ target })
+                body: JSON.stringify({ paths: [...selected] })
             });
+            if (!res.ok) {
+                toast.error(t('common.error') + ": " + await res.text());
+                return;
+            }
+            const results: DeleteResult[] = await res.json();
+            const deleted = results.filter((r) => r.status === "deleted");
+            if (deleted.length) {
+                toast.success(t('disk.deleted', {
+                    count: deleted.length,
+                    size: formatBytes(deleted.reduce((sum, r) => sum + r.bytes, 0)),
+                }));
+            }
+            results.filter((r) => r.status !== "deleted")
+                .forEach((r) => toast.error(`${r.path}: ${r.error}`));
+            setSelected(new Set());
+            fetchUsage();
+        } finally {
+            setDeleting(false);
+        }
+    };
 
+    const fileList = files ? files[fileView] : [];
+
+    const forecastText = (f: DiskForecast) => {
+        if (f.growth_bytes_per_day === null) return t('disk.forecast_collecting');
+        if (f.hours_until_full === null) return t('disk.forecast_stable');
+        const growth = formatBytes(f.growth_bytes_per_day);
+        return f.hours_until_full < 48
+            ? t('disk.forecast_hours', { growth, hours: Math.round(f.hours_until_full) })
+            : t('disk.forecast_days', { growth, days: Math.round(f.hours_until_full / 24) });
+    };
+
+    return () => clearTimeout(timer);
+    }, [open, usage]);
+
+    const isRoot = !tree || usage?.roots.some((r) => r.path === tree.path);
+    const size = (bytes: number | undefined) => (bytes === undefined ? "..." : formatBytes(bytes));
+
+    // Preview first, so the confirmation shows what goes away and how much it frees
+    const previewAndRun = async (key: string, target: string, run: (dryRun: boolean) => Promise<Response>) => {
+        setCleaning(key);
+        try {
+            const preview = await run(true);
+            if (!preview.ok) {
+                toast.error(t('common.error') + ": " + await preview.text());
+                return;
+            }
+            const report: CleanReport = await preview.json();
+            const items = report.items.slice(0, 5)
+                .map((item) => `  ${item.name} — ${formatBytes(item.bytes)}`)
+                .join("\n");
+            const question = t('disk.confirm_clean', { target, size: formatBytes(report.reclaimable_bytes) });
+            if (!confirm(items ? `${question}\n\n${items}` : question)) return;
+
+            const res = await run(false);
             if (res.ok) {
-                toast.success(await res.text());
+                const done: CleanReport = await res.json();
+                toast.success(t('disk.freed', { size: formatBytes(done.freed_bytes ?? 0) }));
                 fetchUsage();
             } else {
                 toast.error(t('common.error') + ": " + await res.text());
+                fetchCleanups();
             }
         } finally {
             setCleaning(null);
         }
     };
 
+    const handleClean = (target: string) => previewAndRun(target, target, (dryRun) => apiFetch("/disk/clean", {
+        method: "POST",
+        body: JSON.stringify({ target, dry_run: dryRun })
+    }));
+
+    const handleRetention = (name: string) => previewAndRun(`retention:${name}`, name, (dryRun) =>
+        apiFetch(`/disk/retention/${encodeURIComponent(name)}/apply`, {
+            method: "POST",
+            body: JSON.stringify({ dry_run: dryRun })
+        }));
+
     return (
-        <Dialog onOpenChange={(open) => open && fetchUsage()}>
+        <Dialog onOpenChange={(isOpen) => { setOpen(isOpen); if (isOpen) fetchUsage(); }}>
             <DialogTrigger asChild>
                 <button className="w-full flex items-center gap-3 rounded-lg px-3 py-2 text-sm font-medium text-muted-foreground hover:bg-muted hover:text-foreground text-left transition-all">
                     <HardDrive className="h-4 w-4" />
                     {t('disk.trigger')}
                 </button>
             </DialogTrigger>
-            <DialogContent className="sm:max-w-[600px]">
+            <DialogContent className="sm:max-w-[600px] max-h-[90vh] overflow-y-auto">
                 <DialogHeader>
                     <DialogTitle className="flex items-center gap-2">
                         <HardDrive className="h-5 w-5" />
@@ -61,6 +261,35 @@ export function DiskManager() {
                     </DialogTitle>
                 </DialogHeader>
 
+                <div className="flex items-center justify-between text-xs text-muted-foreground">
+                    <span>
+                        {usage && usage.scanned_at > 0
+                            ? t('disk.scanned_ago', { minutes: Math.floor(usage.age_secs / 60) })
+                            : t('disk.scanning')}
+                        {usage?.scanning && usage.scanned_at > 0 && ` · ${t('disk.scanning')}`}
+                    </span>
+                    <Button variant="ghost" size="sm" onClick={() => fetchUsage(true)} disabled={loading}>
+                        <RotateCw className={`h-4 w-4 mr-2 ${loading ? "animate-spin" : ""}`} />
+                        {t('disk.rescan')}
+                    </Button>
+                </div>
+
+                {forecasts.length > 0 && (
+                    <div className="space-y-1 text-xs">
+                        {forecasts.map((f) => (
+                            <div key={f.mount} className="flex items-center gap-2">
+                                <span className="font-mono truncate flex-1">{f.mount}</span>
+                                <span className="text-muted-foreground">
+                                    {formatBytes(f.used_bytes)} / {formatBytes(f.total_bytes)}
+                                </span>
+                                <span className={f.hours_until_full !== null && f.hours_until_full < 48 ? "text-destructive font-medium" : "text-muted-foreground"}>
+                                    {forecastText(f)}
+                                </span>
+                            </div>
+                        ))}
+                    </div>
+                )}
+
                 <div className="grid grid-cols-2 gap-4 mt-4">
                     {/* Rust Artifacts */}
                     <Card>
@@ -68,7 +297,7 @@ export function DiskManager() {
                             <CardTitle className="text-sm font-medium">{t('disk.rust_build')}</CardTitle>
                         </CardHeader>
                         <CardContent>
-                            <div className="text-2xl font-bold">{usage?.target_size || "..."}</div>
+                            <div className="text-2xl font-bold">{size(usage?.target_bytes)}</div>
                             <Button variant="destructive" size="sm" className="w-full mt-4"
                                 onClick={() => handleClean("target")} disabled={!!cleaning}>
                                 {cleaning === "target" ? <RotateCw className="animate-spin h-4 w-4" /> : <Trash2 className="h-4 w-4 mr-2" />}
@@ -83,7 +312,7 @@ export function DiskManager() {
                             <CardTitle className="text-sm font-medium">{t('disk.frontend_cache')}</CardTitle>
                         </CardHeader>
                         <CardContent>
-                            <div className="text-2xl font-bold">{usage?.node_modules_size || "..."}</div>
+                            <div className="text-2xl font-bold">{size(usage?.node_modules_bytes)}</div>
                             <Button variant="destructive" size="sm" className="w-full mt-4"
                                 onClick={() => handleClean("node_modules")} disabled={!!cleaning}>
                                 {cleaning === "node_modules" ? <RotateCw className="animate-spin h-4 w-4" /> : <Trash2 className="h-4 w-4 mr-2" />}
@@ -98,7 +327,7 @@ export function DiskManager() {
                             <CardTitle className="text-sm font-medium">{t('disk.docker_system')}</CardTitle>
                         </CardHeader>
                         <CardContent>
-                            <div className="text-2xl font-bold">{usage?.docker_size || "..."}</div>
+                            <div className="text-2xl font-bold">{size(usage?.docker_bytes)}</div>
                             <p className="text-xs text-muted-foreground mb-4">{t('disk.prune_desc')}</p>
                             <Button variant="destructive" size="sm" className="w-full"
                                 onClick={() => handleClean("docker")} disabled={!!cleaning}>
@@ -114,7 +343,7 @@ export function DiskManager() {
                             <CardTitle className="text-sm font-medium">{t('disk.system_logs')}</CardTitle>
                         </CardHeader>
                         <CardContent>
-                            <div className="text-2xl font-bold">{usage?.logs_size || "..."}</div>
+                            <div className="text-2xl font-bold">{size(usage?.logs_bytes)}</div>
                             <Button variant="secondary" size="sm" className="w-full mt-4"
                                 onClick={() => handleClean("logs")} disabled={!!cleaning}>
                                 {cleaning === "logs" ? <RotateCw className="animate-spin h-4 w-4" /> : <Trash2 className="h-4 w-4 mr-2" />}
@@ -124,6 +353,128 @@ export function DiskManager() {
                     </Card>
                 </div>
 
+                <Card className="mt-4">
+                    <CardHeader className="pb-2">
+                        <CardTitle className="text-sm font-medium flex items-center justify-between gap-2">
+                            <span className="truncate">{t('disk.largest_dirs')}: {tree?.path ?? "..."}</span>
+                            {!isRoot && tree && (
+                                <Button variant="ghost" size="sm" onClick={() => fetchTree(parentPath(tree.path))}>
+                                    <ArrowUp className="h-4 w-4 mr-1" />
+                                    {t('disk.up')}
+                                </Button>
+                            )}
+                        </CardTitle>
+                    </CardHeader>
+                    <CardContent className="space-y-1 max-h-64 overflow-y-auto">
+                        {tree?.children?.map((dir) => (
+                            <button key={dir.path} onClick={() => fetchTree(dir.path)}
+                                className="w-full flex items-center gap-2 rounded px-2 py-1 text-sm hover:bg-muted text-left">
+                                <Folder className="h-4 w-4 shrink-0 text-muted-foreground" />
+                                <span className="truncate flex-1 font-mono text-xs">{dir.path.split("/").pop()}</span>
+                                <span className="text-xs text-muted-foreground w-24 text-right">{formatBytes(dir.bytes)}</span>
+                                <div className="w-24 h-2 bg-muted rounded">
+                                    <div className="h-2 bg-primary rounded"
+                                        style={{ width: `${tree.bytes ? Math.max(2, (dir.bytes / tree.bytes) * 100) : 0}%` }} />
+                                </div>
+                            </button>
+                        ))}
+                        {tree && !tree.children?.length && (
+                            <div className="text-xs text-muted-foreground">{t('disk.no_subdirs')}</div>
+                        )}
+                    </CardContent>
+                </Card>
+
+                <Card className="mt-4">
+                    <CardHeader className="pb-2">
+                        <CardTitle className="text-sm font-medium flex items-center justify-between gap-2">
+                            <div className="flex gap-1">
+                                <Button variant={fileView === "largest" ? "secondary" : "ghost"} size="sm"
+                                    onClick={() => setFileView("largest")}>
+                                    {t('disk.largest_files')}
+                                </Button>
+                                <Button variant={fileView === "old_logs" ? "secondary" : "ghost"} size="sm"
+                                    onClick={() => setFileView("old_logs")}>
+                                    {t('disk.old_logs', { days: files?.older_than_days ?? 30 })}
+                                </Button>
+                            </div>
+                            <Button variant="destructive" size="sm" onClick={handleDelete}
+                                disabled={deleting || selected.size === 0}>
+                                {deleting ? <RotateCw className="animate-spin h-4 w-4 mr-2" /> : <Trash2 className="h-4 w-4 mr-2" />}
+                                {t('disk.delete_selected', { count: selected.size })}
+                            </Button>
+                        </CardTitle>
+                    </CardHeader>
+                    <CardContent className="space-y-1 max-h-64 overflow-y-auto">
+                        {fileList.map((file) => (
+                            <label key={file.path} title={file.deletable ? undefined : t('disk.not_deletable')}
+                                className={`flex items-center gap-2 rounded px-2 py-1 text-sm hover:bg-muted ${file.deletable ? "cursor-pointer" : "opacity-60"}`}>
+                                <input type="checkbox" disabled={!file.deletable}
+                                    checked={selected.has(file.path)} onChange={() => toggleSelected(file.path)} />
+                                <File className="h-4 w-4 shrink-0 text-muted-foreground" />
+                                <span className="truncate flex-1 font-mono text-xs" title={file.path}>{file.path}</span>
+                                <span className="text-xs text-muted-foreground w-24 text-right">
+                                    {new Date(file.modified * 1000).toLocaleDateString()}
+                                </span>
+                                <span className="text-xs text-muted-foreground w-20 text-right">{formatBytes(file.bytes)}</span>
+                            </label>
+                        ))}
+                        {files && fileList.length === 0 && (
+                            <div className="text-xs text-muted-foreground">{t('disk.no_files')}</div>
+                        )}
+                        {files && (
+                            <div className="text-xs text-muted-foreground pt-2">
+                                {t('disk.deletable_roots', { roots: files.deletable_roots.join(", ") })}
+                            </div>
+                        )}
+                    </CardContent>
+                </Card>
+
+                {retention && retention.policies.length > 0 && (
+                    <Card className="mt-4">
+                        <CardHeader className="pb-2">
+                            <CardTitle className="text-sm font-medium">{t('disk.retention')}</CardTitle>
+                        </CardHeader>
+                        <CardContent className="space-y-2">
+                            {retention.policies.map((p) => (
+                                <div key={p.name} className="flex items-center gap-2 text-xs">
+                                    <div className="flex-1 min-w-0">
+                                        <div className="font-medium">{p.name} · {t(`disk.retention_action.${p.action}`)}</div>
+                                        <div className="font-mono text-muted-foreground truncate">{p.paths.join(", ")}</div>
+                                        <div className="text-muted-foreground">
+                                            {[
+                                                p.max_age_days !== null && t('disk.retention_age', { days: p.max_age_days }),
+                                                p.max_total_bytes !== null && t('disk.retention_size', { size: formatBytes(p.max_total_bytes) }),
+                                            ].filter(Boolean).join(" · ")}
+                                        </div>
+                                    </div>
+                                    <Button variant="secondary" size="sm" onClick={() => handleRetention(p.name)} disabled={!!cleaning}>
+                                        {cleaning === `retention:${p.name}` ? <RotateCw className="animate-spin h-4 w-4" /> : t('disk.apply')}
+                                    </Button>
+                                </div>
+                            ))}
+                        </CardContent>
+                    </Card>
+                )}
+
+                {cleanups.length > 0 && (
+                    <Card className="mt-4">
+                        <CardHeader className="pb-2">
+                            <CardTitle className="text-sm font-medium">{t('disk.cleanup_history')}</CardTitle>
+                        </CardHeader>
+                        <CardContent className="space-y-1">
+                            {cleanups.map((c) => (
+                                <div key={c.id} className="flex items-center gap-2 text-xs">
+                                    <span className="text-muted-foreground w-36">{new Date(c.timestamp * 1000).toLocaleString()}</span>
+                                    <span className="font-mono flex-1">{c.target} ({c.triggered_by})</span>
+                                    <span className={c.status === "success" ? "" : "text-destructive"}>
+                                        {c.status === "success" ? formatBytes(c.freed_bytes) : c.status}
+                                    </span>
+                                </div>
+                            ))}
+                        </CardContent>
+                    </Card>
+                )}
+
                 <div className="text-xs text-muted-foreground mt-4 text-center">
                     {t('disk.note')}
                 </div>
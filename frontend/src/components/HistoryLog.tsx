@@ -1,4 +1,4 @@
-import { useQuery } from "@tanstack/react-query";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
 import { Card, CardContent, CardHeader, CardTitle } from "./ui/card";
 import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "./ui/table";
 import { Badge } from "./ui/badge";
@@ -6,6 +6,7 @@ import { History, RotateCcw } from "lucide-react";
 import { Button } from "./ui/button";
 import { useTranslation } from "react-i18next";
 import { apiFetch } from "@/api";
+import { toast } from "sonner";
 
 interface DeploymentRecord {
     id: string;
@@ -15,10 +16,20 @@ interface DeploymentRecord {
     status: string;
     image_id?: string;
     container_name?: string;
+    rollback_of?: string;
+    exit_code?: number;
+    duration_ms?: number;
+}
+
+async function rollbackDeployment(id: string) {
+    const res = await apiFetch(`/history/${id}/rollback`, { method: "POST" });
+    if (!res.ok) throw new Error(await res.text());
+    return res;
 }
 
 export default function HistoryLog() {
     const { t } = useTranslation();
+    const queryClient = useQueryClient();
     const { data: history, isLoading } = useQuery<DeploymentRecord[]>({
         queryKey: ["history"],
         queryFn: async () => {
@@ -28,6 +39,17 @@ export default function HistoryLog() {
         }
     });
 
+    const rollback = useMutation({
+        mutationFn: rollbackDeployment,
+        onSuccess: () => {
+            toast.success(t('history.rollback_started'));
+            queryClient.invalidateQueries({ queryKey: ["history"] });
+        },
+        onError: (error) => {
+            toast.error(error.message);
+        },
+    });
+
     if (isLoading) return <div className="p-8 text-center text-muted-foreground">{t('history.loading')}</div>;
 
     return (
@@ -74,8 +96,8 @@ export default function HistoryLog() {
                                         </Badge>
                                     </TableCell>
                                     <TableCell className="text-right">
-                                        {record.image_id && (
-                                            <Button variant="ghost" size="sm" onClick={() => alert(t('history.rollback_wip'))}>
+                                        {(record.image_id || (record.action === "update" && record.container_name === "mini-ops")) && (
+                                            <Button variant="ghost" size="sm" disabled={rollback.isPending} onClick={() => rollback.mutate(record.id)}>
                                                 <RotateCcw className="mr-2 h-4 w-4" />
                                                 {t('history.rollback')}
                                             </Button>
@@ -0,0 +1,123 @@
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "@/components/ui/table";
+import { FileCheck, Check, CheckCheck } from "lucide-react";
+import { apiFetch } from "@/api";
+import { toast } from "sonner";
+import { useTranslation } from "react-i18next";
+
+interface FileState {
+    sha256: string | null;
+    uid: number;
+    gid: number;
+    mode: number;
+    size: number;
+}
+
+interface FileChange {
+    path: string;
+    kind: "added" | "removed" | "modified";
+    before: FileState | null;
+    after: FileState | null;
+}
+
+interface IntegrityReport {
+    paths: string[];
+    baseline_files: number;
+    changes: FileChange[];
+}
+
+function describe(change: FileChange, t: (key: string) => string): string {
+    const { before, after } = change;
+    if (!before || !after) return t(`integrity.${change.kind}`);
+    const parts: string[] = [];
+    if (before.sha256 !== after.sha256 || before.size !== after.size) parts.push(t('integrity.content'));
+    if (before.uid !== after.uid || before.gid !== after.gid) {
+        parts.push(`${t('integrity.owner')} ${before.uid}:${before.gid} → ${after.uid}:${after.gid}`);
+    }
+    if (before.mode !== after.mode) {
+        parts.push(`${t('integrity.mode')} ${before.mode.toString(8)} → ${after.mode.toString(8)}`);
+    }
+    return parts.join(", ");
+}
+
+export function IntegrityCard() {
+    const { t } = useTranslation();
+    const queryClient = useQueryClient();
+
+    const { data } = useQuery<IntegrityReport>({
+        queryKey: ["integrity"],
+        queryFn: () => apiFetch("/security/integrity").then(r => r.json()),
+        refetchInterval: 60000,
+    });
+
+    const acceptMutation = useMutation({
+        mutationFn: async (paths: string[] | null) => {
+            const res = await apiFetch("/security/integrity/baseline", {
+                method: "POST",
+                headers: { "Content-Type": "application/json" },
+                body: JSON.stringify({ paths })
+            });
+            if (!res.ok) throw new Error(await res.text());
+        },
+        onSuccess: () => {
+            queryClient.invalidateQueries({ queryKey: ["integrity"] });
+            queryClient.invalidateQueries({ queryKey: ["security"] });
+            toast.success(t('integrity.accepted'));
+        },
+        onError: (e: Error) => toast.error(e.message),
+    });
+
+    const changes = data?.changes ?? [];
+
+    return (
+        <Card>
+            <CardHeader className="flex flex-row items-center justify-between space-y-0">
+                <CardTitle className="flex items-center gap-2">
+                    <FileCheck className="h-5 w-5" />
+                    {t('integrity.title')}
+                </CardTitle>
+                {changes.length > 0 && (
+                    <Button variant="outline" size="sm" onClick={() => acceptMutation.mutate(null)}>
+                        <CheckCheck className="mr-1 h-4 w-4" />
+                        {t('integrity.accept_all')}
+                    </Button>
+                )}
+            </CardHeader>
+            <CardContent className="space-y-4">
+                {changes.length === 0 ? (
+                    <p className="text-sm text-muted-foreground">
+                        {t('integrity.unchanged', { count: data?.baseline_files ?? 0 })}
+                    </p>
+                ) : (
+                    <div className="rounded-md border overflow-x-auto">
+                        <Table>
+                            <TableHeader>
+                                <TableRow>
+                                    <TableHead>{t('integrity.path')}</TableHead>
+                                    <TableHead>{t('integrity.change')}</TableHead>
+                                    <TableHead className="w-[50px]"></TableHead>
+                                </TableRow>
+                            </TableHeader>
+                            <TableBody>
+                                {changes.map(change => (
+                                    <TableRow key={change.path}>
+                                        <TableCell className="font-mono text-xs">{change.path}</TableCell>
+                                        <TableCell className="text-xs">{describe(change, t)}</TableCell>
+                                        <TableCell>
+                                            <Button variant="ghost" size="icon" className="h-8 w-8" title={t('integrity.accept')}
+                                                onClick={() => acceptMutation.mutate([change.path])}>
+                                                <Check className="h-3.5 w-3.5" />
+                                            </Button>
+                                        </TableCell>
+                                    </TableRow>
+                                ))}
+                            </TableBody>
+                        </Table>
+                    </div>
+                )}
+            </CardContent>
+        </Card>
+    );
+}
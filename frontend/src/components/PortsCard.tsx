@@ -0,0 +1,171 @@
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "@/components/ui/table";
+import { Network, Plus, Trash2, BadgeCheck, AlertTriangle } from "lucide-react";
+import { apiFetch } from "@/api";
+import { toast } from "sonner";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+
+interface OpenPort {
+    protocol: "tcp" | "udp";
+    address: string;
+    port: number;
+    exposure: "loopback" | "public";
+    pid: number | null;
+    process: string | null;
+    container_id: string | null;
+    container: string | null;
+    allowed: boolean;
+}
+
+interface AllowedPort {
+    id: number;
+    port: number;
+    protocol: "tcp" | "udp" | null;
+    process: string | null;
+    note: string | null;
+    added_at: number;
+}
+
+export function PortsCard() {
+    const { t } = useTranslation();
+    const queryClient = useQueryClient();
+    const [newPort, setNewPort] = useState("");
+    const [newProcess, setNewProcess] = useState("");
+    const [newNote, setNewNote] = useState("");
+
+    const { data: ports } = useQuery<OpenPort[]>({
+        queryKey: ["open-ports"],
+        queryFn: () => apiFetch("/security/ports").then(r => r.json()),
+        refetchInterval: 30000,
+    });
+
+    const { data: allowlist } = useQuery<AllowedPort[]>({
+        queryKey: ["port-allowlist"],
+        queryFn: () => apiFetch("/security/ports/allowlist").then(r => r.json()),
+    });
+
+    const refresh = () => {
+        queryClient.invalidateQueries({ queryKey: ["port-allowlist"] });
+        queryClient.invalidateQueries({ queryKey: ["open-ports"] });
+        queryClient.invalidateQueries({ queryKey: ["security"] });
+    };
+
+    const addMutation = useMutation({
+        mutationFn: async (data: { port: number, process: string | null, note: string | null }) => {
+            const res = await apiFetch("/security/ports/allowlist", {
+                method: "POST",
+                headers: { "Content-Type": "application/json" },
+                body: JSON.stringify(data)
+            });
+            if (!res.ok) throw new Error(await res.text());
+        },
+        onSuccess: () => {
+            refresh();
+            setNewPort("");
+            setNewProcess("");
+            setNewNote("");
+            toast.success(t('ports.added'));
+        },
+        onError: (e: Error) => toast.error(e.message),
+    });
+
+    const deleteMutation = useMutation({
+        mutationFn: (id: number) => apiFetch(`/security/ports/allowlist/${id}`, { method: "DELETE" }),
+        onSuccess: refresh,
+    });
+
+    const allow = (port: number, process: string | null) =>
+        addMutation.mutate({ port, process, note: null });
+
+    const publicPorts = ports?.filter(p => p.exposure === "public") ?? [];
+    const loopbackCount = (ports?.length ?? 0) - publicPorts.length;
+
+    return (
+        <Card>
+            <CardHeader>
+                <CardTitle className="flex items-center gap-2">
+                    <Network className="h-5 w-5" />
+                    {t('ports.title')}
+                </CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-4">
+                <div className="rounded-md border overflow-x-auto">
+                    <Table>
+                        <TableHeader>
+                            <TableRow>
+                                <TableHead>{t('ports.port')}</TableHead>
+                                <TableHead>{t('ports.address')}</TableHead>
+                                <TableHead>{t('ports.process')}</TableHead>
+                                <TableHead>{t('ports.container')}</TableHead>
+                                <TableHead className="w-[120px]"></TableHead>
+                            </TableRow>
+                        </TableHeader>
+                        <TableBody>
+                            {publicPorts.map(p => (
+                                <TableRow key={`${p.protocol}-${p.address}-${p.port}`}>
+                                    <TableCell className="font-mono text-xs">{p.port}/{p.protocol}</TableCell>
+                                    <TableCell className="font-mono text-xs">{p.address}</TableCell>
+                                    <TableCell className="text-xs">{p.process ?? "—"}</TableCell>
+                                    <TableCell className="text-xs text-muted-foreground">
+                                        {p.container ?? p.container_id?.slice(0, 12) ?? "—"}
+                                    </TableCell>
+                                    <TableCell>
+                                        {p.allowed ? (
+                                            <BadgeCheck className="h-4 w-4 text-emerald-500" />
+                                        ) : (
+                                            <Button variant="outline" size="sm" className="h-7 text-xs" onClick={() => allow(p.port, p.process)}>
+                                                <AlertTriangle className="mr-1 h-3.5 w-3.5 text-amber-500" />
+                                                {t('ports.allow')}
+                                            </Button>
+                                        )}
+                                    </TableCell>
+                                </TableRow>
+                            ))}
+                        </TableBody>
+                    </Table>
+                </div>
+                <p className="text-xs text-muted-foreground">{t('ports.loopback', { count: loopbackCount })}</p>
+
+                <div>
+                    <h3 className="mb-2 text-sm font-medium">{t('ports.allowlist')}</h3>
+                    <div className="flex gap-2 mb-4">
+                        <Input placeholder={t('ports.port')} value={newPort} onChange={e => setNewPort(e.target.value)} className="w-24" />
+                        <Input placeholder={t('ports.process')} value={newProcess} onChange={e => setNewProcess(e.target.value)} />
+                        <Input placeholder={t('ports.note')} value={newNote} onChange={e => setNewNote(e.target.value)} />
+                        <Button size="icon" onClick={() => addMutation.mutate({
+                            port: Number(newPort),
+                            process: newProcess || null,
+                            note: newNote || null,
+                        })}>
+                            <Plus className="h-4 w-4" />
+                        </Button>
+                    </div>
+                    <div className="rounded-md border overflow-x-auto">
+                        <Table>
+                            <TableBody>
+                                {allowlist?.map(entry => (
+                                    <TableRow key={entry.id}>
+                                        <TableCell className="font-mono text-xs">
+                                            {entry.port}{entry.protocol ? `/${entry.protocol}` : ""}
+                                        </TableCell>
+                                        <TableCell className="text-xs">{entry.process ?? t('ports.any_process')}</TableCell>
+                                        <TableCell className="text-xs text-muted-foreground">{entry.note}</TableCell>
+                                        <TableCell className="w-[50px]">
+                                            <Button variant="ghost" size="icon" className="h-8 w-8 text-destructive" onClick={() => deleteMutation.mutate(entry.id)}>
+                                                <Trash2 className="h-3.5 w-3.5" />
+                                            </Button>
+                                        </TableCell>
+                                    </TableRow>
+                                ))}
+                            </TableBody>
+                        </Table>
+                    </div>
+                </div>
+            </CardContent>
+        </Card>
+    );
+}
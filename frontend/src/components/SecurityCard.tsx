@@ -10,11 +10,24 @@ import { useTranslation } from "react-i18next";
 import { Link } from "react-router-dom";
 
 interface SecurityCheck {
-    name: string;
+    id: string;
     status: "PASS" | "FAIL" | "WARN";
+    severity: "info" | "low" | "medium" | "high" | "critical";
+    categories: string[];
+    name: string;
     message: string;
+    remediation: string | null;
+    evidence: string | null;
 }
 
+const severityClass: Record<SecurityCheck["severity"], string> = {
+    info: "text-muted-foreground",
+    low: "text-sky-500",
+    medium: "text-amber-500",
+    high: "text-orange-500",
+    critical: "text-destructive",
+};
+
 async function fetchSecurityAudit(): Promise<SecurityCheck[]> {
     const res = await apiFetch("/security/audit");
     if (!res.ok) throw new Error("Failed to fetch security audit");
@@ -87,19 +100,34 @@ export function SecurityCard() {
                             <TableRow>
                                 <TableHead className="w-[50px]">{t('security.status')}</TableHead>
                                 <TableHead>{t('security.check')}</TableHead>
+                                <TableHead>{t('security.severity')}</TableHead>
                                 <TableHead>{t('security.message')}</TableHead>
                             </TableRow>
                         </TableHeader>
                         <TableBody>
                             {checks?.map((check) => (
-                                <TableRow key={check.name}>
+                                <TableRow key={check.id}>
                                     <TableCell>
                                         {check.status === "PASS" && <BadgeCheck className="h-5 w-5 text-emerald-500" />}
                                         {check.status === "FAIL" && <XCircle className="h-5 w-5 text-destructive" />}
                                         {check.status === "WARN" && <AlertTriangle className="h-5 w-5 text-amber-500" />}
                                     </TableCell>
-                                    <TableCell className="font-medium">{check.name}</TableCell>
-                                    <TableCell className="text-muted-foreground">{check.message}</TableCell>
+                                    <TableCell className="font-medium">
+                                        <div>{check.name}</div>
+                                        <div className="text-xs font-normal text-muted-foreground">{check.categories.join(", ")}</div>
+                                    </TableCell>
+                                    <TableCell className={`text-xs font-medium ${severityClass[check.severity]}`}>
+                                        {t(`security.severity_${check.severity}`)}
+                                    </TableCell>
+                                    <TableCell className="text-muted-foreground">
+                                        <div>{check.message}</div>
+                                        {check.evidence && (
+                                            <div className="mt-1 font-mono text-xs break-all">{t('security.evidence')}: {check.evidence}</div>
+                                        )}
+                                        {check.remediation && (
+                                            <div className="mt-1 text-xs text-foreground">{t('security.remediation')}: {check.remediation}</div>
+                                        )}
+                                    </TableCell>
                                 </TableRow>
                             ))}
                         </TableBody>
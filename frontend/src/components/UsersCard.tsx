@@ -0,0 +1,73 @@
+import { useQuery } from "@tanstack/react-query";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "@/components/ui/table";
+import { Users } from "lucide-react";
+import { apiFetch } from "@/api";
+import { useTranslation } from "react-i18next";
+
+interface PrivilegeRecord {
+    user: string;
+    via: string;
+    first_seen: number;
+    last_seen: number;
+    removed_at: number | null;
+}
+
+interface UsersReport {
+    errors: string[];
+    history: PrivilegeRecord[];
+}
+
+export function UsersCard() {
+    const { t } = useTranslation();
+
+    const { data } = useQuery<UsersReport>({
+        queryKey: ["users"],
+        queryFn: () => apiFetch("/security/users").then(r => r.json()),
+        refetchInterval: 60000,
+    });
+
+    const current = (data?.history ?? []).filter(r => r.removed_at === null);
+
+    return (
+        <Card>
+            <CardHeader>
+                <CardTitle className="flex items-center gap-2">
+                    <Users className="h-5 w-5" />
+                    {t('users.title')}
+                </CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-4">
+                {current.length === 0 ? (
+                    <p className="text-sm text-muted-foreground">{t('users.none')}</p>
+                ) : (
+                    <div className="rounded-md border overflow-x-auto">
+                        <Table>
+                            <TableHeader>
+                                <TableRow>
+                                    <TableHead>{t('users.user')}</TableHead>
+                                    <TableHead>{t('users.via')}</TableHead>
+                                    <TableHead>{t('users.since')}</TableHead>
+                                </TableRow>
+                            </TableHeader>
+                            <TableBody>
+                                {current.map(record => (
+                                    <TableRow key={`${record.user}-${record.via}`}>
+                                        <TableCell className="font-mono text-xs">{record.user}</TableCell>
+                                        <TableCell className="font-mono text-xs">{record.via}</TableCell>
+                                        <TableCell className="text-xs">
+                                            {new Date(record.first_seen * 1000).toLocaleString()}
+                                        </TableCell>
+                                    </TableRow>
+                                ))}
+                            </TableBody>
+                        </Table>
+                    </div>
+                )}
+                {(data?.errors.length ?? 0) > 0 && (
+                    <p className="text-xs text-muted-foreground">{t('users.partial')}</p>
+                )}
+            </CardContent>
+        </Card>
+    );
+}
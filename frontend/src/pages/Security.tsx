@@ -1,4 +1,7 @@
 import { SecurityCard } from "@/components/SecurityCard";
+import { PortsCard } from "@/components/PortsCard";
+import { IntegrityCard } from "@/components/IntegrityCard";
+import { UsersCard } from "@/components/UsersCard";
 import { ShieldAlert } from "lucide-react";
 import { useTranslation } from "react-i18next";
 
@@ -16,6 +19,9 @@ export default function SecurityPage() {
 
             <div className="grid gap-4">
                 <SecurityCard />
+                <PortsCard />
+                <IntegrityCard />
+                <UsersCard />
             </div>
         </div>
     );
@@ -14,4 +14,7 @@ export interface ContainerInfo {
     status: string;
     state: string;
     ports: string;
+    image_id: string;
+    labels: Record<string, string>;
+    update_available: boolean;
 }
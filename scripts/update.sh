@@ -1,26 +1,48 @@
 #!/bin/bash
+# Usage: update.sh [pull|frontend|backend]
+# Without an argument all steps run in order. The agent calls each step
+# separately so it can apply a timeout per step.
 set -e
 
-echo "Starting update process..."
+STEP="${1:-all}"
 
-# 1. Update Code
-echo "Pulling latest changes..."
-git pull
+step_pull() {
+    echo "Pulling latest changes..."
+    git pull
+}
 
-# 2. Build Frontend (if in prod structure)
-if [ -d "frontend" ]; then
-    echo "Building frontend..."
-    cd frontend
-    npm install
-    npm run build
-    cd ..
-fi
+step_frontend() {
+    # Build Frontend (if in prod structure)
+    if [ -d "frontend" ]; then
+        echo "Building frontend..."
+        cd frontend
+        npm install
+        npm run build
+        cd ..
+    fi
+}
 
-# 3. Build Backend
-echo "Building backend..."
-# Note: cargo build might take a while.
-cargo build --release
+step_backend() {
+    echo "Building backend..."
+    # Note: cargo build might take a while.
+    cargo build --release
+}
 
-echo "Update complete. Service restart required."
-# In a real systemd setup, we might do: sudo systemctl restart mini-ops
-# Here we just exit with success, hoping the supervisor handles it.
+case "$STEP" in
+    pull) step_pull ;;
+    frontend) step_frontend ;;
+    backend) step_backend ;;
+    all)
+        echo "Starting update process..."
+        step_pull
+        step_frontend
+        step_backend
+        echo "Update complete. Service restart required."
+        # In a real systemd setup, we might do: sudo systemctl restart mini-ops
+        # Here we just exit with success, hoping the supervisor handles it.
+        ;;
+    *)
+        echo "Unknown step: $STEP" >&2
+        exit 2
+        ;;
+esac
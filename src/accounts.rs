@@ -0,0 +1,736 @@
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, BTreeSet};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::i18n::{self, Lang};
+use crate::notifications::NotificationService;
+use crate::security::CachedSource;
+use crate::util::{Visit, Walker};
+
+const PASSWD: &str = "/etc/passwd";
+const SHADOW: &str = "/etc/shadow";
+const GROUP: &str = "/etc/group";
+const SUDOERS: &str = "/etc/sudoers";
+const SUDOERS_D: &str = "/etc/sudoers.d";
+/// Searched in addition to the agent's own `PATH`, which a service manager may have trimmed.
+const SYSTEM_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+/// Groups whose members can become root. Docker counts: anyone who can start
+/// a container can mount `/` into it.
+pub const PRIVILEGED_GROUPS: &[&str] = &["sudo", "wheel", "admin", "docker"];
+/// Shells that do not give an interactive login.
+const NO_LOGIN_SHELLS: &[&str] = &["nologin", "false", "sync", "shutdown", "halt"];
+/// One inspection serves all account checks of an audit and the tracker.
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordState {
+    /// `!`, `*` or `!!` prefix: no password login
+    Locked,
+    /// Anyone can log in without a password
+    Empty,
+    Set,
+    /// `/etc/shadow` could not be read
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Account {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: String,
+    pub shell: String,
+    pub login_shell: bool,
+    pub password: PasswordState,
+    /// Primary and supplementary group names
+    pub groups: Vec<String>,
+}
+
+/// A rule from sudoers, with `%group` references kept apart from user names.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SudoRule {
+    pub file: String,
+    pub line: usize,
+    pub rule: String,
+    pub users: Vec<String>,
+    pub groups: Vec<String>,
+    pub nopasswd: bool,
+}
+
+/// A way a user other than root can act as root: `uid0`, a privileged group, or `sudoers`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Privilege {
+    pub user: String,
+    pub via: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct AccountsReport {
+    pub accounts: Vec<Account>,
+    pub privileged: Vec<Privilege>,
+    pub sudo_rules: Vec<SudoRule>,
+    /// World-writable `PATH` directories and files in them
+    pub writable_path: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl AccountsReport {
+    /// Members of each privileged group, e.g. "sudo: alice, bob".
+    pub fn group_summary(&self) -> String {
+        PRIVILEGED_GROUPS.iter()
+            .filter_map(|group| {
+                let via = format!("group:{}", group);
+                let users: Vec<&str> = self.privileged.iter().filter(|p| p.via == via).map(|p| p.user.as_str()).collect();
+                (!users.is_empty()).then(|| format!("{}: {}", group, users.join(", ")))
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Whether every file privileges come from was read. `/etc/shadow` only
+    /// affects password states, and an agent that is not root never reads it.
+    pub fn privileges_complete(&self) -> bool {
+        let shadow = format!("{}:", SHADOW);
+        self.errors.iter().all(|e| e.starts_with(&shadow))
+    }
+}
+
+fn is_login_shell(shell: &str) -> bool {
+    let name = shell.rsplit('/').next().unwrap_or(shell);
+    // An empty shell field means /bin/sh
+    !NO_LOGIN_SHELLS.contains(&name)
+}
+
+fn password_state(hash: &str) -> PasswordState {
+    if hash.is_empty() {
+        PasswordState::Empty
+    } else if hash.starts_with('!') || hash.starts_with('*') {
+        PasswordState::Locked
+    } else {
+        PasswordState::Set
+    }
+}
+
+/// Accounts from `/etc/passwd`, with password state from `/etc/shadow` when
+/// readable and group names from `/etc/group`.
+fn parse_accounts(passwd: &str, shadow: Option<&str>, groups: &[Group]) -> Vec<Account> {
+    let hashes: BTreeMap<&str, &str> = shadow.unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            Some((fields.next()?, fields.next()?))
+        })
+        .collect();
+    passwd.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 7 || fields[0].starts_with('#') {
+                return None;
+            }
+            let (name, uid, gid) = (fields[0], fields[2].parse().ok()?, fields[3].parse().ok()?);
+            // "x" defers to shadow; anything else is the hash itself
+            let password = match (fields[1], shadow) {
+                ("x", Some(_)) => hashes.get(name).map_or(PasswordState::Locked, |h| password_state(h)),
+                ("x", None) => PasswordState::Unknown,
+                (hash, _) => password_state(hash),
+            };
+            let account_groups = groups.iter()
+                .filter(|g| g.gid == gid || g.members.iter().any(|m| m == name))
+                .map(|g| g.name.clone())
+                .collect();
+            Some(Account {
+                name: name.to_string(),
+                uid,
+                gid,
+                home: fields[5].to_string(),
+                shell: fields[6].to_string(),
+                login_shell: is_login_shell(fields[6]),
+                password,
+                groups: account_groups,
+            })
+        })
+        .collect()
+}
+
+struct Group {
+    name: String,
+    gid: u32,
+    members: Vec<String>,
+}
+
+fn parse_groups(text: &str) -> Vec<Group> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 4 || fields[0].starts_with('#') {
+                return None;
+            }
+            Some(Group {
+                name: fields[0].to_string(),
+                gid: fields[2].parse().ok()?,
+                members: fields[3].split(',').map(str::trim).filter(|m| !m.is_empty()).map(str::to_string).collect(),
+            })
+        })
+        .collect()
+}
+
+/// `User_Alias` members by alias name, with nested aliases already expanded.
+/// sudo reads all files as one, so an alias from `/etc/sudoers` applies in `sudoers.d`.
+type UserAliases = BTreeMap<String, Vec<String>>;
+
+/// Alias names are upper case; `ALL` is a keyword, not an alias.
+fn is_alias_name(name: &str) -> bool {
+    name != "ALL"
+        && name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Replaces defined aliases in a user list by their members. Undefined alias
+/// names are returned separately so they are not taken for user names.
+fn expand_users<'a>(names: impl Iterator<Item = &'a str>, aliases: &UserAliases) -> (Vec<String>, Vec<String>) {
+    let (mut expanded, mut undefined) = (Vec::new(), Vec::new());
+    for name in names.map(str::trim).filter(|n| !n.is_empty() && !n.starts_with('!')) {
+        match aliases.get(name) {
+            Some(members) => expanded.extend(members.iter().cloned()),
+            None if is_alias_name(name) => undefined.push(name.to_string()),
+            None => expanded.push(name.to_string()),
+        }
+    }
+    (expanded, undefined)
+}
+
+/// Reads `User_Alias NAME = members [: NAME = members]`.
+fn parse_user_aliases(definitions: &str, aliases: &mut UserAliases) {
+    // `%:group` also contains ':', so a piece without '=' belongs to the previous definition
+    let mut pieces: Vec<String> = Vec::new();
+    for piece in definitions.split(':') {
+        match pieces.last_mut() {
+            Some(last) if !piece.contains('=') => {
+                last.push(':');
+                last.push_str(piece);
+            }
+            _ => pieces.push(piece.to_string()),
+        }
+    }
+    for piece in pieces {
+        if let Some((name, members)) = piece.split_once('=') {
+            let (members, _) = expand_users(members.split(','), aliases);
+            aliases.insert(name.trim().to_string(), members);
+        }
+    }
+}
+
+/// User specifications from one sudoers file: `who host = (runas) [NOPASSWD:] commands`.
+/// `User_Alias` names are expanded to their members and undefined ones reported
+/// in `errors`; other aliases, Defaults and includes are skipped. Continuation
+/// lines are joined.
+fn parse_sudoers(file: &str, text: &str, aliases: &mut UserAliases, errors: &mut Vec<String>) -> Vec<SudoRule> {
+    let mut rules = Vec::new();
+    let mut pending = String::new();
+    let mut start = 0;
+    for (number, line) in text.lines().enumerate() {
+        if pending.is_empty() {
+            start = number + 1;
+        }
+        let line = line.trim();
+        if let Some(continued) = line.strip_suffix('\\') {
+            pending.push_str(continued);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(line);
+        let entry = std::mem::take(&mut pending);
+        // `#include` and `#includedir` are directives, `#123` a uid; both start with '#'
+        let entry = entry.trim();
+        if entry.is_empty() || entry.starts_with('#') || entry.starts_with('@') {
+            continue;
+        }
+        let entry = entry.split(" #").next().unwrap_or(entry).trim();
+        let keyword = entry.split_whitespace().next().unwrap_or("");
+        if keyword == "User_Alias" {
+            parse_user_aliases(&entry[keyword.len()..], aliases);
+            continue;
+        }
+        if keyword.starts_with("Defaults") || ["Runas_Alias", "Host_Alias", "Cmnd_Alias", "Cmd_Alias"].contains(&keyword) {
+            continue;
+        }
+        let Some((spec, commands)) = entry.split_once('=') else {
+            continue;
+        };
+        let Some((who, _host)) = spec.trim().rsplit_once(char::is_whitespace) else {
+            continue;
+        };
+        let (names, undefined) = expand_users(who.split(','), aliases);
+        for alias in undefined {
+            errors.push(format!("{}:{}: User_Alias {} is not defined", file, start, alias));
+        }
+        let (mut users, mut groups) = (Vec::new(), Vec::new());
+        for name in names {
+            if let Some(group) = name.strip_prefix("%:").or_else(|| name.strip_prefix('%')) {
+                groups.push(group.to_string());
+            } else if !name.starts_with('+') {
+                users.push(name);
+            }
+        }
+        rules.push(SudoRule {
+            file: file.to_string(),
+            line: start,
+            rule: entry.to_string(),
+            users,
+            groups,
+            nopasswd: commands.contains("NOPASSWD:"),
+        });
+    }
+    rules
+}
+
+/// Users other than root who can act as root, sorted.
+fn privileges(accounts: &[Account], rules: &[SudoRule]) -> Vec<Privilege> {
+    let mut privileged = BTreeSet::new();
+    let mut grant = |user: &str, via: String| {
+        if user != "root" {
+            privileged.insert(Privilege { user: user.to_string(), via });
+        }
+    };
+    for account in accounts {
+        if account.uid == 0 {
+            grant(&account.name, "uid0".to_string());
+        }
+        for group in account.groups.iter().filter(|g| PRIVILEGED_GROUPS.contains(&g.as_str())) {
+            grant(&account.name, format!("group:{}", group));
+        }
+    }
+    for rule in rules {
+        for user in &rule.users {
+            grant(user, "sudoers".to_string());
+        }
+        // Standard groups are already listed as themselves
+        for group in rule.groups.iter().filter(|g| !PRIVILEGED_GROUPS.contains(&g.as_str())) {
+            for account in accounts.iter().filter(|a| a.groups.contains(group)) {
+                grant(&account.name, "sudoers".to_string());
+            }
+        }
+    }
+    privileged.into_iter().collect()
+}
+
+/// World-writable directories among `dirs`, and world-writable regular files in them.
+fn writable_in_path(dirs: &[PathBuf]) -> Vec<String> {
+    let world_writable = |path: &Path| {
+        std::fs::symlink_metadata(path).is_ok_and(|m| !m.file_type().is_symlink() && m.permissions().mode() & 0o002 != 0)
+    };
+    let mut found = Vec::new();
+    for dir in dirs {
+        if world_writable(dir) {
+            found.push(format!("{}/", dir.display()));
+        }
+        // `/bin` is often a link to `/usr/bin`; its entries are still listed under `/bin`
+        let real = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.clone());
+        Walker::default().max_depth(1).sorted().walk(&real, &mut |visit| {
+            if let Visit::File(path, meta) = visit
+                && meta.is_file()
+                && meta.permissions().mode() & 0o002 != 0
+            {
+                found.push(dir.join(path.strip_prefix(&real).unwrap_or(path)).display().to_string());
+            }
+        });
+    }
+    found
+}
+
+fn path_dirs() -> Vec<PathBuf> {
+    let own = std::env::var("PATH").unwrap_or_default();
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in own.split(':').chain(SYSTEM_PATH.split(':')) {
+        let dir = PathBuf::from(dir);
+        if dir.is_absolute() && !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+fn read(path: &str, errors: &mut Vec<String>) -> Option<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| errors.push(format!("{}: {}", path, e)))
+        .ok()
+}
+
+/// Reads accounts, groups and sudoers. `/etc/shadow` and sudoers need root;
+/// without them password states are `unknown` and sudo rules are missing.
+pub fn inspect() -> AccountsReport {
+    let mut report = AccountsReport::default();
+    let passwd = read(PASSWD, &mut report.errors).unwrap_or_default();
+    let shadow = read(SHADOW, &mut report.errors);
+    let groups = parse_groups(&read(GROUP, &mut report.errors).unwrap_or_default());
+    report.accounts = parse_accounts(&passwd, shadow.as_deref(), &groups);
+
+    let mut aliases = UserAliases::new();
+    // Without sudo installed there are simply no rules
+    if Path::new(SUDOERS).exists()
+        && let Some(text) = read(SUDOERS, &mut report.errors)
+    {
+        report.sudo_rules = parse_sudoers(SUDOERS, &text, &mut aliases, &mut report.errors);
+    }
+    if let Ok(entries) = std::fs::read_dir(SUDOERS_D) {
+        let mut files: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+        files.sort();
+        for file in files {
+            // sudo skips names with a dot or ending in '~'
+            let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            if name.contains('.') || name.ends_with('~') {
+                continue;
+            }
+            let path = file.to_string_lossy().into_owned();
+            if let Some(text) = read(&path, &mut report.errors) {
+                report.sudo_rules.extend(parse_sudoers(&path, &text, &mut aliases, &mut report.errors));
+            }
+        }
+    }
+    report.privileged = privileges(&report.accounts, &report.sudo_rules);
+    report.writable_path = writable_in_path(&path_dirs());
+    report
+}
+
+/// Inspection shared by the account checks and the tracker, refreshed after `CACHE_TTL`.
+pub fn source() -> CachedSource<AccountsReport> {
+    CachedSource::new(CACHE_TTL, || Ok(inspect()))
+}
+
+/// A privilege as it was tracked over time.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PrivilegeRecord {
+    pub user: String,
+    pub via: String,
+    /// Unix timestamps (seconds)
+    pub first_seen: i64,
+    pub last_seen: i64,
+    /// Set once the privilege is gone; cleared if it comes back
+    pub removed_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct UsersReport {
+    #[serde(flatten)]
+    pub current: AccountsReport,
+    pub history: Vec<PrivilegeRecord>,
+}
+
+/// Records who is privileged and alerts when someone new is.
+pub struct PrivilegeTracker {
+    db: SqlitePool,
+    source: Arc<CachedSource<AccountsReport>>,
+    notifier: Arc<NotificationService>,
+}
+
+impl PrivilegeTracker {
+    pub fn new(db: SqlitePool, source: Arc<CachedSource<AccountsReport>>, notifier: Arc<NotificationService>) -> Self {
+        Self { db, source, notifier }
+    }
+
+    /// Creates the table. A new table is filled with the current privileges,
+    /// so existing admins do not trigger alerts.
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'privileged_users'")
+            .fetch_optional(&self.db)
+            .await?
+            .is_some();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS privileged_users (
+                user TEXT NOT NULL,
+                via TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                removed_at INTEGER,
+                PRIMARY KEY (user, via)
+            )"
+        )
+        .execute(&self.db)
+        .await?;
+        if !exists {
+            let report = self.current().await;
+            self.record(&report, chrono::Utc::now().timestamp()).await?;
+        }
+        Ok(())
+    }
+
+    async fn current(&self) -> Arc<AccountsReport> {
+        let source = self.source.clone();
+        tokio::task::spawn_blocking(move || source.get()).await.ok().and_then(Result::ok).unwrap_or_default()
+    }
+
+    /// Stores the current privileges and returns those that were not held
+    /// before. Removals are only recorded when passwd, group and sudoers were
+    /// all read, since an unreadable file would otherwise look like revoked access.
+    async fn record(&self, report: &AccountsReport, now: i64) -> Result<Vec<Privilege>, sqlx::Error> {
+        let held: BTreeSet<(String, String)> = self.history().await?
+            .into_iter()
+            .filter(|r| r.removed_at.is_none())
+            .map(|r| (r.user, r.via))
+            .collect();
+        let mut new = Vec::new();
+        for privilege in &report.privileged {
+            if !held.contains(&(privilege.user.clone(), privilege.via.clone())) {
+                new.push(privilege.clone());
+            }
+            sqlx::query(
+                "INSERT INTO privileged_users (user, via, first_seen, last_seen) VALUES (?, ?, ?, ?)
+                 ON CONFLICT(user, via) DO UPDATE SET last_seen = excluded.last_seen, removed_at = NULL"
+            )
+            .bind(&privilege.user)
+            .bind(&privilege.via)
+            .bind(now)
+            .bind(now)
+            .execute(&self.db)
+            .await?;
+        }
+        if report.privileges_complete() {
+            for (user, via) in held {
+                if !report.privileged.iter().any(|p| p.user == user && p.via == via) {
+                    sqlx::query("UPDATE privileged_users SET removed_at = ? WHERE user = ? AND via = ?")
+                        .bind(now)
+                        .bind(&user)
+                        .bind(&via)
+                        .execute(&self.db)
+                        .await?;
+                }
+            }
+        }
+        Ok(new)
+    }
+
+    pub async fn history(&self) -> Result<Vec<PrivilegeRecord>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT user, via, first_seen, last_seen, removed_at FROM privileged_users ORDER BY first_seen DESC, user, via"
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows.iter()
+            .map(|r| PrivilegeRecord {
+                user: r.get("user"),
+                via: r.get("via"),
+                first_seen: r.get("first_seen"),
+                last_seen: r.get("last_seen"),
+                removed_at: r.get("removed_at"),
+            })
+            .collect())
+    }
+
+    pub async fn report(&self) -> Result<UsersReport, String> {
+        let current = self.current().await;
+        self.record(&current, chrono::Utc::now().timestamp()).await.map_err(|e| e.to_string())?;
+        let history = self.history().await.map_err(|e| e.to_string())?;
+        Ok(UsersReport { current: current.as_ref().clone(), history })
+    }
+
+    async fn check_once(&self) {
+        let current = self.current().await;
+        let new = match self.record(&current, chrono::Utc::now().timestamp()).await {
+            Ok(new) => new,
+            Err(e) => {
+                tracing::error!("Failed to record privileged users: {}", e);
+                return;
+            }
+        };
+        if new.is_empty() {
+            return;
+        }
+        let lang = Lang::from_headers(&i18n::HeaderMap::new());
+        let lines: Vec<String> = new.iter().map(|p| format!("• {} — {}", p.user, p.via)).collect();
+        self.notifier.send_alert(&format!("{}\n\n{}", i18n::t("alert.new_privileged", &lang), lines.join("\n"))).await;
+    }
+
+    /// Checks once a minute, so a new sudo or docker member is reported quickly.
+    pub async fn run_loop(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            self.check_once().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD_FILE: &str = "\
+root:x:0:0:root:/root:/bin/bash
+toor:x:0:0::/root:/bin/sh
+daemon:x:1:1:daemon:/usr/sbin:/usr/sbin/nologin
+alice:x:1000:1000::/home/alice:/bin/bash
+bob:x:1001:1001::/home/bob:/bin/zsh
+svc:x:998:998::/srv:/bin/bash
+ops:x:1002:1002::/home/ops:/bin/bash
+";
+    const SHADOW_FILE: &str = "\
+root:$y$j9T$abc:19000:0:99999:7:::
+toor::19000:0:99999:7:::
+daemon:*:19000:0:99999:7:::
+alice:$6$salt$hash:19000:0:99999:7:::
+bob:!$6$salt$hash:19000:0:99999:7:::
+svc:!!:19000::::::
+";
+    const GROUP_FILE: &str = "\
+root:x:0:
+sudo:x:27:alice
+docker:x:999:bob,alice
+devops:x:1100:ops
+svc:x:998:
+";
+    const SUDOERS_FILE: &str = "\
+Defaults env_reset
+Cmnd_Alias RESTART = /usr/bin/systemctl restart *
+#includedir /etc/sudoers.d
+root ALL=(ALL:ALL) ALL
+%sudo ALL=(ALL:ALL) ALL
+%devops, svc ALL=(root) NOPASSWD: RESTART # services only
+deploy ALL=(ALL) \\
+    NOPASSWD: ALL
+";
+
+    fn sudoers(text: &str) -> (Vec<SudoRule>, Vec<String>) {
+        let mut errors = Vec::new();
+        let rules = parse_sudoers("/etc/sudoers", text, &mut UserAliases::new(), &mut errors);
+        (rules, errors)
+    }
+
+    fn accounts() -> Vec<Account> {
+        parse_accounts(PASSWD_FILE, Some(SHADOW_FILE), &parse_groups(GROUP_FILE))
+    }
+
+    #[test]
+    fn test_parse_accounts() {
+        let accounts = accounts();
+        let state = |name: &str| accounts.iter().find(|a| a.name == name).unwrap().password;
+        assert_eq!(state("root"), PasswordState::Set);
+        assert_eq!(state("toor"), PasswordState::Empty);
+        assert_eq!(state("daemon"), PasswordState::Locked);
+        assert_eq!(state("bob"), PasswordState::Locked);
+        assert_eq!(state("svc"), PasswordState::Locked);
+        // Missing from shadow: treated as having no password login
+        assert_eq!(state("ops"), PasswordState::Locked);
+
+        let alice = accounts.iter().find(|a| a.name == "alice").unwrap();
+        assert_eq!(alice.groups, ["sudo", "docker"]);
+        assert!(alice.login_shell);
+        assert!(!accounts.iter().find(|a| a.name == "daemon").unwrap().login_shell);
+
+        let unreadable = parse_accounts(PASSWD_FILE, None, &[]);
+        assert!(unreadable.iter().all(|a| a.password == PasswordState::Unknown));
+    }
+
+    #[test]
+    fn test_parse_sudoers() {
+        let (rules, errors) = sudoers(SUDOERS_FILE);
+        assert!(errors.is_empty());
+        assert_eq!(rules.len(), 4);
+        assert_eq!(rules[1].groups, ["sudo"]);
+        assert!(!rules[1].nopasswd);
+        assert_eq!(rules[2].groups, ["devops"]);
+        assert_eq!(rules[2].users, ["svc"]);
+        assert!(rules[2].nopasswd);
+        assert_eq!(rules[2].rule, "%devops, svc ALL=(root) NOPASSWD: RESTART");
+        assert_eq!(rules[3].users, ["deploy"]);
+        assert_eq!(rules[3].line, 7);
+        assert!(rules[3].nopasswd);
+    }
+
+    #[test]
+    fn test_sudoers_user_aliases() {
+        let (rules, errors) = sudoers("\
+User_Alias ADMINS = alice, %devops : DEPLOYERS = deploy
+User_Alias EVERYONE = ADMINS, DEPLOYERS, !bob
+ADMINS ALL=(ALL) ALL
+EVERYONE, carol ALL=(ALL) NOPASSWD: ALL
+OPERATORS ALL=(ALL) ALL
+");
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].users, ["alice"]);
+        assert_eq!(rules[0].groups, ["devops"]);
+        assert_eq!(rules[1].users, ["alice", "deploy", "carol"]);
+        assert_eq!(rules[1].groups, ["devops"]);
+        assert!(rules[1].nopasswd);
+        // An undefined alias is not a user called OPERATORS
+        assert!(rules[2].users.is_empty());
+        assert_eq!(errors, ["/etc/sudoers:5: User_Alias OPERATORS is not defined"]);
+
+        let privileged = privileges(&accounts(), &rules);
+        assert!(privileged.iter().any(|p| p.user == "ops" && p.via == "sudoers"));
+        assert!(!privileged.iter().any(|p| p.user == "ADMINS" || p.user == "OPERATORS"));
+    }
+
+    #[test]
+    fn test_privileges() {
+        let privileged = privileges(&accounts(), &sudoers(SUDOERS_FILE).0);
+        let pairs: Vec<String> = privileged.iter().map(|p| format!("{} {}", p.user, p.via)).collect();
+        assert_eq!(pairs, [
+            "alice group:docker",
+            "alice group:sudo",
+            "bob group:docker",
+            "deploy sudoers",
+            "ops sudoers",
+            "svc sudoers",
+            "toor uid0",
+        ]);
+        let report = AccountsReport { privileged, ..Default::default() };
+        assert_eq!(report.group_summary(), "sudo: alice; docker: alice, bob");
+    }
+
+    #[test]
+    fn test_writable_in_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        std::fs::write(dir.join("bin/safe"), "").unwrap();
+        std::fs::write(dir.join("bin/tool"), "").unwrap();
+        std::fs::set_permissions(dir.join("bin/safe"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::set_permissions(dir.join("bin/tool"), std::fs::Permissions::from_mode(0o777)).unwrap();
+        std::fs::create_dir_all(dir.join("open")).unwrap();
+        std::fs::set_permissions(dir.join("open"), std::fs::Permissions::from_mode(0o777)).unwrap();
+
+        let found = writable_in_path(&[dir.join("bin"), dir.join("open"), dir.join("missing")]);
+        assert_eq!(found, [dir.join("bin/tool").display().to_string(), format!("{}/", dir.join("open").display())]);
+    }
+
+    #[tokio::test]
+    async fn test_tracker() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let tracker = PrivilegeTracker::new(pool, Arc::new(source()), Arc::new(NotificationService::new()));
+        // Creates the table; what the sandbox has is recorded without alerting
+        tracker.migrate().await.unwrap();
+        sqlx::query("DELETE FROM privileged_users").execute(&tracker.db).await.unwrap();
+
+        let privilege = |user: &str, via: &str| Privilege { user: user.into(), via: via.into() };
+        let mut report = AccountsReport { privileged: vec![privilege("alice", "group:sudo")], ..Default::default() };
+        assert_eq!(tracker.record(&report, 100).await.unwrap().len(), 1);
+        assert!(tracker.record(&report, 200).await.unwrap().is_empty());
+
+        report.privileged.push(privilege("bob", "group:docker"));
+        assert_eq!(tracker.record(&report, 300).await.unwrap(), [privilege("bob", "group:docker")]);
+
+        // A partial read does not revoke anything
+        let partial = AccountsReport { errors: vec!["/etc/sudoers: Permission denied".into()], ..Default::default() };
+        tracker.record(&partial, 400).await.unwrap();
+        assert!(tracker.history().await.unwrap().iter().all(|r| r.removed_at.is_none()));
+
+        // Without root /etc/shadow is never readable, which must not block removals
+        report.privileged.remove(1);
+        report.errors = vec!["/etc/shadow: Permission denied".into()];
+        tracker.record(&report, 500).await.unwrap();
+        let history = tracker.history().await.unwrap();
+        let bob = history.iter().find(|r| r.user == "bob").unwrap();
+        assert_eq!((bob.first_seen, bob.last_seen, bob.removed_at), (300, 300, Some(500)));
+        let alice = history.iter().find(|r| r.user == "alice").unwrap();
+        assert_eq!((alice.first_seen, alice.last_seen), (100, 500));
+
+        // Coming back is new again
+        report.privileged.push(privilege("bob", "group:docker"));
+        assert_eq!(tracker.record(&report, 600).await.unwrap().len(), 1);
+    }
+}
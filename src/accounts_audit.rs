@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use crate::accounts::{AccountsReport, PasswordState};
+use crate::i18n::{self, Lang};
+use crate::security::{
+    CachedSource, CheckSpec, CheckStatus, SecurityCheck, Severity, SourcedCheck, SourcedCheckFn, CHECK_USERS_EMPTY_PASSWORDS,
+    CHECK_USERS_LOGIN_SHELLS, CHECK_USERS_NOPASSWD, CHECK_USERS_PATH_WRITABLE, CHECK_USERS_PRIVILEGED_GROUPS,
+    CHECK_USERS_UID0,
+};
+
+const UID0: CheckSpec = CheckSpec {
+    id: CHECK_USERS_UID0,
+    key: "uid0",
+    severity: Severity::Critical,
+    categories: &["users"],
+};
+
+const EMPTY_PASSWORDS: CheckSpec = CheckSpec {
+    id: CHECK_USERS_EMPTY_PASSWORDS,
+    key: "empty_passwords",
+    severity: Severity::Critical,
+    categories: &["users"],
+};
+
+const LOGIN_SHELLS: CheckSpec = CheckSpec {
+    id: CHECK_USERS_LOGIN_SHELLS,
+    key: "login_users",
+    severity: Severity::Medium,
+    categories: &["users"],
+};
+
+const PRIVILEGED_GROUPS: CheckSpec = CheckSpec {
+    id: CHECK_USERS_PRIVILEGED_GROUPS,
+    key: "priv_groups",
+    severity: Severity::High,
+    categories: &["users"],
+};
+
+const NOPASSWD: CheckSpec = CheckSpec {
+    id: CHECK_USERS_NOPASSWD,
+    key: "nopasswd",
+    severity: Severity::Medium,
+    categories: &["users"],
+};
+
+const PATH_WRITABLE: CheckSpec = CheckSpec {
+    id: CHECK_USERS_PATH_WRITABLE,
+    key: "path_writable",
+    severity: Severity::High,
+    categories: &["users"],
+};
+
+pub fn accounts_checks(source: Arc<CachedSource<AccountsReport>>) -> Vec<SourcedCheck<AccountsReport>> {
+    let checks: [(&'static CheckSpec, SourcedCheckFn<AccountsReport>); 6] = [
+        (&UID0, check_uid0),
+        (&EMPTY_PASSWORDS, check_empty_passwords),
+        (&LOGIN_SHELLS, check_login_shells),
+        (&PRIVILEGED_GROUPS, check_privileged_groups),
+        (&NOPASSWD, check_nopasswd),
+        (&PATH_WRITABLE, check_path_writable),
+    ];
+    SourcedCheck::all(source, checks)
+}
+
+fn with_list(spec: &CheckSpec, status: CheckStatus, message_key: &str, items: &[String], lang: &Lang) -> SecurityCheck {
+    let message = i18n::t_val(message_key, lang, &items.len().to_string());
+    spec.result(status, message, lang).with_evidence(items.join(", "))
+}
+
+fn check_uid0(report: &AccountsReport, lang: &Lang) -> SecurityCheck {
+    let extra: Vec<String> = report.accounts.iter()
+        .filter(|a| a.uid == 0 && a.name != "root")
+        .map(|a| a.name.clone())
+        .collect();
+    if extra.is_empty() {
+        UID0.pass("audit.uid0.pass", lang)
+    } else {
+        with_list(&UID0, CheckStatus::Fail, "audit.uid0.fail", &extra, lang)
+    }
+}
+
+fn check_empty_passwords(report: &AccountsReport, lang: &Lang) -> SecurityCheck {
+    let empty: Vec<String> = report.accounts.iter()
+        .filter(|a| a.password == PasswordState::Empty)
+        .map(|a| a.name.clone())
+        .collect();
+    if !empty.is_empty() {
+        return with_list(&EMPTY_PASSWORDS, CheckStatus::Fail, "audit.empty_passwords.fail", &empty, lang);
+    }
+    if report.accounts.iter().any(|a| a.password == PasswordState::Unknown) {
+        return EMPTY_PASSWORDS.warn("audit.empty_passwords.unknown", lang);
+    }
+    EMPTY_PASSWORDS.pass("audit.empty_passwords.pass", lang)
+}
+
+/// Accounts that can log in with a password. Each one is a target for
+/// password guessing, so the list is worth a look even when it is expected.
+fn check_login_shells(report: &AccountsReport, lang: &Lang) -> SecurityCheck {
+    let open: Vec<String> = report.accounts.iter()
+        .filter(|a| a.login_shell && a.password == PasswordState::Set && a.name != "root")
+        .map(|a| a.name.clone())
+        .collect();
+    if open.is_empty() {
+        LOGIN_SHELLS.pass("audit.login_users.pass", lang)
+    } else {
+        with_list(&LOGIN_SHELLS, CheckStatus::Warn, "audit.login_users.warn", &open, lang)
+    }
+}
+
+/// sudo and wheel members are expected admins; docker members are root too,
+/// but rarely meant to be.
+fn check_privileged_groups(report: &AccountsReport, lang: &Lang) -> SecurityCheck {
+    let summary = report.group_summary();
+    if report.privileged.iter().any(|p| p.via == "group:docker") {
+        return PRIVILEGED_GROUPS.warn("audit.priv_groups.docker", lang).with_evidence(summary);
+    }
+    let check = PRIVILEGED_GROUPS.pass("audit.priv_groups.pass", lang);
+    if summary.is_empty() { check } else { check.with_evidence(summary) }
+}
+
+fn check_nopasswd(report: &AccountsReport, lang: &Lang) -> SecurityCheck {
+    let rules: Vec<String> = report.sudo_rules.iter()
+        .filter(|r| r.nopasswd)
+        .map(|r| format!("{}:{}: {}", r.file, r.line, r.rule))
+        .collect();
+    if !rules.is_empty() {
+        let message = i18n::t_val("audit.nopasswd.warn", lang, &rules.len().to_string());
+        return NOPASSWD.result(CheckStatus::Warn, message, lang).with_evidence(rules.join("; "));
+    }
+    if report.errors.iter().any(|e| e.contains("sudoers")) {
+        return NOPASSWD.warn("audit.nopasswd.unknown", lang).with_evidence(report.errors.join("; "));
+    }
+    NOPASSWD.pass("audit.nopasswd.pass", lang)
+}
+
+fn check_path_writable(report: &AccountsReport, lang: &Lang) -> SecurityCheck {
+    if report.writable_path.is_empty() {
+        PATH_WRITABLE.pass("audit.path_writable.pass", lang)
+    } else {
+        with_list(&PATH_WRITABLE, CheckStatus::Fail, "audit.path_writable.fail", &report.writable_path, lang)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::{Account, Privilege, SudoRule};
+
+    fn account(name: &str, uid: u32, password: PasswordState) -> Account {
+        Account {
+            name: name.into(),
+            uid,
+            gid: uid,
+            home: format!("/home/{}", name),
+            shell: "/bin/bash".into(),
+            login_shell: true,
+            password,
+            groups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_account_checks() {
+        let mut report = AccountsReport {
+            accounts: vec![account("root", 0, PasswordState::Set), account("alice", 1000, PasswordState::Locked)],
+            ..Default::default()
+        };
+        for check in [check_uid0, check_empty_passwords, check_login_shells, check_nopasswd, check_path_writable] {
+            assert_eq!(check(&report, &Lang::EN).status, CheckStatus::Pass);
+        }
+
+        report.accounts.push(account("toor", 0, PasswordState::Empty));
+        report.accounts.push(account("bob", 1001, PasswordState::Set));
+        let check = check_uid0(&report, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert_eq!(check.evidence.as_deref(), Some("toor"));
+        assert_eq!(check_empty_passwords(&report, &Lang::EN).status, CheckStatus::Fail);
+        let check = check_login_shells(&report, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert_eq!(check.evidence.as_deref(), Some("bob"));
+
+        let unreadable = AccountsReport { accounts: vec![account("bob", 1001, PasswordState::Unknown)], ..Default::default() };
+        assert_eq!(check_empty_passwords(&unreadable, &Lang::EN).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_privilege_checks() {
+        let privilege = |user: &str, via: &str| Privilege { user: user.into(), via: via.into() };
+        let mut report = AccountsReport { privileged: vec![privilege("alice", "group:sudo")], ..Default::default() };
+        let check = check_privileged_groups(&report, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert_eq!(check.evidence.as_deref(), Some("sudo: alice"));
+        report.privileged.push(privilege("bob", "group:docker"));
+        assert_eq!(check_privileged_groups(&report, &Lang::EN).status, CheckStatus::Warn);
+
+        report.sudo_rules.push(SudoRule {
+            file: "/etc/sudoers.d/deploy".into(),
+            line: 1,
+            rule: "deploy ALL=(ALL) NOPASSWD: ALL".into(),
+            users: vec!["deploy".into()],
+            groups: Vec::new(),
+            nopasswd: true,
+        });
+        let check = check_nopasswd(&report, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert_eq!(check.evidence.as_deref(), Some("/etc/sudoers.d/deploy:1: deploy ALL=(ALL) NOPASSWD: ALL"));
+
+        report.writable_path = vec!["/usr/local/bin/tool".into()];
+        assert_eq!(check_path_writable(&report, &Lang::EN).status, CheckStatus::Fail);
+    }
+}
@@ -24,7 +24,7 @@ pub async fn auth_middleware(
         .and_then(|header| header.to_str().ok());
 
     match auth_header {
-        Some(auth_header) if auth_header_is_valid(auth_header, &token) => Ok(next.run(request).await),
+        Some(auth_header) if auth_header_is_valid(auth_header, token) => Ok(next.run(request).await),
         _ => {
             let uri = request.uri();
             if uri.path().contains("/logs") {
@@ -43,7 +43,7 @@ fn auth_header_is_valid(header: &str, expected_token: &str) -> bool {
     false
 }
 
-fn constant_time_eq(a: &str, b: &str) -> bool {
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
     let a_bytes = a.as_bytes();
     let b_bytes = b.as_bytes();
     if a_bytes.len() != b_bytes.len() {
@@ -48,7 +48,11 @@ pub struct SshLoginInfo {
 pub struct SecurityMetrics {
     pub ssh_hardening_score: u32,
     pub fail2ban_active: bool,
+    /// The firewall check passed, whichever backend (UFW, firewalld, nftables, iptables) filters.
+    pub firewall_enabled: bool,
+    /// Same value as `firewall_enabled`, still sent for Hubs that read the old name.
     pub ufw_enabled: bool,
+    /// Ports listening on a non-loopback address, TCP and UDP.
     pub open_ports: Vec<u16>,
     /// Last SSH login event: username + source IP + timestamp.
     /// Sent to the Hub so the commercial dashboard can show login activity
@@ -11,7 +11,8 @@ use crate::cloud_payload::{
 use crate::docker::DockerService;
 use crate::i18n::Lang;
 use crate::metrics::MetricsState;
-use crate::security::SecurityAuditor;
+use crate::ports::Exposure;
+use crate::security::{hardening_score, CheckStatus, SecurityAuditor, CHECK_FAIL2BAN, CHECK_FIREWALL};
 use crate::ssh_alerts::SshAlertsService;
 
 pub struct CloudPushConfig {
@@ -61,8 +62,9 @@ impl CloudPushService {
     pub fn start(
         self: Arc<Self>,
         metrics: Arc<MetricsState>,
-        docker: Option<Arc<DockerService>>,
+        docker: Arc<DockerService>,
         ssh_alerts: Arc<SshAlertsService>,
+        auditor: Arc<SecurityAuditor>,
     ) {
         tokio::spawn(async move {
             let mut interval =
@@ -71,7 +73,7 @@ impl CloudPushService {
             loop {
                 interval.tick().await;
                 match self
-                    .push_once(&metrics, docker.as_ref(), &ssh_alerts)
+                    .push_once(&metrics, &docker, &ssh_alerts, &auditor)
                     .await
                 {
                     Ok(()) => {
@@ -90,10 +92,11 @@ impl CloudPushService {
     async fn push_once(
         &self,
         metrics: &MetricsState,
-        docker: Option<&Arc<DockerService>>,
+        docker: &DockerService,
         ssh_alerts: &SshAlertsService,
+        auditor: &SecurityAuditor,
     ) -> Result<(), String> {
-        let payload = self.build_payload(metrics, docker, ssh_alerts).await?;
+        let payload = self.build_payload(metrics, docker, ssh_alerts, auditor).await?;
         let resp = self
             .client
             .post(format!("{}/api/v1/agents/push", self.config.hub_url))
@@ -117,8 +120,9 @@ impl CloudPushService {
     async fn build_payload(
         &self,
         metrics: &MetricsState,
-        docker: Option<&Arc<DockerService>>,
+        docker: &DockerService,
         ssh_alerts: &SshAlertsService,
+        auditor: &SecurityAuditor,
     ) -> Result<CloudPayload, String> {
         // System metrics
         let stats = metrics.get_current();
@@ -161,8 +165,8 @@ impl CloudPushService {
         };
 
         // Docker metrics
-        let docker_metrics = if let Some(svc) = docker {
-            match svc.list_containers().await {
+        let docker_metrics = if docker.is_connected() {
+            match docker.list_containers().await {
                 Ok(containers) => {
                     let mut total_running = 0u32;
                     let mut total_stopped = 0u32;
@@ -210,15 +214,20 @@ impl CloudPushService {
 
         // Security metrics
         let lang = Lang::EN;
-        let checks = SecurityAuditor::run_audit(&lang).await;
-        let pass_count = checks.iter().filter(|c| c.status == "PASS").count();
-        let ssh_hardening_score = (pass_count as u32 * 100) / 7;
-        let fail2ban_active = checks
-            .iter()
-            .any(|c| c.name.contains("Fail2Ban") && c.status == "PASS");
-        let ufw_enabled = checks
-            .iter()
-            .any(|c| c.name.contains("UFW") && c.status == "PASS");
+        let checks = auditor.run_audit(&lang).await;
+        let passed = |id: &str| checks.iter().any(|c| c.id == id && c.status == CheckStatus::Pass);
+        let ssh_hardening_score = hardening_score(&checks);
+        let fail2ban_active = passed(CHECK_FAIL2BAN);
+        let firewall_enabled = passed(CHECK_FIREWALL);
+        let mut open_ports: Vec<u16> = tokio::task::spawn_blocking(crate::ports::scan)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| p.exposure == Exposure::Public)
+            .map(|p| p.port)
+            .collect();
+        open_ports.sort_unstable();
+        open_ports.dedup();
 
         // SSH alerts
         let logs = ssh_alerts
@@ -247,8 +256,9 @@ impl CloudPushService {
         let security = SecurityMetrics {
             ssh_hardening_score,
             fail2ban_active,
-            ufw_enabled,
-            open_ports: Vec::new(),
+            firewall_enabled,
+            ufw_enabled: firewall_enabled,
+            open_ports,
             last_ssh_login,
             trusted_ips: trusted_ip_strings,
         };
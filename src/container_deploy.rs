@@ -0,0 +1,415 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::deployment::{DeploymentGuard, DeploymentService};
+use crate::docker::{ContainerSnapshot, DockerService};
+use crate::history::{DeploymentRecord, HistoryManager};
+use crate::registry::RegistryClient;
+
+/// Pull + recreate for user containers, with automatic rollback to the previous image.
+///
+/// Progress goes to the same broadcast channel as the agent self-update, so
+/// `/api/deploy/logs` shows both.
+pub struct ContainerDeployer {
+    docker: Arc<DockerService>,
+    deployment: Arc<DeploymentService>,
+    history: Arc<HistoryManager>,
+    registry: Arc<RegistryClient>,
+    health_timeout: Duration,
+}
+
+impl ContainerDeployer {
+    pub fn new(
+        docker: Arc<DockerService>,
+        deployment: Arc<DeploymentService>,
+        history: Arc<HistoryManager>,
+        registry: Arc<RegistryClient>,
+    ) -> Self {
+        let health_timeout = std::env::var("REDEPLOY_HEALTH_TIMEOUT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(120u64);
+        Self {
+            docker,
+            deployment,
+            history,
+            registry,
+            health_timeout: Duration::from_secs(health_timeout),
+        }
+    }
+
+    /// Pulls `image` (defaults to the container's current `repo:tag`), recreates the
+    /// container with the same config and waits for its health check. On failure the
+    /// container is recreated on the image id it was running before.
+    pub async fn redeploy(&self, guard: &DeploymentGuard, container: &str, image: Option<String>) -> Result<(), String> {
+        let log = |line: String| self.deployment.send(line);
+
+        let snapshot = self.docker.snapshot_container(container).await?;
+        let target = image.unwrap_or_else(|| snapshot.image.clone());
+        let deployment_id = guard.id.clone();
+        log(format!("🚀 Redeploying {} with {}", snapshot.name, target));
+
+        let pulled = match self.docker.pull_image(&target, self.registry.docker_credentials(&target), &log).await {
+            Ok(()) => self.docker.image_id(&target).await,
+            Err(e) => Err(e),
+        };
+        let new_image_id = match pulled {
+            Ok(id) => id,
+            Err(e) => {
+                log(format!("❌ {}", e));
+                self.record(deployment_id, &snapshot, None, &snapshot.image_id, Err(e.clone()), None).await;
+                return Err(e);
+            }
+        };
+        if new_image_id == snapshot.image_id {
+            log(format!("✅ {} already runs the latest {}", snapshot.name, target));
+            return Ok(());
+        }
+
+        log(format!("Recreating {} ({} → {})", snapshot.name, short_id(&snapshot.image_id), short_id(&new_image_id)));
+        match swap_container(&*self.docker, &snapshot, &target, self.health_timeout, &log).await {
+            Ok(()) => {
+                log(format!("✅ {} redeployed", snapshot.name));
+                self.record(
+                    deployment_id,
+                    &snapshot,
+                    Some(new_image_id.clone()),
+                    &snapshot.image_id,
+                    Ok(format!("Redeployed {} from {} to {}", target, short_id(&snapshot.image_id), short_id(&new_image_id))),
+                    None,
+                ).await;
+                Ok(())
+            }
+            Err(failure) => {
+                log(format!("❌ Redeploy failed: {}", failure.error));
+                let failed_id = self.record(deployment_id, &snapshot, Some(new_image_id.clone()), &snapshot.image_id, Err(failure.error.clone()), None).await;
+                // Separate record, so its output is kept apart from the failed attempt
+                let rollback_id = uuid::Uuid::new_v4().to_string();
+                self.deployment.begin(&rollback_id);
+                // The pull moved the tag to the new image; point it back at the one the old container runs
+                if let Err(e) = self.docker.tag_image(&snapshot.image_id, &snapshot.image).await {
+                    tracing::warn!("Rollback of {}: {}", snapshot.name, e);
+                }
+                let restored = failure.restored.map(|()| format!("Automatic rollback to {}", short_id(&snapshot.image_id)));
+                match &restored {
+                    Ok(_) => log(format!("✅ {} restored on {}", snapshot.name, short_id(&snapshot.image_id))),
+                    Err(e) => log(format!("❌ Rollback failed: {}", e)),
+                }
+                self.record(rollback_id, &snapshot, Some(snapshot.image_id.clone()), &new_image_id, restored, Some(failed_id)).await;
+                Err(failure.error)
+            }
+        }
+    }
+
+    /// Recreates `container` on `image_id` (the image a past deployment recorded),
+    /// linking the new history record (`guard.id`) to `rollback_of`. If the old image
+    /// does not come up healthy, the container it replaced is put back.
+    pub async fn rollback_to(&self, guard: &DeploymentGuard, container: &str, image_id: &str, rollback_of: String) -> Result<(), String> {
+        let log = |line: String| self.deployment.send(line);
+        let snapshot = self.docker.snapshot_container(container).await?;
+        if snapshot.image_id == image_id {
+            log(format!("✅ {} already runs {}", snapshot.name, short_id(image_id)));
+            return Ok(());
+        }
+        log(format!("↩️ Rolling back {} to {}", snapshot.name, short_id(image_id)));
+
+        // Re-point the tag at the old image so the container config keeps a pullable reference
+        let image = match self.docker.tag_image(image_id, &snapshot.image).await {
+            Ok(()) => snapshot.image.clone(),
+            Err(e) => {
+                tracing::warn!("Rollback of {}: {}", snapshot.name, e);
+                image_id.to_string()
+            }
+        };
+
+        match swap_container(&*self.docker, &snapshot, &image, self.health_timeout, &log).await {
+            Ok(()) => {
+                log(format!("✅ {} rolled back", snapshot.name));
+                self.record(
+                    guard.id.clone(),
+                    &snapshot,
+                    Some(image_id.to_string()),
+                    &snapshot.image_id,
+                    Ok(format!("Rollback to {}", short_id(image_id))),
+                    Some(rollback_of),
+                ).await;
+                Ok(())
+            }
+            Err(failure) => {
+                log(format!("❌ Rollback failed: {}", failure.error));
+                if let Err(e) = &failure.restored {
+                    log(format!("❌ Could not restore {}: {}", snapshot.name, e));
+                }
+                self.record(guard.id.clone(), &snapshot, Some(image_id.to_string()), &snapshot.image_id, Err(failure.error.clone()), Some(rollback_of)).await;
+                Err(failure.error)
+            }
+        }
+    }
+
+    /// Adds a history record and returns its id. `outcome` carries the details or the error;
+    /// records with `rollback_of` are rollbacks, the rest redeploys.
+    async fn record(
+        &self,
+        id: String,
+        snapshot: &ContainerSnapshot,
+        image_id: Option<String>,
+        previous_image_id: &str,
+        outcome: Result<String, String>,
+        rollback_of: Option<String>,
+    ) -> String {
+        let action = if rollback_of.is_some() { "rollback" } else { "redeploy" };
+        let (status, details) = match outcome {
+            Ok(details) => ("success", details),
+            Err(e) => ("failed", e),
+        };
+        self.history.add_record(DeploymentRecord {
+            id: id.clone(),
+            timestamp: chrono::Utc::now(),
+            action: action.to_string(),
+            details,
+            status: status.to_string(),
+            image_id,
+            previous_image_id: Some(previous_image_id.to_string()),
+            container_name: Some(snapshot.name.clone()),
+            rollback_of,
+            exit_code: None,
+            duration_ms: None,
+        }).await;
+        id
+    }
+}
+
+/// Container calls a swap is made of; `DockerService` implements them over the API.
+pub(crate) trait ContainerRuntime {
+    async fn stop_container(&self, id: &str) -> Result<(), String>;
+    async fn start_container(&self, id: &str) -> Result<(), String>;
+    async fn rename_container(&self, id: &str, name: &str) -> Result<(), String>;
+    async fn create_container(&self, snapshot: &ContainerSnapshot, image: &str) -> Result<String, String>;
+    async fn remove_container(&self, id: &str) -> Result<(), String>;
+    async fn wait_until_healthy(&self, id: &str, timeout: Duration, settle: Duration) -> Result<(), String>;
+}
+
+impl ContainerRuntime for DockerService {
+    async fn stop_container(&self, id: &str) -> Result<(), String> {
+        DockerService::stop_container(self, id).await
+    }
+
+    async fn start_container(&self, id: &str) -> Result<(), String> {
+        DockerService::start_container(self, id).await
+    }
+
+    async fn rename_container(&self, id: &str, name: &str) -> Result<(), String> {
+        DockerService::rename_container(self, id, name).await
+    }
+
+    async fn create_container(&self, snapshot: &ContainerSnapshot, image: &str) -> Result<String, String> {
+        DockerService::create_container(self, snapshot, image).await
+    }
+
+    async fn remove_container(&self, id: &str) -> Result<(), String> {
+        DockerService::remove_container(self, id).await
+    }
+
+    async fn wait_until_healthy(&self, id: &str, timeout: Duration, settle: Duration) -> Result<(), String> {
+        DockerService::wait_until_healthy(self, id, timeout, settle).await
+    }
+}
+
+/// Why a swap failed, and whether the old container could be put back.
+pub(crate) struct SwapFailure {
+    pub error: String,
+    pub restored: Result<(), String>,
+}
+
+/// Replaces the container described by `snapshot` with one running `image`.
+///
+/// The old container is stopped and renamed out of the way, not removed, and is only
+/// deleted once the new one passes its health check. On any failure the new container
+/// is removed and the old one gets its name back and is started again.
+pub(crate) async fn swap_container(
+    runtime: &impl ContainerRuntime,
+    snapshot: &ContainerSnapshot,
+    image: &str,
+    health_timeout: Duration,
+    log: &impl Fn(String),
+) -> Result<(), SwapFailure> {
+    let backup = format!("{}-replaced", snapshot.name);
+
+    if let Err(e) = runtime.stop_container(&snapshot.name).await {
+        tracing::warn!("Stopping {} before replacing it: {}", snapshot.name, e);
+    }
+    if let Err(error) = runtime.rename_container(&snapshot.name, &backup).await {
+        let restored = match snapshot.running {
+            true => runtime.start_container(&snapshot.name).await,
+            false => Ok(()),
+        };
+        return Err(SwapFailure { error, restored });
+    }
+
+    let new_id = match runtime.create_container(snapshot, image).await {
+        Ok(id) => id,
+        Err(error) => {
+            let restored = restore(runtime, snapshot, &backup, None, log).await;
+            return Err(SwapFailure { error, restored });
+        }
+    };
+    let result = match runtime.start_container(&new_id).await {
+        Ok(()) => {
+            log("Waiting for health check...".to_string());
+            runtime.wait_until_healthy(&new_id, health_timeout, Duration::from_secs(10)).await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(error) = result {
+        let restored = restore(runtime, snapshot, &backup, Some(&new_id), log).await;
+        return Err(SwapFailure { error, restored });
+    }
+
+    if let Err(e) = runtime.remove_container(&backup).await {
+        tracing::warn!("{} was replaced but the old container is left as {}: {}", snapshot.name, backup, e);
+    }
+    Ok(())
+}
+
+/// Removes the failed replacement and gives the old container (renamed to `backup`) its name back.
+async fn restore(
+    runtime: &impl ContainerRuntime,
+    snapshot: &ContainerSnapshot,
+    backup: &str,
+    new_id: Option<&str>,
+    log: &impl Fn(String),
+) -> Result<(), String> {
+    log(format!("↩️ Restoring the previous {} container", snapshot.name));
+    if let Some(id) = new_id {
+        runtime.remove_container(id).await?;
+    }
+    runtime.rename_container(backup, &snapshot.name).await?;
+    if snapshot.running {
+        runtime.start_container(&snapshot.name).await?;
+    }
+    Ok(())
+}
+
+/// `sha256:0123456789ab...` -> `0123456789ab`
+fn short_id(id: &str) -> &str {
+    let id = id.strip_prefix("sha256:").unwrap_or(id);
+    &id[..id.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every call and fails the first one starting with `fail`.
+    #[derive(Default)]
+    struct FakeRuntime {
+        calls: std::sync::Mutex<Vec<String>>,
+        fail: Option<&'static str>,
+    }
+
+    impl FakeRuntime {
+        fn call(&self, call: String) -> Result<(), String> {
+            let failed = self.fail.is_some_and(|f| call.starts_with(f));
+            self.calls.lock().unwrap().push(call.clone());
+            if failed { Err(format!("{} failed", call)) } else { Ok(()) }
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl ContainerRuntime for FakeRuntime {
+        async fn stop_container(&self, id: &str) -> Result<(), String> {
+            self.call(format!("stop {}", id))
+        }
+
+        async fn start_container(&self, id: &str) -> Result<(), String> {
+            self.call(format!("start {}", id))
+        }
+
+        async fn rename_container(&self, id: &str, name: &str) -> Result<(), String> {
+            self.call(format!("rename {} {}", id, name))
+        }
+
+        async fn create_container(&self, snapshot: &ContainerSnapshot, image: &str) -> Result<String, String> {
+            self.call(format!("create {} {}", snapshot.name, image)).map(|()| "new-id".to_string())
+        }
+
+        async fn remove_container(&self, id: &str) -> Result<(), String> {
+            self.call(format!("remove {}", id))
+        }
+
+        async fn wait_until_healthy(&self, id: &str, _timeout: Duration, _settle: Duration) -> Result<(), String> {
+            self.call(format!("health {}", id))
+        }
+    }
+
+    fn snapshot() -> ContainerSnapshot {
+        ContainerSnapshot {
+            name: "web".to_string(),
+            image: "nginx:1.27".to_string(),
+            image_id: "sha256:old".to_string(),
+            running: true,
+            body: Default::default(),
+        }
+    }
+
+    async fn swap(runtime: &FakeRuntime) -> Result<(), SwapFailure> {
+        swap_container(runtime, &snapshot(), "nginx:1.28", Duration::from_secs(1), &|_| {}).await
+    }
+
+    #[tokio::test]
+    async fn test_swap_removes_old_container_only_after_health_check() {
+        let runtime = FakeRuntime::default();
+        assert!(swap(&runtime).await.is_ok());
+        assert_eq!(runtime.calls(), [
+            "stop web",
+            "rename web web-replaced",
+            "create web nginx:1.28",
+            "start new-id",
+            "health new-id",
+            "remove web-replaced",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_swap_restores_old_container_when_unhealthy() {
+        let runtime = FakeRuntime { fail: Some("health"), ..Default::default() };
+        let failure = swap(&runtime).await.err().unwrap();
+        assert_eq!(failure.error, "health new-id failed");
+        assert!(failure.restored.is_ok());
+        assert_eq!(runtime.calls(), [
+            "stop web",
+            "rename web web-replaced",
+            "create web nginx:1.28",
+            "start new-id",
+            "health new-id",
+            "remove new-id",
+            "rename web-replaced web",
+            "start web",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_swap_restores_old_container_when_create_fails() {
+        let runtime = FakeRuntime { fail: Some("create"), ..Default::default() };
+        let failure = swap(&runtime).await.err().unwrap();
+        assert!(failure.restored.is_ok());
+        assert_eq!(runtime.calls()[3..], ["rename web-replaced web", "start web"]);
+    }
+
+    #[tokio::test]
+    async fn test_swap_leaves_container_in_place_when_rename_fails() {
+        let runtime = FakeRuntime { fail: Some("rename web "), ..Default::default() };
+        let failure = swap(&runtime).await.err().unwrap();
+        assert!(failure.restored.is_ok());
+        assert_eq!(runtime.calls(), ["stop web", "rename web web-replaced", "start web"]);
+    }
+
+    #[test]
+    fn test_short_id() {
+        assert_eq!(short_id("sha256:0123456789abcdef0123"), "0123456789ab");
+        assert_eq!(short_id("abc"), "abc");
+    }
+}
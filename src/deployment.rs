@@ -1,117 +1,631 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use axum::{
     extract::State,
     http::{header, StatusCode},
     response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
 };
-use futures_util::stream;
-use crate::history::{HistoryManager, DeploymentRecord};
+use futures_util::{stream, StreamExt};
+use std::path::{Path, PathBuf};
+use crate::history::{HistoryManager, DeploymentRecord, LogLine};
+use crate::self_update::{self, ReleaseSource, UpdateMode};
 
+/// Name the agent itself uses in deployment history.
+pub const AGENT_NAME: &str = "mini-ops";
+
+/// Lines kept in memory for clients that connect to `/api/deploy/logs` mid-deployment.
+const REPLAY_LINES: usize = 5000;
+
+/// Output of the current (or last) deployment: the replay buffer plus the id lines are saved under.
+#[derive(Default)]
+struct LogBuffer {
+    deployment_id: Option<String>,
+    next_seq: i64,
+    lines: std::collections::VecDeque<LogLine>,
+}
+
+/// Deployment that currently owns the log stream.
+struct ActiveDeployment {
+    id: String,
+    cancel: Arc<tokio::sync::Notify>,
+    cancellable: bool,
+}
+
+/// Held for the duration of a deployment; dropping it lets the next one start.
+pub struct DeploymentGuard {
+    pub id: String,
+    cancel: Arc<tokio::sync::Notify>,
+    active: Arc<Mutex<Option<ActiveDeployment>>>,
+}
+
+impl Drop for DeploymentGuard {
+    fn drop(&mut self) {
+        let mut active = self.active.lock().unwrap();
+        if active.as_ref().is_some_and(|a| a.id == self.id) {
+            *active = None;
+        }
+    }
+}
+
+/// One command of a deployment. Runs without a shell, in its own process group.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub working_dir: Option<PathBuf>,
+    pub timeout: std::time::Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepOutcome {
+    Success,
+    Failed(Option<i32>),
+    TimedOut,
+    Cancelled,
+}
+
+impl StepOutcome {
+    /// Value stored in `DeploymentRecord::status`.
+    pub fn status(&self) -> &'static str {
+        match self {
+            StepOutcome::Success => "success",
+            StepOutcome::Failed(_) => "failed",
+            StepOutcome::TimedOut => "timeout",
+            StepOutcome::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            StepOutcome::Success => Some(0),
+            StepOutcome::Failed(code) => *code,
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            StepOutcome::Success => "succeeded".to_string(),
+            StepOutcome::Failed(Some(code)) => format!("failed with exit code {}", code),
+            StepOutcome::Failed(None) => "failed".to_string(),
+            StepOutcome::TimedOut => "timed out".to_string(),
+            StepOutcome::Cancelled => "was cancelled".to_string(),
+        }
+    }
+}
+
+/// `scripts/update.sh` split into steps, each limited by `UPDATE_TIMEOUT_<STEP>` seconds.
+fn agent_update_steps() -> Vec<Step> {
+    [("pull", 300), ("frontend", 900), ("backend", 1800)]
+        .into_iter()
+        .map(|(name, default_secs)| {
+            let secs = std::env::var(format!("UPDATE_TIMEOUT_{}", name.to_uppercase()))
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|s| *s > 0)
+                .unwrap_or(default_secs);
+            Step {
+                name: name.to_string(),
+                program: "bash".to_string(),
+                args: vec!["./scripts/update.sh".to_string(), name.to_string()],
+                env: Vec::new(),
+                working_dir: None,
+                timeout: std::time::Duration::from_secs(secs),
+            }
+        })
+        .collect()
+}
+
+/// SIGTERM to the whole group, then SIGKILL if the leader has not exited within 5 seconds.
+pub(crate) async fn kill_process_group(pgid: i32, child: &mut tokio::process::Child) {
+    // SAFETY: killpg only sends a signal; pgid is the group this child leads
+    unsafe { libc::killpg(pgid, libc::SIGTERM) };
+    if tokio::time::timeout(std::time::Duration::from_secs(5), child.wait()).await.is_err() {
+        unsafe { libc::killpg(pgid, libc::SIGKILL) };
+        let _ = child.wait().await;
+    }
+}
+
+/// Line tagged with the deployment it belongs to, on its way to `deployment_logs`.
+type PendingLine = (String, LogLine);
+
+/// Streams deployment progress to SSE clients and persists it per deployment.
 #[derive(Clone)]
 pub struct DeploymentService {
-    tx: tokio::sync::broadcast::Sender<String>,
+    tx: tokio::sync::broadcast::Sender<LogLine>,
+    buffer: Arc<Mutex<LogBuffer>>,
+    active: Arc<Mutex<Option<ActiveDeployment>>>,
+    persist_tx: tokio::sync::mpsc::UnboundedSender<PendingLine>,
+    persist_rx: Arc<Mutex<Option<tokio::sync::mpsc::UnboundedReceiver<PendingLine>>>>,
+    history: Arc<HistoryManager>,
 }
 
 impl DeploymentService {
-    pub fn new() -> Self {
+    pub fn new(history: Arc<HistoryManager>) -> Self {
         let (tx, _) = tokio::sync::broadcast::channel(100);
-        Self { tx }
+        let (persist_tx, persist_rx) = tokio::sync::mpsc::unbounded_channel();
+        Self {
+            tx,
+            buffer: Arc::new(Mutex::new(LogBuffer::default())),
+            active: Arc::new(Mutex::new(None)),
+            persist_tx,
+            persist_rx: Arc::new(Mutex::new(Some(persist_rx))),
+            history,
+        }
+    }
+
+    /// Writes lines of the current deployment to `deployment_logs`, batching bursts
+    /// into one transaction.
+    pub async fn run_log_writer(self: Arc<Self>) {
+        let Some(mut rx) = self.persist_rx.lock().unwrap().take() else {
+            return;
+        };
+        let mut batch = Vec::new();
+        while rx.recv_many(&mut batch, 500).await > 0 {
+            if let Err(e) = self.history.append_logs(&batch).await {
+                tracing::error!("Failed to save {} deployment log lines: {}", batch.len(), e);
+            }
+            batch.clear();
+        }
     }
 
-    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<String> {
-        self.tx.subscribe()
+    /// Claims the single deployment slot. Fails with the id of the deployment already running.
+    /// `cancellable` deployments stop when `/api/deploy/cancel` is called.
+    pub fn try_start(&self, deployment_id: &str, cancellable: bool) -> Result<DeploymentGuard, String> {
+        let mut active = self.active.lock().unwrap();
+        if let Some(running) = active.as_ref() {
+            return Err(running.id.clone());
+        }
+        let cancel = Arc::new(tokio::sync::Notify::new());
+        *active = Some(ActiveDeployment {
+            id: deployment_id.to_string(),
+            cancel: cancel.clone(),
+            cancellable,
+        });
+        self.begin(deployment_id);
+        Ok(DeploymentGuard {
+            id: deployment_id.to_string(),
+            cancel,
+            active: self.active.clone(),
+        })
     }
 
-    pub async fn run_update_stream(&self) {
-        use std::process::Stdio;
-        use tokio::io::{AsyncBufReadExt, BufReader};
-        use tokio::process::Command;
+    /// Asks the running deployment to stop; returns its id.
+    pub fn cancel(&self) -> Result<String, (StatusCode, String)> {
+        let active = self.active.lock().unwrap();
+        match active.as_ref() {
+            None => Err((StatusCode::NOT_FOUND, "No deployment in progress".to_string())),
+            Some(a) if !a.cancellable => Err((StatusCode::CONFLICT, format!("Deployment {} cannot be cancelled", a.id))),
+            Some(a) => {
+                // notify_one keeps a permit, so a cancel between two steps is not lost
+                a.cancel.notify_one();
+                Ok(a.id.clone())
+            }
+        }
+    }
+
+    /// Starts a new log section: clears the replay buffer and saves further lines under `deployment_id`.
+    pub fn begin(&self, deployment_id: &str) {
+        let mut buffer = self.buffer.lock().unwrap();
+        *buffer = LogBuffer {
+            deployment_id: Some(deployment_id.to_string()),
+            ..LogBuffer::default()
+        };
+    }
+
+    /// Publishes one progress line to everyone tailing `/api/deploy/logs`.
+    pub fn send(&self, line: impl Into<String>) {
+        self.emit("info", line.into());
+    }
+
+    fn emit(&self, stream: &str, line: String) {
+        // Buffer and broadcast under one lock so a subscriber sees each line exactly once
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.next_seq += 1;
+        let line = LogLine {
+            seq: buffer.next_seq,
+            stream: stream.to_string(),
+            line,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        };
+
+        if let Some(id) = &buffer.deployment_id {
+            let _ = self.persist_tx.send((id.clone(), line.clone()));
+        }
+        let _ = self.tx.send(line.clone());
+        buffer.lines.push_back(line);
+        if buffer.lines.len() > REPLAY_LINES {
+            buffer.lines.pop_front();
+        }
+    }
+
+    /// Buffered lines of the current deployment plus a receiver for everything after them.
+    pub fn subscribe(&self) -> (Vec<LogLine>, tokio::sync::broadcast::Receiver<LogLine>) {
+        let buffer = self.buffer.lock().unwrap();
+        (buffer.lines.iter().cloned().collect(), self.tx.subscribe())
+    }
+
+    /// Updates the agent according to `UPDATE_MODE`: `scripts/update.sh pull|frontend|backend`
+    /// (source) or a signed release binary (release). The current build is saved first so the
+    /// record can be rolled back; the final status, exit code and duration go to history.
+    pub async fn run_update_stream(&self, guard: DeploymentGuard) {
+        let svc = self.clone();
 
-        let tx = self.tx.clone();
-        
         tokio::spawn(async move {
-            let _ = tx.send("🚀 Starting update process...".to_string());
-            
-            let mut cmd = Command::new("bash");
-            cmd.arg("./scripts/update.sh");
-            cmd.stdout(Stdio::piped());
-            cmd.stderr(Stdio::piped());
-
-            let mut child = match cmd.spawn() {
-                Ok(child) => child,
-                Err(e) => {
-                    let _ = tx.send(format!("❌ Failed to start script: {}", e));
-                    return;
+            let started = std::time::Instant::now();
+            svc.send("🚀 Starting update process...");
+
+            let id = guard.id.clone();
+            match tokio::task::spawn_blocking(move || backup_agent(&id)).await {
+                Ok(Ok(dir)) => svc.send(format!("📦 Current build saved to {} for rollback", dir.display())),
+                Ok(Err(e)) => svc.send(format!("⚠️ Could not save current build, rollback will be unavailable: {}", e)),
+                Err(e) => svc.send(format!("⚠️ Backup task failed: {}", e)),
+            }
+
+            let mode = UpdateMode::from_env();
+            let (outcome, restart) = match mode {
+                UpdateMode::Source => (svc.run_steps(&agent_update_steps(), &guard, None).await, false),
+                UpdateMode::Release => svc.install_release(&guard.cancel).await,
+            };
+            match outcome {
+                StepOutcome::Success => svc.send("✅ Update complete!"),
+                _ => svc.send(format!("❌ Update {}", outcome.describe())),
+            }
+            svc.history.finish(&guard.id, outcome.status(), outcome.exit_code(), started.elapsed().as_millis() as i64).await;
+
+            if restart {
+                if self_update::running_under_systemd() {
+                    svc.send("🔄 Restarting through systemd...");
+                    restart_after_flush().await;
                 }
+                svc.send("⚠️ Not running under systemd: restart mini-ops to use the new version");
+            }
+        });
+    }
+
+    /// Downloads, verifies and installs the release binary; the returned flag is whether a new
+    /// binary was installed. A cancel stops the download, but once the release is verified the
+    /// install runs to completion, so history never says `cancelled` for a replaced binary.
+    async fn install_release(&self, cancel: &tokio::sync::Notify) -> (StepOutcome, bool) {
+        let downloaded = tokio::select! {
+            result = self.download_release() => result,
+            _ = cancel.notified() => return (StepOutcome::Cancelled, false),
+        };
+        let (binary, exe) = match downloaded {
+            Ok(Some(download)) => download,
+            Ok(None) => return (StepOutcome::Success, false),
+            Err(e) => {
+                self.send(format!("❌ {}", e));
+                return (StepOutcome::Failed(None), false);
+            }
+        };
+
+        let installed = tokio::task::spawn_blocking(move || self_update::install_binary(&binary, &exe))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+        match installed {
+            Ok(()) => {
+                self.send("📦 New binary installed");
+                (StepOutcome::Success, true)
+            }
+            Err(e) => {
+                self.send(format!("❌ {}", e));
+                (StepOutcome::Failed(None), false)
+            }
+        }
+    }
+
+    /// Fetches and verifies the release binary, returning it with the path it replaces.
+    /// `None` when the running binary already matches the release checksum.
+    async fn download_release(&self) -> Result<Option<(Vec<u8>, PathBuf)>, String> {
+        let source = ReleaseSource::from_env()?;
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+
+        let release = source.release().await?;
+        let current = tokio::fs::read(&exe).await.map_err(|e| format!("Failed to read {}: {}", exe.display(), e))?;
+        if self_update::sha256_hex(&current) == release.sha256 {
+            self.send("✅ Already running the latest release");
+            return Ok(None);
+        }
+
+        self.send(format!("⬇️ Downloading {} ({})", source.url(), release.version));
+        let binary = source.download_verified(&release).await?;
+        self.send(format!("🔏 Version {}, checksum and signature verified ({} KB)", release.version, binary.len() / 1024));
+        Ok(Some((binary, exe)))
+    }
+
+    /// Runs `steps` in order and stops at the first one that does not succeed.
+    /// With a `deadline`, each step's timeout is cut to the time that is left.
+    pub async fn run_steps(&self, steps: &[Step], guard: &DeploymentGuard, deadline: Option<std::time::Instant>) -> StepOutcome {
+        for step in steps {
+            self.send(format!("▶️ {}", step.name));
+            let timeout = match deadline {
+                Some(deadline) => step.timeout.min(deadline.saturating_duration_since(std::time::Instant::now())),
+                None => step.timeout,
             };
+            let outcome = self.run_step(step, timeout, &guard.cancel).await;
+            if outcome != StepOutcome::Success {
+                self.send(format!("❌ Step '{}' {}", step.name, outcome.describe()));
+                return outcome;
+            }
+        }
+        StepOutcome::Success
+    }
+
+    async fn run_step(&self, step: &Step, timeout: std::time::Duration, cancel: &tokio::sync::Notify) -> StepOutcome {
+        use std::process::Stdio;
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let mut cmd = tokio::process::Command::new(&step.program);
+        cmd.args(&step.args)
+            .envs(step.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Own process group, so a kill also reaches npm/cargo children
+            .process_group(0)
+            .kill_on_drop(true);
+        if let Some(dir) = &step.working_dir {
+            cmd.current_dir(dir);
+        }
 
-            let stdout = child.stdout.take().expect("Failed to open stdout");
-            let stderr = child.stderr.take().expect("Failed to open stderr");
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.send(format!("❌ Failed to start {}: {}", step.program, e));
+                return StepOutcome::Failed(None);
+            }
+        };
+        let pgid = child.id().map(|id| id as i32);
 
-            let mut stdout_reader = BufReader::new(stdout).lines();
-            let mut stderr_reader = BufReader::new(stderr).lines();
+        let stdout = child.stdout.take().expect("Failed to open stdout");
+        let stderr = child.stderr.take().expect("Failed to open stderr");
 
-            loop {
-                tokio::select! {
-                    result = stdout_reader.next_line() => {
-                        match result {
-                            Ok(Some(line)) => { let _ = tx.send(format!("STDOUT: {}", line)); }
-                            Ok(None) => break, // EOF
-                            Err(_) => break,
-                        }
-                    }
-                    result = stderr_reader.next_line() => {
-                        match result {
-                            Ok(Some(line)) => { let _ = tx.send(format!("STDERR: {}", line)); }
-                            Ok(None) => break, // EOF
-                            Err(_) => break,
-                        }
+        let stopped = {
+            let output = async {
+                let mut stdout_reader = BufReader::new(stdout).lines();
+                let mut stderr_reader = BufReader::new(stderr).lines();
+                let (mut stdout_open, mut stderr_open) = (true, true);
+
+                while stdout_open || stderr_open {
+                    tokio::select! {
+                        result = stdout_reader.next_line(), if stdout_open => match result {
+                            Ok(Some(line)) => self.emit("stdout", line),
+                            _ => stdout_open = false,
+                        },
+                        result = stderr_reader.next_line(), if stderr_open => match result {
+                            Ok(Some(line)) => self.emit("stderr", line),
+                            _ => stderr_open = false,
+                        },
                     }
                 }
+            };
+
+            tokio::select! {
+                _ = output => None,
+                _ = tokio::time::sleep(timeout) => Some(StepOutcome::TimedOut),
+                _ = cancel.notified() => Some(StepOutcome::Cancelled),
             }
+        };
 
-            match child.wait().await {
-                Ok(status) => {
-                    if status.success() {
-                        let _ = tx.send("✅ Update complete! Service restarting...".to_string());
-                    } else {
-                        let _ = tx.send(format!("❌ Update failed with status: {}", status));
-                    }
+        if let Some(outcome) = stopped {
+            self.send(format!("⏹️ Stopping '{}'...", step.name));
+            if let Some(pgid) = pgid {
+                kill_process_group(pgid, &mut child).await;
+            }
+            return outcome;
+        }
+
+        match child.wait().await {
+            Ok(status) if status.success() => StepOutcome::Success,
+            Ok(status) => StepOutcome::Failed(status.code()),
+            Err(e) => {
+                self.send(format!("❌ Failed to wait on child: {}", e));
+                StepOutcome::Failed(None)
+            }
+        }
+    }
+
+    /// Restores the binary and frontend saved before `original_id` ran, records the
+    /// rollback and exits so the supervisor (systemd `Restart=always`) starts the old build.
+    pub async fn rollback_agent(&self, guard: DeploymentGuard, original_id: String) {
+        let svc = self.clone();
+
+        tokio::spawn(async move {
+            svc.send(format!("↩️ Restoring agent build saved before deployment {}", original_id));
+
+            let id = original_id.clone();
+            let result = tokio::task::spawn_blocking(move || restore_agent(&id))
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+
+            let (status, details) = match &result {
+                Ok(()) => ("success", "Restored previous agent binary and frontend".to_string()),
+                Err(e) => ("failed", e.clone()),
+            };
+            svc.history.add_record(DeploymentRecord {
+                id: guard.id.clone(),
+                timestamp: chrono::Utc::now(),
+                action: "rollback".to_string(),
+                details,
+                status: status.to_string(),
+                image_id: None,
+                previous_image_id: None,
+                container_name: Some(AGENT_NAME.to_string()),
+                rollback_of: Some(original_id),
+                exit_code: None,
+                duration_ms: None,
+            }).await;
+
+            match result {
+                Ok(()) => {
+                    svc.send("✅ Rollback complete. Restarting agent...");
+                    restart_after_flush().await;
                 }
                 Err(e) => {
-                    let _ = tx.send(format!("❌ Failed to wait on child: {}", e));
+                    svc.send(format!("❌ Rollback failed: {}", e));
                 }
             }
         });
     }
 }
 
+/// Exits so the service manager starts the agent again, after giving the SSE clients and
+/// the log writer a moment to catch up.
+async fn restart_after_flush() -> ! {
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    std::process::exit(0)
+}
+
+fn backup_root() -> PathBuf {
+    PathBuf::from(std::env::var("AGENT_BACKUP_DIR").unwrap_or_else(|_| "backups".to_string()))
+}
+
+/// Whether a build was saved before the given agent deployment.
+pub fn has_agent_backup(deployment_id: &str) -> bool {
+    backup_root().join(deployment_id).join(AGENT_NAME).is_file()
+}
+
+/// Saves the running binary and `frontend/dist` under `<AGENT_BACKUP_DIR>/<deployment_id>`.
+fn backup_agent(deployment_id: &str) -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let dest = backup_root().join(deployment_id);
+    backup_build(&exe, Path::new("frontend/dist"), &dest)?;
+
+    let keep = std::env::var("AGENT_BACKUP_KEEP").ok().and_then(|s| s.parse().ok()).unwrap_or(5usize);
+    prune_backups(&backup_root(), keep);
+    Ok(dest)
+}
+
+fn restore_agent(deployment_id: &str) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    restore_build(&backup_root().join(deployment_id), &exe, Path::new("frontend/dist"))
+}
+
+fn backup_build(exe: &Path, dist: &Path, dest: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    std::fs::copy(exe, dest.join(AGENT_NAME))
+        .map_err(|e| format!("Failed to copy {}: {}", exe.display(), e))?;
+    if dist.is_dir() {
+        copy_dir(dist, &dest.join("dist"))?;
+    }
+    Ok(())
+}
+
+fn restore_build(src: &Path, exe: &Path, dist: &Path) -> Result<(), String> {
+    let saved = src.join(AGENT_NAME);
+    if !saved.is_file() {
+        return Err(format!("No saved build in {}", src.display()));
+    }
+
+    // Write next to the target and rename: replacing a running executable in place fails with ETXTBSY
+    let tmp = exe.with_extension("rollback");
+    std::fs::copy(&saved, &tmp).map_err(|e| format!("Failed to copy {}: {}", saved.display(), e))?;
+    std::fs::rename(&tmp, exe).map_err(|e| format!("Failed to replace {}: {}", exe.display(), e))?;
+
+    let saved_dist = src.join("dist");
+    if saved_dist.is_dir() {
+        if dist.exists() {
+            std::fs::remove_dir_all(dist).map_err(|e| format!("Failed to remove {}: {}", dist.display(), e))?;
+        }
+        copy_dir(&saved_dist, dist)?;
+    }
+    Ok(())
+}
+
+fn copy_dir(src: &Path, dest: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let entries = std::fs::read_dir(src).map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let target = dest.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target).map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Keeps only the `keep` most recent backups.
+fn prune_backups(root: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    let mut dirs: Vec<(std::time::SystemTime, PathBuf)> = entries.flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    dirs.sort_by_key(|d| std::cmp::Reverse(d.0));
+
+    for (_, dir) in dirs.into_iter().skip(keep) {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            tracing::warn!("Failed to prune backup {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// 409 response naming the deployment that holds the slot.
+pub fn already_running(deployment_id: &str) -> Response {
+    (
+        StatusCode::CONFLICT,
+        axum::Json(serde_json::json!({
+            "error": "Another deployment is in progress",
+            "deployment_id": deployment_id,
+        })),
+    )
+        .into_response()
+}
+
 pub async fn trigger_update_handler(
     State(state): State<Arc<DeploymentService>>,
-    State(history): State<Arc<HistoryManager>>,
 ) -> Response {
+    let deployment_id = uuid::Uuid::new_v4().to_string();
+    let guard = match state.try_start(&deployment_id, true) {
+        Ok(guard) => guard,
+        Err(running) => return already_running(&running),
+    };
+
     // Record deployment
-     history.add_record(DeploymentRecord {
-        id: uuid::Uuid::new_v4().to_string(),
+    state.history.add_record(DeploymentRecord {
+        id: deployment_id.clone(),
         timestamp: chrono::Utc::now(),
         action: "update".to_string(),
         details: "Agent Update Triggered".to_string(),
         status: "in_progress".to_string(),
         image_id: None, // Agent update is source-based for now
-        container_name: Some("mini-ops".to_string()),
-    });
+        previous_image_id: None,
+        container_name: Some(AGENT_NAME.to_string()),
+        rollback_of: None,
+        exit_code: None,
+        duration_ms: None,
+    }).await;
     
-    state.run_update_stream().await;
+    state.run_update_stream(guard).await;
     (StatusCode::OK, "Update triggered. Connect to stream for logs.").into_response()
 }
 
+pub async fn cancel_deployment_handler(State(state): State<Arc<DeploymentService>>) -> Response {
+    match state.cancel() {
+        Ok(id) => {
+            state.send(format!("🛑 Cancellation requested for {}", id));
+            axum::Json(serde_json::json!({ "deployment_id": id })).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Replays the current deployment's buffered lines, then tails new ones.
 pub async fn deploy_logs_sse_handler(
     State(state): State<Arc<DeploymentService>>,
 ) -> Response {
-    let rx = state.subscribe();
-    let sse_stream = stream::unfold(rx, |mut rx| async move {
+    let (buffered, rx) = state.subscribe();
+    let replay = stream::iter(buffered.into_iter().map(|line| Ok(log_event(&line))));
+    let live = stream::unfold(rx, |mut rx| async move {
         match rx.recv().await {
-            Ok(msg) => Some((Ok::<Event, std::convert::Infallible>(Event::default().data(msg)), rx)),
+            Ok(line) => Some((Ok::<Event, std::convert::Infallible>(log_event(&line)), rx)),
             Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
                 Some((Ok(Event::default().data("⚠️ Log stream lagged, some lines were skipped")), rx))
             }
@@ -121,7 +635,181 @@ pub async fn deploy_logs_sse_handler(
 
     (
         [(header::CACHE_CONTROL, "no-cache")],
-        Sse::new(sse_stream).keep_alive(KeepAlive::default()),
+        Sse::new(replay.chain(live)).keep_alive(KeepAlive::default()),
     )
         .into_response()
 }
+
+/// Keeps the `STDOUT:`/`STDERR:` prefixes the dashboard has always shown.
+fn log_event(line: &LogLine) -> Event {
+    let data = match line.stream.as_str() {
+        "stdout" => format!("STDOUT: {}", line.line),
+        "stderr" => format!("STDERR: {}", line.line),
+        _ => line.line.clone(),
+    };
+    Event::default().id(line.seq.to_string()).data(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> tempfile::TempDir {
+        tempfile::tempdir().unwrap()
+    }
+
+    #[test]
+    fn test_backup_and_restore_build() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        let exe = root.join("mini-ops");
+        let dist = root.join("dist");
+        std::fs::write(&exe, "old binary").unwrap();
+        std::fs::create_dir_all(dist.join("assets")).unwrap();
+        std::fs::write(dist.join("index.html"), "old index").unwrap();
+        std::fs::write(dist.join("assets/app.js"), "old js").unwrap();
+
+        let saved = root.join("backups/deploy-1");
+        backup_build(&exe, &dist, &saved).unwrap();
+
+        // Simulate an update that replaced both
+        std::fs::write(&exe, "new binary").unwrap();
+        std::fs::remove_dir_all(&dist).unwrap();
+        std::fs::create_dir_all(&dist).unwrap();
+        std::fs::write(dist.join("index.html"), "new index").unwrap();
+
+        restore_build(&saved, &exe, &dist).unwrap();
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "old binary");
+        assert_eq!(std::fs::read_to_string(dist.join("index.html")).unwrap(), "old index");
+        assert_eq!(std::fs::read_to_string(dist.join("assets/app.js")).unwrap(), "old js");
+    }
+
+    #[test]
+    fn test_restore_without_backup_fails() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        assert!(restore_build(&root.join("missing"), &root.join("mini-ops"), &root.join("dist")).is_err());
+    }
+
+    async fn service() -> (Arc<DeploymentService>, Arc<HistoryManager>) {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let history = Arc::new(HistoryManager::new(pool));
+        history.migrate("/nonexistent/history.json").await.unwrap();
+        let service = Arc::new(DeploymentService::new(history.clone()));
+        tokio::spawn(service.clone().run_log_writer());
+        (service, history)
+    }
+
+    fn step(script: &str, timeout_ms: u64) -> Step {
+        Step {
+            name: "test".to_string(),
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: vec![("GREETING".to_string(), "hello".to_string())],
+            working_dir: None,
+            timeout: std::time::Duration::from_millis(timeout_ms),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_one_deployment_at_a_time() {
+        let (service, _) = service().await;
+        let guard = service.try_start("first", true).unwrap();
+        assert_eq!(service.try_start("second", true).err(), Some("first".to_string()));
+        drop(guard);
+        assert!(service.try_start("second", true).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_requires_cancellable_deployment() {
+        let (service, _) = service().await;
+        assert_eq!(service.cancel().unwrap_err().0, StatusCode::NOT_FOUND);
+
+        let guard = service.try_start("redeploy", false).unwrap();
+        assert_eq!(service.cancel().unwrap_err().0, StatusCode::CONFLICT);
+        drop(guard);
+
+        let _guard = service.try_start("update", true).unwrap();
+        assert_eq!(service.cancel().unwrap(), "update");
+    }
+
+    #[tokio::test]
+    async fn test_steps_report_exit_code_and_stop_at_failure() {
+        let (service, _) = service().await;
+        let guard = service.try_start("d1", true).unwrap();
+
+        let outcome = service.run_steps(&[step("echo $GREETING", 5000), step("exit 3", 5000), step("echo never", 5000)], &guard, None).await;
+        assert_eq!(outcome, StepOutcome::Failed(Some(3)));
+        assert_eq!((outcome.status(), outcome.exit_code()), ("failed", Some(3)));
+
+        let (lines, _) = service.subscribe();
+        assert!(lines.iter().any(|l| l.stream == "stdout" && l.line == "hello"));
+        assert!(!lines.iter().any(|l| l.line == "never"));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_cancel_kill_the_process_group() {
+        let (service, _) = service().await;
+        let tmp = temp_dir();
+        let marker = tmp.path().join("marker");
+        // The background child outlives `sh` unless the whole group is killed
+        let script = format!("(sleep 1; touch {}) & sleep 30", marker.display());
+
+        let guard = service.try_start("d1", true).unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(service.run_steps(&[step(&script, 200)], &guard, None).await, StepOutcome::TimedOut);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        drop(guard);
+
+        let guard = service.try_start("d2", true).unwrap();
+        service.cancel().unwrap();
+        assert_eq!(service.run_steps(&[step(&script, 30_000)], &guard, None).await, StepOutcome::Cancelled);
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_replays_then_tails_and_persists() {
+        let (service, history) = service().await;
+
+        service.send("from a previous deployment");
+        service.begin("d1");
+        service.send("starting");
+        service.emit("stdout", "building".to_string());
+
+        let (buffered, mut rx) = service.subscribe();
+        assert_eq!(buffered.iter().map(|l| l.line.as_str()).collect::<Vec<_>>(), ["starting", "building"]);
+
+        service.emit("stderr", "warning".to_string());
+        let live = rx.recv().await.unwrap();
+        assert_eq!((live.seq, live.stream.as_str(), live.line.as_str()), (3, "stderr", "warning"));
+
+        let mut saved = Vec::new();
+        for _ in 0..50 {
+            saved = history.get_logs("d1").await.unwrap();
+            if saved.len() == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(saved.iter().map(|l| l.line.as_str()).collect::<Vec<_>>(), ["starting", "building", "warning"]);
+    }
+
+    #[test]
+    fn test_prune_backups_keeps_newest() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        for name in ["a", "b", "c"] {
+            std::fs::create_dir_all(root.join(name)).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        prune_backups(root, 2);
+        assert!(!root.join("a").exists());
+        assert!(root.join("b").exists() && root.join("c").exists());
+    }
+}
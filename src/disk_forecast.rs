@@ -0,0 +1,337 @@
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::disk_ops::format_bytes;
+use crate::i18n::{self, Lang};
+use crate::metrics::{MetricsState, MountUsage};
+use crate::notifications::NotificationService;
+
+/// Points the regression works on; longer histories are thinned out evenly.
+const MAX_POINTS: usize = 300;
+/// With fewer samples, or a shorter span, there is no forecast.
+const MIN_SAMPLES: usize = 12;
+const MIN_SPAN_SECS: i64 = 3600;
+/// The same mount is not alerted about again for this long.
+const ALERT_COOLDOWN: Duration = Duration::from_secs(6 * 3600);
+/// The `metrics` table holds the root filesystem (or all disks summed up when there is no `/`).
+const ROOT_MOUNT: &str = "/";
+
+#[derive(Debug, Clone)]
+pub struct ForecastConfig {
+    /// History the trend is fitted on
+    pub window: Duration,
+    /// Alert when a mount is expected to be full within this many hours; 0 disables the alert
+    pub alert_hours: u64,
+}
+
+impl ForecastConfig {
+    /// `DISK_FORECAST_WINDOW_HOURS` (default 168) and `DISK_FULL_ALERT_HOURS` (default 48).
+    pub fn from_env() -> Self {
+        let hours = |var: &str, default: u64| std::env::var(var).ok().and_then(|s| s.parse().ok()).unwrap_or(default);
+        Self {
+            window: Duration::from_secs(hours("DISK_FORECAST_WINDOW_HOURS", 168).max(1) * 3600),
+            alert_hours: hours("DISK_FULL_ALERT_HOURS", 48),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiskForecast {
+    pub mount: String,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+    /// Trend over the window; negative when usage shrinks, `None` without enough history
+    pub growth_bytes_per_day: Option<f64>,
+    /// Unix timestamp (seconds) the mount is expected to be full at; `None` when it is not growing
+    pub full_at: Option<i64>,
+    pub hours_until_full: Option<f64>,
+    pub samples: usize,
+}
+
+/// Theil–Sen estimator: the median of the slopes between all pairs of points. A one-off jump,
+/// such as a large download deleted again, moves it far less than least squares would.
+fn theil_sen_slope(points: &[(i64, u64)]) -> Option<f64> {
+    let mut slopes = Vec::with_capacity(points.len() * points.len() / 2);
+    for (i, (t1, y1)) in points.iter().enumerate() {
+        for (t2, y2) in &points[i + 1..] {
+            if t2 != t1 {
+                slopes.push((*y2 as f64 - *y1 as f64) / (*t2 - *t1) as f64);
+            }
+        }
+    }
+    if slopes.is_empty() {
+        return None;
+    }
+    slopes.sort_by(f64::total_cmp);
+    let mid = slopes.len() / 2;
+    Some(if slopes.len() % 2 == 0 { (slopes[mid - 1] + slopes[mid]) / 2.0 } else { slopes[mid] })
+}
+
+/// Forecast from `(timestamp, used, total)` samples in time order.
+pub fn forecast(mount: &str, samples: &[(i64, u64, u64)], now: i64) -> DiskForecast {
+    let (last_ts, used, total) = samples.last().copied().unwrap_or((now, 0, 0));
+    let mut result = DiskForecast {
+        mount: mount.to_string(),
+        used_bytes: used,
+        total_bytes: total,
+        growth_bytes_per_day: None,
+        full_at: None,
+        hours_until_full: None,
+        samples: samples.len(),
+    };
+    let span = samples.first().map(|(first, _, _)| last_ts - first).unwrap_or(0);
+    if samples.len() < MIN_SAMPLES || span < MIN_SPAN_SECS {
+        return result;
+    }
+
+    let step = samples.len().div_ceil(MAX_POINTS);
+    let points: Vec<(i64, u64)> = samples.iter().step_by(step).map(|(t, used, _)| (*t, *used)).collect();
+    let Some(slope) = theil_sen_slope(&points) else {
+        return result;
+    };
+    result.growth_bytes_per_day = Some(slope * 86400.0);
+    if slope > 0.0 {
+        let full_at = last_ts + (total.saturating_sub(used) as f64 / slope) as i64;
+        result.full_at = Some(full_at);
+        result.hours_until_full = Some(((full_at - now) as f64 / 3600.0).max(0.0));
+    }
+    result
+}
+
+/// Keeps per-mount usage history and warns before a mount fills up.
+pub struct DiskForecaster {
+    db: SqlitePool,
+    metrics: Arc<MetricsState>,
+    notifier: Arc<NotificationService>,
+    config: ForecastConfig,
+    alerted: Mutex<HashMap<String, Instant>>,
+}
+
+impl DiskForecaster {
+    pub fn new(db: SqlitePool, metrics: Arc<MetricsState>, notifier: Arc<NotificationService>, config: ForecastConfig) -> Self {
+        Self { db, metrics, notifier, config, alerted: Mutex::new(HashMap::new()) }
+    }
+
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS disk_mount_samples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mount TEXT NOT NULL,
+                used INTEGER NOT NULL,
+                total INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )"
+        )
+        .execute(&self.db)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_disk_mount_samples ON disk_mount_samples (mount, timestamp)")
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Stores a sample per mount other than `/`, whose history is already in `metrics`,
+    /// and drops samples older than the window.
+    async fn record(&self, mounts: &[MountUsage], now: i64) -> Result<(), sqlx::Error> {
+        for m in mounts.iter().filter(|m| m.mount != ROOT_MOUNT) {
+            sqlx::query("INSERT INTO disk_mount_samples (mount, used, total, timestamp) VALUES (?, ?, ?, ?)")
+                .bind(&m.mount)
+                .bind(m.used as i64)
+                .bind(m.total as i64)
+                .bind(now)
+                .execute(&self.db)
+                .await?;
+        }
+        sqlx::query("DELETE FROM disk_mount_samples WHERE timestamp < ?")
+            .bind(now - self.config.window.as_secs() as i64)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn samples(&self, mount: &str, since: i64) -> Result<Vec<(i64, u64, u64)>, sqlx::Error> {
+        let rows = if mount == ROOT_MOUNT {
+            sqlx::query(
+                "SELECT timestamp, disk_used AS used, disk_total AS total FROM metrics
+                 WHERE timestamp >= ? ORDER BY timestamp"
+            )
+            .bind(since)
+            .fetch_all(&self.db)
+            .await?
+        } else {
+            sqlx::query(
+                "SELECT timestamp, used, total FROM disk_mount_samples
+                 WHERE mount = ? AND timestamp >= ? ORDER BY timestamp"
+            )
+            .bind(mount)
+            .bind(since)
+            .fetch_all(&self.db)
+            .await?
+        };
+        Ok(rows.iter()
+            .map(|r| (r.get::<i64, _>("timestamp"), r.get::<i64, _>("used") as u64, r.get::<i64, _>("total") as u64))
+            .collect())
+    }
+
+    /// A forecast for `/` and for every other mounted filesystem.
+    pub async fn forecasts(&self) -> Result<Vec<DiskForecast>, sqlx::Error> {
+        let now = chrono::Utc::now().timestamp();
+        let since = now - self.config.window.as_secs() as i64;
+        let mut mounts = vec![ROOT_MOUNT.to_string()];
+        mounts.extend(self.metrics.mounts().into_iter().map(|m| m.mount).filter(|m| m != ROOT_MOUNT));
+
+        let mut forecasts = Vec::new();
+        for mount in mounts {
+            let samples = self.samples(&mount, since).await?;
+            forecasts.push(forecast(&mount, &samples, now));
+        }
+        Ok(forecasts)
+    }
+
+    /// Mounts expected to be full within `alert_hours` that were not alerted about recently.
+    fn due_alerts(&self, forecasts: &[DiskForecast]) -> Vec<DiskForecast> {
+        let mut alerted = self.alerted.lock().unwrap();
+        let mut due = Vec::new();
+        for f in forecasts {
+            match f.hours_until_full {
+                Some(hours) if hours <= self.config.alert_hours as f64 => {
+                    if alerted.get(&f.mount).is_none_or(|at| at.elapsed() >= ALERT_COOLDOWN) {
+                        alerted.insert(f.mount.clone(), Instant::now());
+                        due.push(f.clone());
+                    }
+                }
+                // Alert again straight away if it gets worse after recovering
+                _ => {
+                    alerted.remove(&f.mount);
+                }
+            }
+        }
+        due
+    }
+
+    async fn check_alerts(&self) {
+        let forecasts = match self.forecasts().await {
+            Ok(f) => f,
+            Err(e) => {
+                tracing::error!("Disk forecast failed: {}", e);
+                return;
+            }
+        };
+        let lang = Lang::from_headers(&i18n::HeaderMap::new());
+        for f in self.due_alerts(&forecasts) {
+            let detail = format!(
+                "{} — {}, +{}/{}",
+                f.mount,
+                i18n::t_val("alert.hours_left", &lang, &format!("{:.0}", f.hours_until_full.unwrap_or(0.0))),
+                format_bytes(f.growth_bytes_per_day.unwrap_or(0.0) as u64),
+                i18n::t("alert.per_day", &lang),
+            );
+            self.notifier.send_alert(&i18n::t_val("alert.disk_full_forecast", &lang, &detail)).await;
+        }
+    }
+
+    /// Samples every mount once a minute and checks the forecasts every ten minutes.
+    pub async fn run_loop(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        let mut ticks: u64 = 0;
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.record(&self.metrics.mounts(), chrono::Utc::now().timestamp()).await {
+                tracing::error!("Failed to record disk samples: {}", e);
+            }
+            if self.config.alert_hours > 0 && ticks.is_multiple_of(10) {
+                self.check_alerts().await;
+            }
+            ticks += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    /// One sample a minute for `minutes`, growing by `per_hour` bytes an hour from `start` used.
+    fn series(minutes: i64, start: u64, per_hour: u64, total: u64) -> Vec<(i64, u64, u64)> {
+        (0..minutes).map(|m| (1_700_000_000 + m * 60, start + per_hour * m as u64 / 60, total)).collect()
+    }
+
+    #[test]
+    fn test_forecast_linear_growth() {
+        // 1 GiB an hour with 24 GiB left
+        let samples = series(6 * 60, 50 * GIB, GIB, 80 * GIB);
+        let now = samples.last().unwrap().0;
+        let f = forecast("/", &samples, now);
+        assert!((f.growth_bytes_per_day.unwrap() / GIB as f64 - 24.0).abs() < 0.1);
+        let hours = f.hours_until_full.unwrap();
+        assert!((hours - 24.0).abs() < 0.2, "{}", hours);
+        assert!(f.full_at.unwrap() > now);
+        assert_eq!(f.used_bytes, samples.last().unwrap().1);
+    }
+
+    #[test]
+    fn test_forecast_ignores_outliers() {
+        let mut samples = series(6 * 60, 50 * GIB, GIB, 80 * GIB);
+        // A 20 GiB download that was removed again an hour later
+        for s in &mut samples[200..260] {
+            s.1 += 20 * GIB;
+        }
+        let hours = forecast("/", &samples, samples.last().unwrap().0).hours_until_full.unwrap();
+        assert!((hours - 24.0).abs() < 2.0, "{}", hours);
+    }
+
+    #[test]
+    fn test_forecast_needs_growth_and_history() {
+        let flat = series(6 * 60, 50 * GIB, 0, 80 * GIB);
+        let f = forecast("/data", &flat, flat.last().unwrap().0);
+        assert_eq!(f.growth_bytes_per_day, Some(0.0));
+        assert_eq!(f.full_at, None);
+
+        let short = series(30, 50 * GIB, GIB, 80 * GIB);
+        let f = forecast("/data", &short, short.last().unwrap().0);
+        assert_eq!((f.growth_bytes_per_day, f.hours_until_full, f.samples), (None, None, 30));
+        assert_eq!(forecast("/data", &[], 0).total_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_forecasts_from_history_and_alerts_once() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE metrics (id INTEGER PRIMARY KEY, cpu_usage REAL, memory_used INTEGER, memory_total INTEGER,
+                     disk_used INTEGER, disk_total INTEGER, timestamp INTEGER)")
+            .execute(&pool).await.unwrap();
+        let forecaster = DiskForecaster::new(
+            pool.clone(),
+            Arc::new(MetricsState::new()),
+            Arc::new(NotificationService::new()),
+            ForecastConfig { window: Duration::from_secs(24 * 3600), alert_hours: 48 },
+        );
+        forecaster.migrate().await.unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        // Outside the window, dropped by the next record
+        forecaster.record(&[MountUsage { mount: "/data".to_string(), used: 0, total: 100 * GIB }], now - 48 * 3600).await.unwrap();
+        for m in 0..120 {
+            let ts = now - (120 - m) * 60;
+            sqlx::query("INSERT INTO metrics (disk_used, disk_total, timestamp) VALUES (?, ?, ?)")
+                .bind((50 * GIB + GIB * m as u64 / 60) as i64).bind((80 * GIB) as i64).bind(ts)
+                .execute(&pool).await.unwrap();
+            let mount = MountUsage { mount: "/data".to_string(), used: 10 * GIB, total: 100 * GIB };
+            forecaster.record(&[mount], ts).await.unwrap();
+        }
+
+        let root = forecaster.forecasts().await.unwrap().remove(0);
+        assert_eq!(root.mount, "/");
+        assert!(root.hours_until_full.unwrap() < 48.0);
+        let data = forecast("/data", &forecaster.samples("/data", 0).await.unwrap(), now);
+        assert_eq!((data.samples, data.growth_bytes_per_day), (120, Some(0.0)));
+
+        let forecasts = [root, data];
+        assert_eq!(forecaster.due_alerts(&forecasts).len(), 1);
+        assert!(forecaster.due_alerts(&forecasts).is_empty());
+    }
+}
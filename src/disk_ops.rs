@@ -1,80 +1,177 @@
+use crate::docker::DockerService;
+use crate::retention::JournaldPolicy;
+use crate::util::{Visit, Walker};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
-use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct DiskUsageBreakdown {
-    pub target_size: String,
-    pub node_modules_size: String,
-    pub docker_size: String,
-    pub logs_size: String,
+/// A file reported by the large and old file finders.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FileEntry {
+    pub path: String,
+    /// Allocated size, as `du` counts it
+    pub bytes: u64,
+    /// Last modification, Unix seconds
+    pub modified: i64,
+    /// Inside one of the roots files may be deleted from
+    pub deletable: bool,
+}
+
+impl FileEntry {
+    pub fn new(path: &Path, meta: &std::fs::Metadata, delete_roots: &[PathBuf]) -> Self {
+        Self {
+            path: path.display().to_string(),
+            bytes: meta.blocks() * 512,
+            modified: meta.mtime(),
+            deletable: delete_roots.iter().any(|root| path.starts_with(root)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeleteResult {
+    pub path: String,
+    /// "deleted", "refused" or "failed"
+    pub status: String,
+    /// Space freed
+    pub bytes: u64,
+    pub error: Option<String>,
+}
+
+/// What `DiskOps::clean` can clean.
+pub const CLEAN_TARGETS: &[&str] = &["target", "node_modules", "docker", "logs"];
+
+/// journald storage, persistent and volatile.
+pub const JOURNAL_DIRS: &[&str] = &["/var/log/journal", "/run/log/journal"];
+
+/// How many entries of a cleaned directory a report lists.
+const REPORT_ITEMS: usize = 20;
+
+/// One thing a cleaner removes, e.g. a directory or the unused Docker images.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CleanItem {
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// What a cleaner removes, or would remove on a dry run, and how much space that frees.
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanReport {
+    pub target: String,
+    pub dry_run: bool,
+    /// Largest first; directories list at most `REPORT_ITEMS` entries
+    pub items: Vec<CleanItem>,
+    /// Estimate made before cleaning
+    pub reclaimable_bytes: u64,
+    /// Measured after cleaning; `None` on a dry run
+    pub freed_bytes: Option<u64>,
+    pub message: String,
 }
 
 pub struct DiskOps;
 
 impl DiskOps {
-    pub fn get_usage(root_dir: &str) -> DiskUsageBreakdown {
-        let target = Self::get_dir_size(&format!("{}/target", root_dir));
-        let node_modules = Self::get_dir_size(&format!("{}/frontend/node_modules", root_dir));
-        let logs = Self::get_logs_size();
-        let docker = Self::get_docker_size();
-
-        DiskUsageBreakdown {
-            target_size: target,
-            node_modules_size: node_modules,
-            docker_size: docker,
-            logs_size: logs,
-        }
-    }
-
-    fn get_dir_size(path: &str) -> String {
-        let output = Command::new("du")
-            .arg("-sh")
-            .arg(path)
-            .output();
-
-        match output {
-            Ok(o) if o.status.success() => {
-                let out = String::from_utf8_lossy(&o.stdout);
-                out.split_whitespace().next().unwrap_or("0B").to_string()
-            }
-            _ => "0B".to_string(),
-        }
-    }
-
-    fn get_logs_size() -> String {
-         let output = Command::new("journalctl")
-            .arg("--disk-usage")
-            .output();
-         // Output format: "Archived and active journals take up 16.0M in the file system."
-         // We need to parse this. Or just use du on /var/log/journal if we have access.
-         // Let's rely on journalctl output parsing for now, or just say "Unknown".
-         
-        match output {
-            Ok(o) if o.status.success() => {
-                let out = String::from_utf8_lossy(&o.stdout);
-                // Extract size string? It's human readable text.
-                // Simple regex or split?
-                // "take up 16.0M"
-                 out.split("take up ").nth(1)
-                    .and_then(|s| s.split(" ").next())
-                    .unwrap_or("Unknown")
-                    .to_string()
-            }
-            _ => "Unknown".to_string(),
+    /// Runs the cleaner for `target` (`target`, `node_modules`, `docker` or `logs`).
+    /// With `dry_run` nothing is removed and the report only carries the estimate.
+    pub async fn clean(
+        target: &str,
+        root_dir: &str,
+        docker: &DockerService,
+        journald: &JournaldPolicy,
+        dry_run: bool,
+    ) -> Result<CleanReport, String> {
+        match target {
+            "target" => Self::clean_target(root_dir, dry_run).await,
+            "node_modules" => Self::clean_node_modules(root_dir, dry_run).await,
+            "docker" => Self::clean_docker(docker, dry_run).await,
+            "logs" => Self::clean_logs(journald, dry_run).await,
+            _ => Err("Invalid target".to_string()),
         }
     }
 
-    fn get_docker_size() -> String {
-        // docker system df --format "{{.Size}}" prints multiple lines (Images, Containers, Volumes, Build Cache)
-        // We probably want a summary or just "Total".
-        // simpler: docker system df -v ? 
-        // Let's just run `du -sh /var/lib/docker` if we are root?
-        // Since we are running as root (currently), du /var/lib/docker is accurate.
-        
-        Self::get_dir_size("/var/lib/docker")
+    /// Files under `dirs` not modified for `older_than`, largest first.
+    pub fn find_old_files(dirs: &[PathBuf], older_than: Duration, limit: usize, delete_roots: &[PathBuf]) -> Vec<FileEntry> {
+        let cutoff = SystemTime::now()
+            .checked_sub(older_than)
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let mut found = Vec::new();
+        let mut walker = Walker::default();
+        for dir in dirs {
+            walker.walk(dir, &mut |visit| {
+                if let Visit::File(path, meta) = visit
+                    && meta.mtime() < cutoff
+                {
+                    found.push(FileEntry::new(path, meta, delete_roots));
+                }
+            });
+        }
+        found.sort_by_key(|f| std::cmp::Reverse(f.bytes));
+        found.truncate(limit);
+        found
     }
 
-    pub async fn clean_target(root_dir: &str) -> Result<String, String> {
+    /// Deletes regular files that live inside `allowed_roots`, each checked on its own.
+    pub fn delete_files(paths: &[String], allowed_roots: &[PathBuf]) -> Vec<DeleteResult> {
+        paths.iter().map(|path| {
+            let (status, bytes, error) = match Self::delete_file(Path::new(path), allowed_roots) {
+                Ok(bytes) => ("deleted", bytes, None),
+                Err((status, e)) => (status, 0, Some(e)),
+            };
+            DeleteResult { path: path.clone(), status: status.to_string(), bytes, error }
+        }).collect()
+    }
+
+    /// The directory is opened first and checked by where it really is (symlinks resolved),
+    /// then the file is checked and unlinked relative to that open directory, so replacing a
+    /// path component with a symlink between the check and the delete does not escape the roots.
+    fn delete_file(path: &Path, allowed_roots: &[PathBuf]) -> Result<u64, (&'static str, String)> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::io::AsRawFd;
+
+        if !path.is_absolute() || path.components().any(|c| matches!(c, Component::ParentDir | Component::CurDir)) {
+            return Err(("refused", "Path must be absolute and must not contain '.' or '..'".to_string()));
+        }
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(("refused", "Not a file path".to_string()));
+        };
+
+        let dir = std::fs::File::open(parent)
+            .map_err(|e| ("failed", format!("Cannot open {}: {}", parent.display(), e)))?;
+        let real_dir = std::fs::read_link(format!("/proc/self/fd/{}", dir.as_raw_fd()))
+            .map_err(|e| ("failed", format!("Cannot resolve {}: {}", parent.display(), e)))?;
+        if !allowed_roots.iter().any(|root| real_dir.starts_with(root)) {
+            return Err(("refused", format!("{} is outside the allowed roots", real_dir.join(name).display())));
+        }
+
+        let name = CString::new(name.as_bytes()).map_err(|_| ("refused", "Invalid file name".to_string()))?;
+        // SAFETY: `dir` is an open directory, `name` a NUL-terminated string and `stat` a writable buffer
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { libc::fstatat(dir.as_raw_fd(), name.as_ptr(), &mut stat, libc::AT_SYMLINK_NOFOLLOW) } != 0 {
+            return Err(("failed", std::io::Error::last_os_error().to_string()));
+        }
+        if stat.st_mode & libc::S_IFMT != libc::S_IFREG {
+            return Err(("refused", "Only regular files can be deleted, not symlinks or directories".to_string()));
+        }
+        if unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), 0) } != 0 {
+            return Err(("failed", std::io::Error::last_os_error().to_string()));
+        }
+        Ok(stat.st_blocks as u64 * 512)
+    }
+
+    pub async fn clean_target(root_dir: &str, dry_run: bool) -> Result<CleanReport, String> {
+        let dir = Path::new(root_dir).join("target");
+        let items = measure_entries(dir.clone()).await;
+        let reclaimable: u64 = items.iter().map(|i| i.bytes).sum();
+        if dry_run {
+            return Ok(report("target", items, reclaimable, None, format!("cargo clean would free {}", format_bytes(reclaimable))));
+        }
+
         let output = Command::new("cargo")
             .arg("clean")
             .current_dir(root_dir)
@@ -82,63 +179,356 @@ impl DiskOps {
             .map_err(|e| e.to_string())?;
 
         if output.status.success() {
-             Ok("Target cleaned (cargo clean executed).".to_string())
+            let freed = reclaimable.saturating_sub(measure(dir).await);
+            Ok(report("target", items, reclaimable, Some(freed), format!("Target cleaned (cargo clean executed), freed {}.", format_bytes(freed))))
         } else {
-             Err(String::from_utf8_lossy(&output.stderr).to_string())
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
         }
     }
 
-    pub async fn clean_node_modules(root_dir: &str) -> Result<String, String> {
-        let path = format!("{}/frontend/node_modules", root_dir);
+    pub async fn clean_node_modules(root_dir: &str, dry_run: bool) -> Result<CleanReport, String> {
+        let dir = Path::new(root_dir).join("frontend/node_modules");
+        let items = measure_entries(dir.clone()).await;
+        let reclaimable: u64 = items.iter().map(|i| i.bytes).sum();
+        if dry_run {
+            return Ok(report("node_modules", items, reclaimable, None, format!("Deleting node_modules would free {}", format_bytes(reclaimable))));
+        }
+
         let output = Command::new("rm")
             .arg("-rf")
-            .arg(path)
+            .arg(&dir)
             .output()
             .map_err(|e| e.to_string())?;
-            
+
         if output.status.success() {
-             Ok("Node modules deleted.".to_string())
+            let freed = reclaimable.saturating_sub(measure(dir).await);
+            Ok(report("node_modules", items, reclaimable, Some(freed), format!("Node modules deleted, freed {}.", format_bytes(freed))))
         } else {
-             Err(String::from_utf8_lossy(&output.stderr).to_string())
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
+        }
+    }
+
+    /// The estimate comes from Docker's disk usage API and the freed space from the prune responses.
+    pub async fn clean_docker(docker: &DockerService, dry_run: bool) -> Result<CleanReport, String> {
+        let estimate = docker.reclaimable_space().await?;
+        let reclaimable = estimate.total();
+        let mut items = vec![
+            CleanItem { name: "Unused images".to_string(), bytes: estimate.images },
+            CleanItem { name: "Stopped containers".to_string(), bytes: estimate.containers },
+            CleanItem { name: "Build cache".to_string(), bytes: estimate.build_cache },
+        ];
+        items.sort_by_key(|i| std::cmp::Reverse(i.bytes));
+        if dry_run {
+            return Ok(report("docker", items, reclaimable, None, format!("Docker prune would free {}", format_bytes(reclaimable))));
         }
+
+        let freed = docker.prune_system().await?.total();
+        Ok(report("docker", items, reclaimable, Some(freed), format!("Docker prune finished, freed {}.", format_bytes(freed))))
     }
 
-    pub async fn clean_docker() -> Result<String, String> {
-         let output = Command::new("docker")
-            .arg("system")
-            .arg("prune")
-            .arg("-af") // -a: all unused images, -f: force
+    /// `journalctl --vacuum-*` only removes archived journal files, so the estimate is the size
+    /// of the archived files the policy's limits would remove.
+    pub async fn clean_logs(policy: &JournaldPolicy, dry_run: bool) -> Result<CleanReport, String> {
+        let dirs: Vec<PathBuf> = JOURNAL_DIRS.iter().map(PathBuf::from).collect();
+        let items = tokio::task::spawn_blocking({
+            let (dirs, policy) = (dirs.clone(), policy.clone());
+            move || journal_vacuum_plan(&dirs, &policy, SystemTime::now())
+        }).await.unwrap_or_default();
+        let reclaimable: u64 = items.iter().map(|i| i.bytes).sum();
+        if dry_run {
+            return Ok(report("logs", items, reclaimable, None, format!("Vacuuming journald logs would free {}", format_bytes(reclaimable))));
+        }
+
+        let before = measure_all(dirs.clone()).await;
+        let output = Command::new("journalctl")
+            .args(policy.vacuum_args())
             .output()
             .map_err(|e| e.to_string())?;
 
         if output.status.success() {
-             let out = String::from_utf8_lossy(&output.stdout).to_string();
-             if out.trim().is_empty() {
-                 Ok("Docker Prune finished (No output).".to_string())
-             } else {
-                 Ok(out)
-             }
+            let freed = before.saturating_sub(measure_all(dirs).await);
+            let out = String::from_utf8_lossy(&output.stderr).to_string(); // Journalctl prints to stderr
+            let message = if out.trim().is_empty() {
+                format!("Logs vacuumed, freed {}.", format_bytes(freed))
+            } else {
+                format!("{}\nFreed {}.", out.trim(), format_bytes(freed))
+            };
+            Ok(report("logs", items, reclaimable, Some(freed), message))
         } else {
-             Err(String::from_utf8_lossy(&output.stderr).to_string())
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
         }
     }
-    
-    pub async fn clean_logs() -> Result<String, String> {
-         let output = Command::new("journalctl")
-            .arg("--vacuum-time=1d")
-            .output()
-            .map_err(|e| e.to_string())?;
+}
 
-        if output.status.success() {
-         let out = String::from_utf8_lossy(&output.stderr).to_string(); // Journalctl prints to stderr
-         if out.trim().is_empty() {
-              Ok("Logs vacuumed (No output).".to_string())
-         } else {
-              Ok(out)
-         }
+fn report(target: &str, items: Vec<CleanItem>, reclaimable_bytes: u64, freed_bytes: Option<u64>, message: String) -> CleanReport {
+    CleanReport {
+        target: target.to_string(),
+        dry_run: freed_bytes.is_none(),
+        items,
+        reclaimable_bytes,
+        freed_bytes,
+        message,
+    }
+}
+
+/// `1536` -> `1.5 KiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
     } else {
-         Err(String::from_utf8_lossy(&output.stderr).to_string())
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Allocated size of everything below `path` (or of `path` itself when it is a file).
+fn tree_bytes(path: &Path) -> u64 {
+    let mut bytes = 0;
+    Walker::default().walk(path, &mut |visit| {
+        if let Visit::Enter(_, meta) | Visit::File(_, meta) = visit {
+            bytes += meta.blocks() * 512;
+        }
+    });
+    bytes
+}
+
+async fn measure(path: PathBuf) -> u64 {
+    tokio::task::spawn_blocking(move || tree_bytes(&path)).await.unwrap_or(0)
+}
+
+async fn measure_all(paths: Vec<PathBuf>) -> u64 {
+    tokio::task::spawn_blocking(move || paths.iter().map(|p| tree_bytes(p)).sum()).await.unwrap_or(0)
+}
+
+/// Sizes of the entries directly inside `dir`: the largest `REPORT_ITEMS` and the
+/// rest summed up in one item.
+async fn measure_entries(dir: PathBuf) -> Vec<CleanItem> {
+    tokio::task::spawn_blocking(move || {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+        let mut items: Vec<CleanItem> = entries.flatten()
+            .map(|e| CleanItem { name: e.path().display().to_string(), bytes: tree_bytes(&e.path()) })
+            .collect();
+        items.sort_by_key(|i| std::cmp::Reverse(i.bytes));
+        if items.len() > REPORT_ITEMS {
+            let rest = items.split_off(REPORT_ITEMS);
+            items.push(CleanItem {
+                name: format!("{} more", rest.len()),
+                bytes: rest.iter().map(|i| i.bytes).sum(),
+            });
+        }
+        items
+    }).await.unwrap_or_default()
+}
+
+/// Archived journal files (`system@...journal`, `*.journal~`) that vacuuming with `policy` removes:
+/// those not written to for `max_age_days`, then the oldest until all journal files fit in `max_size_bytes`.
+fn journal_vacuum_plan(dirs: &[PathBuf], policy: &JournaldPolicy, now: SystemTime) -> Vec<CleanItem> {
+    let mut total = 0;
+    let mut archived = Vec::new();
+    let mut walker = Walker::default();
+    for dir in dirs {
+        walker.walk(dir, &mut |visit| {
+            let Visit::File(path, meta) = visit else {
+                return;
+            };
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !name.ends_with(".journal") && !name.ends_with(".journal~") {
+                return;
+            }
+            let bytes = meta.blocks() * 512;
+            total += bytes;
+            if name.contains('@') || name.ends_with(".journal~") {
+                let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                archived.push((modified, CleanItem { name: path.display().to_string(), bytes }));
+            }
+        });
+    }
+    archived.sort_by_key(|(modified, _)| *modified);
+
+    let cutoff = policy.max_age_days.map(|days| now - Duration::from_secs(days as u64 * 86400));
+    let mut items = Vec::new();
+    for (modified, item) in archived {
+        let too_old = cutoff.is_some_and(|cutoff| modified < cutoff);
+        let over_size = policy.max_size_bytes.is_some_and(|max| total > max);
+        if too_old || over_size {
+            total -= item.bytes;
+            items.push(item);
+        }
+    }
+    items.sort_by_key(|i| std::cmp::Reverse(i.bytes));
+    items
+}
+
+/// Every file deletion attempt, kept in the `file_deletions` table.
+pub struct DeletionAudit {
+    db: SqlitePool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletionRecord {
+    pub id: i64,
+    /// Unix timestamp (seconds)
+    pub timestamp: i64,
+    pub path: String,
+    pub bytes: u64,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+impl DeletionAudit {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS file_deletions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                bytes INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                error TEXT
+            )"
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record(&self, results: &[DeleteResult]) {
+        let now = chrono::Utc::now().timestamp();
+        for r in results {
+            let saved = sqlx::query(
+                "INSERT INTO file_deletions (timestamp, path, bytes, status, error) VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(now)
+            .bind(&r.path)
+            .bind(r.bytes as i64)
+            .bind(&r.status)
+            .bind(&r.error)
+            .execute(&self.db)
+            .await;
+            if let Err(e) = saved {
+                tracing::error!("Failed to record deletion of {}: {}", r.path, e);
+            }
+        }
+    }
+
+    /// Newest first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<DeletionRecord>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, timestamp, path, bytes, status, error FROM file_deletions ORDER BY id DESC LIMIT ?"
+        )
+        .bind(limit.clamp(1, 1000))
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows.iter().map(|row| DeletionRecord {
+            id: row.get("id"),
+            timestamp: row.get("timestamp"),
+            path: row.get("path"),
+            bytes: row.get::<i64, _>("bytes") as u64,
+            status: row.get("status"),
+            error: row.get("error"),
+        }).collect())
+    }
+}
+
+/// Finished cleaner runs with the space they freed, kept in the `disk_cleanups` table.
+pub struct CleanupHistory {
+    db: SqlitePool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanupRecord {
+    pub id: i64,
+    /// Unix timestamp (seconds)
+    pub timestamp: i64,
+    pub target: String,
+    /// `manual` or `job`
+    pub triggered_by: String,
+    /// `success` or `failed`
+    pub status: String,
+    pub reclaimable_bytes: u64,
+    pub freed_bytes: u64,
+    pub message: String,
+}
+
+impl CleanupHistory {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS disk_cleanups (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                target TEXT NOT NULL,
+                triggered_by TEXT NOT NULL,
+                status TEXT NOT NULL,
+                reclaimable_bytes INTEGER NOT NULL,
+                freed_bytes INTEGER NOT NULL,
+                message TEXT NOT NULL
+            )"
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Records a real run; dry runs are not kept.
+    pub async fn record(&self, target: &str, triggered_by: &str, result: &Result<CleanReport, String>) {
+        let (status, reclaimable, freed, message) = match result {
+            Ok(r) if r.dry_run => return,
+            Ok(r) => ("success", r.reclaimable_bytes, r.freed_bytes.unwrap_or(0), r.message.as_str()),
+            Err(e) => ("failed", 0, 0, e.as_str()),
+        };
+        let saved = sqlx::query(
+            "INSERT INTO disk_cleanups (timestamp, target, triggered_by, status, reclaimable_bytes, freed_bytes, message)
+             VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(chrono::Utc::now().timestamp())
+        .bind(target)
+        .bind(triggered_by)
+        .bind(status)
+        .bind(reclaimable as i64)
+        .bind(freed as i64)
+        .bind(message)
+        .execute(&self.db)
+        .await;
+        if let Err(e) = saved {
+            tracing::error!("Failed to record {} cleanup: {}", target, e);
+        }
     }
+
+    /// Newest first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<CleanupRecord>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, timestamp, target, triggered_by, status, reclaimable_bytes, freed_bytes, message
+             FROM disk_cleanups ORDER BY id DESC LIMIT ?"
+        )
+        .bind(limit.clamp(1, 1000))
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows.iter().map(|row| CleanupRecord {
+            id: row.get("id"),
+            timestamp: row.get("timestamp"),
+            target: row.get("target"),
+            triggered_by: row.get("triggered_by"),
+            status: row.get("status"),
+            reclaimable_bytes: row.get::<i64, _>("reclaimable_bytes") as u64,
+            freed_bytes: row.get::<i64, _>("freed_bytes") as u64,
+            message: row.get("message"),
+        }).collect())
     }
 }
 
@@ -167,19 +557,156 @@ mod tests {
         assert!(!root.contains('&'));
     }
 
+    #[tokio::test]
+    async fn test_clean_rejects_unknown_target() {
+        let docker = DockerService::new();
+        assert_eq!(DiskOps::clean("/", ".", &docker, &JournaldPolicy::default(), true).await.unwrap_err(), "Invalid target");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_without_removing() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("frontend/node_modules/react")).unwrap();
+        std::fs::create_dir_all(root.join("frontend/node_modules/vite")).unwrap();
+        std::fs::write(root.join("frontend/node_modules/react/index.js"), vec![0u8; 50_000]).unwrap();
+        std::fs::write(root.join("frontend/node_modules/vite/index.js"), vec![0u8; 10_000]).unwrap();
+
+        let report = DiskOps::clean_node_modules(root.to_str().unwrap(), true).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.freed_bytes, None);
+        assert!(report.reclaimable_bytes >= 60_000);
+        assert_eq!(report.items[0].name, root.join("frontend/node_modules/react").display().to_string());
+        assert!(root.join("frontend/node_modules/react/index.js").exists());
+
+        let report = DiskOps::clean_node_modules(root.to_str().unwrap(), false).await.unwrap();
+        assert_eq!(report.freed_bytes, Some(report.reclaimable_bytes));
+        assert!(!root.join("frontend/node_modules").exists());
+    }
+
     #[test]
-    fn test_disk_usage_breakdown_has_all_fields() {
-        // DiskUsageBreakdown должен сериализоваться без ошибок
-        let breakdown = DiskUsageBreakdown {
-            target_size: "100M".to_string(),
-            node_modules_size: "500M".to_string(),
-            docker_size: "2G".to_string(),
-            logs_size: "50M".to_string(),
+    fn test_archived_journals_are_reclaimable() {
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        let old = SystemTime::now() - Duration::from_secs(3 * 86400);
+        for name in ["system.journal", "system@0005f-0001.journal", "user-1000@0005f-0002.journal~", "fresh@0005f-0003.journal"] {
+            std::fs::write(dir.join(name), vec![0u8; 8192]).unwrap();
+            if !name.starts_with("fresh") {
+                std::fs::File::options().write(true).open(dir.join(name)).unwrap().set_modified(old).unwrap();
+            }
+        }
+        let plan = |max_age_days, max_size_bytes| {
+            let policy = JournaldPolicy { max_age_days, max_size_bytes };
+            let mut names: Vec<String> = journal_vacuum_plan(&[dir.to_path_buf()], &policy, SystemTime::now())
+                .into_iter()
+                .map(|i| i.name.rsplit('/').next().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
         };
-        let json = serde_json::to_string(&breakdown).unwrap();
-        assert!(json.contains("target_size"));
-        assert!(json.contains("node_modules_size"));
-        assert!(json.contains("docker_size"));
-        assert!(json.contains("logs_size"));
+        assert_eq!(plan(Some(1), None), ["system@0005f-0001.journal", "user-1000@0005f-0002.journal~"]);
+        // Four files of 8 KiB; the active one is never removed
+        assert_eq!(plan(None, Some(20_000)), ["system@0005f-0001.journal", "user-1000@0005f-0002.journal~"]);
+        assert_eq!(plan(None, Some(1)).len(), 3);
+        assert!(plan(Some(7), Some(1 << 30)).is_empty());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_history_keeps_real_runs() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let history = CleanupHistory::new(pool);
+        history.migrate().await.unwrap();
+        let run = |dry_run: bool| Ok(CleanReport {
+            target: "docker".to_string(),
+            dry_run,
+            items: Vec::new(),
+            reclaimable_bytes: 1000,
+            freed_bytes: (!dry_run).then_some(900),
+            message: "done".to_string(),
+        });
+        history.record("docker", "manual", &run(true)).await;
+        history.record("docker", "job", &run(false)).await;
+        history.record("logs", "manual", &Err("Permission denied".to_string())).await;
+
+        let records = history.list(10).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].target.as_str(), records[0].status.as_str()), ("logs", "failed"));
+        assert_eq!((records[1].triggered_by.as_str(), records[1].freed_bytes), ("job", 900));
+    }
+
+    /// Paths in the results are canonical, so the directory is created under the canonical temp dir.
+    fn temp_dir() -> tempfile::TempDir {
+        tempfile::tempdir_in(std::fs::canonicalize(std::env::temp_dir()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_delete_files_stays_inside_allowed_roots() {
+        let base_tmp = temp_dir();
+        let base = base_tmp.path();
+        let allowed = base.join("logs");
+        let outside = base.join("etc");
+        std::fs::create_dir_all(allowed.join("app")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(allowed.join("app/old.log"), vec![0u8; 10_000]).unwrap();
+        std::fs::write(outside.join("passwd"), "root").unwrap();
+        // A symlinked directory and a symlinked file pointing out of the allowed root
+        std::os::unix::fs::symlink(&outside, allowed.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.join("passwd"), allowed.join("passwd-link")).unwrap();
+
+        let paths: Vec<String> = [
+            allowed.join("app/old.log"),
+            allowed.join("escape/passwd"),
+            allowed.join("passwd-link"),
+            allowed.join("app"),
+            outside.join("passwd"),
+            allowed.join("app/../../etc/passwd"),
+            allowed.join("missing.log"),
+        ].iter().map(|p| p.display().to_string()).collect();
+        let results = DiskOps::delete_files(&paths, std::slice::from_ref(&allowed));
+        let statuses: Vec<&str> = results.iter().map(|r| r.status.as_str()).collect();
+        assert_eq!(statuses, ["deleted", "refused", "refused", "refused", "refused", "refused", "failed"]);
+        assert!(results[0].bytes >= 10_000);
+
+        assert!(!allowed.join("app/old.log").exists());
+        assert_eq!(std::fs::read_to_string(outside.join("passwd")).unwrap(), "root");
+        assert!(allowed.join("passwd-link").symlink_metadata().is_ok());
+    }
+
+    #[test]
+    fn test_find_old_files() {
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("nginx")).unwrap();
+        std::fs::write(dir.join("nginx/access.log.1"), vec![0u8; 20_000]).unwrap();
+        std::fs::write(dir.join("fresh.log"), vec![0u8; 30_000]).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(40 * 86400);
+        std::fs::File::options().write(true).open(dir.join("nginx/access.log.1")).unwrap().set_modified(old).unwrap();
+
+        let found = DiskOps::find_old_files(&[dir.to_path_buf()], Duration::from_secs(30 * 86400), 10, &[dir.to_path_buf()]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, dir.join("nginx/access.log.1").display().to_string());
+        assert!(found[0].deletable);
+        assert!(!DiskOps::find_old_files(&[dir.to_path_buf()], Duration::from_secs(30 * 86400), 10, &[])[0].deletable);
+    }
+
+    #[tokio::test]
+    async fn test_deletions_are_audited() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let audit = DeletionAudit::new(pool);
+        audit.migrate().await.unwrap();
+        audit.record(&DiskOps::delete_files(&["relative/path".to_string()], &[])).await;
+
+        let records = audit.list(10).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].path, "relative/path");
+        assert_eq!(records[0].status, "refused");
+        assert!(records[0].error.is_some());
     }
 }
@@ -0,0 +1,616 @@
+use crate::disk_ops::{DiskOps, FileEntry, JOURNAL_DIRS};
+use crate::util::{Visit, Walker};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Where to scan and which paths make up the cleanable categories.
+#[derive(Debug, Clone)]
+pub struct DiskScanConfig {
+    pub roots: Vec<PathBuf>,
+    /// Checkout the `target` / `node_modules` cleaners work on
+    pub project_dir: PathBuf,
+    pub docker_dir: PathBuf,
+    pub journal_dirs: Vec<PathBuf>,
+    /// Searched for old files
+    pub log_dirs: Vec<PathBuf>,
+    /// Files may only be deleted from below these
+    pub delete_roots: Vec<PathBuf>,
+    /// Older scans are refreshed in the background
+    pub max_age: Duration,
+}
+
+impl DiskScanConfig {
+    /// `DISK_SCAN_ROOTS` (comma-separated, default `DEFAULT_SCAN_ROOTS`), `DISK_SCAN_MAX_AGE` (seconds, default 900),
+    /// `DISK_LOG_DIRS` (default `/var/log`) and `DISK_DELETE_ROOTS` (default `/var/log,/tmp,/var/tmp`).
+    pub fn from_env() -> Self {
+        let paths = |var: &str, default: &str| -> Vec<PathBuf> {
+            std::env::var(var).unwrap_or_else(|_| default.to_string())
+                .split(',')
+                .map(str::trim)
+                .filter(|r| !r.is_empty())
+                .map(|r| std::fs::canonicalize(r).unwrap_or_else(|_| PathBuf::from(r)))
+                .collect()
+        };
+        let max_age = std::env::var("DISK_SCAN_MAX_AGE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(900u64);
+        Self {
+            roots: paths("DISK_SCAN_ROOTS", DEFAULT_SCAN_ROOTS),
+            project_dir: PathBuf::from("."),
+            docker_dir: PathBuf::from("/var/lib/docker"),
+            journal_dirs: JOURNAL_DIRS.iter().map(PathBuf::from).collect(),
+            log_dirs: paths("DISK_LOG_DIRS", "/var/log"),
+            delete_roots: paths("DISK_DELETE_ROOTS", "/var/log,/tmp,/var/tmp"),
+            max_age: Duration::from_secs(max_age),
+        }
+    }
+}
+
+/// Where data usually piles up, including Docker's data root. Scanning all of `/`
+/// takes long and mostly finds the OS itself.
+const DEFAULT_SCAN_ROOTS: &str = "/var,/home,/opt,/srv,/root";
+
+/// How many of the largest files a scan remembers.
+const MAX_LARGE_FILES: usize = 500;
+
+/// Directories deeper than this below a root are counted into their parent but
+/// not kept, so the cached tree stays small.
+const MAX_TREE_DEPTH: usize = 8;
+
+/// One directory of a scan with everything below it summed up.
+#[derive(Debug, Clone)]
+struct DirNode {
+    name: String,
+    bytes: u64,
+    files: u64,
+    /// Largest first
+    children: Vec<DirNode>,
+}
+
+impl DirNode {
+    fn find(&self, rel: &Path) -> Option<&DirNode> {
+        rel.components().try_fold(self, |node, part| {
+            let name = part.as_os_str().to_string_lossy();
+            node.children.iter().find(|c| c.name == name)
+        })
+    }
+
+    fn to_usage(&self, path: &Path, depth: usize, limit: usize) -> DirUsage {
+        DirUsage {
+            path: path.display().to_string(),
+            bytes: self.bytes,
+            files: self.files,
+            children: if depth == 0 {
+                Vec::new()
+            } else {
+                self.children.iter()
+                    .take(limit)
+                    .map(|c| c.to_usage(&path.join(&c.name), depth - 1, limit))
+                    .collect()
+            },
+        }
+    }
+}
+
+/// Allocated size of a directory tree, as `du` counts it.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirUsage {
+    pub path: String,
+    pub bytes: u64,
+    pub files: u64,
+    /// Largest subdirectories first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DirUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsageBreakdown {
+    pub target_bytes: u64,
+    pub node_modules_bytes: u64,
+    pub docker_bytes: u64,
+    pub logs_bytes: u64,
+    pub roots: Vec<DirUsage>,
+    /// Unix timestamp (seconds) of the scan these numbers come from
+    pub scanned_at: i64,
+    pub age_secs: u64,
+    pub scan_duration_ms: u64,
+    /// Entries that could not be read, usually for lack of permissions
+    pub errors: u64,
+    /// A newer scan is running in the background
+    pub scanning: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LargeFiles {
+    /// Largest files under the scan roots
+    pub largest: Vec<FileEntry>,
+    /// Files under the log directories not modified for `older_than_days`, largest first
+    pub old_logs: Vec<FileEntry>,
+    pub older_than_days: u64,
+    pub deletable_roots: Vec<String>,
+    /// Unix timestamp (seconds) of the scan `largest` comes from
+    pub scanned_at: i64,
+    pub age_secs: u64,
+    pub scanning: bool,
+}
+
+struct DiskScan {
+    roots: Vec<(PathBuf, DirNode)>,
+    target_bytes: u64,
+    node_modules_bytes: u64,
+    docker_bytes: u64,
+    logs_bytes: u64,
+    /// Largest first: (bytes, mtime, path)
+    largest_files: Vec<(u64, i64, PathBuf)>,
+    scanned_at: i64,
+    finished: Instant,
+    duration: Duration,
+    errors: u64,
+}
+
+impl DiskScan {
+    /// Stands in until the first scan finishes: no roots, nothing counted.
+    fn pending() -> Self {
+        Self {
+            roots: Vec::new(),
+            target_bytes: 0,
+            node_modules_bytes: 0,
+            docker_bytes: 0,
+            logs_bytes: 0,
+            largest_files: Vec::new(),
+            scanned_at: 0,
+            finished: Instant::now(),
+            duration: Duration::ZERO,
+            errors: 0,
+        }
+    }
+
+    fn find(&self, path: &Path) -> Option<(&Path, &DirNode)> {
+        self.roots.iter().find_map(|(root, node)| {
+            let rel = path.strip_prefix(root).ok()?;
+            Some((root.as_path(), node.find(rel)?))
+        })
+    }
+}
+
+/// Sums walked trees up into `DirNode`s and remembers the largest files seen.
+#[derive(Default)]
+struct Scanner {
+    walker: Walker,
+    /// The `MAX_LARGE_FILES` largest files so far, smallest on top
+    largest: BinaryHeap<Reverse<(u64, i64, PathBuf)>>,
+}
+
+impl Scanner {
+    fn walk(&mut self, root: &Path) -> Option<DirNode> {
+        let mut stack: Vec<DirNode> = Vec::new();
+        let mut tree = None;
+        let Self { walker, largest } = self;
+        walker.walk(root, &mut |visit| match visit {
+            Visit::Enter(path, meta) => {
+                let name = if stack.is_empty() {
+                    path.display().to_string()
+                } else {
+                    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+                };
+                stack.push(DirNode { name, bytes: meta.blocks() * 512, files: 0, children: Vec::new() });
+            }
+            Visit::File(path, meta) => {
+                let bytes = meta.blocks() * 512;
+                match stack.last_mut() {
+                    Some(dir) => {
+                        dir.bytes += bytes;
+                        dir.files += 1;
+                    }
+                    None => tree = Some(DirNode { name: path.display().to_string(), bytes, files: 1, children: Vec::new() }),
+                }
+                track_file(largest, bytes, meta, path);
+            }
+            Visit::Leave => {
+                let Some(mut node) = stack.pop() else { return };
+                node.children.sort_by_key(|c| Reverse(c.bytes));
+                // The parent sits `stack.len() - 1` levels below the root
+                let keep = stack.len() <= MAX_TREE_DEPTH;
+                match stack.last_mut() {
+                    Some(parent) => {
+                        parent.bytes += node.bytes;
+                        parent.files += node.files;
+                        if keep {
+                            parent.children.push(node);
+                        }
+                    }
+                    None => tree = Some(node),
+                }
+            }
+        });
+        tree
+    }
+}
+
+/// Keeps `path` when it is among the `MAX_LARGE_FILES` largest regular files so far.
+fn track_file(largest: &mut BinaryHeap<Reverse<(u64, i64, PathBuf)>>, bytes: u64, meta: &std::fs::Metadata, path: &Path) {
+    if !meta.is_file() {
+        return;
+    }
+    if largest.len() >= MAX_LARGE_FILES {
+        match largest.peek() {
+            Some(Reverse((smallest, _, _))) if bytes > *smallest => {
+                largest.pop();
+            }
+            _ => return,
+        }
+    }
+    largest.push(Reverse((bytes, meta.mtime(), path.to_path_buf())));
+}
+
+fn run_scan(config: &DiskScanConfig) -> DiskScan {
+    let started = Instant::now();
+    let mut scanner = Scanner::default();
+    let roots: Vec<(PathBuf, DirNode)> = config.roots.iter()
+        .filter_map(|root| scanner.walk(root).map(|node| (root.clone(), node)))
+        .collect();
+    let mut scan = DiskScan {
+        roots,
+        target_bytes: 0,
+        node_modules_bytes: 0,
+        docker_bytes: 0,
+        logs_bytes: 0,
+        largest_files: std::mem::take(&mut scanner.largest).into_sorted_vec().into_iter().map(|Reverse(f)| f).collect(),
+        scanned_at: chrono::Utc::now().timestamp(),
+        finished: started,
+        duration: Duration::ZERO,
+        errors: scanner.walker.errors,
+    };
+
+    // Categories inside a scan root come from the tree, the rest are walked on their own
+    let mut size_of = |path: &Path| -> u64 {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some((_, node)) = scan.find(&path) {
+            return node.bytes;
+        }
+        let mut scanner = Scanner::default();
+        let bytes = scanner.walk(&path).map(|n| n.bytes).unwrap_or(0);
+        scan.errors += scanner.walker.errors;
+        bytes
+    };
+    let target_bytes = size_of(&config.project_dir.join("target"));
+    let node_modules_bytes = size_of(&config.project_dir.join("frontend/node_modules"));
+    let docker_bytes = size_of(&config.docker_dir);
+    let logs_bytes = config.journal_dirs.iter().map(|d| size_of(d)).sum();
+
+    scan.target_bytes = target_bytes;
+    scan.node_modules_bytes = node_modules_bytes;
+    scan.docker_bytes = docker_bytes;
+    scan.logs_bytes = logs_bytes;
+    scan.finished = Instant::now();
+    scan.duration = started.elapsed();
+    scan
+}
+
+/// Native disk usage scanner. Walks run on the blocking pool and results are cached;
+/// stale results are served while a fresh scan runs in the background.
+pub struct DiskUsageService {
+    config: DiskScanConfig,
+    cache: RwLock<Option<Arc<DiskScan>>>,
+    scan_lock: tokio::sync::Mutex<()>,
+    scanning: AtomicBool,
+    stale: AtomicBool,
+}
+
+impl DiskUsageService {
+    pub fn new(config: DiskScanConfig) -> Self {
+        Self {
+            config,
+            cache: RwLock::new(None),
+            scan_lock: tokio::sync::Mutex::new(()),
+            scanning: AtomicBool::new(false),
+            stale: AtomicBool::new(false),
+        }
+    }
+
+    /// Marks the cached numbers as outdated, e.g. after a cleanup; the next request starts a rescan.
+    pub fn mark_stale(&self) {
+        self.stale.store(true, Ordering::Relaxed);
+    }
+
+    /// The cached scan, refreshed in the background when outdated. Before the first scan
+    /// finishes an empty one is returned, so only `refresh` waits for a walk.
+    async fn scan(self: &Arc<Self>, refresh: bool) -> Arc<DiskScan> {
+        if refresh {
+            return self.rescan().await;
+        }
+        let cached = self.cache.read().unwrap().clone();
+        let outdated = cached.as_ref()
+            .is_none_or(|scan| scan.finished.elapsed() >= self.config.max_age || self.stale.load(Ordering::Relaxed));
+        if outdated && !self.scanning.swap(true, Ordering::Relaxed) {
+            let this = self.clone();
+            tokio::spawn(async move { this.rescan().await });
+        }
+        cached.unwrap_or_else(|| Arc::new(DiskScan::pending()))
+    }
+
+    async fn rescan(&self) -> Arc<DiskScan> {
+        let requested = Instant::now();
+        let _lock = self.scan_lock.lock().await;
+        // A scan that finished while this one waited for the lock is fresh enough
+        let cached = self.cache.read().unwrap().clone();
+        if let Some(scan) = cached
+            && scan.finished >= requested
+        {
+            self.scanning.store(false, Ordering::Relaxed);
+            return scan;
+        }
+
+        self.scanning.store(true, Ordering::Relaxed);
+        self.stale.store(false, Ordering::Relaxed);
+        let config = self.config.clone();
+        let scan = tokio::task::spawn_blocking(move || run_scan(&config)).await;
+        self.scanning.store(false, Ordering::Relaxed);
+
+        let scan = Arc::new(scan.unwrap_or_else(|e| {
+            tracing::error!("Disk scan failed: {}", e);
+            run_scan(&DiskScanConfig { roots: Vec::new(), ..self.config.clone() })
+        }));
+        tracing::debug!("Disk scan finished in {:?}", scan.duration);
+        *self.cache.write().unwrap() = Some(scan.clone());
+        scan
+    }
+
+    pub async fn usage(self: &Arc<Self>, refresh: bool) -> DiskUsageBreakdown {
+        let scan = self.scan(refresh).await;
+        DiskUsageBreakdown {
+            target_bytes: scan.target_bytes,
+            node_modules_bytes: scan.node_modules_bytes,
+            docker_bytes: scan.docker_bytes,
+            logs_bytes: scan.logs_bytes,
+            roots: scan.roots.iter().map(|(path, node)| node.to_usage(path, 0, 0)).collect(),
+            scanned_at: scan.scanned_at,
+            age_secs: scan.finished.elapsed().as_secs(),
+            scan_duration_ms: scan.duration.as_millis() as u64,
+            errors: scan.errors,
+            scanning: self.scanning.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Largest directories under `path` (a scan root by default), `depth` levels down with
+    /// at most `limit` entries per level. `None` when `path` is not inside a scan root.
+    pub async fn largest_dirs(self: &Arc<Self>, path: Option<&str>, depth: usize, limit: usize) -> Option<DirUsage> {
+        let scan = self.scan(false).await;
+        let path = match path {
+            Some(p) => PathBuf::from(p),
+            None => scan.roots.first()?.0.clone(),
+        };
+        // Normalizes `/var/` and `/var/./log`; `..` is not resolved and simply finds nothing
+        let path: PathBuf = path.components().collect();
+        let (_, node) = scan.find(&path)?;
+        Some(node.to_usage(&path, depth, limit))
+    }
+
+    /// The `limit` largest files of the cached scan, and the `limit` largest files under the log
+    /// directories that were not modified for `older_than_days` (looked up fresh each time).
+    pub async fn large_files(self: &Arc<Self>, limit: usize, older_than_days: u64) -> LargeFiles {
+        let scan = self.scan(false).await;
+        let roots = &self.config.delete_roots;
+        let largest = scan.largest_files.iter()
+            .take(limit)
+            .map(|(bytes, modified, path)| FileEntry {
+                path: path.display().to_string(),
+                bytes: *bytes,
+                modified: *modified,
+                deletable: roots.iter().any(|root| path.starts_with(root)),
+            })
+            .collect();
+
+        let (dirs, roots_owned) = (self.config.log_dirs.clone(), roots.clone());
+        let older_than = Duration::from_secs(older_than_days * 86400);
+        let old_logs = tokio::task::spawn_blocking(move || DiskOps::find_old_files(&dirs, older_than, limit, &roots_owned))
+            .await
+            .unwrap_or_default();
+
+        LargeFiles {
+            largest,
+            old_logs,
+            older_than_days,
+            deletable_roots: roots.iter().map(|r| r.display().to_string()).collect(),
+            scanned_at: scan.scanned_at,
+            age_secs: scan.finished.elapsed().as_secs(),
+            scanning: self.scanning.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn delete_roots(&self) -> &[PathBuf] {
+        &self.config.delete_roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Paths in the results are canonical, so the directory is created under the canonical temp dir.
+    fn temp_dir() -> tempfile::TempDir {
+        tempfile::tempdir_in(std::fs::canonicalize(std::env::temp_dir()).unwrap()).unwrap()
+    }
+
+    fn write(path: &Path, bytes: usize) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, vec![1u8; bytes]).unwrap();
+    }
+
+    fn unscanned(root: &Path, project: &Path) -> Arc<DiskUsageService> {
+        Arc::new(DiskUsageService::new(DiskScanConfig {
+            roots: vec![root.to_path_buf()],
+            project_dir: project.to_path_buf(),
+            docker_dir: root.join("docker"),
+            journal_dirs: vec![root.join("journal"), root.join("missing")],
+            log_dirs: vec![root.join("journal")],
+            delete_roots: vec![root.join("small")],
+            max_age: Duration::from_secs(3600),
+        }))
+    }
+
+    /// A service whose first scan has finished.
+    async fn service(root: &Path, project: &Path) -> Arc<DiskUsageService> {
+        let svc = unscanned(root, project);
+        svc.usage(true).await;
+        svc
+    }
+
+    #[tokio::test]
+    async fn test_scan_sums_bytes_and_categories() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        let outside_tmp = temp_dir();
+        let outside = outside_tmp.path();
+        write(&root.join("docker/overlay2/layer"), 100_000);
+        write(&root.join("journal/system.journal"), 40_000);
+        write(&root.join("small/file"), 200_000);
+        write(&outside.join("target/debug/app"), 20_000);
+        std::fs::hard_link(root.join("small/file"), root.join("small/link")).unwrap();
+        std::os::unix::fs::symlink("/usr", root.join("small/usr")).unwrap();
+
+        let svc = service(root, outside).await;
+        let usage = svc.usage(false).await;
+        // Allocated size: at least the file length, rounded up to blocks
+        assert!(usage.docker_bytes >= 100_000 && usage.docker_bytes < 120_000, "{}", usage.docker_bytes);
+        assert!(usage.logs_bytes >= 40_000 && usage.logs_bytes < 60_000);
+        assert!(usage.target_bytes >= 20_000);
+        assert_eq!(usage.node_modules_bytes, 0);
+        assert_eq!(usage.roots.len(), 1);
+        // The hard link is counted once and the symlink is not followed
+        assert!(usage.roots[0].bytes < 450_000, "{}", usage.roots[0].bytes);
+        // layer, journal, file and the symlink itself
+        assert_eq!(usage.roots[0].files, 4);
+        assert!(!usage.scanning);
+
+        let json = serde_json::to_value(&usage).unwrap();
+        for field in ["target_bytes", "node_modules_bytes", "docker_bytes", "logs_bytes", "age_secs", "scanned_at"] {
+            assert!(json.get(field).is_some(), "missing {}", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disk_usage_breakdown_has_all_fields() {
+        let tmp = temp_dir();
+        let svc = service(tmp.path(), tmp.path()).await;
+        let json = serde_json::to_value(svc.usage(false).await).unwrap();
+        for field in ["target_bytes", "node_modules_bytes", "docker_bytes", "logs_bytes", "roots", "scanned_at", "age_secs", "scan_duration_ms", "errors", "scanning"] {
+            assert!(json.get(field).is_some(), "missing {}", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_largest_dirs_drill_down() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        write(&root.join("a/big/file"), 50_000);
+        write(&root.join("a/small/file"), 5_000);
+        write(&root.join("b/file"), 20_000);
+        write(&root.join("c/file"), 1_000);
+
+        let svc = service(root, root).await;
+        let top = svc.largest_dirs(None, 1, 2).await.unwrap();
+        assert_eq!(top.path, root.display().to_string());
+        let names: Vec<&str> = top.children.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(names, [root.join("a").display().to_string(), root.join("b").display().to_string()]);
+        assert!(top.children[0].children.is_empty());
+
+        let a = svc.largest_dirs(Some(&format!("{}/a/", root.display())), 2, 10).await.unwrap();
+        assert_eq!(a.children[0].path, root.join("a/big").display().to_string());
+        assert_eq!(a.children[0].files, 1);
+        assert!(svc.largest_dirs(Some("/definitely/not/scanned"), 1, 10).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_first_request_does_not_wait() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        write(&root.join("file"), 10_000);
+        let svc = unscanned(root, root);
+        let usage = svc.usage(false).await;
+        assert!(usage.roots.is_empty());
+        assert_eq!(usage.scanned_at, 0);
+        assert!(usage.scanning);
+
+        for _ in 0..100 {
+            let usage = svc.usage(false).await;
+            if !usage.roots.is_empty() {
+                assert!(!usage.scanning);
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("background scan did not finish");
+    }
+
+    #[tokio::test]
+    async fn test_deep_directories_are_summed_not_kept() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        let deep: PathBuf = (0..MAX_TREE_DEPTH + 2).map(|i| format!("d{}", i)).collect();
+        write(&root.join(&deep).join("file"), 10_000);
+
+        let svc = service(root, root).await;
+        let last_kept: PathBuf = deep.iter().take(MAX_TREE_DEPTH).collect();
+        let node = svc.largest_dirs(Some(&root.join(&last_kept).display().to_string()), 1, 10).await.unwrap();
+        assert!(node.bytes >= 10_000);
+        assert!(node.children.is_empty());
+        let below: PathBuf = deep.iter().take(MAX_TREE_DEPTH + 1).collect();
+        assert!(svc.largest_dirs(Some(&root.join(below).display().to_string()), 1, 10).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_large_files() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        write(&root.join("a/big.iso"), 300_000);
+        write(&root.join("small/huge.tar"), 500_000);
+        write(&root.join("b/tiny"), 100);
+        write(&root.join("journal/old.journal"), 40_000);
+        write(&root.join("journal/new.journal"), 80_000);
+        let old = std::time::SystemTime::now() - Duration::from_secs(10 * 86400);
+        std::fs::File::options().write(true).open(root.join("journal/old.journal")).unwrap().set_modified(old).unwrap();
+        std::os::unix::fs::symlink(root.join("a/big.iso"), root.join("small/link")).unwrap();
+
+        let files = service(root, root).await.large_files(2, 7).await;
+        let paths: Vec<&str> = files.largest.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, [root.join("small/huge.tar").display().to_string(), root.join("a/big.iso").display().to_string()]);
+        assert!(files.largest[0].deletable);
+        assert!(!files.largest[1].deletable);
+        assert_eq!(files.old_logs.len(), 1);
+        assert_eq!(files.old_logs[0].path, root.join("journal/old.journal").display().to_string());
+        assert_eq!(files.deletable_roots, [root.join("small").display().to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_results_are_cached_until_stale() {
+        let tmp = temp_dir();
+        let root = tmp.path();
+        write(&root.join("file"), 10_000);
+        let svc = service(root, root).await;
+        let first = svc.usage(false).await;
+
+        write(&root.join("more"), 50_000);
+        assert_eq!(svc.usage(false).await.roots[0].bytes, first.roots[0].bytes);
+        assert!(svc.usage(true).await.roots[0].bytes > first.roots[0].bytes);
+
+        // Stale results are served while the rescan runs in the background
+        write(&root.join("even-more"), 50_000);
+        svc.mark_stale();
+        let served = svc.usage(false).await.roots[0].bytes;
+        for _ in 0..100 {
+            if svc.usage(false).await.roots[0].bytes > served {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("background rescan did not pick up the new file");
+    }
+}
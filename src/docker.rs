@@ -1,6 +1,18 @@
-use bollard::query_parameters::{ListContainersOptions, RestartContainerOptions, StartContainerOptions, StopContainerOptions, LogsOptions};
-use bollard::Docker;
+use bollard::auth::DockerCredentials;
+use bollard::models::{ContainerCreateBody, EndpointSettings, HostConfig, Mount, MountPoint, MountPointTypeEnum, MountTypeEnum, NetworkingConfig};
+use bollard::query_parameters::{
+    CreateContainerOptions, CreateImageOptions, DataUsageOptions, InspectContainerOptions, ListContainersOptions,
+    ListImagesOptions, LogsOptions, PruneBuildOptions, PruneImagesOptions, RemoveContainerOptions, RenameContainerOptions, RestartContainerOptions, StartContainerOptions, StopContainerOptions, TagImageOptions,
+};
+use bollard::{Docker, API_DEFAULT_VERSION};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Request timeout (seconds) used for every Docker API connection.
+const CONNECT_TIMEOUT_SECS: u64 = 120;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ContainerInfo {
@@ -10,17 +22,299 @@ pub struct ContainerInfo {
     pub status: String,
     pub state: String, // running, exited, etc.
     pub ports: String,
+    #[serde(default)]
+    pub image_id: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Set from the last registry check; `false` until the image has been checked.
+    #[serde(default)]
+    pub update_available: bool,
+}
+
+/// Where the Docker-compatible API lives.
+///
+/// Resolved from `DOCKER_HOST` (`unix://`, `tcp://`, `ssh://`) or, when unset,
+/// from the first socket that exists among the well-known Docker, rootless
+/// Docker and Podman locations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockerEndpoint {
+    Unix(PathBuf),
+    Tcp { addr: String, tls: Option<TlsConfig> },
+    Ssh { addr: String, key_path: Option<String> },
+}
+
+/// Client certificates for a TLS-protected TCP daemon (`ca.pem`, `cert.pem`, `key.pem`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsConfig {
+    pub ca: PathBuf,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl TlsConfig {
+    fn from_dir(dir: &Path) -> Self {
+        Self {
+            ca: dir.join("ca.pem"),
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+        }
+    }
+}
+
+impl DockerEndpoint {
+    /// Builds the endpoint from `DOCKER_HOST`, `DOCKER_TLS_VERIFY`, `DOCKER_CERT_PATH`
+    /// and `DOCKER_SSH_KEY`, falling back to socket auto-detection.
+    pub fn from_env() -> Result<Self, String> {
+        let cert_path = std::env::var("DOCKER_CERT_PATH").ok().filter(|s| !s.is_empty());
+        let tls_verify = std::env::var("DOCKER_TLS_VERIFY")
+            .map(|v| !v.is_empty() && v != "0")
+            .unwrap_or(false);
+
+        match std::env::var("DOCKER_HOST") {
+            Ok(host) if !host.trim().is_empty() => {
+                let tls_dir = match cert_path {
+                    Some(dir) => Some(PathBuf::from(dir)),
+                    None if tls_verify => Some(default_cert_dir()),
+                    None => None,
+                };
+                let key_path = std::env::var("DOCKER_SSH_KEY").ok().filter(|s| !s.is_empty());
+                Self::parse(host.trim(), tls_dir.as_deref(), key_path)
+            }
+            _ => Self::detect_socket(&socket_candidates())
+                .ok_or_else(|| "No Docker or Podman socket found and DOCKER_HOST is not set".to_string()),
+        }
+    }
+
+    /// Parses a `DOCKER_HOST`-style address. `tls_dir` enables client TLS for `tcp://`.
+    pub fn parse(host: &str, tls_dir: Option<&Path>, key_path: Option<String>) -> Result<Self, String> {
+        if let Some(path) = host.strip_prefix("unix://") {
+            if path.is_empty() {
+                return Err("DOCKER_HOST unix:// address has no socket path".to_string());
+            }
+            Ok(DockerEndpoint::Unix(PathBuf::from(path)))
+        } else if let Some(addr) = host.strip_prefix("tcp://").or_else(|| host.strip_prefix("https://")) {
+            if addr.is_empty() {
+                return Err("DOCKER_HOST tcp:// address has no host".to_string());
+            }
+            let tls = match (tls_dir, host.starts_with("https://")) {
+                (Some(dir), _) => Some(TlsConfig::from_dir(dir)),
+                (None, true) => Some(TlsConfig::from_dir(&default_cert_dir())),
+                (None, false) => None,
+            };
+            Ok(DockerEndpoint::Tcp { addr: addr.trim_end_matches('/').to_string(), tls })
+        } else if host.starts_with("ssh://") {
+            Ok(DockerEndpoint::Ssh { addr: host.to_string(), key_path })
+        } else if host.starts_with('/') {
+            Ok(DockerEndpoint::Unix(PathBuf::from(host)))
+        } else {
+            Err(format!("Unsupported DOCKER_HOST '{}': use unix://, tcp:// or ssh://", host))
+        }
+    }
+
+    /// Returns the first candidate socket that exists on disk.
+    fn detect_socket(candidates: &[PathBuf]) -> Option<Self> {
+        candidates.iter()
+            .find(|p| p.exists())
+            .map(|p| DockerEndpoint::Unix(p.clone()))
+    }
+
+    fn connect(&self) -> Result<Docker, String> {
+        let docker = match self {
+            DockerEndpoint::Unix(path) => {
+                Docker::connect_with_unix(&path.to_string_lossy(), CONNECT_TIMEOUT_SECS, API_DEFAULT_VERSION)
+            }
+            DockerEndpoint::Tcp { addr, tls: None } => {
+                Docker::connect_with_http(addr, CONNECT_TIMEOUT_SECS, API_DEFAULT_VERSION)
+            }
+            DockerEndpoint::Tcp { addr, tls: Some(tls) } => {
+                // Both ring and aws-lc-rs end up linked, so rustls cannot pick a provider on its own.
+                let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+                Docker::connect_with_ssl(addr, &tls.key, &tls.cert, &tls.ca, CONNECT_TIMEOUT_SECS, API_DEFAULT_VERSION)
+            }
+            DockerEndpoint::Ssh { addr, key_path } => {
+                Docker::connect_with_ssh(addr, CONNECT_TIMEOUT_SECS, API_DEFAULT_VERSION, key_path.clone())
+            }
+        };
+        docker.map_err(|e| format!("Failed to connect to Docker at {}: {}", self, e))
+    }
+}
+
+impl std::fmt::Display for DockerEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DockerEndpoint::Unix(path) => write!(f, "unix://{}", path.display()),
+            DockerEndpoint::Tcp { addr, tls: Some(_) } => write!(f, "tcp://{} (tls)", addr),
+            DockerEndpoint::Tcp { addr, tls: None } => write!(f, "tcp://{}", addr),
+            DockerEndpoint::Ssh { addr, .. } => write!(f, "{}", addr),
+        }
+    }
+}
+
+fn default_cert_dir() -> PathBuf {
+    std::env::var("HOME")
+        .map(|h| PathBuf::from(h).join(".docker"))
+        .unwrap_or_else(|_| PathBuf::from("/root/.docker"))
+}
+
+/// Rootful Docker first, then rootless Docker and Podman sockets for the current user.
+fn socket_candidates() -> Vec<PathBuf> {
+    let mut candidates = vec![PathBuf::from("/var/run/docker.sock")];
+
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            // /proc/self is owned by our effective uid
+            use std::os::unix::fs::MetadataExt;
+            std::fs::metadata("/proc/self").ok().map(|m| PathBuf::from(format!("/run/user/{}", m.uid())))
+        });
+
+    if let Some(dir) = runtime_dir {
+        candidates.push(dir.join("docker.sock"));
+        candidates.push(dir.join("podman/podman.sock"));
+    }
+    candidates.push(PathBuf::from("/run/podman/podman.sock"));
+    candidates
+}
+
+/// Everything needed to create an equivalent container again with another image.
+#[derive(Debug, Clone)]
+pub struct ContainerSnapshot {
+    pub name: String,
+    /// Image reference from the container config, usually `repo:tag`
+    pub image: String,
+    /// Image id (`sha256:...`) the container is currently running
+    pub image_id: String,
+    /// Whether it was running, i.e. whether to start it again if it has to be restored
+    pub running: bool,
+    pub(crate) body: ContainerCreateBody,
+}
+
+/// Space `docker system prune -a` frees (or would free), in bytes.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DockerReclaimable {
+    /// Images no container uses
+    pub images: u64,
+    /// Stopped containers
+    pub containers: u64,
+    pub build_cache: u64,
+}
+
+impl DockerReclaimable {
+    pub fn total(&self) -> u64 {
+        self.images + self.containers + self.build_cache
+    }
+}
+
+/// Connection state reported by `/api/docker/status`.
+#[derive(Serialize, Debug, Clone)]
+pub struct DockerStatus {
+    pub endpoint: Option<String>,
+    pub connected: bool,
+    pub error: Option<String>,
 }
 
 pub struct DockerService {
-    docker: Docker,
+    /// `None` until `DOCKER_HOST` or a socket is found; re-resolved on every check until then
+    endpoint: RwLock<Option<DockerEndpoint>>,
+    docker: RwLock<Option<Docker>>,
+    last_error: RwLock<Option<String>>,
 }
 
 impl DockerService {
-    pub fn new() -> Result<Self, String> {
-        let docker = Docker::connect_with_socket_defaults()
-            .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
-        Ok(Self { docker })
+    /// Creates a disconnected service. `run_health_loop` resolves the endpoint and connects,
+    /// and keeps retrying, so Docker started after the agent is picked up.
+    pub fn new() -> Self {
+        Self {
+            endpoint: RwLock::new(None),
+            docker: RwLock::new(None),
+            last_error: RwLock::new(None),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.docker.read().unwrap().is_some()
+    }
+
+    pub fn status(&self) -> DockerStatus {
+        DockerStatus {
+            endpoint: self.endpoint.read().unwrap().as_ref().map(|e| e.to_string()),
+            connected: self.is_connected(),
+            error: self.last_error.read().unwrap().clone(),
+        }
+    }
+
+    fn client(&self) -> Result<Docker, String> {
+        self.docker.read().unwrap()
+            .clone()
+            .ok_or_else(|| "Docker integration is not available".to_string())
+    }
+
+    /// Pings the current client, (re)connecting when there is none or the ping fails.
+    /// Until an endpoint has been found, looks for one first.
+    async fn check_connection(&self) {
+        let resolved = self.endpoint.read().unwrap().clone();
+        let endpoint = match resolved {
+            Some(endpoint) => endpoint,
+            None => match DockerEndpoint::from_env() {
+                Ok(endpoint) => {
+                    *self.endpoint.write().unwrap() = Some(endpoint.clone());
+                    endpoint
+                }
+                Err(e) => {
+                    self.set_error(format!("Docker integration disabled: {}", e));
+                    return;
+                }
+            },
+        };
+
+        if let Ok(docker) = self.client() {
+            if docker.ping().await.is_ok() {
+                return;
+            }
+            tracing::warn!("Docker at {} stopped responding", endpoint);
+            *self.docker.write().unwrap() = None;
+        }
+
+        let connected = match endpoint.connect() {
+            Ok(docker) => docker.negotiate_version().await
+                .map_err(|e| format!("Docker at {} is unreachable: {}", endpoint, e)),
+            Err(e) => Err(e),
+        };
+
+        match connected {
+            Ok(docker) => {
+                tracing::info!("Connected to Docker at {}", endpoint);
+                *self.docker.write().unwrap() = Some(docker);
+                *self.last_error.write().unwrap() = None;
+            }
+            Err(e) => self.set_error(e),
+        }
+    }
+
+    fn set_error(&self, e: String) {
+        let mut last = self.last_error.write().unwrap();
+        // Log once per distinct error instead of on every retry
+        if last.as_deref() != Some(e.as_str()) {
+            tracing::error!("{}", e);
+        }
+        *last = Some(e);
+    }
+
+    /// Periodically re-checks the daemon. Interval comes from `DOCKER_HEALTH_INTERVAL` (seconds, default 30).
+    pub async fn run_health_loop(self: Arc<Self>) {
+        let secs = std::env::var("DOCKER_HEALTH_INTERVAL")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(30u64);
+        let mut interval = tokio::time::interval(Duration::from_secs(secs));
+        // The first tick fires immediately and makes the initial connection
+        loop {
+            interval.tick().await;
+            self.check_connection().await;
+        }
     }
 
     pub async fn list_containers(&self) -> Result<Vec<ContainerInfo>, String> {
@@ -29,7 +323,7 @@ impl DockerService {
             ..Default::default()
         };
 
-        let containers = self.docker.list_containers(Some(options)).await
+        let containers = self.client()?.list_containers(Some(options)).await
             .map_err(|e| format!("Failed to list containers: {}", e))?;
 
         let result = containers.into_iter().map(|c| {
@@ -48,6 +342,9 @@ impl DockerService {
                 status: c.status.unwrap_or_default(),
                 state: c.state.map(|s| s.to_string()).unwrap_or_else(|| "unknown".to_string()),
                 ports,
+                image_id: c.image_id.unwrap_or_default(),
+                labels: c.labels.unwrap_or_default(),
+                update_available: false,
             }
         }).collect();
 
@@ -56,7 +353,7 @@ impl DockerService {
 
     pub async fn start_container(&self, id: &str) -> Result<(), String> {
         tracing::info!("Starting container: {}", id);
-        self.docker.start_container(id, None::<StartContainerOptions>).await
+        self.client()?.start_container(id, None::<StartContainerOptions>).await
             .map_err(|e| {
                 tracing::error!("Failed to start container {}: {}", id, e);
                 format!("Failed to start container: {}", e)
@@ -65,7 +362,7 @@ impl DockerService {
 
     pub async fn stop_container(&self, id: &str) -> Result<(), String> {
         tracing::info!("Stopping container: {}", id);
-        self.docker.stop_container(id, None::<StopContainerOptions>).await
+        self.client()?.stop_container(id, None::<StopContainerOptions>).await
             .map_err(|e| {
                 tracing::error!("Failed to stop container {}: {}", id, e);
                 format!("Failed to stop container: {}", e)
@@ -74,13 +371,275 @@ impl DockerService {
 
     pub async fn restart_container(&self, id: &str) -> Result<(), String> {
         tracing::info!("Restarting container: {}", id);
-        self.docker.restart_container(id, None::<RestartContainerOptions>).await
+        self.client()?.restart_container(id, None::<RestartContainerOptions>).await
             .map_err(|e| {
                 tracing::error!("Failed to restart container {}: {}", id, e);
                 format!("Failed to restart container: {}", e)
             })
     }
 
+    /// Returns the health check status reported by inspect: `healthy`, `unhealthy`,
+    /// `starting`, or `None` when the container has no health check.
+    pub async fn container_health(&self, id: &str) -> Result<Option<String>, String> {
+        let details = self.client()?.inspect_container(id, None::<InspectContainerOptions>).await
+            .map_err(|e| format!("Failed to inspect container: {}", e))?;
+
+        Ok(details.state
+            .and_then(|s| s.health)
+            .and_then(|h| h.status)
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty() && s != "none"))
+    }
+
+    /// Captures config, host config and network attachments so the container can be recreated.
+    pub async fn snapshot_container(&self, id: &str) -> Result<ContainerSnapshot, String> {
+        let details = self.client()?.inspect_container(id, None::<InspectContainerOptions>).await
+            .map_err(|e| format!("Failed to inspect container: {}", e))?;
+
+        let config = details.config.unwrap_or_default();
+        let name = details.name.unwrap_or_default().trim_start_matches('/').to_string();
+        let running = details.state.and_then(|s| s.running).unwrap_or(false);
+        let container_id = details.id.unwrap_or_default();
+
+        // Only user-defined settings survive; runtime-assigned addresses and ids are dropped
+        let endpoints = details.network_settings
+            .and_then(|n| n.networks)
+            .map(|networks| networks.into_iter().map(|(net, ep)| {
+                let aliases = ep.aliases.map(|a| a.into_iter()
+                    .filter(|alias| !container_id.starts_with(alias.as_str()))
+                    .collect());
+                (net, EndpointSettings {
+                    ipam_config: ep.ipam_config,
+                    links: ep.links,
+                    aliases,
+                    driver_opts: ep.driver_opts,
+                    gw_priority: ep.gw_priority,
+                    ..Default::default()
+                })
+            }).collect());
+
+        let body = ContainerCreateBody {
+            hostname: config.hostname.filter(|h| !container_id.starts_with(h.as_str())),
+            domainname: config.domainname,
+            user: config.user,
+            attach_stdin: config.attach_stdin,
+            attach_stdout: config.attach_stdout,
+            attach_stderr: config.attach_stderr,
+            exposed_ports: config.exposed_ports,
+            tty: config.tty,
+            open_stdin: config.open_stdin,
+            stdin_once: config.stdin_once,
+            env: config.env,
+            cmd: config.cmd,
+            healthcheck: config.healthcheck,
+            args_escaped: config.args_escaped,
+            image: config.image.clone(),
+            volumes: config.volumes,
+            working_dir: config.working_dir,
+            entrypoint: config.entrypoint,
+            network_disabled: config.network_disabled,
+            on_build: config.on_build,
+            labels: config.labels,
+            stop_signal: config.stop_signal,
+            stop_timeout: config.stop_timeout,
+            shell: config.shell,
+            host_config: details.host_config.map(|mut host_config| {
+                // Anonymous volumes are not part of the host config; without this the
+                // replacement would get fresh, empty ones
+                let anonymous = anonymous_volumes(details.mounts.as_deref().unwrap_or_default(), &host_config);
+                if !anonymous.is_empty() {
+                    host_config.mounts.get_or_insert_with(Vec::new).extend(anonymous);
+                }
+                host_config
+            }),
+            networking_config: endpoints.map(|e| NetworkingConfig { endpoints_config: Some(e) }),
+        };
+
+        Ok(ContainerSnapshot {
+            name,
+            image: config.image.unwrap_or_default(),
+            image_id: details.image.unwrap_or_default(),
+            running,
+            body,
+        })
+    }
+
+    /// Pulls `image`, reporting each progress message through `on_progress`.
+    pub async fn pull_image(
+        &self,
+        image: &str,
+        credentials: Option<DockerCredentials>,
+        mut on_progress: impl FnMut(String),
+    ) -> Result<(), String> {
+        use futures_util::StreamExt;
+
+        let options = CreateImageOptions {
+            from_image: Some(image.to_string()),
+            ..Default::default()
+        };
+        let mut stream = self.client()?.create_image(Some(options), None, credentials);
+        while let Some(item) = stream.next().await {
+            let info = item.map_err(|e| format!("Failed to pull {}: {}", image, e))?;
+            if let Some(err) = info.error_detail.and_then(|d| d.message) {
+                return Err(format!("Failed to pull {}: {}", image, err));
+            }
+            // Per-layer byte counters are too noisy for the log stream
+            if info.progress_detail.is_some_and(|p| p.current.is_some()) {
+                continue;
+            }
+            if let Some(status) = info.status {
+                match info.id {
+                    Some(id) => on_progress(format!("{}: {}", id, status)),
+                    None => on_progress(status),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves a tag or id to the local image id.
+    pub async fn image_id(&self, image: &str) -> Result<String, String> {
+        self.client()?.inspect_image(image).await
+            .map_err(|e| format!("Failed to inspect image {}: {}", image, e))?
+            .id
+            .ok_or_else(|| format!("Image {} has no id", image))
+    }
+
+    /// Points `reference` (`repo:tag`) at an existing local image id.
+    pub async fn tag_image(&self, image_id: &str, reference: &str) -> Result<(), String> {
+        let (repo, tag) = match reference.rsplit_once(':') {
+            Some((repo, tag)) if !tag.contains('/') => (repo, tag),
+            _ => (reference, "latest"),
+        };
+        let options = TagImageOptions {
+            repo: Some(repo.to_string()),
+            tag: Some(tag.to_string()),
+        };
+        self.client()?.tag_image(image_id, Some(options)).await
+            .map_err(|e| format!("Failed to tag {} as {}: {}", image_id, reference, e))
+    }
+
+    /// Creates (but does not start) a container from `snapshot` running `image` and returns its id.
+    pub async fn create_container(&self, snapshot: &ContainerSnapshot, image: &str) -> Result<String, String> {
+        let mut body = snapshot.body.clone();
+        body.image = Some(image.to_string());
+        let options = CreateContainerOptions { name: Some(snapshot.name.clone()), ..Default::default() };
+        self.client()?.create_container(Some(options), body).await
+            .map(|created| created.id)
+            .map_err(|e| format!("Failed to create container {}: {}", snapshot.name, e))
+    }
+
+    pub async fn rename_container(&self, id: &str, name: &str) -> Result<(), String> {
+        let options = RenameContainerOptions { name: name.to_string() };
+        self.client()?.rename_container(id, options).await
+            .map_err(|e| format!("Failed to rename container {} to {}: {}", id, name, e))
+    }
+
+    /// Force-removes a container. Its volumes are kept, a replacement may still use them.
+    pub async fn remove_container(&self, id: &str) -> Result<(), String> {
+        let options = RemoveContainerOptions { force: true, ..Default::default() };
+        self.client()?.remove_container(id, Some(options)).await
+            .map_err(|e| format!("Failed to remove container {}: {}", id, e))
+    }
+
+    /// Waits until the container's health check passes. Containers without a health check
+    /// only have to stay running for `settle` (capped by `timeout`).
+    pub async fn wait_until_healthy(&self, id: &str, timeout: Duration, settle: Duration) -> Result<(), String> {
+        let started = std::time::Instant::now();
+        let settle = settle.min(timeout);
+
+        loop {
+            let details = self.client()?.inspect_container(id, None::<InspectContainerOptions>).await
+                .map_err(|e| format!("Failed to inspect container: {}", e))?;
+            let state = details.state.unwrap_or_default();
+
+            if !state.running.unwrap_or(false) {
+                return Err(format!("Container exited with code {}", state.exit_code.unwrap_or(-1)));
+            }
+
+            let health = state.health.and_then(|h| h.status).map(|s| s.to_string());
+            match health.as_deref() {
+                Some("healthy") => return Ok(()),
+                Some("unhealthy") => return Err("Health check reports unhealthy".to_string()),
+                Some("starting") => {}
+                _ if started.elapsed() >= settle => return Ok(()),
+                _ => {}
+            }
+
+            if started.elapsed() >= timeout {
+                return Err(format!("Container did not become healthy within {}s", timeout.as_secs()));
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    }
+
+    /// `RepoDigests` of a local image (`nginx@sha256:...`), i.e. the manifest digests it was pulled by.
+    pub async fn image_repo_digests(&self, image: &str) -> Result<Vec<String>, String> {
+        let details = self.client()?.inspect_image(image).await
+            .map_err(|e| format!("Failed to inspect image {}: {}", image, e))?;
+        Ok(details.repo_digests.unwrap_or_default())
+    }
+
+    /// Reclaimable space as reported by the daemon's disk usage API (`docker system df`).
+    /// Daemons older than API 1.52 only list the objects, so the sums are made here; build
+    /// cache is then not included.
+    pub async fn reclaimable_space(&self) -> Result<DockerReclaimable, String> {
+        let docker = self.client()?;
+        let usage = docker.df(None::<DataUsageOptions>).await
+            .map_err(|e| format!("Failed to read Docker disk usage: {}", e))?;
+        if let (Some(images), Some(containers)) = (&usage.images_disk_usage, &usage.containers_disk_usage) {
+            let bytes = |n: Option<i64>| n.unwrap_or(0).max(0) as u64;
+            return Ok(DockerReclaimable {
+                images: bytes(images.reclaimable),
+                containers: bytes(containers.reclaimable),
+                build_cache: bytes(usage.build_cache_disk_usage.as_ref().and_then(|b| b.reclaimable)),
+            });
+        }
+
+        let containers = docker.list_containers(Some(ListContainersOptions { all: true, size: true, ..Default::default() })).await
+            .map_err(|e| format!("Failed to list containers: {}", e))?;
+        let images = docker.list_images(Some(ListImagesOptions::default())).await
+            .map_err(|e| format!("Failed to list images: {}", e))?;
+        let used: std::collections::HashSet<&str> = containers.iter().filter_map(|c| c.image_id.as_deref()).collect();
+        Ok(DockerReclaimable {
+            images: images.iter()
+                .filter(|i| !used.contains(i.id.as_str()))
+                .map(|i| (i.size - i.shared_size.max(0)).max(0) as u64)
+                .sum(),
+            containers: containers.iter()
+                .filter(|c| c.state.map(|s| s.to_string()) != Some("running".to_string()))
+                .map(|c| c.size_rw.unwrap_or(0).max(0) as u64)
+                .sum(),
+            build_cache: 0,
+        })
+    }
+
+    /// Same as `docker system prune -af`: stopped containers, unused networks, every image
+    /// no container uses and the build cache. Returns the space the daemon reports as freed.
+    pub async fn prune_system(&self) -> Result<DockerReclaimable, String> {
+        let docker = self.client()?;
+        let containers = docker.prune_containers(None).await
+            .map_err(|e| format!("Failed to prune containers: {}", e))?;
+        docker.prune_networks(None).await
+            .map_err(|e| format!("Failed to prune networks: {}", e))?;
+        let filters = HashMap::from([("dangling".to_string(), vec!["false".to_string()])]);
+        let images = docker.prune_images(Some(PruneImagesOptions { filters: Some(filters) })).await
+            .map_err(|e| format!("Failed to prune images: {}", e))?;
+        // Podman and daemons without BuildKit may not support this
+        let build_cache = match docker.prune_build(Some(PruneBuildOptions { all: Some(true), ..Default::default() })).await {
+            Ok(res) => res.space_reclaimed.unwrap_or(0),
+            Err(e) => {
+                tracing::warn!("Failed to prune build cache: {}", e);
+                0
+            }
+        };
+        Ok(DockerReclaimable {
+            images: images.space_reclaimed.unwrap_or(0).max(0) as u64,
+            containers: containers.space_reclaimed.unwrap_or(0).max(0) as u64,
+            build_cache: build_cache.max(0) as u64,
+        })
+    }
+
     /// Создает поток логов контейнера с поддержкой фильтрации.
     /// 
     /// # Аргументы
@@ -92,7 +651,7 @@ impl DockerService {
         id: &str, 
         since: Option<i64>, 
         tail: Option<String>
-    ) -> impl futures_util::Stream<Item = Result<String, String>> {
+    ) -> Result<impl futures_util::Stream<Item = Result<String, String>> + use<>, String> {
         use futures_util::StreamExt;
         tracing::info!("Creating log stream for container: {} (since: {:?}, tail: {:?})", id, since, tail);
 
@@ -105,11 +664,125 @@ impl DockerService {
             ..Default::default()
         });
 
-        self.docker.logs(id, options).map(|res| {
+        Ok(self.client()?.logs(id, options).map(|res| {
             match res {
                 Ok(log) => Ok(log.to_string()),
                 Err(e) => Err(format!("Log error: {}", e)),
             }
+        }))
+    }
+}
+
+/// Volume mounts of a container that neither `Binds` nor `Mounts` of its host config
+/// declare (`-v /data`, image `VOLUME`), re-attached by volume name.
+fn anonymous_volumes(mounts: &[MountPoint], host_config: &HostConfig) -> Vec<Mount> {
+    let declared: Vec<&str> = host_config.binds.iter().flatten()
+        .filter_map(|bind| bind.split(':').nth(1))
+        .chain(host_config.mounts.iter().flatten().filter_map(|m| m.target.as_deref()))
+        .collect();
+
+    mounts.iter()
+        .filter(|m| m.typ == Some(MountPointTypeEnum::VOLUME))
+        .filter_map(|m| Some((m.name.clone()?, m.destination.clone()?)))
+        .filter(|(_, destination)| !declared.contains(&destination.as_str()))
+        .map(|(name, destination)| Mount {
+            target: Some(destination),
+            source: Some(name),
+            typ: Some(MountTypeEnum::VOLUME),
+            ..Default::default()
         })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anonymous_volumes_are_reattached_by_name() {
+        let volume = |name: &str, destination: &str| MountPoint {
+            typ: Some(MountPointTypeEnum::VOLUME),
+            name: Some(name.to_string()),
+            destination: Some(destination.to_string()),
+            ..Default::default()
+        };
+        let mounts = vec![
+            volume("3f2a9c", "/var/lib/data"),
+            volume("pgdata", "/var/lib/postgresql"),
+            volume("cache", "/cache"),
+            MountPoint {
+                typ: Some(MountPointTypeEnum::BIND),
+                source: Some("/srv/config".to_string()),
+                destination: Some("/config".to_string()),
+                ..Default::default()
+            },
+        ];
+        let host_config = HostConfig {
+            binds: Some(vec!["pgdata:/var/lib/postgresql:rw".to_string(), "/srv/config:/config".to_string()]),
+            mounts: Some(vec![Mount { target: Some("/cache".to_string()), source: Some("cache".to_string()), ..Default::default() }]),
+            ..Default::default()
+        };
+
+        let anonymous = anonymous_volumes(&mounts, &host_config);
+        assert_eq!(anonymous.len(), 1);
+        assert_eq!(anonymous[0].source.as_deref(), Some("3f2a9c"));
+        assert_eq!(anonymous[0].target.as_deref(), Some("/var/lib/data"));
+        assert_eq!(anonymous[0].typ, Some(MountTypeEnum::VOLUME));
+    }
+
+    #[test]
+    fn test_parse_unix_socket() {
+        let ep = DockerEndpoint::parse("unix:///run/user/1000/docker.sock", None, None).unwrap();
+        assert_eq!(ep, DockerEndpoint::Unix(PathBuf::from("/run/user/1000/docker.sock")));
+
+        // Bare absolute paths are accepted as sockets too
+        let ep = DockerEndpoint::parse("/run/podman/podman.sock", None, None).unwrap();
+        assert_eq!(ep, DockerEndpoint::Unix(PathBuf::from("/run/podman/podman.sock")));
+    }
+
+    #[test]
+    fn test_parse_tcp_plain_and_tls() {
+        let ep = DockerEndpoint::parse("tcp://10.0.0.5:2375/", None, None).unwrap();
+        assert_eq!(ep, DockerEndpoint::Tcp { addr: "10.0.0.5:2375".to_string(), tls: None });
+
+        let ep = DockerEndpoint::parse("tcp://docker.internal:2376", Some(Path::new("/etc/mini-ops/certs")), None).unwrap();
+        match ep {
+            DockerEndpoint::Tcp { addr, tls: Some(tls) } => {
+                assert_eq!(addr, "docker.internal:2376");
+                assert_eq!(tls.ca, PathBuf::from("/etc/mini-ops/certs/ca.pem"));
+                assert_eq!(tls.cert, PathBuf::from("/etc/mini-ops/certs/cert.pem"));
+                assert_eq!(tls.key, PathBuf::from("/etc/mini-ops/certs/key.pem"));
+            }
+            other => panic!("expected TLS endpoint, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ssh() {
+        let ep = DockerEndpoint::parse("ssh://deploy@10.0.0.5", None, Some("/home/deploy/.ssh/id_ed25519".to_string())).unwrap();
+        assert_eq!(ep, DockerEndpoint::Ssh {
+            addr: "ssh://deploy@10.0.0.5".to_string(),
+            key_path: Some("/home/deploy/.ssh/id_ed25519".to_string()),
+        });
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_scheme() {
+        assert!(DockerEndpoint::parse("npipe:////./pipe/docker_engine", None, None).is_err());
+        assert!(DockerEndpoint::parse("unix://", None, None).is_err());
+        assert!(DockerEndpoint::parse("tcp://", None, None).is_err());
+    }
+
+    #[test]
+    fn test_detect_socket_picks_first_existing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("podman")).unwrap();
+        let podman = dir.join("podman/podman.sock");
+        std::fs::write(&podman, "").unwrap();
+
+        let candidates = vec![dir.join("docker.sock"), podman.clone()];
+        assert_eq!(DockerEndpoint::detect_socket(&candidates), Some(DockerEndpoint::Unix(podman)));
+        assert_eq!(DockerEndpoint::detect_socket(&[dir.join("missing.sock")]), None);
     }
 }
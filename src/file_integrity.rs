@@ -0,0 +1,490 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::i18n::{self, Lang};
+use crate::util::{glob_match, Visit, Walker};
+use crate::security::{CheckSpec, CheckStatus, SecurityCheck, SecurityCheckProvider, Severity, CHECK_FILE_INTEGRITY};
+
+/// Watched when the checks config has no `integrity_paths`. Directories are
+/// read recursively; `*` and `?` match within one path component.
+pub const DEFAULT_PATHS: &[&str] = &[
+    "/etc/passwd",
+    "/etc/shadow",
+    "/etc/group",
+    "/etc/gshadow",
+    "/etc/sudoers",
+    "/etc/sudoers.d",
+    "/etc/ssh/sshd_config",
+    "/etc/ssh/sshd_config.d",
+    "/root/.ssh/authorized_keys*",
+    "/home/*/.ssh/authorized_keys*",
+    "/etc/crontab",
+    "/etc/cron.d",
+    "/var/spool/cron",
+    "/etc/systemd/system",
+];
+/// Levels read below a watched directory.
+const MAX_DEPTH: usize = 4;
+/// Files tracked at most; the rest are ignored so a broad pattern cannot stall the monitor.
+const MAX_FILES: usize = 5000;
+/// Larger files are tracked by owner, mode and size only.
+const MAX_HASH_BYTES: u64 = 16 * 1024 * 1024;
+/// One scan serves the audit check and the monitor's alert of the same round.
+const SCAN_TTL: Duration = Duration::from_secs(10);
+/// Changes named in evidence and alerts; the rest are only counted.
+const MAX_LISTED: usize = 10;
+
+const FILE_INTEGRITY: CheckSpec = CheckSpec {
+    id: CHECK_FILE_INTEGRITY,
+    key: "integrity",
+    severity: Severity::High,
+    categories: &["integrity"],
+};
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct FileState {
+    /// Of the content, or of the target for symlinks; `None` when unreadable or too large
+    pub sha256: Option<String>,
+    pub uid: u32,
+    pub gid: u32,
+    /// Permission bits, e.g. 0o640
+    pub mode: u32,
+    pub size: u64,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub before: Option<FileState>,
+    pub after: Option<FileState>,
+}
+
+impl FileChange {
+    /// E.g. "/etc/shadow: mode 640 -> 644" or "/etc/cron.d/backup: added".
+    pub fn describe(&self) -> String {
+        let (Some(before), Some(after)) = (&self.before, &self.after) else {
+            let kind = if self.kind == ChangeKind::Added { "added" } else { "removed" };
+            return format!("{}: {}", self.path, kind);
+        };
+        let mut what = Vec::new();
+        if before.sha256 != after.sha256 || before.size != after.size {
+            what.push("content".to_string());
+        }
+        if (before.uid, before.gid) != (after.uid, after.gid) {
+            what.push(format!("owner {}:{} -> {}:{}", before.uid, before.gid, after.uid, after.gid));
+        }
+        if before.mode != after.mode {
+            what.push(format!("mode {:o} -> {:o}", before.mode, after.mode));
+        }
+        format!("{}: {}", self.path, what.join(", "))
+    }
+}
+
+/// Current state of every file the patterns match. Reads the files, so it blocks.
+pub fn scan(patterns: &[String]) -> BTreeMap<String, FileState> {
+    let mut files = BTreeMap::new();
+    let mut walker = Walker::default().max_depth(MAX_DEPTH).sorted();
+    for pattern in patterns {
+        for path in expand(pattern) {
+            walker.walk(&path, &mut |visit| {
+                if let Visit::File(path, meta) = visit
+                    && files.len() < MAX_FILES
+                {
+                    files.insert(path.to_string_lossy().into_owned(), file_state(path, meta));
+                }
+            });
+        }
+    }
+    files
+}
+
+/// Paths matching an absolute pattern whose components may hold `*` and `?`.
+fn expand(pattern: &str) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from("/")];
+    for component in Path::new(pattern).components() {
+        let Component::Normal(part) = component else {
+            continue;
+        };
+        let part = part.to_string_lossy();
+        if !part.contains(['*', '?']) {
+            paths.iter_mut().for_each(|p| p.push(part.as_ref()));
+            continue;
+        }
+        let mut matched = Vec::new();
+        for dir in &paths {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if glob_match(part.as_bytes(), entry.file_name().as_encoded_bytes()) {
+                    matched.push(entry.path());
+                }
+            }
+        }
+        matched.sort();
+        paths = matched;
+    }
+    paths
+}
+
+fn file_state(path: &Path, meta: &Metadata) -> FileState {
+    let sha256 = if meta.file_type().is_symlink() {
+        // Enabling a systemd unit only adds a link; its target is what matters
+        std::fs::read_link(path).ok().map(|target| hex::encode(Sha256::digest(target.as_os_str().as_encoded_bytes())))
+    } else if meta.len() <= MAX_HASH_BYTES {
+        std::fs::File::open(path).ok().and_then(|mut file| {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut file, &mut hasher).ok()?;
+            Some(hex::encode(hasher.finalize()))
+        })
+    } else {
+        None
+    };
+    FileState { sha256, uid: meta.uid(), gid: meta.gid(), mode: meta.mode() & 0o7777, size: meta.len() }
+}
+
+/// What differs between the baseline and the files on disk, in path order.
+pub fn diff(baseline: &BTreeMap<String, FileState>, current: &BTreeMap<String, FileState>) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    for (path, before) in baseline {
+        match current.get(path) {
+            None => changes.push(FileChange { path: path.clone(), kind: ChangeKind::Removed, before: Some(before.clone()), after: None }),
+            Some(after) if after != before => changes.push(FileChange {
+                path: path.clone(),
+                kind: ChangeKind::Modified,
+                before: Some(before.clone()),
+                after: Some(after.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (path, after) in current {
+        if !baseline.contains_key(path) {
+            changes.push(FileChange { path: path.clone(), kind: ChangeKind::Added, before: None, after: Some(after.clone()) });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Described changes, the ones past `MAX_LISTED` only counted.
+fn list_changes(changes: &[FileChange], separator: &str, prefix: &str) -> String {
+    let mut lines: Vec<String> = changes.iter().take(MAX_LISTED).map(|c| format!("{}{}", prefix, c.describe())).collect();
+    if changes.len() > MAX_LISTED {
+        lines.push(format!("{}+{} more", prefix, changes.len() - MAX_LISTED));
+    }
+    lines.join(separator)
+}
+
+#[derive(Serialize)]
+pub struct IntegrityReport {
+    pub paths: Vec<String>,
+    pub baseline_files: usize,
+    pub changes: Vec<FileChange>,
+}
+
+/// Baseline of the watched files, stored in SQLite and cached, since the audit
+/// compares against it from a blocking thread.
+pub struct FileIntegrity {
+    db: SqlitePool,
+    patterns: Vec<String>,
+    baseline: RwLock<BTreeMap<String, FileState>>,
+    scanned: Mutex<Option<(Instant, Arc<Vec<FileChange>>)>>,
+    /// Bumped by every baseline reload, so a scan that overlapped one is not cached
+    generation: AtomicU64,
+    /// Changes already alerted about, by path and new state
+    alerted: Mutex<HashSet<String>>,
+}
+
+impl FileIntegrity {
+    /// Relative patterns are ignored.
+    pub fn new(db: SqlitePool, patterns: Vec<String>) -> Self {
+        let patterns = patterns.into_iter()
+            .filter(|p| {
+                let absolute = p.starts_with('/');
+                if !absolute {
+                    tracing::warn!("Ignoring relative integrity path '{}'", p);
+                }
+                absolute
+            })
+            .collect();
+        Self {
+            db,
+            patterns,
+            baseline: RwLock::new(BTreeMap::new()),
+            scanned: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            alerted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Creates the table and loads the baseline. A new table is filled with the
+    /// files as they are now.
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'file_integrity'")
+            .fetch_optional(&self.db)
+            .await?
+            .is_some();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS file_integrity (
+                path TEXT PRIMARY KEY,
+                sha256 TEXT,
+                uid INTEGER NOT NULL,
+                gid INTEGER NOT NULL,
+                mode INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                baselined_at INTEGER NOT NULL
+            )"
+        )
+        .execute(&self.db)
+        .await?;
+        self.reload().await?;
+        if !exists {
+            let current = self.scan_now().await;
+            let changes = diff(&BTreeMap::new(), &current);
+            self.store(&changes).await?;
+            tracing::info!("File integrity baseline created with {} files", changes.len());
+        }
+        Ok(())
+    }
+
+    async fn reload(&self) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query("SELECT path, sha256, uid, gid, mode, size FROM file_integrity")
+            .fetch_all(&self.db)
+            .await?;
+        let baseline = rows.iter()
+            .map(|row| {
+                let state = FileState {
+                    sha256: row.get("sha256"),
+                    uid: row.get::<i64, _>("uid") as u32,
+                    gid: row.get::<i64, _>("gid") as u32,
+                    mode: row.get::<i64, _>("mode") as u32,
+                    size: row.get::<i64, _>("size") as u64,
+                };
+                (row.get("path"), state)
+            })
+            .collect();
+        *self.baseline.write().unwrap() = baseline;
+        let mut scanned = self.scanned.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        *scanned = None;
+        Ok(())
+    }
+
+    /// Writes the new side of each change into the baseline.
+    async fn store(&self, changes: &[FileChange]) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().timestamp();
+        for change in changes {
+            let Some(after) = &change.after else {
+                sqlx::query("DELETE FROM file_integrity WHERE path = ?")
+                    .bind(&change.path)
+                    .execute(&self.db)
+                    .await?;
+                continue;
+            };
+            sqlx::query(
+                "INSERT OR REPLACE INTO file_integrity (path, sha256, uid, gid, mode, size, baselined_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&change.path)
+            .bind(&after.sha256)
+            .bind(after.uid as i64)
+            .bind(after.gid as i64)
+            .bind(after.mode as i64)
+            .bind(after.size as i64)
+            .bind(now)
+            .execute(&self.db)
+            .await?;
+        }
+        self.reload().await
+    }
+
+    async fn scan_now(&self) -> BTreeMap<String, FileState> {
+        let patterns = self.patterns.clone();
+        tokio::task::spawn_blocking(move || scan(&patterns)).await.unwrap_or_default()
+    }
+
+    /// Differences from the baseline. Reads the files, so it blocks.
+    pub fn changes(&self) -> Arc<Vec<FileChange>> {
+        if let Some((at, changes)) = self.scanned.lock().unwrap().as_ref()
+            && at.elapsed() < SCAN_TTL
+        {
+            return changes.clone();
+        }
+        // The scan runs unlocked so `reload` never waits for it; a result that
+        // raced a reload is returned but not cached
+        let generation = self.generation.load(Ordering::Acquire);
+        let current = scan(&self.patterns);
+        let changes = Arc::new(diff(&self.baseline.read().unwrap(), &current));
+        let mut scanned = self.scanned.lock().unwrap();
+        if self.generation.load(Ordering::Acquire) == generation {
+            *scanned = Some((Instant::now(), changes.clone()));
+        }
+        changes
+    }
+
+    pub async fn report(self: &Arc<Self>) -> IntegrityReport {
+        let integrity = self.clone();
+        let changes = tokio::task::spawn_blocking(move || integrity.changes()).await.unwrap_or_default();
+        IntegrityReport {
+            paths: self.patterns.clone(),
+            baseline_files: self.baseline.read().unwrap().len(),
+            changes: changes.as_ref().clone(),
+        }
+    }
+
+    /// Accepts the current state of `paths`, or of every changed file when
+    /// `paths` is `None`. Returns how many baseline entries changed.
+    pub async fn rebaseline(&self, paths: Option<Vec<String>>) -> Result<usize, String> {
+        let current = self.scan_now().await;
+        let changes: Vec<FileChange> = diff(&self.baseline.read().unwrap(), &current)
+            .into_iter()
+            .filter(|c| paths.as_ref().is_none_or(|paths| paths.contains(&c.path)))
+            .collect();
+        self.store(&changes).await.map_err(|e| e.to_string())?;
+        Ok(changes.len())
+    }
+
+    /// Changes not alerted about yet. A file alerts again when it changes
+    /// further, or changes back and then again.
+    pub fn unreported(&self, changes: &[FileChange]) -> Vec<FileChange> {
+        let key = |c: &FileChange| format!("{}:{:?}", c.path, c.after);
+        let mut alerted = self.alerted.lock().unwrap();
+        let new = changes.iter().filter(|c| !alerted.contains(&key(c))).cloned().collect();
+        *alerted = changes.iter().map(key).collect();
+        new
+    }
+
+    /// Alert text for changes found by the security monitor.
+    pub fn alert(changes: &[FileChange], lang: &Lang) -> String {
+        format!("{}\n\n{}\n\n{}",
+            i18n::t("alert.files_changed", lang),
+            list_changes(changes, "\n", "• "),
+            i18n::t("alert.files_changed_hint", lang))
+    }
+}
+
+/// Warns while watched files differ from the baseline.
+pub struct FileIntegrityCheck {
+    integrity: Arc<FileIntegrity>,
+}
+
+impl FileIntegrityCheck {
+    pub fn new(integrity: Arc<FileIntegrity>) -> Self {
+        Self { integrity }
+    }
+}
+
+impl SecurityCheckProvider for FileIntegrityCheck {
+    fn id(&self) -> &str {
+        FILE_INTEGRITY.id
+    }
+
+    fn run(&self, lang: &Lang) -> SecurityCheck {
+        let watched = self.integrity.baseline.read().unwrap().len();
+        check_integrity(&self.integrity.changes(), watched, lang)
+    }
+}
+
+fn check_integrity(changes: &[FileChange], watched: usize, lang: &Lang) -> SecurityCheck {
+    if changes.is_empty() {
+        let message = i18n::t_val("audit.integrity.pass", lang, &watched.to_string());
+        return FILE_INTEGRITY.result(CheckStatus::Pass, message, lang);
+    }
+    let message = i18n::t_val("audit.integrity.warn", lang, &changes.len().to_string());
+    FILE_INTEGRITY.result(CheckStatus::Warn, message, lang).with_evidence(list_changes(changes, "; ", ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_scan_and_diff() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("home/alice/.ssh")).unwrap();
+        std::fs::create_dir_all(dir.join("cron.d")).unwrap();
+        std::fs::write(dir.join("passwd"), "root:x:0:0::/root:/bin/bash\n").unwrap();
+        std::fs::write(dir.join("home/alice/.ssh/authorized_keys"), "ssh-ed25519 AAAA alice\n").unwrap();
+        std::fs::write(dir.join("cron.d/backup"), "0 3 * * * root /backup.sh\n").unwrap();
+        std::fs::set_permissions(dir.join("cron.d/backup"), std::fs::Permissions::from_mode(0o644)).unwrap();
+        std::os::unix::fs::symlink("/lib/systemd/system/nginx.service", dir.join("cron.d/link")).unwrap();
+
+        let root = dir.to_string_lossy();
+        let patterns = vec![
+            format!("{}/passwd", root),
+            format!("{}/home/*/.ssh/authorized_keys*", root),
+            format!("{}/cron.d", root),
+            format!("{}/missing", root),
+        ];
+        let baseline = scan(&patterns);
+        assert_eq!(baseline.len(), 4);
+        assert!(baseline.values().all(|s| s.sha256.is_some()));
+
+        std::fs::write(dir.join("passwd"), "root:x:0:0::/root:/bin/bash\nevil:x:0:0::/:/bin/sh\n").unwrap();
+        std::fs::set_permissions(dir.join("cron.d/backup"), std::fs::Permissions::from_mode(0o666)).unwrap();
+        std::fs::remove_file(dir.join("home/alice/.ssh/authorized_keys")).unwrap();
+        std::fs::write(dir.join("cron.d/miner"), "* * * * * root /tmp/x\n").unwrap();
+
+        let changes = diff(&baseline, &scan(&patterns));
+        let described: Vec<String> = changes.iter().map(|c| c.describe().replace(root.as_ref(), "")).collect();
+        assert_eq!(described, [
+            "/cron.d/backup: mode 644 -> 666",
+            "/cron.d/miner: added",
+            "/home/alice/.ssh/authorized_keys: removed",
+            "/passwd: content",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_baseline_and_alerts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("sudoers"), "root ALL=(ALL) ALL\n").unwrap();
+
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let integrity = Arc::new(FileIntegrity::new(pool, vec![dir.to_string_lossy().into_owned(), "relative".into()]));
+        integrity.migrate().await.unwrap();
+        assert_eq!(integrity.patterns.len(), 1);
+        assert!(integrity.changes().is_empty());
+
+        std::fs::write(dir.join("sudoers"), "root ALL=(ALL) ALL\nbob ALL=(ALL) NOPASSWD: ALL\n").unwrap();
+        std::fs::write(dir.join("extra"), "x").unwrap();
+        // Skip the cached scan from before the changes
+        *integrity.scanned.lock().unwrap() = None;
+        let changes = integrity.report().await.changes;
+        assert_eq!(changes.len(), 2);
+        let check = check_integrity(&changes, 1, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+
+        // Each change alerts once
+        assert_eq!(integrity.unreported(&changes).len(), 2);
+        assert!(integrity.unreported(&changes).is_empty());
+
+        let sudoers = dir.join("sudoers").to_string_lossy().into_owned();
+        assert_eq!(integrity.rebaseline(Some(vec![sudoers])).await.unwrap(), 1);
+        assert_eq!(integrity.changes().len(), 1);
+        assert_eq!(integrity.rebaseline(None).await.unwrap(), 1);
+        assert!(integrity.changes().is_empty());
+        assert_eq!(integrity.baseline.read().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,917 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::ports::{listening_sockets, ListeningSocket, Protocol};
+use crate::security::{CachedSource, SecurityAuditor};
+
+/// An audit runs several firewall checks; they share one inspection.
+const CACHE_TTL: Duration = Duration::from_secs(10);
+/// Nested chains deeper than this are not followed.
+const MAX_JUMP_DEPTH: usize = 16;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Ufw,
+    Firewalld,
+    Nftables,
+    Iptables,
+}
+
+impl Backend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Ufw => "UFW",
+            Backend::Firewalld => "firewalld",
+            Backend::Nftables => "nftables",
+            Backend::Iptables => "iptables",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    Accept,
+    Drop,
+    Reject,
+}
+
+impl Policy {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "accept" | "allow" => Some(Policy::Accept),
+            "drop" | "deny" => Some(Policy::Drop),
+            "reject" | "default" => Some(Policy::Reject),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Policy::Accept => "accept",
+            Policy::Drop => "drop",
+            Policy::Reject => "reject",
+        }
+    }
+}
+
+/// Ports a rule accepts from any source. `protocol` is `None` when the rule
+/// matches both TCP and UDP.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PortRange {
+    pub protocol: Option<Protocol>,
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    fn contains(&self, protocol: Protocol, port: u16) -> bool {
+        self.protocol.is_none_or(|p| p == protocol) && (self.start..=self.end).contains(&port)
+    }
+
+    /// `22`, `8000-8100` or a service name from `/etc/services`.
+    fn parse(spec: &str, protocol: Option<Protocol>) -> Option<Self> {
+        let (start, end) = match spec.split_once(['-', ':']) {
+            Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+            None => {
+                let port = spec.parse().ok().or_else(|| service_port(spec))?;
+                (port, port)
+            }
+        };
+        Some(Self { protocol, start, end })
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)?;
+        } else {
+            write!(f, "{}-{}", self.start, self.end)?;
+        }
+        match self.protocol {
+            Some(protocol) => write!(f, "/{}", protocol.as_str()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What the active firewall does with incoming traffic.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Ruleset {
+    pub backend: Backend,
+    /// Applies to traffic no rule accepts.
+    pub default_input: Policy,
+    /// Ports accepted from any source address.
+    pub allowed: Vec<PortRange>,
+    /// A rule accepts all incoming traffic regardless of port.
+    pub allow_all: bool,
+}
+
+impl Ruleset {
+    pub(crate) fn new(backend: Backend, default_input: Policy) -> Self {
+        Self { backend, default_input, allowed: Vec::new(), allow_all: false }
+    }
+
+    /// Lets everything in, by policy or by an accept-all rule.
+    pub fn is_open(&self) -> bool {
+        self.allow_all || self.default_input == Policy::Accept
+    }
+
+    pub fn allows(&self, protocol: Protocol, port: u16) -> bool {
+        self.is_open() || self.allowed.iter().any(|r| r.contains(protocol, port))
+    }
+
+    /// One line for check evidence, e.g. `default input drop; allowed 22/tcp, 443/tcp`.
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{}: default input {}", self.backend.name(), self.default_input.as_str());
+        if self.allow_all {
+            summary.push_str("; accept-all rule");
+        } else if !self.allowed.is_empty() {
+            let allowed: Vec<String> = self.allowed.iter().map(|r| r.to_string()).collect();
+            summary.push_str(&format!("; allowed {}", allowed.join(", ")));
+        }
+        summary
+    }
+}
+
+/// A listening socket bound to a non-loopback address.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ExposedPort {
+    pub protocol: Protocol,
+    pub address: IpAddr,
+    pub port: u16,
+    /// The firewall lets outside traffic reach it.
+    pub reachable: bool,
+}
+
+/// A port Docker publishes with a DNAT rule. Such traffic is forwarded to the
+/// container and never passes the host's INPUT chain.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DockerPublish {
+    pub protocol: Protocol,
+    pub port: u16,
+    /// Host address the port is published on; `None` means all addresses.
+    pub host_ip: Option<IpAddr>,
+    pub destination: String,
+}
+
+impl DockerPublish {
+    pub fn is_public(&self) -> bool {
+        self.host_ip.is_none_or(|ip| !ip.is_loopback())
+    }
+}
+
+impl fmt::Display for DockerPublish {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let host = self.host_ip.map(|ip| ip.to_string()).unwrap_or_else(|| "0.0.0.0".to_string());
+        write!(f, "{}:{}/{} -> {}", host, self.port, self.protocol.as_str(), self.destination)
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct FirewallReport {
+    /// `None` when no firewall filters incoming traffic.
+    pub ruleset: Option<Ruleset>,
+    pub exposed: Vec<ExposedPort>,
+    pub docker_published: Vec<DockerPublish>,
+    /// `DOCKER-USER` has rules of its own, so published ports may be filtered there.
+    pub docker_user_rules: bool,
+    /// Why a backend could not be read, e.g. missing root permissions.
+    pub errors: Vec<String>,
+}
+
+impl FirewallReport {
+    /// Exposed ports the firewall lets through, as `22/tcp`.
+    pub fn reachable(&self) -> Vec<String> {
+        let mut ports: Vec<String> = self.exposed.iter()
+            .filter(|p| p.reachable)
+            .map(|p| format!("{}/{}", p.port, p.protocol.as_str()))
+            .collect();
+        ports.dedup();
+        ports
+    }
+
+    /// Publicly published Docker ports the host firewall would otherwise block.
+    pub fn docker_bypass(&self) -> Vec<&DockerPublish> {
+        let Some(ruleset) = &self.ruleset else {
+            return Vec::new();
+        };
+        self.docker_published.iter()
+            .filter(|p| p.is_public() && !ruleset.allows(p.protocol, p.port))
+            .collect()
+    }
+}
+
+
+/// Detects the firewall backend, reads its rules and matches them against
+/// the listening sockets and Docker's published ports.
+pub fn inspect() -> FirewallReport {
+    let mut report = FirewallReport::default();
+    report.ruleset = detect(&mut report.errors);
+
+    // Docker keeps its rules in iptables whatever the host firewall is
+    if let Ok(Some(nat)) = run("iptables", &["-t", "nat", "-S", "DOCKER"]) {
+        report.docker_published = parse_docker_nat(&nat);
+    }
+    if let Ok(Some(user)) = run("iptables", &["-S", "DOCKER-USER"]) {
+        report.docker_user_rules = has_docker_user_rules(&user);
+    }
+    report.exposed = exposed_ports(&listening_sockets(), report.ruleset.as_ref(), &report.docker_published);
+    report
+}
+
+/// First backend that filters incoming traffic. UFW and firewalld manage
+/// nftables/iptables underneath, so their own view is preferred.
+fn detect(errors: &mut Vec<String>) -> Option<Ruleset> {
+    if let Some(status) = read("ufw", &["status", "verbose"], errors) {
+        match parse_ufw(&status, ufw_app_ports) {
+            Ok(Some(ruleset)) => return Some(ruleset),
+            Ok(None) => {}
+            // The nftables/iptables rules UFW generates are read below instead
+            Err(e) => errors.push(e),
+        }
+    }
+    // `--state` exits non-zero when firewalld is stopped, which is not an error
+    if let Ok(Some(state)) = run("firewall-cmd", &["--state"])
+        && state.trim() == "running"
+        && let Some(zone) = read("firewall-cmd", &["--list-all"], errors)
+    {
+        return Some(parse_firewalld(&zone, firewalld_service_ports));
+    }
+    if let Some(rules) = read("nft", &["list", "ruleset"], errors)
+        && let Some(ruleset) = parse_nft(&rules)
+    {
+        return Some(ruleset);
+    }
+    read("iptables", &["-S"], errors).and_then(|rules| parse_iptables(&rules))
+}
+
+/// Runs a firewall tool. `Ok(None)` when it is not installed.
+fn run(name: &str, args: &[&str]) -> Result<Option<String>, String> {
+    let Some(path) = SecurityAuditor::find_system_binary(name) else {
+        return Ok(None);
+    };
+    let output = Command::new(&path)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{}: {}", name, e))?;
+    if output.status.success() {
+        Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{} {}: {}", name, args.join(" "), stderr.trim()))
+    }
+}
+
+fn read(name: &str, args: &[&str], errors: &mut Vec<String>) -> Option<String> {
+    run(name, args).unwrap_or_else(|e| {
+        tracing::debug!("Firewall inspection: {}", e);
+        errors.push(e);
+        None
+    })
+}
+
+fn exposed_ports(sockets: &[ListeningSocket], ruleset: Option<&Ruleset>, docker: &[DockerPublish]) -> Vec<ExposedPort> {
+    let mut exposed: Vec<ExposedPort> = sockets.iter()
+        .filter(|s| !s.is_loopback())
+        .map(|s| ExposedPort {
+            protocol: s.protocol,
+            address: s.address,
+            port: s.port,
+            reachable: ruleset.is_none_or(|r| r.allows(s.protocol, s.port))
+                || docker.iter().any(|d| d.is_public() && d.protocol == s.protocol && d.port == s.port),
+        })
+        .collect();
+    exposed.sort_by_key(|p| (p.protocol, p.port, p.address));
+    exposed.dedup();
+    exposed
+}
+
+/// Port of a service name from `/etc/services`, e.g. `ssh` -> 22.
+fn service_port(name: &str) -> Option<u16> {
+    let services = std::fs::read_to_string("/etc/services").ok()?;
+    services.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let service = fields.next()?;
+            let (port, _) = fields.next()?.split_once('/')?;
+            (service == name || fields.any(|alias| alias == name)).then(|| port.parse().ok()).flatten()
+        })
+}
+
+impl Ruleset {
+    pub(crate) fn allow(&mut self, range: PortRange) {
+        if !self.allowed.contains(&range) {
+            self.allowed.push(range);
+        }
+    }
+}
+
+// --- UFW ---
+
+const UFW_APPS_DIR: &str = "/etc/ufw/applications.d";
+
+/// Parses `ufw status verbose`; `None` when UFW is inactive. Rules limited
+/// to a source address are not public and are left out. Output without a
+/// readable incoming policy (truncated or localized) is an error rather than
+/// a guess.
+fn parse_ufw(status: &str, app_ports: impl Fn(&str) -> Vec<PortRange>) -> Result<Option<Ruleset>, String> {
+    if !status.lines().any(|l| l.trim() == "Status: active") {
+        return Ok(None);
+    }
+    let default_input = status.lines()
+        .find_map(|l| l.strip_prefix("Default:"))
+        .and_then(|defaults| defaults.split(',').find(|d| d.contains("(incoming)")))
+        .and_then(|d| d.split_whitespace().next())
+        .and_then(Policy::parse)
+        .ok_or("ufw status verbose: no default policy for incoming traffic")?;
+    let mut ruleset = Ruleset::new(Backend::Ufw, default_input);
+
+    let rules = status.lines().skip_while(|l| !l.starts_with("--")).skip(1);
+    for line in rules {
+        let columns: Vec<&str> = line.split("  ").map(str::trim).filter(|c| !c.is_empty()).collect();
+        let [to, action, from] = columns[..] else {
+            continue;
+        };
+        if !(action.starts_with("ALLOW") || action.starts_with("LIMIT"))
+            || from.trim_end_matches(" (v6)") != "Anywhere"
+        {
+            continue;
+        }
+        let to = to.trim_end_matches(" (v6)");
+        let to = to.split(" on ").next().unwrap_or(to);
+        if to == "Anywhere" {
+            ruleset.allow_all = true;
+            continue;
+        }
+        // `22/tcp`, `80,443/tcp`, `192.168.1.10 22/tcp` or an app profile name
+        let mut ports = parse_ufw_ports(to.rsplit(' ').next().unwrap_or(to));
+        if ports.is_empty() {
+            ports = app_ports(to);
+        }
+        for range in ports {
+            ruleset.allow(range);
+        }
+    }
+    Ok(Some(ruleset))
+}
+
+/// `80,443/tcp`, `8000:8100/udp` or `53`; app profiles join specs with `|`.
+fn parse_ufw_ports(spec: &str) -> Vec<PortRange> {
+    spec.split('|')
+        .flat_map(|part| {
+            let (ports, proto) = match part.split_once('/') {
+                Some((ports, proto)) => (ports, Protocol::parse(proto)),
+                None => (part, None),
+            };
+            ports.split(',').filter_map(move |p| PortRange::parse(p, proto))
+        })
+        .collect()
+}
+
+/// Ports of an application profile such as `Nginx Full`.
+fn ufw_app_ports(name: &str) -> Vec<PortRange> {
+    let Ok(entries) = std::fs::read_dir(UFW_APPS_DIR) else {
+        return Vec::new();
+    };
+    let header = format!("[{}]", name);
+    for entry in entries.flatten() {
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let mut in_section = false;
+        for line in content.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_section = line == header;
+            } else if in_section && let Some(ports) = line.strip_prefix("ports=") {
+                return parse_ufw_ports(ports);
+            }
+        }
+    }
+    Vec::new()
+}
+
+// --- firewalld ---
+
+/// Parses `firewall-cmd --list-all` for the default zone. Rich rules are not
+/// evaluated.
+fn parse_firewalld(zone: &str, service_ports: impl Fn(&str) -> Vec<PortRange>) -> Ruleset {
+    let field = |name: &str| {
+        zone.lines()
+            .find_map(|l| l.trim().strip_prefix(name).and_then(|rest| rest.strip_prefix(':')))
+            .map(str::trim)
+            .unwrap_or_default()
+    };
+    // `default` rejects what the zone does not allow
+    let default_input = Policy::parse(field("target").trim_matches('%')).unwrap_or(Policy::Reject);
+    let mut ruleset = Ruleset::new(Backend::Firewalld, default_input);
+    for service in field("services").split_whitespace() {
+        for range in service_ports(service) {
+            ruleset.allow(range);
+        }
+    }
+    for range in parse_firewalld_ports(field("ports")) {
+        ruleset.allow(range);
+    }
+    ruleset
+}
+
+/// `22/tcp 9000-9100/udp`.
+fn parse_firewalld_ports(ports: &str) -> Vec<PortRange> {
+    ports.split_whitespace()
+        .filter_map(|p| {
+            let (port, proto) = p.split_once('/')?;
+            PortRange::parse(port, Protocol::parse(proto))
+        })
+        .collect()
+}
+
+fn firewalld_service_ports(service: &str) -> Vec<PortRange> {
+    let info = run("firewall-cmd", &[&format!("--info-service={}", service)]).ok().flatten().unwrap_or_default();
+    info.lines()
+        .find_map(|l| l.trim().strip_prefix("ports:"))
+        .map(parse_firewalld_ports)
+        .unwrap_or_default()
+}
+
+// --- nftables / iptables rules ---
+
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Accept,
+    Deny(Policy),
+    Return,
+    Jump(String),
+}
+
+/// A filter rule reduced to what decides whether outside traffic gets in.
+#[derive(Debug)]
+struct Rule {
+    verdict: Verdict,
+    /// Destination ports; `None` matches any port.
+    ports: Option<Vec<PortRange>>,
+    /// Only matches some sources, established connections or ICMP.
+    restricted: bool,
+    /// Has any match at all, e.g. an interface or rate limit.
+    conditional: bool,
+}
+
+type Chains = HashMap<String, Vec<Rule>>;
+
+/// Follows a chain the way a new connection from outside would. `scope` are
+/// the ports the jump into this chain was limited to. Returns `true` when an
+/// unconditional verdict ends the evaluation.
+fn walk(chains: &Chains, chain: &str, scope: Option<&[PortRange]>, ruleset: &mut Ruleset, depth: usize) -> bool {
+    let Some(rules) = chains.get(chain) else {
+        return false;
+    };
+    if depth > MAX_JUMP_DEPTH {
+        return false;
+    }
+    for rule in rules.iter().filter(|r| !r.restricted) {
+        let ports = rule.ports.as_deref().or(scope);
+        let unconditional = !rule.conditional;
+        match &rule.verdict {
+            Verdict::Accept => match ports {
+                Some(ports) => ports.iter().for_each(|p| ruleset.allow(p.clone())),
+                None if unconditional => {
+                    ruleset.allow_all = true;
+                    return true;
+                }
+                // Accepting everything on one interface (e.g. a VPN) is not public
+                None => {}
+            },
+            Verdict::Deny(policy) if unconditional => {
+                if scope.is_none() {
+                    ruleset.default_input = *policy;
+                    return true;
+                }
+                return false;
+            }
+            Verdict::Return if unconditional => return false,
+            Verdict::Jump(target) if walk(chains, target, ports, ruleset, depth + 1) && unconditional => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Parses `nft list ruleset`; `None` when no chain hooks into input. Several
+/// input chains all have to accept a packet, so the ones that drop by default
+/// decide.
+fn parse_nft(text: &str) -> Option<Ruleset> {
+    let mut chains: Chains = HashMap::new();
+    let mut base: Vec<(String, Policy)> = Vec::new();
+    let mut table = String::new();
+    let mut chain: Option<String> = None;
+    let mut depth = 0usize;
+
+    for line in text.lines().map(str::trim) {
+        let opens = line.matches('{').count();
+        let closes = line.matches('}').count();
+        if depth == 0 && let Some(rest) = line.strip_prefix("table ") {
+            table = rest.trim_end_matches('{').trim().to_string();
+        } else if depth == 1 && let Some(rest) = line.strip_prefix("chain ") {
+            let name = rest.trim_end_matches('{').trim();
+            chain = Some(format!("{} {}", table, name));
+        } else if depth == 2 && let Some(current) = &chain {
+            if line.starts_with("type ") {
+                if line.contains("hook input") {
+                    let policy = line.split("policy ").nth(1)
+                        .and_then(|p| Policy::parse(p.trim_end_matches(';').trim()))
+                        .unwrap_or(Policy::Accept);
+                    base.push((current.clone(), policy));
+                }
+            } else if let Some(mut rule) = parse_nft_rule(line) {
+                // Jump targets are chains of the same table
+                if let Verdict::Jump(target) = &rule.verdict {
+                    rule.verdict = Verdict::Jump(format!("{} {}", table, target));
+                }
+                chains.entry(current.clone()).or_default().push(rule);
+            }
+        }
+        depth = (depth + opens).saturating_sub(closes);
+        if depth < 2 {
+            chain = None;
+        }
+    }
+
+    if base.is_empty() {
+        return None;
+    }
+    let evaluated: Vec<Ruleset> = base.iter()
+        .map(|(name, policy)| {
+            let mut ruleset = Ruleset::new(Backend::Nftables, *policy);
+            walk(&chains, name, None, &mut ruleset, 0);
+            ruleset
+        })
+        .collect();
+    let mut closed = evaluated.iter().filter(|r| !r.is_open());
+    let Some(first) = closed.next() else {
+        return evaluated.into_iter().next();
+    };
+    let mut merged = first.clone();
+    for ruleset in closed {
+        ruleset.allowed.iter().for_each(|p| merged.allow(p.clone()));
+    }
+    Some(merged)
+}
+
+fn parse_nft_rule(line: &str) -> Option<Rule> {
+    let line = line.split(" comment \"").next().unwrap_or(line);
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let at = tokens.iter().position(|t| matches!(*t, "accept" | "drop" | "reject" | "return" | "jump" | "goto"))?;
+    let verdict = match tokens[at] {
+        "accept" => Verdict::Accept,
+        "drop" => Verdict::Deny(Policy::Drop),
+        "reject" => Verdict::Deny(Policy::Reject),
+        "return" => Verdict::Return,
+        _ => Verdict::Jump(tokens.get(at + 1)?.to_string()),
+    };
+    let cond = &tokens[..at];
+
+    let conditional = cond.iter().any(|t| !matches!(*t, "counter" | "packets" | "bytes") && t.parse::<u64>().is_err());
+    let mut rule = Rule { verdict, ports: None, restricted: false, conditional };
+    for (i, token) in cond.iter().enumerate() {
+        let next = cond.get(i + 1).copied().unwrap_or_default();
+        match *token {
+            "iif" | "iifname" if next.trim_matches('"') == "lo" => return None,
+            "saddr" | "icmp" | "icmpv6" | "ipv6-icmp" | "pkttype" | "fib" | "!=" | "@nh" => rule.restricted = true,
+            "ct" if next != "state" || !cond.get(i + 2).is_some_and(|s| s.contains("new")) => rule.restricted = true,
+            "dport" => {
+                let proto = i.checked_sub(1).and_then(|p| Protocol::parse(cond[p]));
+                let specs: Vec<&str> = if next == "{" {
+                    cond[i + 2..].iter().take_while(|t| **t != "}").map(|t| t.trim_end_matches(',')).collect()
+                } else {
+                    vec![next]
+                };
+                let ports: Vec<PortRange> = specs.iter().filter_map(|s| PortRange::parse(s, proto)).collect();
+                // Named sets (`@ports`) and other forms cannot be resolved here
+                if ports.len() != specs.len() || ports.is_empty() {
+                    rule.restricted = true;
+                }
+                rule.ports = Some(ports);
+            }
+            _ => {}
+        }
+    }
+    Some(rule)
+}
+
+/// Parses `iptables -S`; `None` when INPUT accepts everything and has no rules.
+fn parse_iptables(text: &str) -> Option<Ruleset> {
+    let mut chains: Chains = HashMap::new();
+    let mut policy = Policy::Accept;
+    for line in text.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["-P", "INPUT", target] => policy = Policy::parse(target).unwrap_or(Policy::Accept),
+            ["-A", chain, args @ ..] => {
+                if let Some(rule) = parse_iptables_rule(args) {
+                    chains.entry(chain.to_string()).or_default().push(rule);
+                }
+            }
+            _ => {}
+        }
+    }
+    if policy == Policy::Accept && !chains.contains_key("INPUT") {
+        return None;
+    }
+    let mut ruleset = Ruleset::new(Backend::Iptables, policy);
+    walk(&chains, "INPUT", None, &mut ruleset, 0);
+    Some(ruleset)
+}
+
+fn parse_iptables_rule(args: &[&str]) -> Option<Rule> {
+    let conditional = args.iter()
+        .any(|a| a.starts_with('-') && !matches!(*a, "-j" | "--jump" | "-g" | "--goto" | "-m" | "--comment"));
+    let mut rule = Rule { verdict: Verdict::Return, ports: None, restricted: false, conditional };
+    let mut proto = None;
+    let mut target = None;
+    let mut ports = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        let value = args.get(i + 1).copied().unwrap_or_default();
+        match *arg {
+            "-i" | "--in-interface" if value == "lo" => return None,
+            "-s" | "--source" if value != "0.0.0.0/0" => rule.restricted = true,
+            "!" | "--src-range" | "--match-set" => rule.restricted = true,
+            "-p" | "--protocol" => {
+                proto = Protocol::parse(value);
+                if value.contains("icmp") {
+                    rule.restricted = true;
+                }
+            }
+            "--state" | "--ctstate" if !value.contains("NEW") => rule.restricted = true,
+            "--dport" | "--destination-port" | "--dports" | "--destination-ports" => {
+                ports.extend(value.split(',').map(|p| (p, proto)));
+            }
+            "-j" | "--jump" | "-g" | "--goto" => target = Some(value),
+            _ => {}
+        }
+    }
+    rule.verdict = match target? {
+        "ACCEPT" => Verdict::Accept,
+        "DROP" => Verdict::Deny(Policy::Drop),
+        "REJECT" => Verdict::Deny(Policy::Reject),
+        "RETURN" => Verdict::Return,
+        chain => Verdict::Jump(chain.to_string()),
+    };
+    if !ports.is_empty() {
+        rule.ports = Some(ports.into_iter().filter_map(|(p, proto)| PortRange::parse(p, proto)).collect());
+    }
+    Some(rule)
+}
+
+// --- Docker ---
+
+/// DNAT rules from `iptables -t nat -S DOCKER`.
+fn parse_docker_nat(text: &str) -> Vec<DockerPublish> {
+    text.lines()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if !tokens.windows(2).any(|w| w == ["-j", "DNAT"]) {
+                return None;
+            }
+            let value = |flag: &str| tokens.iter().position(|t| *t == flag).and_then(|i| tokens.get(i + 1)).copied();
+            Some(DockerPublish {
+                protocol: Protocol::parse(value("-p")?)?,
+                port: value("--dport")?.parse().ok()?,
+                host_ip: value("-d").and_then(|d| d.split('/').next()?.parse().ok()),
+                destination: value("--to-destination")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Docker creates `DOCKER-USER` with a lone `RETURN`; anything else was added
+/// to filter published ports.
+fn has_docker_user_rules(text: &str) -> bool {
+    text.lines()
+        .map(str::trim)
+        .any(|l| l.starts_with("-A DOCKER-USER") && l != "-A DOCKER-USER -j RETURN")
+}
+
+/// Inspection shared by all firewall checks of an audit, refreshed after `CACHE_TTL`.
+pub fn source() -> CachedSource<FirewallReport> {
+    CachedSource::new(CACHE_TTL, || Ok(inspect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(protocol: Option<Protocol>, start: u16, end: u16) -> PortRange {
+        PortRange { protocol, start, end }
+    }
+
+    fn socket(protocol: Protocol, address: &str, port: u16) -> ListeningSocket {
+        ListeningSocket { protocol, address: address.parse().unwrap(), port, inode: 0 }
+    }
+
+    #[test]
+    fn test_parse_ufw() {
+        let status = "\
+Status: active
+Logging: on (low)
+Default: deny (incoming), allow (outgoing), disabled (routed)
+New profiles: skip
+
+To                         Action      From
+--                         ------      ----
+22/tcp                     LIMIT IN    Anywhere
+80,443/tcp                 ALLOW IN    Anywhere
+3000                       DENY IN     Anywhere
+5432/tcp                   ALLOW IN    10.0.0.0/8
+8000:8100/udp              ALLOW IN    Anywhere
+Nginx Full                 ALLOW IN    Anywhere
+22/tcp (v6)                LIMIT IN    Anywhere (v6)
+";
+        let app = |name: &str| if name == "Nginx Full" { parse_ufw_ports("80,443/tcp") } else { Vec::new() };
+        let ruleset = parse_ufw(status, app).unwrap().unwrap();
+        assert_eq!(ruleset.backend, Backend::Ufw);
+        assert_eq!(ruleset.default_input, Policy::Drop);
+        assert!(!ruleset.allow_all);
+        let tcp = Some(Protocol::Tcp);
+        assert_eq!(ruleset.allowed, vec![
+            range(tcp, 22, 22),
+            range(tcp, 80, 80),
+            range(tcp, 443, 443),
+            range(Some(Protocol::Udp), 8000, 8100),
+        ]);
+        assert!(ruleset.allows(Protocol::Udp, 8050));
+        assert!(!ruleset.allows(Protocol::Tcp, 5432));
+        assert!(!ruleset.allows(Protocol::Tcp, 3000));
+        assert_eq!(ruleset.summary(), "UFW: default input drop; allowed 22/tcp, 80/tcp, 443/tcp, 8000-8100/udp");
+
+        assert!(parse_ufw("Status: inactive\n", app).unwrap().is_none());
+        // Without the policy line nothing says the default is accept
+        assert!(parse_ufw("Status: active\n\nTo Action From\n-- ------ ----\n", app).is_err());
+
+        let allow_all = "Status: active\nDefault: deny (incoming), allow (outgoing)\n\nTo Action From\n-- ------ ----\nAnywhere                   ALLOW IN    Anywhere\n";
+        let ruleset = parse_ufw(allow_all, app).unwrap().unwrap();
+        assert!(ruleset.allow_all);
+        assert!(ruleset.is_open());
+    }
+
+    #[test]
+    fn test_parse_firewalld() {
+        let zone = "\
+public (active)
+  target: default
+  icmp-block-inversion: no
+  interfaces: eth0
+  sources: 
+  services: dhcpv6-client ssh
+  ports: 8080/tcp 9000-9100/udp
+  protocols: 
+  rich rules: 
+";
+        let services = |name: &str| if name == "ssh" { parse_firewalld_ports("22/tcp") } else { Vec::new() };
+        let ruleset = parse_firewalld(zone, services);
+        assert_eq!(ruleset.default_input, Policy::Reject);
+        assert!(ruleset.allows(Protocol::Tcp, 22));
+        assert!(ruleset.allows(Protocol::Tcp, 8080));
+        assert!(ruleset.allows(Protocol::Udp, 9050));
+        assert!(!ruleset.allows(Protocol::Tcp, 3306));
+
+        let trusted = parse_firewalld("trusted (active)\n  target: ACCEPT\n", services);
+        assert!(trusted.is_open());
+    }
+
+    #[test]
+    fn test_parse_nft() {
+        let text = r#"
+table inet filter {
+	set blocked {
+		type ipv4_addr
+		elements = { 192.0.2.1, 192.0.2.2 }
+	}
+
+	chain input {
+		type filter hook input priority filter; policy drop;
+		ct state established,related accept
+		iif "lo" accept
+		ip saddr 10.0.0.0/8 tcp dport 5432 accept
+		ip protocol icmp accept
+		tcp dport 22 ct state new counter packets 10 bytes 600 accept comment "ssh"
+		tcp dport { 80, 443 } accept
+		udp dport 51820 accept
+		jump services
+		iifname "wg0" accept
+	}
+
+	chain services {
+		tcp dport 9000-9100 accept
+		return
+	}
+
+	chain forward {
+		type filter hook forward priority filter; policy drop;
+		accept
+	}
+}
+"#;
+        let ruleset = parse_nft(text).unwrap();
+        assert_eq!(ruleset.backend, Backend::Nftables);
+        assert_eq!(ruleset.default_input, Policy::Drop);
+        assert!(!ruleset.allow_all);
+        let tcp = Some(Protocol::Tcp);
+        assert_eq!(ruleset.allowed, vec![
+            range(tcp, 22, 22),
+            range(tcp, 80, 80),
+            range(tcp, 443, 443),
+            range(Some(Protocol::Udp), 51820, 51820),
+            range(tcp, 9000, 9100),
+        ]);
+
+        let open = "table inet filter {\n\tchain input {\n\t\ttype filter hook input priority filter; policy accept;\n\t}\n}\n";
+        assert!(parse_nft(open).unwrap().is_open());
+        assert!(parse_nft("table ip nat {\n}\n").is_none());
+
+        // A trailing catch-all drop closes an accept policy
+        let catch_all = "table ip filter {\n\tchain INPUT {\n\t\ttype filter hook input priority filter; policy accept;\n\t\tmeta l4proto tcp tcp dport 22 counter packets 0 bytes 0 accept\n\t\tcounter packets 0 bytes 0 reject with icmp type port-unreachable\n\t}\n}\n";
+        let ruleset = parse_nft(catch_all).unwrap();
+        assert_eq!(ruleset.default_input, Policy::Reject);
+        assert_eq!(ruleset.allowed, vec![range(tcp, 22, 22)]);
+    }
+
+    #[test]
+    fn test_parse_iptables() {
+        let text = "\
+-P INPUT DROP
+-P FORWARD DROP
+-P OUTPUT ACCEPT
+-N ssh-limit
+-A INPUT -i lo -j ACCEPT
+-A INPUT -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT
+-A INPUT -p icmp -j ACCEPT
+-A INPUT -s 192.168.0.0/16 -p tcp -m tcp --dport 3306 -j ACCEPT
+-A INPUT -p tcp -m tcp --dport 22 -j ssh-limit
+-A INPUT -p tcp -m multiport --dports 80,443 -j ACCEPT
+-A INPUT -p udp -m udp --dport 60000:61000 -j ACCEPT
+-A ssh-limit -m recent --update --seconds 60 --hitcount 5 -j DROP
+-A ssh-limit -j ACCEPT
+";
+        let ruleset = parse_iptables(text).unwrap();
+        assert_eq!(ruleset.backend, Backend::Iptables);
+        assert_eq!(ruleset.default_input, Policy::Drop);
+        assert!(!ruleset.allow_all);
+        let tcp = Some(Protocol::Tcp);
+        assert_eq!(ruleset.allowed, vec![
+            range(tcp, 22, 22),
+            range(tcp, 80, 80),
+            range(tcp, 443, 443),
+            range(Some(Protocol::Udp), 60000, 61000),
+        ]);
+
+        assert!(parse_iptables("-P INPUT ACCEPT\n-P FORWARD ACCEPT\n-P OUTPUT ACCEPT\n").is_none());
+        let allow_all = parse_iptables("-P INPUT DROP\n-A INPUT -j ACCEPT\n").unwrap();
+        assert!(allow_all.is_open());
+    }
+
+    #[test]
+    fn test_docker_bypass() {
+        let nat = "\
+-N DOCKER
+-A DOCKER -i docker0 -j RETURN
+-A DOCKER ! -i docker0 -p tcp -m tcp --dport 8080 -j DNAT --to-destination 172.17.0.2:80
+-A DOCKER -d 127.0.0.1/32 ! -i docker0 -p tcp -m tcp --dport 5432 -j DNAT --to-destination 172.17.0.3:5432
+-A DOCKER ! -i docker0 -p tcp -m tcp --dport 443 -j DNAT --to-destination 172.17.0.4:443
+";
+        let published = parse_docker_nat(nat);
+        assert_eq!(published.len(), 3);
+        assert_eq!(published[0].to_string(), "0.0.0.0:8080/tcp -> 172.17.0.2:80");
+        assert!(!published[1].is_public());
+
+        let mut ruleset = Ruleset::new(Backend::Ufw, Policy::Drop);
+        ruleset.allow(range(Some(Protocol::Tcp), 443, 443));
+        let sockets = [
+            socket(Protocol::Tcp, "0.0.0.0", 8080),
+            socket(Protocol::Tcp, "0.0.0.0", 443),
+            socket(Protocol::Tcp, "127.0.0.1", 5432),
+            socket(Protocol::Tcp, "0.0.0.0", 3000),
+            socket(Protocol::Tcp, "::", 3000),
+        ];
+        let report = FirewallReport {
+            exposed: exposed_ports(&sockets, Some(&ruleset), &published),
+            ruleset: Some(ruleset),
+            docker_published: published,
+            ..Default::default()
+        };
+        assert_eq!(report.exposed.len(), 4);
+        assert_eq!(report.reachable(), vec!["443/tcp", "8080/tcp"]);
+        let bypass = report.docker_bypass();
+        assert_eq!(bypass.len(), 1);
+        assert_eq!(bypass[0].port, 8080);
+
+        assert!(!has_docker_user_rules("-N DOCKER-USER\n-A DOCKER-USER -j RETURN\n"));
+        assert!(has_docker_user_rules("-N DOCKER-USER\n-A DOCKER-USER -i eth0 -p tcp -m tcp --dport 8080 -j DROP\n-A DOCKER-USER -j RETURN\n"));
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use crate::firewall::FirewallReport;
+use crate::i18n::{self, Lang};
+use crate::security::{
+    CachedSource, CheckSpec, CheckStatus, SecurityCheck, Severity, SourcedCheck, SourcedCheckFn, CHECK_FIREWALL,
+    CHECK_FIREWALL_DOCKER_BYPASS,
+};
+
+pub(crate) const FIREWALL: CheckSpec = CheckSpec {
+    id: CHECK_FIREWALL,
+    key: "firewall",
+    severity: Severity::High,
+    categories: &["firewall", "network"],
+};
+
+const DOCKER_BYPASS: CheckSpec = CheckSpec {
+    id: CHECK_FIREWALL_DOCKER_BYPASS,
+    key: "fw_docker",
+    severity: Severity::High,
+    categories: &["firewall", "docker", "network"],
+};
+
+pub fn firewall_checks(source: Arc<CachedSource<FirewallReport>>) -> Vec<SourcedCheck<FirewallReport>> {
+    let checks: [(&'static CheckSpec, SourcedCheckFn<FirewallReport>); 2] = [
+        (&FIREWALL, check_firewall),
+        (&DOCKER_BYPASS, check_docker_bypass),
+    ];
+    SourcedCheck::all(source, checks)
+}
+
+fn check_firewall(report: &FirewallReport, lang: &Lang) -> SecurityCheck {
+    let Some(ruleset) = &report.ruleset else {
+        if !report.errors.is_empty() {
+            return FIREWALL.warn("audit.firewall.error", lang).with_evidence(report.errors.join("; "));
+        }
+        return FIREWALL.fail("audit.firewall.fail", lang);
+    };
+
+    let mut evidence = ruleset.summary();
+    let reachable = report.reachable();
+    if !reachable.is_empty() {
+        evidence.push_str(&format!("; reachable {}", reachable.join(", ")));
+    }
+    let backend = ruleset.backend.name();
+    if ruleset.is_open() {
+        let message = i18n::t_val("audit.firewall.open", lang, backend);
+        return FIREWALL.result(CheckStatus::Fail, message, lang).with_evidence(evidence);
+    }
+    let message = i18n::t_val("audit.firewall.pass", lang, backend);
+    FIREWALL.result(CheckStatus::Pass, message, lang).with_evidence(evidence)
+}
+
+fn check_docker_bypass(report: &FirewallReport, lang: &Lang) -> SecurityCheck {
+    let bypass = report.docker_bypass();
+    if bypass.is_empty() {
+        return DOCKER_BYPASS.pass("audit.fw_docker.pass", lang);
+    }
+    let ports: Vec<String> = bypass.iter().map(|p| format!("{}/{}", p.port, p.protocol.as_str())).collect();
+    let evidence: Vec<String> = bypass.iter().map(|p| p.to_string()).collect();
+    // Rules in DOCKER-USER may already filter these; we cannot tell what they match
+    let (status, key) = if report.docker_user_rules {
+        (CheckStatus::Warn, "audit.fw_docker.filtered")
+    } else {
+        (CheckStatus::Fail, "audit.fw_docker.fail")
+    };
+    let message = i18n::t_val(key, lang, &ports.join(", "));
+    DOCKER_BYPASS.result(status, message, lang).with_evidence(evidence.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firewall::{Backend, DockerPublish, ExposedPort, Policy, PortRange, Ruleset};
+    use crate::ports::Protocol;
+
+    fn ufw(ports: &[u16]) -> Ruleset {
+        let mut ruleset = Ruleset::new(Backend::Ufw, Policy::Drop);
+        for port in ports {
+            ruleset.allow(PortRange { protocol: Some(Protocol::Tcp), start: *port, end: *port });
+        }
+        ruleset
+    }
+
+    fn exposed(port: u16, reachable: bool) -> ExposedPort {
+        ExposedPort { protocol: Protocol::Tcp, address: "0.0.0.0".parse().unwrap(), port, reachable }
+    }
+
+    #[test]
+    fn test_check_firewall() {
+        let none = FirewallReport::default();
+        assert_eq!(check_firewall(&none, &Lang::EN).status, CheckStatus::Fail);
+
+        let unreadable = FirewallReport { errors: vec!["nft list ruleset: Operation not permitted".into()], ..Default::default() };
+        let check = check_firewall(&unreadable, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.evidence.unwrap().contains("Operation not permitted"));
+
+        let closed = FirewallReport {
+            ruleset: Some(ufw(&[22])),
+            exposed: vec![exposed(22, true), exposed(5432, false)],
+            ..Default::default()
+        };
+        let check = check_firewall(&closed, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert!(check.message.contains("UFW"));
+        assert_eq!(check.evidence.as_deref(), Some("UFW: default input drop; allowed 22/tcp; reachable 22/tcp"));
+
+        let mut allow_all = ufw(&[]);
+        allow_all.allow_all = true;
+        let open = FirewallReport { ruleset: Some(allow_all), ..Default::default() };
+        let check = check_firewall(&open, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.remediation.is_some());
+    }
+
+    #[test]
+    fn test_check_docker_bypass() {
+        let published = vec![
+            DockerPublish { protocol: Protocol::Tcp, port: 8080, host_ip: None, destination: "172.17.0.2:80".into() },
+            DockerPublish { protocol: Protocol::Tcp, port: 443, host_ip: None, destination: "172.17.0.3:443".into() },
+            DockerPublish {
+                protocol: Protocol::Tcp,
+                port: 5432,
+                host_ip: Some("127.0.0.1".parse().unwrap()),
+                destination: "172.17.0.4:5432".into(),
+            },
+        ];
+        let mut report = FirewallReport { ruleset: Some(ufw(&[22, 443])), docker_published: published, ..Default::default() };
+        let check = check_docker_bypass(&report, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.message.contains("8080/tcp"));
+        assert_eq!(check.evidence.as_deref(), Some("0.0.0.0:8080/tcp -> 172.17.0.2:80"));
+
+        report.docker_user_rules = true;
+        assert_eq!(check_docker_bypass(&report, &Lang::EN).status, CheckStatus::Warn);
+
+        // Without a firewall there is nothing to bypass; the firewall check fails instead
+        report.ruleset = None;
+        assert_eq!(check_docker_bypass(&report, &Lang::EN).status, CheckStatus::Pass);
+    }
+}
@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::docker::{ContainerInfo, DockerService};
+use crate::history::{DeploymentRecord, HistoryManager};
+use crate::i18n::{self, Lang};
+use crate::notifications::NotificationService;
+use crate::util::load_json_config;
+
+/// Auto-healing rule for containers that are running but report `unhealthy`.
+///
+/// A rule targets either a container by name or every container carrying a label
+/// (`key` or `key=value`). The first matching rule in the file wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealingRule {
+    pub name: String,
+    #[serde(default)]
+    pub container: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// How long the container must stay unhealthy before it is restarted
+    #[serde(default = "default_unhealthy_for_secs")]
+    pub unhealthy_for_secs: u64,
+    /// Restart budget per `window_secs`; once spent the rule only alerts
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_unhealthy_for_secs() -> u64 { 180 }
+fn default_max_restarts() -> u32 { 3 }
+fn default_window_secs() -> u64 { 3600 }
+
+impl HealingRule {
+    pub fn matches(&self, container: &ContainerInfo) -> bool {
+        if let Some(name) = &self.container {
+            return container.name == *name;
+        }
+        if let Some(selector) = &self.label {
+            return match selector.split_once('=') {
+                Some((key, value)) => container.labels.get(key).is_some_and(|v| v == value),
+                None => container.labels.contains_key(selector),
+            };
+        }
+        false
+    }
+}
+
+/// Rules from a JSON array; without the file there are none.
+pub fn load_rules(path: &str) -> Result<Vec<HealingRule>, String> {
+    let rules: Vec<HealingRule> = load_json_config(path, "healing rules", Vec::new())?;
+
+    for rule in &rules {
+        if rule.container.is_none() && rule.label.is_none() {
+            return Err(format!("Healing rule '{}' needs either `container` or `label`", rule.name));
+        }
+    }
+    Ok(rules)
+}
+
+#[derive(Debug, PartialEq)]
+enum Decision {
+    Wait,
+    Restart,
+    Alert,
+}
+
+/// Per-container state kept between evaluations.
+#[derive(Default)]
+struct Track {
+    unhealthy_since: Option<Instant>,
+    restarts: VecDeque<Instant>,
+    alerted: bool,
+}
+
+impl Track {
+    fn evaluate(&mut self, rule: &HealingRule, unhealthy: bool, now: Instant) -> Decision {
+        let window = Duration::from_secs(rule.window_secs);
+        while self.restarts.front().is_some_and(|t| now.duration_since(*t) > window) {
+            self.restarts.pop_front();
+        }
+
+        if !unhealthy {
+            self.unhealthy_since = None;
+            self.alerted = false;
+            return Decision::Wait;
+        }
+
+        let since = *self.unhealthy_since.get_or_insert(now);
+        if now.duration_since(since) < Duration::from_secs(rule.unhealthy_for_secs) {
+            return Decision::Wait;
+        }
+
+        if self.restarts.len() < rule.max_restarts as usize {
+            self.restarts.push_back(now);
+            // Give the restarted container a full grace period before judging it again
+            self.unhealthy_since = None;
+            Decision::Restart
+        } else if !self.alerted {
+            self.alerted = true;
+            Decision::Alert
+        } else {
+            Decision::Wait
+        }
+    }
+}
+
+pub struct HealingService {
+    docker: Arc<DockerService>,
+    notifier: Arc<NotificationService>,
+    history: Arc<HistoryManager>,
+    rules: Vec<HealingRule>,
+    tracks: Mutex<HashMap<String, Track>>,
+}
+
+impl HealingService {
+    pub fn new(
+        docker: Arc<DockerService>,
+        notifier: Arc<NotificationService>,
+        history: Arc<HistoryManager>,
+        rules: Vec<HealingRule>,
+    ) -> Self {
+        Self {
+            docker,
+            notifier,
+            history,
+            rules,
+            tracks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Evaluates the rules every `HEALING_INTERVAL` seconds (default 30).
+    pub async fn run_loop(self: Arc<Self>) {
+        let secs = std::env::var("HEALING_INTERVAL")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(30u64);
+        tracing::info!("Starting container auto-healing loop ({} rules, every {}s)", self.rules.len(), secs);
+
+        let mut interval = tokio::time::interval(Duration::from_secs(secs));
+        loop {
+            interval.tick().await;
+            if self.docker.is_connected() {
+                self.check_once().await;
+            }
+        }
+    }
+
+    async fn check_once(&self) {
+        let containers = match self.docker.list_containers().await {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::warn!("Auto-healing: {}", e);
+                return;
+            }
+        };
+
+        let mut seen = Vec::new();
+        for container in containers.iter().filter(|c| c.state == "running") {
+            let Some(rule) = self.rules.iter().find(|r| r.matches(container)) else {
+                continue;
+            };
+            seen.push(container.id.clone());
+
+            let unhealthy = match self.docker.container_health(&container.id).await {
+                Ok(health) => health.as_deref() == Some("unhealthy"),
+                Err(e) => {
+                    tracing::warn!("Auto-healing: {}", e);
+                    continue;
+                }
+            };
+
+            let decision = {
+                let mut tracks = self.tracks.lock().unwrap();
+                tracks.entry(container.id.clone())
+                    .or_default()
+                    .evaluate(rule, unhealthy, Instant::now())
+            };
+
+            match decision {
+                Decision::Wait => {}
+                Decision::Restart => self.restart(container, rule).await,
+                Decision::Alert => self.give_up(container, rule).await,
+            }
+        }
+
+        // Forget containers that are gone or stopped so the map does not grow forever
+        self.tracks.lock().unwrap().retain(|id, _| seen.contains(id));
+    }
+
+    async fn restart(&self, container: &ContainerInfo, rule: &HealingRule) {
+        let lang = Lang::from_headers(&i18n::HeaderMap::new());
+        let result = self.docker.restart_container(&container.id).await;
+
+        let (status, message) = match &result {
+            Ok(()) => ("success", format!("{}: {}", i18n::t("heal.restarted", &lang), container.name)),
+            Err(e) => ("failed", format!("{}: {} ({})", i18n::t("heal.failed", &lang), container.name, e)),
+        };
+        tracing::warn!("Auto-healing rule '{}': {}", rule.name, message);
+
+        let details = format!("Restarted after being unhealthy for {}s (rule: {})", rule.unhealthy_for_secs, rule.name);
+        self.record(container, status, details).await;
+        self.notifier.send_alert(&message).await;
+    }
+
+    /// The restart budget is spent: the container is left alone, which is logged like a restart.
+    async fn give_up(&self, container: &ContainerInfo, rule: &HealingRule) {
+        let lang = Lang::from_headers(&i18n::HeaderMap::new());
+        tracing::warn!("Auto-healing rule '{}': restart budget spent for {}", rule.name, container.name);
+
+        let details = format!(
+            "Not restarted: {} restarts in {}s already used (rule: {})",
+            rule.max_restarts, rule.window_secs, rule.name
+        );
+        self.record(container, "failed", details).await;
+        self.notifier.send_alert(&format!(
+            "{}: {} ({} / {}s)",
+            i18n::t("heal.exhausted", &lang), container.name, rule.max_restarts, rule.window_secs
+        )).await;
+    }
+
+    async fn record(&self, container: &ContainerInfo, status: &str, details: String) {
+        self.history.add_record(DeploymentRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now(),
+            action: "auto_heal".to_string(),
+            details,
+            status: status.to_string(),
+            image_id: Some(container.image_id.clone()).filter(|s| !s.is_empty()),
+            previous_image_id: None,
+            container_name: Some(container.name.clone()),
+            rollback_of: None,
+            exit_code: None,
+            duration_ms: None,
+        }).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> HealingRule {
+        HealingRule {
+            name: "web".to_string(),
+            container: Some("web".to_string()),
+            label: None,
+            unhealthy_for_secs: 180,
+            max_restarts: 2,
+            window_secs: 3600,
+        }
+    }
+
+    fn container(name: &str, labels: &[(&str, &str)]) -> ContainerInfo {
+        ContainerInfo {
+            id: format!("{}-id", name),
+            name: name.to_string(),
+            image: "nginx:latest".to_string(),
+            status: "Up 5 minutes (unhealthy)".to_string(),
+            state: "running".to_string(),
+            ports: String::new(),
+            image_id: String::new(),
+            labels: labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            update_available: false,
+        }
+    }
+
+    #[test]
+    fn test_rule_matches_by_name_and_label() {
+        assert!(rule().matches(&container("web", &[])));
+        assert!(!rule().matches(&container("db", &[])));
+
+        let by_label = HealingRule { container: None, label: Some("mini-ops.heal=true".to_string()), ..rule() };
+        assert!(by_label.matches(&container("api", &[("mini-ops.heal", "true")])));
+        assert!(!by_label.matches(&container("api", &[("mini-ops.heal", "false")])));
+
+        let by_key = HealingRule { container: None, label: Some("mini-ops.heal".to_string()), ..rule() };
+        assert!(by_key.matches(&container("api", &[("mini-ops.heal", "")])));
+    }
+
+    #[test]
+    fn test_waits_for_unhealthy_period_before_restart() {
+        let mut track = Track::default();
+        let start = Instant::now();
+        assert_eq!(track.evaluate(&rule(), true, start), Decision::Wait);
+        assert_eq!(track.evaluate(&rule(), true, start + Duration::from_secs(60)), Decision::Wait);
+        assert_eq!(track.evaluate(&rule(), true, start + Duration::from_secs(180)), Decision::Restart);
+    }
+
+    #[test]
+    fn test_recovery_resets_timer() {
+        let mut track = Track::default();
+        let start = Instant::now();
+        track.evaluate(&rule(), true, start);
+        track.evaluate(&rule(), false, start + Duration::from_secs(100));
+        assert_eq!(track.evaluate(&rule(), true, start + Duration::from_secs(200)), Decision::Wait);
+    }
+
+    #[test]
+    fn test_restart_budget_then_single_alert() {
+        let mut track = Track::default();
+        let mut now = Instant::now();
+        let mut decisions = Vec::new();
+        for _ in 0..5 {
+            track.evaluate(&rule(), true, now);
+            now += Duration::from_secs(180);
+            decisions.push(track.evaluate(&rule(), true, now));
+        }
+        assert_eq!(decisions, vec![
+            Decision::Restart,
+            Decision::Restart,
+            Decision::Alert,
+            Decision::Wait,
+            Decision::Wait,
+        ]);
+    }
+
+    #[test]
+    fn test_budget_refills_after_window() {
+        let mut track = Track::default();
+        let start = Instant::now();
+        let r = HealingRule { max_restarts: 1, unhealthy_for_secs: 0, ..rule() };
+        assert_eq!(track.evaluate(&r, true, start), Decision::Restart);
+        assert_eq!(track.evaluate(&r, true, start + Duration::from_secs(10)), Decision::Alert);
+        assert_eq!(track.evaluate(&r, true, start + Duration::from_secs(3700)), Decision::Restart);
+    }
+
+    #[test]
+    fn test_load_rules_requires_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("healing.json");
+        std::fs::write(&path, r#"[{"name": "broken"}]"#).unwrap();
+        assert!(load_rules(path.to_str().unwrap()).is_err());
+
+        std::fs::write(&path, r#"[{"name": "web", "container": "web"}]"#).unwrap();
+        let rules = load_rules(path.to_str().unwrap()).unwrap();
+        assert_eq!(rules[0].unhealthy_for_secs, 180);
+        assert_eq!(rules[0].max_restarts, 3);
+        assert_eq!(rules[0].window_secs, 3600);
+
+        assert!(load_rules("/nonexistent/healing.json").unwrap().is_empty());
+    }
+}
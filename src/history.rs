@@ -1,6 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
+use sqlx::{Row, SqlitePool};
 use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,43 +7,423 @@ pub struct DeploymentRecord {
     pub id: String,
     pub timestamp: DateTime<Utc>,
     pub action: String, // "update", "rollback"
-    pub details: String, 
-    pub status: String, // "success", "failed"
+    pub details: String,
+    pub status: String, // "in_progress", "success", "failed", "interrupted"
     pub image_id: Option<String>,
+    /// Image the container ran before this deployment; what a rollback restores
+    #[serde(default)]
+    pub previous_image_id: Option<String>,
     pub container_name: Option<String>,
+    /// For rollbacks: id of the deployment that was rolled back
+    #[serde(default)]
+    pub rollback_of: Option<String>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub duration_ms: Option<i64>,
 }
 
+impl DeploymentRecord {
+    /// Image that rolling back this deployment restores: the one that ran before it.
+    /// Records without `previous_image_id` (written before it existed, auto-heals) fall
+    /// back to the recorded `image_id`.
+    pub fn rollback_image(&self) -> Option<&str> {
+        self.previous_image_id.as_deref().or(self.image_id.as_deref())
+    }
+}
+
+/// One line of deployment output. `seq` orders lines within a deployment and doubles as the SSE event id.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    pub seq: i64,
+    /// "stdout", "stderr" or "info" for progress messages
+    pub stream: String,
+    pub line: String,
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+}
+
+/// Filters and paging for `GET /api/history`.
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub action: Option<String>,
+    pub status: Option<String>,
+    pub container: Option<String>,
+    /// Unix timestamp (seconds), inclusive
+    pub since: Option<i64>,
+    /// Unix timestamp (seconds), exclusive
+    pub until: Option<i64>,
+}
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+// Shared by list and count so both see the same filter; ?1..?5 are bound in `bind_filters`
+const FILTER_SQL: &str = "WHERE (?1 IS NULL OR action = ?1)
+    AND (?2 IS NULL OR status = ?2)
+    AND (?3 IS NULL OR container_name = ?3)
+    AND (?4 IS NULL OR timestamp >= ?4)
+    AND (?5 IS NULL OR timestamp < ?5)";
+
+/// Deployment history stored in the `deployments` table.
 #[derive(Clone)]
 pub struct HistoryManager {
-    file_path: String,
+    db: SqlitePool,
 }
 
 impl HistoryManager {
-    pub fn new(file_path: &str) -> Self {
-        Self {
-            file_path: file_path.to_string(),
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+
+    /// Creates the table, imports a legacy `history.json` once (renaming it to
+    /// `*.imported`) and marks deployments cut short by a restart as `interrupted`.
+    pub async fn migrate(&self, legacy_json: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS deployments (
+                id TEXT PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                details TEXT NOT NULL,
+                status TEXT NOT NULL,
+                image_id TEXT,
+                previous_image_id TEXT,
+                container_name TEXT,
+                rollback_of TEXT,
+                exit_code INTEGER,
+                duration_ms INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_deployments_timestamp ON deployments(timestamp);
+            CREATE TABLE IF NOT EXISTS deployment_logs (
+                deployment_id TEXT NOT NULL,
+                seq INTEGER NOT NULL,
+                stream TEXT NOT NULL,
+                line TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                PRIMARY KEY (deployment_id, seq)
+            );"
+        )
+        .execute(&self.db)
+        .await?;
+        // Tables created before previous_image_id was recorded
+        let has_previous = sqlx::query("SELECT 1 FROM pragma_table_info('deployments') WHERE name = 'previous_image_id'")
+            .fetch_optional(&self.db)
+            .await?
+            .is_some();
+        if !has_previous {
+            sqlx::query("ALTER TABLE deployments ADD COLUMN previous_image_id TEXT").execute(&self.db).await?;
+        }
+
+        match std::fs::read_to_string(legacy_json) {
+            Ok(content) => {
+                let records: Vec<DeploymentRecord> = serde_json::from_str(&content).unwrap_or_else(|e| {
+                    tracing::error!("Skipping history import, {} is not valid: {}", legacy_json, e);
+                    Vec::new()
+                });
+                let mut tx = self.db.begin().await?;
+                for record in &records {
+                    insert(&mut *tx, record, true).await?;
+                }
+                tx.commit().await?;
+
+                if let Err(e) = std::fs::rename(legacy_json, format!("{}.imported", legacy_json)) {
+                    tracing::warn!("Failed to rename {}: {}", legacy_json, e);
+                }
+                tracing::info!("Imported {} deployment records from {}", records.len(), legacy_json);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to read {}: {}", legacy_json, e),
         }
+
+        sqlx::query("UPDATE deployments SET status = 'interrupted' WHERE status = 'in_progress'")
+            .execute(&self.db)
+            .await?;
+        Ok(())
     }
 
-    pub fn get_history(&self) -> Vec<DeploymentRecord> {
-        if !Path::new(&self.file_path).exists() {
-            return Vec::new();
+    /// Newest first, plus the total number of matching records.
+    pub async fn get_history(&self, query: &HistoryQuery) -> Result<(Vec<DeploymentRecord>, i64), sqlx::Error> {
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let offset = query.offset.unwrap_or(0).max(0);
+
+        let sql = format!(
+            "SELECT id, timestamp, action, details, status, image_id, previous_image_id, container_name,
+                    rollback_of, exit_code, duration_ms
+             FROM deployments {} ORDER BY timestamp DESC, rowid DESC LIMIT ?6 OFFSET ?7",
+            FILTER_SQL
+        );
+        let rows = bind_filters(sqlx::query(&sql), query)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.db)
+            .await?;
+
+        let count_sql = format!("SELECT COUNT(*) FROM deployments {}", FILTER_SQL);
+        let total: i64 = bind_filters(sqlx::query(&count_sql), query)
+            .fetch_one(&self.db)
+            .await?
+            .get(0);
+
+        Ok((rows.iter().map(from_row).collect(), total))
+    }
+
+    pub async fn get_record(&self, id: &str) -> Result<Option<DeploymentRecord>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM deployments WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+        Ok(row.as_ref().map(from_row))
+    }
+
+    pub async fn add_record(&self, record: DeploymentRecord) {
+        if let Err(e) = insert(&self.db, &record, false).await {
+            tracing::error!("Failed to save deployment record {}: {}", record.id, e);
         }
+    }
+
+    /// Sets the final outcome of a deployment that was recorded as `in_progress`.
+    pub async fn finish(&self, id: &str, status: &str, exit_code: Option<i32>, duration_ms: i64) {
+        let result = sqlx::query(
+            "UPDATE deployments SET status = ?, exit_code = ?, duration_ms = ? WHERE id = ?"
+        )
+        .bind(status)
+        .bind(exit_code)
+        .bind(duration_ms)
+        .bind(id)
+        .execute(&self.db)
+        .await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to update deployment record {}: {}", id, e);
+        }
+    }
+
+    /// Stores a batch of output lines in one transaction.
+    pub async fn append_logs(&self, lines: &[(String, LogLine)]) -> Result<(), sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        for (deployment_id, line) in lines {
+            sqlx::query(
+                "INSERT OR REPLACE INTO deployment_logs (deployment_id, seq, stream, line, timestamp)
+                 VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(deployment_id)
+            .bind(line.seq)
+            .bind(&line.stream)
+            .bind(&line.line)
+            .bind(line.timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    pub async fn get_logs(&self, deployment_id: &str) -> Result<Vec<LogLine>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT seq, stream, line, timestamp FROM deployment_logs WHERE deployment_id = ? ORDER BY seq"
+        )
+        .bind(deployment_id)
+        .fetch_all(&self.db)
+        .await?;
 
-        let content = fs::read_to_string(&self.file_path).unwrap_or_else(|_| "[]".to_string());
-        serde_json::from_str(&content).unwrap_or_else(|_| Vec::new())
+        Ok(rows.iter().map(|row| LogLine {
+            seq: row.get("seq"),
+            stream: row.get("stream"),
+            line: row.get("line"),
+            timestamp: row.get("timestamp"),
+        }).collect())
+    }
+}
+
+async fn insert<'e>(
+    executor: impl sqlx::SqliteExecutor<'e>,
+    record: &DeploymentRecord,
+    ignore_existing: bool,
+) -> Result<(), sqlx::Error> {
+    let verb = if ignore_existing { "INSERT OR IGNORE" } else { "INSERT" };
+    sqlx::query(&format!(
+        "{} INTO deployments (id, timestamp, action, details, status, image_id, previous_image_id,
+                              container_name, rollback_of, exit_code, duration_ms)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        verb
+    ))
+    .bind(&record.id)
+    .bind(record.timestamp.timestamp_millis())
+    .bind(&record.action)
+    .bind(&record.details)
+    .bind(&record.status)
+    .bind(&record.image_id)
+    .bind(&record.previous_image_id)
+    .bind(&record.container_name)
+    .bind(&record.rollback_of)
+    .bind(record.exit_code)
+    .bind(record.duration_ms)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+type SqliteQuery<'q> = sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>;
+
+fn bind_filters<'q>(q: SqliteQuery<'q>, query: &'q HistoryQuery) -> SqliteQuery<'q> {
+    q.bind(query.action.as_deref())
+        .bind(query.status.as_deref())
+        .bind(query.container.as_deref())
+        .bind(query.since.map(|s| s * 1000))
+        .bind(query.until.map(|s| s * 1000))
+}
+
+fn from_row(row: &sqlx::sqlite::SqliteRow) -> DeploymentRecord {
+    DeploymentRecord {
+        id: row.get("id"),
+        timestamp: DateTime::from_timestamp_millis(row.get("timestamp")).unwrap_or_default(),
+        action: row.get("action"),
+        details: row.get("details"),
+        status: row.get("status"),
+        image_id: row.get("image_id"),
+        previous_image_id: row.get("previous_image_id"),
+        container_name: row.get("container_name"),
+        rollback_of: row.get("rollback_of"),
+        exit_code: row.get("exit_code"),
+        duration_ms: row.get("duration_ms"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn manager() -> HistoryManager {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let history = HistoryManager::new(pool);
+        history.migrate("/nonexistent/history.json").await.unwrap();
+        history
     }
 
-    pub fn add_record(&self, record: DeploymentRecord) {
-        let mut history = self.get_history();
-        history.push(record);
-        
-        // Keep only last 50 records
-        if history.len() > 50 {
-            history.remove(0);
+    fn record(id: &str, action: &str, status: &str, container: &str, secs: i64) -> DeploymentRecord {
+        DeploymentRecord {
+            id: id.to_string(),
+            timestamp: DateTime::from_timestamp(secs, 0).unwrap(),
+            action: action.to_string(),
+            details: String::new(),
+            status: status.to_string(),
+            image_id: None,
+            previous_image_id: None,
+            container_name: Some(container.to_string()),
+            rollback_of: None,
+            exit_code: None,
+            duration_ms: None,
         }
+    }
+
+    #[tokio::test]
+    async fn test_filters_and_pagination() {
+        let history = manager().await;
+        history.add_record(record("a", "update", "success", "mini-ops", 100)).await;
+        history.add_record(record("b", "redeploy", "failed", "web", 200)).await;
+        history.add_record(record("c", "redeploy", "success", "web", 300)).await;
+        history.add_record(record("d", "rollback", "success", "web", 400)).await;
+
+        let (all, total) = history.get_history(&HistoryQuery::default()).await.unwrap();
+        assert_eq!(total, 4);
+        assert_eq!(all.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["d", "c", "b", "a"]);
+
+        let page = HistoryQuery { limit: Some(2), offset: Some(1), ..Default::default() };
+        let (records, total) = history.get_history(&page).await.unwrap();
+        assert_eq!(total, 4);
+        assert_eq!(records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["c", "b"]);
+
+        let filtered = HistoryQuery {
+            action: Some("redeploy".to_string()),
+            container: Some("web".to_string()),
+            since: Some(250),
+            ..Default::default()
+        };
+        let (records, total) = history.get_history(&filtered).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(records[0].id, "c");
+
+        let failed = HistoryQuery { status: Some("failed".to_string()), until: Some(200), ..Default::default() };
+        assert_eq!(history.get_history(&failed).await.unwrap().1, 0);
+    }
+
+    #[tokio::test]
+    async fn test_finish_updates_final_state() {
+        let history = manager().await;
+        history.add_record(record("u1", "update", "in_progress", "mini-ops", 100)).await;
+        history.finish("u1", "failed", Some(2), 1500).await;
+
+        let saved = history.get_record("u1").await.unwrap().unwrap();
+        assert_eq!(saved.status, "failed");
+        assert_eq!(saved.exit_code, Some(2));
+        assert_eq!(saved.duration_ms, Some(1500));
+        assert!(history.get_record("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_targets_previous_image() {
+        let history = manager().await;
+        let mut redeploy = record("r1", "redeploy", "failed", "web", 100);
+        redeploy.image_id = Some("sha256:new".to_string());
+        redeploy.previous_image_id = Some("sha256:old".to_string());
+        history.add_record(redeploy).await;
+
+        let saved = history.get_record("r1").await.unwrap().unwrap();
+        assert_eq!(saved.rollback_image(), Some("sha256:old"));
+        // Records from before previous_image_id roll back to the image they recorded
+        let mut legacy = record("a", "redeploy", "success", "web", 100);
+        assert_eq!(legacy.rollback_image(), None);
+        legacy.image_id = Some("sha256:recorded".to_string());
+        assert_eq!(legacy.rollback_image(), Some("sha256:recorded"));
+    }
+
+    #[tokio::test]
+    async fn test_logs_are_stored_per_deployment_in_order() {
+        let history = manager().await;
+        let line = |seq, stream: &str, text: &str| LogLine {
+            seq,
+            stream: stream.to_string(),
+            line: text.to_string(),
+            timestamp: 1000 + seq,
+        };
+        history.append_logs(&[
+            ("d1".to_string(), line(2, "stderr", "warning")),
+            ("d1".to_string(), line(1, "stdout", "building")),
+            ("d2".to_string(), line(1, "info", "other")),
+        ]).await.unwrap();
+
+        let logs = history.get_logs("d1").await.unwrap();
+        assert_eq!(logs.iter().map(|l| l.line.as_str()).collect::<Vec<_>>(), ["building", "warning"]);
+        assert_eq!(logs[1].stream, "stderr");
+        assert!(history.get_logs("missing").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_imports_legacy_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        std::fs::write(&path, r#"[
+            {"id": "old-1", "timestamp": "2025-01-01T10:00:00Z", "action": "update",
+             "details": "Agent Update Triggered", "status": "in_progress",
+             "image_id": null, "container_name": "mini-ops"}
+        ]"#).unwrap();
+
+        let history = manager().await;
+        let path_str = path.to_str().unwrap();
+        history.migrate(path_str).await.unwrap();
+
+        let imported = history.get_record("old-1").await.unwrap().unwrap();
+        assert_eq!(imported.timestamp.to_rfc3339(), "2025-01-01T10:00:00+00:00");
+        // Anything still running when the agent stopped can never finish
+        assert_eq!(imported.status, "interrupted");
+        assert!(!path.exists());
 
-        let content = serde_json::to_string_pretty(&history).unwrap_or_default();
-        let _ = fs::write(&self.file_path, content);
+        let renamed = format!("{}.imported", path_str);
+        assert!(std::path::Path::new(&renamed).exists());
     }
 }
@@ -9,14 +9,14 @@ pub enum Lang {
 
 impl Lang {
     pub fn from_headers(headers: &HeaderMap) -> Self {
-        if let Some(accept_lang) = headers.get("accept-language") {
-            if let Ok(s) = accept_lang.to_str() {
-                let s_lower = s.to_lowercase();
-                if s_lower.contains("ru") {
-                    return Lang::RU;
-                } else if s_lower.contains("en") {
-                    return Lang::EN;
-                }
+        if let Some(accept_lang) = headers.get("accept-language")
+            && let Ok(s) = accept_lang.to_str()
+        {
+            let s_lower = s.to_lowercase();
+            if s_lower.contains("ru") {
+                return Lang::RU;
+            } else if s_lower.contains("en") {
+                return Lang::EN;
             }
         }
         
@@ -35,91 +35,369 @@ pub fn t(key: &str, lang: &Lang) -> String {
         Lang::RU => match key {
             "alert.critical_cpu" => "Критическая нагрузка CPU: {val}%".to_string(),
             "alert.low_disk" => "Мало места на диске: {val}% занято".to_string(),
+            "alert.disk_full_forecast" => "💽 Диск скоро заполнится: {val}".to_string(),
+            "alert.hours_left" => "осталось ~{val} ч".to_string(),
+            "alert.per_day" => "сутки".to_string(),
+            "alert.updates_overdue" => "🩹 Обновления безопасности не установлены дольше {val} дн.:".to_string(),
+            "alert.files_changed" => "📝 Изменены отслеживаемые файлы:".to_string(),
+            "alert.files_changed_hint" => "Если изменения ожидаемы, обновите эталон в разделе Безопасность.".to_string(),
+            "alert.new_privileged" => "👤 Новые привилегированные пользователи:".to_string(),
             "alert.test" => "Это тестовое уведомление от агента Mini-Ops!".to_string(),
             "security.detected" => "Обнаружена проблема безопасности!".to_string(),
             "security.resolved" => "✅ Проблема безопасности решена".to_string(),
             "security.check" => "Проверка".to_string(),
             "security.message" => "Описание".to_string(),
+            "security.evidence" => "Основание".to_string(),
+            "security.remediation" => "Как исправить".to_string(),
             
+            "heal.restarted" => "♻️ Авто-восстановление: контейнер перезапущен".to_string(),
+            "heal.failed" => "❌ Авто-восстановление: не удалось перезапустить контейнер".to_string(),
+            "heal.exhausted" => "Контейнер нездоров, лимит авто-перезапусков исчерпан".to_string(),
+            "updates.available" => "📦 Доступны новые версии образов:".to_string(),
+            "jobs.failed" => "❌ Задание по расписанию не выполнено: {val}".to_string(),
+
             "audit.ssh_root.name" => "Доступ root через SSH".to_string(),
             "audit.ssh_root.fail" => "Root-доступ разрешен по паролю/ключам (небезопасно)".to_string(),
             "audit.ssh_root.pass" => "Доступ для root ограничен или отключен".to_string(),
-            "audit.ssh_config.warn" => "Не удалось прочитать конфиг sshd".to_string(),
+            "audit.ssh_root.keys_only" => "Root может входить только по SSH-ключам".to_string(),
+            "audit.ssh_root.forced_commands" => "Root может входить только по ключам с forced command".to_string(),
+            "audit.ssh_root.match" => "Блок Match разрешает вход root для части подключений".to_string(),
+            "audit.ssh_root.fix" => "Укажите `PermitRootLogin no` (или `prohibit-password`) в /etc/ssh/sshd_config и перезапустите sshd".to_string(),
+            "audit.source.warn" => "Не удалось получить данные для проверки".to_string(),
             
-            "audit.ufw.name" => "Файрвол (UFW)".to_string(),
-            "audit.ufw.pass" => "UFW активен".to_string(),
-            "audit.ufw.fail" => "UFW отключен".to_string(),
-            "audit.ufw.warn" => "UFW не найден или недоступен".to_string(),
-            "audit.ufw.error" => "UFW найден, но команда не выполнена (возможно, недостаточно прав)".to_string(),
+            "audit.firewall.name" => "Файрвол".to_string(),
+            "audit.firewall.pass" => "{val} активен, входящие подключения по умолчанию запрещены".to_string(),
+            "audit.firewall.open" => "{val} активен, но пропускает весь входящий трафик".to_string(),
+            "audit.firewall.fail" => "Активный файрвол не найден (UFW, firewalld, nftables, iptables)".to_string(),
+            "audit.firewall.error" => "Не удалось прочитать правила файрвола (возможно, недостаточно прав)".to_string(),
+            "audit.firewall.fix" => "Запретите входящие по умолчанию и разрешите только нужные порты, например: `ufw default deny incoming && ufw allow 22/tcp && ufw enable`".to_string(),
+
+            "audit.fw_docker.name" => "Порты Docker в обход файрвола".to_string(),
+            "audit.fw_docker.pass" => "Опубликованные порты Docker не обходят файрвол".to_string(),
+            "audit.fw_docker.fail" => "Docker публикует порты в обход файрвола: {val}".to_string(),
+            "audit.fw_docker.filtered" => "Docker публикует порты в обход файрвола, но в цепочке DOCKER-USER есть правила: {val}".to_string(),
+            "audit.fw_docker.fix" => "Публикуйте порты только на 127.0.0.1 (`-p 127.0.0.1:8080:80`) или ограничьте доступ правилами в цепочке DOCKER-USER".to_string(),
             
             "audit.docker_sock.name" => "Права на Docker Socket".to_string(),
             "audit.docker_sock.fail" => "Socket доступен всем на запись (опасно!)".to_string(),
             "audit.docker_sock.pass" => "Права доступа выглядят безопасно".to_string(),
             "audit.docker_sock.warn" => "Не удалось проверить /var/run/docker.sock".to_string(),
+            "audit.docker_sock.fix" => "Верните права: `chmod 660 /var/run/docker.sock` и владельца root:docker".to_string(),
             
             "audit.disk_enc.name" => "Шифрование диска".to_string(),
             "audit.disk_enc.pass" => "Найдены зашифрованные разделы".to_string(),
             "audit.disk_enc.warn" => "Зашифрованные разделы LUKS не найдены".to_string(),
             "audit.disk_enc.error" => "Не удалось запустить lsblk".to_string(),
+            "audit.disk_enc.fix" => "Используйте LUKS для разделов с данными при установке или переносе сервера".to_string(),
             
             "audit.fail2ban.name" => "Fail2Ban".to_string(),
             "audit.fail2ban.pass" => "Сервис активен".to_string(),
             "audit.fail2ban.warn" => "Сервис не запущен".to_string(),
             "audit.fail2ban.missing" => "Fail2Ban не установлен".to_string(),
+            "audit.fail2ban.fix" => "Установите и запустите Fail2Ban: `apt install fail2ban && systemctl enable --now fail2ban`".to_string(),
             
             "audit.ssh_passwd.name" => "SSH Password Auth".to_string(),
             "audit.ssh_passwd.pass" => "Вход по паролю отключен".to_string(),
             "audit.ssh_passwd.fail" => "Вход по паролю включен (небезопасно)".to_string(),
+            "audit.ssh_passwd.match" => "Блок Match включает вход по паролю для части подключений".to_string(),
+            "audit.ssh_passwd.fix" => "Настройте вход по ключам, затем укажите `PasswordAuthentication no` в /etc/ssh/sshd_config и перезапустите sshd".to_string(),
+
+            "audit.ssh_pubkey.name" => "SSH вход по ключам".to_string(),
+            "audit.ssh_pubkey.pass" => "Вход по ключам включен".to_string(),
+            "audit.ssh_pubkey.warn" => "Вход по ключам отключен".to_string(),
+            "audit.ssh_pubkey.fix" => "Укажите `PubkeyAuthentication yes` в sshd_config и входите по SSH-ключам".to_string(),
+
+            "audit.ssh_empty.name" => "SSH пустые пароли".to_string(),
+            "audit.ssh_empty.pass" => "Вход с пустым паролем запрещен".to_string(),
+            "audit.ssh_empty.fail" => "Учетные записи с пустым паролем могут войти".to_string(),
+            "audit.ssh_empty.match" => "Блок Match разрешает пустые пароли для части подключений".to_string(),
+            "audit.ssh_empty.fix" => "Укажите `PermitEmptyPasswords no` в sshd_config и перезапустите sshd".to_string(),
+
+            "audit.ssh_auth_tries.name" => "SSH MaxAuthTries".to_string(),
+            "audit.ssh_auth_tries.pass" => "Не более {val} попыток входа за подключение".to_string(),
+            "audit.ssh_auth_tries.warn" => "{val} попыток входа за подключение ускоряют подбор".to_string(),
+            "audit.ssh_auth_tries.fix" => "Укажите `MaxAuthTries 4` или меньше в sshd_config".to_string(),
+
+            "audit.ssh_x11.name" => "SSH X11 Forwarding".to_string(),
+            "audit.ssh_x11.pass" => "Проброс X11 отключен".to_string(),
+            "audit.ssh_x11.warn" => "Проброс X11 включен".to_string(),
+            "audit.ssh_x11.fix" => "Укажите `X11Forwarding no`, если не нужны графические приложения через SSH".to_string(),
+
+            "audit.ssh_tcp_fwd.name" => "SSH TCP Forwarding".to_string(),
+            "audit.ssh_tcp_fwd.pass" => "Проброс TCP отключен".to_string(),
+            "audit.ssh_tcp_fwd.warn" => "Проброс TCP разрешен ({val})".to_string(),
+            "audit.ssh_tcp_fwd.fix" => "Укажите `AllowTcpForwarding no`, если пользователям не нужны SSH-туннели".to_string(),
+
+            "audit.ssh_idle.name" => "SSH ClientAliveInterval".to_string(),
+            "audit.ssh_idle.pass" => "Неотвечающие сессии проверяются (ClientAliveInterval {val})".to_string(),
+            "audit.ssh_idle.warn" => "Неотвечающие сессии не закрываются (ClientAliveInterval 0)".to_string(),
+            "audit.ssh_idle.fix" => "Укажите `ClientAliveInterval 300` и `ClientAliveCountMax 2` в sshd_config".to_string(),
+
+            "audit.ssh_crypto.name" => "SSH алгоритмы шифрования".to_string(),
+            "audit.ssh_crypto.pass" => "Слабые алгоритмы обмена ключами, шифры и MAC не включены".to_string(),
+            "audit.ssh_crypto.fail" => "Включены слабые алгоритмы: {val}".to_string(),
+            "audit.ssh_crypto.fix" => "Уберите CBC-шифры, MAC на MD5/SHA1-96 и обмен ключами на SHA1 из Ciphers, MACs и KexAlgorithms".to_string(),
+
+            "audit.ssh_port.name" => "SSH порт".to_string(),
+            "audit.ssh_port.pass" => "SSH слушает порт {val}".to_string(),
+            "audit.ssh_port.warn" => "SSH слушает стандартный порт 22".to_string(),
+            "audit.ssh_port.fix" => "Перенесите SSH на другой порт, чтобы убрать шум сканеров; сначала откройте его в файрволе".to_string(),
+
+            "audit.ssh_allow.name" => "SSH AllowUsers/AllowGroups".to_string(),
+            "audit.ssh_allow.pass" => "Вход ограничен: {val}".to_string(),
+            "audit.ssh_allow.warn" => "Войти по SSH может любая учетная запись с рабочей оболочкой".to_string(),
+            "audit.ssh_allow.fix" => "Добавьте `AllowUsers` или `AllowGroups` с учетными записями, которым нужен SSH".to_string(),
+
+            "audit.ssh_keys.name" => "SSH authorized_keys".to_string(),
+            "audit.ssh_keys.pass" => "Проверено ключей: {val}, проблем нет".to_string(),
+            "audit.ssh_keys.none" => "Файлы authorized_keys не найдены".to_string(),
+            "audit.ssh_keys.fail" => "Найдены слабые ключи или небезопасные права".to_string(),
+            "audit.ssh_keys.fix" => "Замените DSA и короткие RSA-ключи на ed25519; выполните `chmod 700 ~/.ssh` и `chmod 600 ~/.ssh/authorized_keys`".to_string(),
             
             "audit.ports.name" => "Открытые порты".to_string(),
-            "audit.ports.pass" => "Подозрительных портов не найдено".to_string(),
-            "audit.ports.warn" => "Найдены лишние порты".to_string(),
-            "audit.ports.error" => "Ошибка сканирования портов".to_string(),
+            "audit.ports.pass" => "Все публичные порты есть в списке разрешенных".to_string(),
+            "audit.ports.warn" => "Публичные порты вне списка разрешенных: {val}".to_string(),
+            "audit.ports.fix" => "Остановите лишние сервисы, привяжите их к 127.0.0.1 или добавьте порт в список разрешенных (Безопасность → Открытые порты)".to_string(),
+
+            "audit.updates.name" => "Обновления безопасности".to_string(),
+            "audit.updates.pass" => "Нет ожидающих обновлений безопасности".to_string(),
+            "audit.updates.warn" => "Ожидают установки обновлений безопасности: {val}".to_string(),
+            "audit.updates.unmarked" => "Ожидают установки обновлений: {val}; менеджер пакетов не помечает обновления безопасности".to_string(),
+            "audit.updates.unsupported" => "Не найден поддерживаемый менеджер пакетов (apt, dnf, apk)".to_string(),
+            "audit.updates.error" => "Не удалось получить список обновлений".to_string(),
+            "audit.updates.fix" => "Установите обновления: `apt upgrade`, `dnf upgrade --security` или `apk upgrade`".to_string(),
+
+            "audit.auto_updates.name" => "Автоматические обновления".to_string(),
+            "audit.auto_updates.pass" => "Обновления безопасности устанавливаются автоматически".to_string(),
+            "audit.auto_updates.warn" => "Автоматические обновления отключены".to_string(),
+            "audit.auto_updates.unknown" => "Не удалось определить, включены ли автоматические обновления".to_string(),
+            "audit.auto_updates.fix" => "Debian/Ubuntu: `apt install unattended-upgrades && dpkg-reconfigure -plow unattended-upgrades`; RHEL/Fedora: установите dnf-automatic и включите `dnf-automatic-install.timer`".to_string(),
+
+            "audit.reboot.name" => "Требуется перезагрузка".to_string(),
+            "audit.reboot.pass" => "Перезагрузка не требуется".to_string(),
+            "audit.reboot.warn" => "Для применения обновлений нужна перезагрузка".to_string(),
+            "audit.reboot.fix" => "Перезагрузите сервер в окно обслуживания, чтобы запустить обновленные ядро и библиотеки".to_string(),
+
+            "audit.integrity.name" => "Целостность файлов".to_string(),
+            "audit.integrity.pass" => "Отслеживаемые файлы ({val}) совпадают с эталоном".to_string(),
+            "audit.integrity.warn" => "Файлов изменено с момента снятия эталона: {val}".to_string(),
+            "audit.integrity.fix" => "Проверьте изменения; если они ожидаемы, обновите эталон: `POST /api/security/integrity/baseline`".to_string(),
+            "audit.uid0.name" => "Учётные записи с UID 0".to_string(),
+            "audit.uid0.pass" => "UID 0 только у root".to_string(),
+            "audit.uid0.fail" => "Кроме root, UID 0 у пользователей: {val}".to_string(),
+            "audit.uid0.fix" => "Удалите лишние учётные записи или назначьте им обычный UID: `usermod -u <uid> <user>`".to_string(),
+            "audit.empty_passwords.name" => "Пустые пароли".to_string(),
+            "audit.empty_passwords.pass" => "Учётных записей с пустым паролем нет".to_string(),
+            "audit.empty_passwords.fail" => "Пользователей с пустым паролем: {val}".to_string(),
+            "audit.empty_passwords.unknown" => "Не удалось прочитать /etc/shadow (агент запущен не от root)".to_string(),
+            "audit.empty_passwords.fix" => "Задайте пароль или заблокируйте вход: `passwd -l <user>`".to_string(),
+            "audit.login_users.name" => "Пользователи с входом по паролю".to_string(),
+            "audit.login_users.pass" => "Кроме root, ни у кого нет одновременно оболочки и действующего пароля".to_string(),
+            "audit.login_users.warn" => "Пользователей с оболочкой и незаблокированным паролем: {val}".to_string(),
+            "audit.login_users.fix" => "Заблокируйте пароль тем, кто входит по ключу (`passwd -l <user>`), а служебным учётным записям назначьте `/usr/sbin/nologin`".to_string(),
+            "audit.priv_groups.name" => "Привилегированные группы".to_string(),
+            "audit.priv_groups.pass" => "В группе docker никого нет".to_string(),
+            "audit.priv_groups.docker" => "Есть участники группы docker — это равносильно правам root".to_string(),
+            "audit.priv_groups.fix" => "Удалите из группы docker тех, кому не нужен полный доступ к серверу: `gpasswd -d <user> docker`, или используйте rootless Docker".to_string(),
+            "audit.nopasswd.name" => "sudo без пароля".to_string(),
+            "audit.nopasswd.pass" => "Правил NOPASSWD в sudoers нет".to_string(),
+            "audit.nopasswd.warn" => "Правил NOPASSWD в sudoers: {val}".to_string(),
+            "audit.nopasswd.unknown" => "Не удалось прочитать sudoers (агент запущен не от root)".to_string(),
+            "audit.nopasswd.fix" => "Уберите NOPASSWD или ограничьте такие правила конкретными командами (`visudo`)".to_string(),
+            "audit.path_writable.name" => "Права на каталоги PATH".to_string(),
+            "audit.path_writable.pass" => "Каталоги PATH и файлы в них не доступны на запись всем".to_string(),
+            "audit.path_writable.fail" => "Доступны на запись всем в PATH: {val}".to_string(),
+            "audit.path_writable.fix" => "Снимите право записи для остальных: `chmod o-w <путь>`".to_string(),
+
+            "audit.custom.pass" => "Условие выполнено".to_string(),
+            "audit.custom.exit_code" => "Команда завершилась с кодом {val}".to_string(),
+            "audit.custom.no_match" => "Вывод команды не совпал с шаблоном".to_string(),
+            "audit.custom.missing_line" => "Нет строки: {val}".to_string(),
+            "audit.custom.error" => "Проверку не удалось выполнить: {val}".to_string(),
             _ => key.to_string(),
         },
         Lang::EN => match key {
             "alert.critical_cpu" => "Critical CPU usage: {val}%".to_string(),
             "alert.low_disk" => "Low disk space: {val}% used".to_string(),
+            "alert.disk_full_forecast" => "💽 Disk filling up: {val}".to_string(),
+            "alert.hours_left" => "full in ~{val}h".to_string(),
+            "alert.per_day" => "day".to_string(),
+            "alert.updates_overdue" => "🩹 Security updates pending for over {val} days:".to_string(),
+            "alert.files_changed" => "📝 Watched files changed:".to_string(),
+            "alert.files_changed_hint" => "If these changes are expected, accept them as the new baseline on the Security page.".to_string(),
+            "alert.new_privileged" => "👤 New privileged users:".to_string(),
             "alert.test" => "This is a test notification from Mini-Ops agent!".to_string(),
             "security.detected" => "Security Issue Detected!".to_string(),
             "security.resolved" => "✅ Security Issue Resolved".to_string(),
             "security.check" => "Check".to_string(),
             "security.message" => "Message".to_string(),
+            "security.evidence" => "Evidence".to_string(),
+            "security.remediation" => "How to fix".to_string(),
             
+            "heal.restarted" => "♻️ Auto-healing: container restarted".to_string(),
+            "heal.failed" => "❌ Auto-healing: failed to restart container".to_string(),
+            "heal.exhausted" => "Container is unhealthy and its auto-restart budget is exhausted".to_string(),
+            "updates.available" => "📦 Newer images are available for:".to_string(),
+            "jobs.failed" => "❌ Scheduled job failed: {val}".to_string(),
+
             "audit.ssh_root.name" => "SSH Root Login".to_string(),
             "audit.ssh_root.fail" => "Root login is permitted via SSH via password/keys".to_string(),
             "audit.ssh_root.pass" => "Root login appears disabled or restricted".to_string(),
-            "audit.ssh_config.warn" => "Could not read /etc/ssh/sshd_config".to_string(),
+            "audit.ssh_root.keys_only" => "Root can log in with SSH keys only".to_string(),
+            "audit.ssh_root.forced_commands" => "Root can log in only with keys bound to a forced command".to_string(),
+            "audit.ssh_root.match" => "A Match block allows root login for some connections".to_string(),
+            "audit.ssh_root.fix" => "Set `PermitRootLogin no` (or `prohibit-password`) in /etc/ssh/sshd_config and restart sshd".to_string(),
+            "audit.source.warn" => "Could not read the data this check needs".to_string(),
             
-            "audit.ufw.name" => "Firewall (UFW)".to_string(),
-            "audit.ufw.pass" => "UFW is active".to_string(),
-            "audit.ufw.fail" => "UFW is inactive".to_string(),
-            "audit.ufw.warn" => "UFW command not found or not accessible".to_string(),
-            "audit.ufw.error" => "UFW found but command failed (possibly insufficient permissions)".to_string(),
+            "audit.firewall.name" => "Firewall".to_string(),
+            "audit.firewall.pass" => "{val} is active and denies incoming traffic by default".to_string(),
+            "audit.firewall.open" => "{val} is active but lets all incoming traffic through".to_string(),
+            "audit.firewall.fail" => "No active firewall found (UFW, firewalld, nftables, iptables)".to_string(),
+            "audit.firewall.error" => "Could not read firewall rules (possibly insufficient permissions)".to_string(),
+            "audit.firewall.fix" => "Deny incoming traffic by default and allow only the ports you need, e.g. `ufw default deny incoming && ufw allow 22/tcp && ufw enable`".to_string(),
+
+            "audit.fw_docker.name" => "Docker Ports Bypassing Firewall".to_string(),
+            "audit.fw_docker.pass" => "No Docker-published ports bypass the firewall".to_string(),
+            "audit.fw_docker.fail" => "Docker publishes ports past the firewall: {val}".to_string(),
+            "audit.fw_docker.filtered" => "Docker publishes ports past the firewall, but DOCKER-USER has rules: {val}".to_string(),
+            "audit.fw_docker.fix" => "Publish ports on 127.0.0.1 only (`-p 127.0.0.1:8080:80`) or restrict them with rules in the DOCKER-USER chain".to_string(),
             
             "audit.docker_sock.name" => "Docker Socket Permissions".to_string(),
             "audit.docker_sock.fail" => "Docker socket is world-writable (dangerous!)".to_string(),
             "audit.docker_sock.pass" => "Permissions look safe".to_string(),
             "audit.docker_sock.warn" => "Could not verify /var/run/docker.sock".to_string(),
+            "audit.docker_sock.fix" => "Restore permissions: `chmod 660 /var/run/docker.sock` owned by root:docker".to_string(),
             
             "audit.disk_enc.name" => "Disk Encryption".to_string(),
             "audit.disk_enc.pass" => "Found encrypted partitions".to_string(),
             "audit.disk_enc.warn" => "No LUKS encrypted partitions found".to_string(),
             "audit.disk_enc.error" => "Could not run lsblk".to_string(),
+            "audit.disk_enc.fix" => "Use LUKS for data partitions when provisioning or migrating the server".to_string(),
 
             "audit.fail2ban.name" => "Fail2Ban".to_string(),
             "audit.fail2ban.pass" => "Service is active".to_string(),
             "audit.fail2ban.warn" => "Service is not running".to_string(),
             "audit.fail2ban.missing" => "Fail2Ban is not installed".to_string(),
+            "audit.fail2ban.fix" => "Install and start Fail2Ban: `apt install fail2ban && systemctl enable --now fail2ban`".to_string(),
 
             "audit.ssh_passwd.name" => "SSH Password Auth".to_string(),
             "audit.ssh_passwd.pass" => "Password authentication is disabled".to_string(),
             "audit.ssh_passwd.fail" => "Password authentication is enabled (insecure)".to_string(),
+            "audit.ssh_passwd.match" => "A Match block enables password authentication for some connections".to_string(),
+            "audit.ssh_passwd.fix" => "Set up key-based login, then set `PasswordAuthentication no` in /etc/ssh/sshd_config and restart sshd".to_string(),
+
+            "audit.ssh_pubkey.name" => "SSH Key Authentication".to_string(),
+            "audit.ssh_pubkey.pass" => "Key-based login is enabled".to_string(),
+            "audit.ssh_pubkey.warn" => "Key-based login is disabled".to_string(),
+            "audit.ssh_pubkey.fix" => "Set `PubkeyAuthentication yes` in sshd_config and log in with SSH keys".to_string(),
+
+            "audit.ssh_empty.name" => "SSH Empty Passwords".to_string(),
+            "audit.ssh_empty.pass" => "Empty passwords are rejected".to_string(),
+            "audit.ssh_empty.fail" => "Accounts with an empty password can log in".to_string(),
+            "audit.ssh_empty.match" => "A Match block allows empty passwords for some connections".to_string(),
+            "audit.ssh_empty.fix" => "Set `PermitEmptyPasswords no` in sshd_config and restart sshd".to_string(),
+
+            "audit.ssh_auth_tries.name" => "SSH MaxAuthTries".to_string(),
+            "audit.ssh_auth_tries.pass" => "At most {val} login attempts per connection".to_string(),
+            "audit.ssh_auth_tries.warn" => "{val} login attempts per connection speed up brute force".to_string(),
+            "audit.ssh_auth_tries.fix" => "Set `MaxAuthTries 4` or lower in sshd_config".to_string(),
+
+            "audit.ssh_x11.name" => "SSH X11 Forwarding".to_string(),
+            "audit.ssh_x11.pass" => "X11 forwarding is disabled".to_string(),
+            "audit.ssh_x11.warn" => "X11 forwarding is enabled".to_string(),
+            "audit.ssh_x11.fix" => "Set `X11Forwarding no` unless you run graphical apps over SSH".to_string(),
+
+            "audit.ssh_tcp_fwd.name" => "SSH TCP Forwarding".to_string(),
+            "audit.ssh_tcp_fwd.pass" => "TCP forwarding is disabled".to_string(),
+            "audit.ssh_tcp_fwd.warn" => "TCP forwarding is allowed ({val})".to_string(),
+            "audit.ssh_tcp_fwd.fix" => "Set `AllowTcpForwarding no` unless users need SSH tunnels".to_string(),
+
+            "audit.ssh_idle.name" => "SSH ClientAliveInterval".to_string(),
+            "audit.ssh_idle.pass" => "Unresponsive sessions are checked (ClientAliveInterval {val})".to_string(),
+            "audit.ssh_idle.warn" => "Unresponsive sessions are never dropped (ClientAliveInterval 0)".to_string(),
+            "audit.ssh_idle.fix" => "Set `ClientAliveInterval 300` and `ClientAliveCountMax 2` in sshd_config".to_string(),
+
+            "audit.ssh_crypto.name" => "SSH Algorithms".to_string(),
+            "audit.ssh_crypto.pass" => "No weak key exchange, cipher or MAC algorithms".to_string(),
+            "audit.ssh_crypto.fail" => "Weak algorithms are enabled: {val}".to_string(),
+            "audit.ssh_crypto.fix" => "Remove CBC ciphers, MD5/SHA1-96 MACs and SHA1 key exchange from Ciphers, MACs and KexAlgorithms".to_string(),
+
+            "audit.ssh_port.name" => "SSH Port".to_string(),
+            "audit.ssh_port.pass" => "SSH listens on port {val}".to_string(),
+            "audit.ssh_port.warn" => "SSH listens on the default port 22".to_string(),
+            "audit.ssh_port.fix" => "Move SSH to another port to cut scanner noise; open it in the firewall first".to_string(),
+
+            "audit.ssh_allow.name" => "SSH AllowUsers/AllowGroups".to_string(),
+            "audit.ssh_allow.pass" => "Login is limited: {val}".to_string(),
+            "audit.ssh_allow.warn" => "Any account with a working shell can log in over SSH".to_string(),
+            "audit.ssh_allow.fix" => "Add `AllowUsers` or `AllowGroups` with the accounts that need SSH".to_string(),
+
+            "audit.ssh_keys.name" => "SSH authorized_keys".to_string(),
+            "audit.ssh_keys.pass" => "{val} keys checked, no problems".to_string(),
+            "audit.ssh_keys.none" => "No authorized_keys files found".to_string(),
+            "audit.ssh_keys.fail" => "Weak keys or unsafe permissions found".to_string(),
+            "audit.ssh_keys.fix" => "Replace DSA and short RSA keys with ed25519; run `chmod 700 ~/.ssh` and `chmod 600 ~/.ssh/authorized_keys`".to_string(),
 
             "audit.ports.name" => "Listening Ports".to_string(),
-            "audit.ports.pass" => "No suspicious ports found".to_string(),
-            "audit.ports.warn" => "Unnecessary ports found".to_string(),
-            "audit.ports.error" => "Error scanning ports".to_string(),
+            "audit.ports.pass" => "All public ports are in the allowlist".to_string(),
+            "audit.ports.warn" => "Public ports not in the allowlist: {val}".to_string(),
+            "audit.ports.fix" => "Stop unneeded services, bind them to 127.0.0.1, or add the port to the allowlist (Security → Listening Ports)".to_string(),
+
+            "audit.updates.name" => "Security Updates".to_string(),
+            "audit.updates.pass" => "No security updates are pending".to_string(),
+            "audit.updates.warn" => "Security updates pending: {val}".to_string(),
+            "audit.updates.unmarked" => "Updates pending: {val}; the package manager does not mark security updates".to_string(),
+            "audit.updates.unsupported" => "No supported package manager (apt, dnf, apk) found".to_string(),
+            "audit.updates.error" => "Could not list pending updates".to_string(),
+            "audit.updates.fix" => "Install them: `apt upgrade`, `dnf upgrade --security` or `apk upgrade`".to_string(),
+
+            "audit.auto_updates.name" => "Automatic Updates".to_string(),
+            "audit.auto_updates.pass" => "Security updates are installed automatically".to_string(),
+            "audit.auto_updates.warn" => "Automatic updates are disabled".to_string(),
+            "audit.auto_updates.unknown" => "Could not tell whether automatic updates are enabled".to_string(),
+            "audit.auto_updates.fix" => "Debian/Ubuntu: `apt install unattended-upgrades && dpkg-reconfigure -plow unattended-upgrades`; RHEL/Fedora: install dnf-automatic and enable `dnf-automatic-install.timer`".to_string(),
+
+            "audit.reboot.name" => "Reboot Required".to_string(),
+            "audit.reboot.pass" => "No reboot is pending".to_string(),
+            "audit.reboot.warn" => "A reboot is needed to finish applying updates".to_string(),
+            "audit.reboot.fix" => "Reboot in a maintenance window so the updated kernel and libraries are loaded".to_string(),
+
+            "audit.integrity.name" => "File Integrity".to_string(),
+            "audit.integrity.pass" => "Watched files ({val}) match the baseline".to_string(),
+            "audit.integrity.warn" => "Files changed since the baseline: {val}".to_string(),
+            "audit.integrity.fix" => "Review the changes; if they are expected, accept them with `POST /api/security/integrity/baseline`".to_string(),
+            "audit.uid0.name" => "UID 0 Accounts".to_string(),
+            "audit.uid0.pass" => "Only root has UID 0".to_string(),
+            "audit.uid0.fail" => "Accounts other than root with UID 0: {val}".to_string(),
+            "audit.uid0.fix" => "Remove the extra accounts or give them a regular UID: `usermod -u <uid> <user>`".to_string(),
+            "audit.empty_passwords.name" => "Empty Passwords".to_string(),
+            "audit.empty_passwords.pass" => "No account has an empty password".to_string(),
+            "audit.empty_passwords.fail" => "Accounts with an empty password: {val}".to_string(),
+            "audit.empty_passwords.unknown" => "Could not read /etc/shadow (the agent is not running as root)".to_string(),
+            "audit.empty_passwords.fix" => "Set a password or lock the account: `passwd -l <user>`".to_string(),
+            "audit.login_users.name" => "Password Login Users".to_string(),
+            "audit.login_users.pass" => "No user besides root has both a login shell and a usable password".to_string(),
+            "audit.login_users.warn" => "Users with a login shell and an unlocked password: {val}".to_string(),
+            "audit.login_users.fix" => "Lock the password of users who log in with keys (`passwd -l <user>`) and give service accounts `/usr/sbin/nologin`".to_string(),
+            "audit.priv_groups.name" => "Privileged Groups".to_string(),
+            "audit.priv_groups.pass" => "The docker group has no members".to_string(),
+            "audit.priv_groups.docker" => "The docker group has members, which is equivalent to root".to_string(),
+            "audit.priv_groups.fix" => "Remove users who do not need full server access from docker: `gpasswd -d <user> docker`, or use rootless Docker".to_string(),
+            "audit.nopasswd.name" => "Passwordless sudo".to_string(),
+            "audit.nopasswd.pass" => "No NOPASSWD rules in sudoers".to_string(),
+            "audit.nopasswd.warn" => "NOPASSWD rules in sudoers: {val}".to_string(),
+            "audit.nopasswd.unknown" => "Could not read sudoers (the agent is not running as root)".to_string(),
+            "audit.nopasswd.fix" => "Remove NOPASSWD or limit such rules to specific commands (`visudo`)".to_string(),
+            "audit.path_writable.name" => "PATH Permissions".to_string(),
+            "audit.path_writable.pass" => "No PATH directory or file in it is world-writable".to_string(),
+            "audit.path_writable.fail" => "World-writable in PATH: {val}".to_string(),
+            "audit.path_writable.fix" => "Remove write access for others: `chmod o-w <path>`".to_string(),
+
+            "audit.custom.pass" => "Condition is met".to_string(),
+            "audit.custom.exit_code" => "Command exited with code {val}".to_string(),
+            "audit.custom.no_match" => "Command output does not match the pattern".to_string(),
+            "audit.custom.missing_line" => "Missing line: {val}".to_string(),
+            "audit.custom.error" => "Check could not run: {val}".to_string(),
             _ => key.to_string(),
         }
     }
@@ -0,0 +1,190 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::docker::{ContainerInfo, DockerService};
+use crate::i18n::{self, Lang};
+use crate::notifications::NotificationService;
+use crate::registry::{ImageRef, RegistryClient};
+
+/// Result of comparing a running container's image with its registry tag.
+#[derive(Serialize, Debug, Clone)]
+pub struct ImageUpdateStatus {
+    pub container_id: String,
+    pub container_name: String,
+    pub image: String,
+    pub local_digest: Option<String>,
+    pub remote_digest: Option<String>,
+    pub update_available: bool,
+    pub checked_at: i64,
+    pub error: Option<String>,
+}
+
+pub struct ImageUpdateChecker {
+    docker: Arc<DockerService>,
+    notifier: Arc<NotificationService>,
+    registry: Arc<RegistryClient>,
+    results: Mutex<HashMap<String, ImageUpdateStatus>>,
+}
+
+impl ImageUpdateChecker {
+    pub fn new(docker: Arc<DockerService>, notifier: Arc<NotificationService>, registry: Arc<RegistryClient>) -> Self {
+        Self {
+            docker,
+            notifier,
+            registry,
+            results: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks all running containers every `IMAGE_UPDATE_INTERVAL_HOURS` (default 24)
+    /// and sends one notification listing the outdated ones.
+    pub async fn run_loop(self: Arc<Self>) {
+        let hours = std::env::var("IMAGE_UPDATE_INTERVAL_HOURS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24u64);
+        let mut interval = tokio::time::interval(Duration::from_secs(hours * 3600));
+
+        loop {
+            interval.tick().await;
+            if !self.docker.is_connected() {
+                continue;
+            }
+
+            let outdated: Vec<String> = self.check_all().await
+                .into_iter()
+                .filter(|s| s.update_available)
+                .map(|s| format!("• {} ({})", s.container_name, s.image))
+                .collect();
+
+            if !outdated.is_empty() {
+                let lang = Lang::from_headers(&i18n::HeaderMap::new());
+                self.notifier.send_alert(&format!("{}\n\n{}", i18n::t("updates.available", &lang), outdated.join("\n"))).await;
+            }
+        }
+    }
+
+    /// Queries the registry for every running container and replaces the cached results.
+    pub async fn check_all(&self) -> Vec<ImageUpdateStatus> {
+        let containers = match self.docker.list_containers().await {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::warn!("Image update check: {}", e);
+                return Vec::new();
+            }
+        };
+
+        // Several containers often share one image; ask the registry once per tag
+        let mut remote_cache: HashMap<String, Result<String, String>> = HashMap::new();
+        let mut statuses = Vec::new();
+
+        for container in containers.iter().filter(|c| c.state == "running") {
+            let status = self.check_container(container, &mut remote_cache).await;
+            if let Some(e) = &status.error {
+                tracing::debug!("Image update check for {}: {}", container.name, e);
+            }
+            statuses.push(status);
+        }
+
+        let mut results = self.results.lock().unwrap();
+        *results = statuses.iter().map(|s| (s.container_id.clone(), s.clone())).collect();
+        statuses
+    }
+
+    async fn check_container(
+        &self,
+        container: &ContainerInfo,
+        remote_cache: &mut HashMap<String, Result<String, String>>,
+    ) -> ImageUpdateStatus {
+        let mut status = ImageUpdateStatus {
+            container_id: container.id.clone(),
+            container_name: container.name.clone(),
+            image: container.image.clone(),
+            local_digest: None,
+            remote_digest: None,
+            update_available: false,
+            checked_at: chrono::Utc::now().timestamp(),
+            error: None,
+        };
+
+        let Some(image_ref) = ImageRef::parse(&container.image) else {
+            status.error = Some("Image is pinned by digest or has no tag".to_string());
+            return status;
+        };
+
+        let local = match self.docker.image_repo_digests(&container.image_id).await {
+            Ok(digests) => local_digest_for(&digests, &image_ref),
+            Err(e) => {
+                status.error = Some(e);
+                return status;
+            }
+        };
+        let Some(local) = local else {
+            status.error = Some("Image was built locally or has no registry digest".to_string());
+            return status;
+        };
+        status.local_digest = Some(local.clone());
+
+        let remote = match remote_cache.get(&container.image) {
+            Some(r) => r.clone(),
+            None => {
+                let r = self.registry.manifest_digest(&image_ref).await;
+                remote_cache.insert(container.image.clone(), r.clone());
+                r
+            }
+        };
+
+        match remote {
+            Ok(remote) => {
+                status.update_available = remote != local;
+                status.remote_digest = Some(remote);
+            }
+            Err(e) => status.error = Some(e),
+        }
+        status
+    }
+
+    pub fn results(&self) -> Vec<ImageUpdateStatus> {
+        self.results.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Copies the cached `update_available` flag into freshly listed containers.
+    pub fn annotate(&self, containers: &mut [ContainerInfo]) {
+        let results = self.results.lock().unwrap();
+        for container in containers {
+            container.update_available = results.get(&container.id)
+                .is_some_and(|s| s.update_available && s.image == container.image);
+        }
+    }
+}
+
+/// Picks the `repo@sha256:...` entry that belongs to the container's repository.
+fn local_digest_for(repo_digests: &[String], image: &ImageRef) -> Option<String> {
+    let repository = image.digest_repository();
+    repo_digests.iter()
+        .filter_map(|d| d.split_once('@'))
+        .find(|(repo, _)| *repo == repository || *repo == format!("docker.io/{}", repository))
+        .map(|(_, digest)| digest.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_digest_for_matches_repository() {
+        let digests = vec![
+            "myfork/nginx@sha256:aaa".to_string(),
+            "nginx@sha256:bbb".to_string(),
+        ];
+        let image = ImageRef::parse("nginx:1.27").unwrap();
+        assert_eq!(local_digest_for(&digests, &image), Some("sha256:bbb".to_string()));
+
+        let ghcr = ImageRef::parse("ghcr.io/org/app:v1").unwrap();
+        assert_eq!(local_digest_for(&["ghcr.io/org/app@sha256:ccc".to_string()], &ghcr), Some("sha256:ccc".to_string()));
+        assert_eq!(local_digest_for(&[], &ghcr), None);
+    }
+}
@@ -3,15 +3,38 @@ mod notifications;
 mod docker;
 mod deployment;
 mod disk_ops;
+mod disk_forecast;
+mod disk_usage;
+mod retention;
 mod auth;
 mod history;
 mod security;
+mod security_checks;
+mod firewall;
+mod firewall_audit;
+mod ports;
+mod port_allowlist;
+mod os_updates;
+mod updates_audit;
+mod file_integrity;
+mod accounts;
+mod accounts_audit;
+mod ssh_audit;
+mod sshd_config;
 mod i18n;
 mod ssh_alerts;
 mod cloud_payload;
 mod cloud_push;
-
-use security::{SecurityAuditor, SecurityCheck, SecurityMonitor};
+mod healing;
+mod registry;
+mod image_updates;
+mod container_deploy;
+mod pipelines;
+mod self_update;
+mod scheduler;
+mod util;
+
+use security::{CheckSources, SecurityAuditor, SecurityCheck, SecurityMonitor};
 use ssh_alerts::{SshAlertsService, SshLoginEvent};
 
 use rand::Rng;
@@ -21,15 +44,16 @@ use axum::{
     http::{header, StatusCode},
     middleware,
     response::{IntoResponse, Response, sse::{Event, Sse}},
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use serde::Deserialize;
 use metrics::{MetricsState, SystemStats};
 use notifications::NotificationService;
 use docker::DockerService;
-use deployment::{DeploymentService, deploy_logs_sse_handler, trigger_update_handler};
-use disk_ops::{DiskOps, DiskUsageBreakdown};
+use deployment::{DeploymentService, cancel_deployment_handler, deploy_logs_sse_handler, trigger_update_handler};
+use disk_ops::{CleanupHistory, DeletionAudit, DiskOps, CLEAN_TARGETS};
+use disk_usage::{DiskUsageBreakdown, DiskUsageService, LargeFiles};
 use history::HistoryManager;
 use auth::auth_middleware;
 use rust_embed::RustEmbed;
@@ -112,6 +136,10 @@ async fn main() {
     .execute(&pool)
     .await
     .expect("Could not initialize schema");
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_metrics_timestamp ON metrics (timestamp)")
+        .execute(&pool)
+        .await
+        .expect("Could not initialize schema");
 
     // Initialize SSH Alerts tables
     sqlx::query(
@@ -138,8 +166,57 @@ async fn main() {
     let metrics_state = Arc::new(MetricsState::new());
     let notifications = Arc::new(NotificationService::new());
     
+    // Ports allowed to listen publicly; the listening ports check reads it
+    let port_allowlist = Arc::new(port_allowlist::PortAllowlist::new(pool.clone()));
+    port_allowlist.migrate().await.expect("Could not initialize port allowlist schema");
+
+    // Pending OS updates: read by the update checks, aged and alerted on by the tracker
+    let updates_source = Arc::new(os_updates::source());
+    let update_tracker = Arc::new(os_updates::UpdateTracker::new(
+        pool.clone(),
+        updates_source.clone(),
+        notifications.clone(),
+        os_updates::UpdateTracker::alert_days_from_env(),
+    ));
+    update_tracker.migrate().await.expect("Could not initialize pending updates schema");
+    tokio::spawn(update_tracker.clone().run_loop());
+
+    // Audit checks: built-in ones plus custom checks, minus those disabled in the file
+    let security_checks_file = std::env::var("SECURITY_CHECKS_FILE").unwrap_or_else(|_| "security-checks.json".to_string());
+    // A broken file stops startup: running the defaults would re-enable the checks it disables
+    let security_config = security_checks::load_config(&security_checks_file)
+        .unwrap_or_else(|e| panic!("Could not load security checks: {}", e));
+
+    // Baseline of critical system files; the integrity check and the monitor compare against it
+    let file_integrity = Arc::new(file_integrity::FileIntegrity::new(pool.clone(), security_config.integrity_paths()));
+    file_integrity.migrate().await.expect("Could not initialize file integrity schema");
+
+    // Local accounts and sudo rules: read by the user checks, tracked by the privilege tracker
+    let accounts_source = Arc::new(accounts::source());
+    let privilege_tracker = Arc::new(accounts::PrivilegeTracker::new(
+        pool.clone(),
+        accounts_source.clone(),
+        notifications.clone(),
+    ));
+    privilege_tracker.migrate().await.expect("Could not initialize privileged users schema");
+    tokio::spawn(privilege_tracker.clone().run_loop());
+
+    let check_sources = CheckSources {
+        ports: port_allowlist.clone(),
+        updates: updates_source.clone(),
+        integrity: file_integrity.clone(),
+        accounts: accounts_source.clone(),
+    };
+    let security_auditor = Arc::new(
+        SecurityAuditor::from_config(&security_config, &check_sources)
+            .unwrap_or_else(|e| panic!("Could not load security checks from {}: {}", security_checks_file, e)),
+    );
+    tracing::info!("Security audit runs {} checks", security_auditor.ids().len());
+
     // Start Security Monitor
-    let security_monitor = Arc::new(SecurityMonitor::new(notifications.clone()));
+    let security_monitor = Arc::new(
+        SecurityMonitor::new(notifications.clone(), security_auditor.clone()).with_integrity(file_integrity.clone()),
+    );
     tokio::spawn(async move {
         security_monitor.run_loop().await;
     });
@@ -162,16 +239,92 @@ async fn main() {
     }
     ssh_alerts_service.set_token(internal_token);
 
-    let docker_service = match DockerService::new() {
-        Ok(s) => Some(Arc::new(s)),
-        Err(e) => {
-            tracing::error!("Failed to initialize Docker service: {}", e);
-            None
+    let docker_service = Arc::new(DockerService::new());
+    tokio::spawn(docker_service.clone().run_health_loop());
+
+    let history_manager = Arc::new(HistoryManager::new(pool.clone()));
+    history_manager.migrate("history.json").await.expect("Could not initialize deployment history");
+    let deployment_service = Arc::new(DeploymentService::new(history_manager.clone()));
+    tokio::spawn(deployment_service.clone().run_log_writer());
+
+    // Container auto-healing (optional, enabled by a rules file)
+    let healing_file = std::env::var("HEALING_RULES_FILE").unwrap_or_else(|_| "healing.json".to_string());
+    match healing::load_rules(&healing_file) {
+        Ok(rules) if !rules.is_empty() => {
+            let healing_service = Arc::new(healing::HealingService::new(
+                docker_service.clone(),
+                notifications.clone(),
+                history_manager.clone(),
+                rules,
+            ));
+            tokio::spawn(healing_service.run_loop());
         }
-    };
+        Ok(_) => {}
+        Err(e) => tracing::error!("Container auto-healing disabled: {}", e),
+    }
 
-    let deployment_service = Arc::new(DeploymentService::new());
-    let history_manager = Arc::new(HistoryManager::new("history.json"));
+    // Image update detection: compares running images with their registry tags
+    let registry_client = Arc::new(registry::RegistryClient::from_env().unwrap_or_else(|e| {
+        tracing::error!("Registry credentials ignored: {}", e);
+        registry::RegistryClient::new(Default::default(), Vec::new()).expect("Failed to build HTTP client")
+    }));
+    let image_updates = Arc::new(image_updates::ImageUpdateChecker::new(
+        docker_service.clone(),
+        notifications.clone(),
+        registry_client.clone(),
+    ));
+    if std::env::var("IMAGE_UPDATE_CHECK").as_deref() != Ok("false") {
+        tokio::spawn(image_updates.clone().run_loop());
+    }
+
+    let container_deployer = Arc::new(container_deploy::ContainerDeployer::new(
+        docker_service.clone(),
+        deployment_service.clone(),
+        history_manager.clone(),
+        registry_client.clone(),
+    ));
+
+    // Webhook deploy pipelines for user applications (optional, enabled by a config file)
+    let pipelines_file = std::env::var("PIPELINES_FILE").unwrap_or_else(|_| "pipelines.json".to_string());
+    let pipelines = pipelines::load_pipelines(&pipelines_file).unwrap_or_else(|e| {
+        tracing::error!("Deploy pipelines disabled: {}", e);
+        Vec::new()
+    });
+    let pipeline_runner = Arc::new(pipelines::PipelineRunner::new(
+        pipelines,
+        deployment_service.clone(),
+        history_manager.clone(),
+    ));
+    if pipeline_runner.len() > 0 {
+        tracing::info!("Loaded {} deploy pipelines from {}", pipeline_runner.len(), pipelines_file);
+    }
+
+    // Scheduled maintenance jobs; commands they may run come from a config file
+    let job_commands_file = std::env::var("JOB_COMMANDS_FILE").unwrap_or_else(|_| "job-commands.json".to_string());
+    let job_commands = scheduler::load_commands(&job_commands_file).unwrap_or_else(|e| {
+        tracing::error!("Command jobs disabled: {}", e);
+        Vec::new()
+    });
+    // Journald and log file retention; without a file the `logs` cleaner keeps one day of journal
+    let retention_file = std::env::var("RETENTION_FILE").unwrap_or_else(|_| "retention.json".to_string());
+    let retention_config = Arc::new(retention::load_config(&retention_file).unwrap_or_else(|e| {
+        tracing::error!("Retention policies disabled: {}", e);
+        retention::RetentionConfig::default()
+    }));
+    let disk_cleanups = Arc::new(CleanupHistory::new(pool.clone()));
+    disk_cleanups.migrate().await.expect("Could not initialize disk cleanup history schema");
+
+    let job_scheduler = Arc::new(scheduler::Scheduler::new(
+        pool.clone(),
+        docker_service.clone(),
+        notifications.clone(),
+        disk_cleanups.clone(),
+        retention_config.clone(),
+        security_auditor.clone(),
+        job_commands,
+    ));
+    job_scheduler.migrate().await.expect("Could not initialize jobs schema");
+    tokio::spawn(job_scheduler.clone().run_loop());
 
     // Cloud Push (optional)
     if std::env::var("CLOUD_PUSH_ENABLED").as_deref() == Ok("true") {
@@ -197,6 +350,7 @@ async fn main() {
                             Arc::clone(&metrics_state),
                             docker_service.clone(),
                             Arc::clone(&ssh_alerts_service),
+                            security_auditor.clone(),
                         );
                         tracing::info!("Cloud push enabled, interval={}s", interval);
                     }
@@ -211,6 +365,20 @@ async fn main() {
         }
     }
 
+    let disk_usage = Arc::new(DiskUsageService::new(disk_usage::DiskScanConfig::from_env()));
+    let disk_audit = Arc::new(DeletionAudit::new(pool.clone()));
+    disk_audit.migrate().await.expect("Could not initialize file deletion audit schema");
+
+    // Disk fill-rate forecasts from the usage history, with a "full within N hours" alert
+    let disk_forecaster = Arc::new(disk_forecast::DiskForecaster::new(
+        pool.clone(),
+        metrics_state.clone(),
+        notifications.clone(),
+        disk_forecast::ForecastConfig::from_env(),
+    ));
+    disk_forecaster.migrate().await.expect("Could not initialize disk forecast schema");
+    tokio::spawn(disk_forecaster.clone().run_loop());
+
     // 3. Start Background Task for Metrics & Alerts
     let metrics_clone = Arc::clone(&metrics_state);
     let notifier_clone = Arc::clone(&notifications);
@@ -251,15 +419,48 @@ async fn main() {
         .route("/stats", get(get_stats_handler))
         .route("/stats/history", get(get_history_handler))
         .route("/history", get(list_deployments_handler))
+        .route("/history/{id}", get(get_deployment_handler))
+        .route("/history/{id}/logs", get(get_deployment_logs_handler))
+        .route("/history/{id}/rollback", post(rollback_deployment_handler))
         .route("/test-notification", post(test_notification_handler))
+        .route("/docker/status", get(docker_status_handler))
         .route("/docker/containers", get(list_containers_handler))
+        .route("/docker/updates", get(list_image_updates_handler))
+        .route("/docker/updates/check", post(check_image_updates_handler))
+        .route("/docker/containers/{id}/redeploy", post(redeploy_container_handler))
         .route("/docker/containers/{id}/{action}", post(container_action_handler))
         .route("/docker/containers/{id}/logs", get(docker_logs_sse_handler)) // SSE by default now
         .route("/disk/usage", get(get_disk_usage_handler))
+        .route("/disk/tree", get(get_disk_tree_handler))
+        .route("/disk/forecast", get(get_disk_forecast_handler))
         .route("/disk/clean", post(clean_disk_handler))
+        .route("/disk/cleanups", get(get_cleanups_handler))
+        .route("/disk/retention", get(get_retention_handler))
+        .route("/disk/retention/{name}/apply", post(apply_retention_handler))
+        .route("/disk/large-files", get(get_large_files_handler))
+        .route("/disk/delete", post(delete_files_handler))
+        .route("/disk/deletions", get(get_deletions_handler))
         .route("/deploy/webhook", post(trigger_update_handler))
         .route("/deploy/logs", get(deploy_logs_sse_handler))
+        .route("/deploy/cancel", post(cancel_deployment_handler))
+        .route("/jobs", get(list_jobs_handler))
+        .route("/jobs", post(create_job_handler))
+        .route("/jobs/commands", get(list_job_commands_handler))
+        .route("/jobs/{id}", get(get_job_handler))
+        .route("/jobs/{id}", put(update_job_handler))
+        .route("/jobs/{id}", delete(delete_job_handler))
+        .route("/jobs/{id}/run", post(run_job_handler))
+        .route("/jobs/{id}/runs", get(list_job_runs_handler))
         .route("/security/audit", get(get_security_audit_handler))
+        .route("/security/firewall", get(get_firewall_handler))
+        .route("/security/ports", get(get_open_ports_handler))
+        .route("/security/ports/allowlist", get(get_port_allowlist_handler))
+        .route("/security/ports/allowlist", post(add_allowed_port_handler))
+        .route("/security/ports/allowlist/{id}", delete(delete_allowed_port_handler))
+        .route("/security/updates", get(get_os_updates_handler))
+        .route("/security/integrity", get(get_integrity_handler))
+        .route("/security/integrity/baseline", post(rebaseline_integrity_handler))
+        .route("/security/users", get(get_users_handler))
         .route("/ssh/logs", get(get_ssh_logs_handler))
         .route("/ssh/trusted-ips", get(get_trusted_ips_handler))
         .route("/ssh/trusted-ips", post(add_trusted_ip_handler))
@@ -268,7 +469,9 @@ async fn main() {
         .route("/version", get(get_version_handler));
 
     let internal_api = Router::new()
-        .route("/internal/ssh-login", post(ssh_login_handler));
+        .route("/internal/ssh-login", post(ssh_login_handler))
+        // Authenticated by the pipeline's webhook signature instead of the bearer token
+        .route("/webhooks/{name}", post(pipeline_webhook_handler));
 
     let api_routes = Router::new()
         .merge(protected_api.layer(middleware::from_fn(auth_middleware)))
@@ -285,9 +488,23 @@ async fn main() {
             db: pool, 
             notifier: notifications, 
             docker: docker_service,
+            disk_usage,
+            disk_audit,
+            disk_cleanups,
+            disk_forecast: disk_forecaster,
+            retention: retention_config,
+            image_updates,
+            deployer: container_deployer,
+            pipelines: pipeline_runner,
             deployment: deployment_service,
             history: history_manager,
+            jobs: job_scheduler,
             ssh_alerts: ssh_alerts_service,
+            security: security_auditor,
+            ports: port_allowlist,
+            updates: update_tracker,
+            integrity: file_integrity,
+            privileges: privilege_tracker,
         });
 
     let app_host = std::env::var("APP_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
@@ -306,10 +523,24 @@ struct AppState {
     metrics: Arc<MetricsState>,
     db: sqlx::SqlitePool,
     notifier: Arc<NotificationService>,
-    docker: Option<Arc<DockerService>>,
+    docker: Arc<DockerService>,
+    disk_usage: Arc<DiskUsageService>,
+    disk_audit: Arc<DeletionAudit>,
+    disk_cleanups: Arc<CleanupHistory>,
+    disk_forecast: Arc<disk_forecast::DiskForecaster>,
+    retention: Arc<retention::RetentionConfig>,
+    image_updates: Arc<image_updates::ImageUpdateChecker>,
+    deployer: Arc<container_deploy::ContainerDeployer>,
+    pipelines: Arc<pipelines::PipelineRunner>,
     deployment: Arc<DeploymentService>,
     history: Arc<HistoryManager>,
+    jobs: Arc<scheduler::Scheduler>,
     ssh_alerts: Arc<SshAlertsService>,
+    security: Arc<SecurityAuditor>,
+    ports: Arc<port_allowlist::PortAllowlist>,
+    updates: Arc<os_updates::UpdateTracker>,
+    integrity: Arc<file_integrity::FileIntegrity>,
+    privileges: Arc<accounts::PrivilegeTracker>,
 }
 
 impl FromRef<AppState> for Arc<DeploymentService> {
@@ -351,8 +582,77 @@ async fn get_history_handler(State(state): State<AppState>) -> Json<Vec<SystemSt
     Json(stats)
 }
 
-async fn list_deployments_handler(State(state): State<AppState>) -> Json<Vec<history::DeploymentRecord>> {
-    Json(state.history.get_history())
+/// Paginated deployment history; the total number of matches is returned in `X-Total-Count`.
+async fn list_deployments_handler(
+    State(state): State<AppState>,
+    Query(query): Query<history::HistoryQuery>,
+) -> Response {
+    match state.history.get_history(&query).await {
+        Ok((records, total)) => ([("x-total-count", total.to_string())], Json(records)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn get_deployment_logs_handler(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    match state.history.get_record(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return (StatusCode::NOT_FOUND, "Deployment not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+    match state.history.get_logs(&id).await {
+        Ok(lines) => Json(lines).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn get_deployment_handler(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    match state.history.get_record(&id).await {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Deployment not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Rolls a container back to the image it ran before a deployment (`previous_image_id`, or the
+/// recorded `image_id` for records without one), or the agent back to the build saved before a
+/// self-update. Progress goes to `/api/deploy/logs`.
+async fn rollback_deployment_handler(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let record = match state.history.get_record(&id).await {
+        Ok(Some(record)) => record,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Deployment not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    if record.container_name.as_deref() == Some(deployment::AGENT_NAME) {
+        if record.action != "update" || !deployment::has_agent_backup(&record.id) {
+            return (StatusCode::BAD_REQUEST, "No saved build for this deployment").into_response();
+        }
+        let guard = match state.deployment.try_start(&uuid::Uuid::new_v4().to_string(), false) {
+            Ok(guard) => guard,
+            Err(running) => return deployment::already_running(&running),
+        };
+        state.deployment.rollback_agent(guard, record.id).await;
+        return (StatusCode::OK, "Rollback triggered. Connect to stream for logs.").into_response();
+    }
+
+    let (Some(container), Some(image_id)) = (record.container_name.clone(), record.rollback_image().map(str::to_string)) else {
+        return (StatusCode::BAD_REQUEST, "Deployment has no container image to roll back to").into_response();
+    };
+    if !state.docker.is_connected() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Docker integration is not available").into_response();
+    }
+    let guard = match state.deployment.try_start(&uuid::Uuid::new_v4().to_string(), false) {
+        Ok(guard) => guard,
+        Err(running) => return deployment::already_running(&running),
+    };
+
+    let deployer = state.deployer.clone();
+    tokio::spawn(async move {
+        if let Err(e) = deployer.rollback_to(&guard, &container, &image_id, record.id).await {
+            tracing::error!("Rollback of {} failed: {}", container, e);
+        }
+    });
+    (StatusCode::OK, "Rollback triggered. Connect to stream for logs.").into_response()
 }
 
 async fn test_notification_handler(State(state): State<AppState>, headers: header::HeaderMap) -> impl IntoResponse {
@@ -362,34 +662,176 @@ async fn test_notification_handler(State(state): State<AppState>, headers: heade
 }
 
 async fn list_containers_handler(State(state): State<AppState>) -> Response {
-    if let Some(docker) = &state.docker {
-        match docker.list_containers().await {
-            Ok(containers) => Json(containers).into_response(),
-            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    if !state.docker.is_connected() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Docker integration is not available").into_response();
+    }
+    match state.docker.list_containers().await {
+        Ok(mut containers) => {
+            state.image_updates.annotate(&mut containers);
+            Json(containers).into_response()
         }
-    } else {
-        (StatusCode::SERVICE_UNAVAILABLE, "Docker integration is not available").into_response()
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn list_image_updates_handler(State(state): State<AppState>) -> Json<Vec<image_updates::ImageUpdateStatus>> {
+    Json(state.image_updates.results())
+}
+
+async fn check_image_updates_handler(State(state): State<AppState>) -> Response {
+    if !state.docker.is_connected() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Docker integration is not available").into_response();
     }
+    Json(state.image_updates.check_all().await).into_response()
+}
+
+async fn docker_status_handler(State(state): State<AppState>) -> Json<docker::DockerStatus> {
+    Json(state.docker.status())
 }
 
 async fn container_action_handler(
     State(state): State<AppState>,
     Path((id, action)): Path<(String, String)>,
 ) -> Response {
-    if let Some(docker) = &state.docker {
-        let result = match action.as_str() {
-            "start" => docker.start_container(&id).await,
-            "stop" => docker.stop_container(&id).await,
-            "restart" => docker.restart_container(&id).await,
-            _ => return (StatusCode::BAD_REQUEST, "Invalid action. Use start, stop, or restart").into_response(),
-        };
+    if !state.docker.is_connected() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Docker integration is not available").into_response();
+    }
+    let docker = &state.docker;
+    let result = match action.as_str() {
+        "start" => docker.start_container(&id).await,
+        "stop" => docker.stop_container(&id).await,
+        "restart" => docker.restart_container(&id).await,
+        _ => return (StatusCode::BAD_REQUEST, "Invalid action. Use start, stop, or restart").into_response(),
+    };
 
-        match result {
-            Ok(_) => StatusCode::OK.into_response(),
-            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    match result {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct RedeployRequest {
+    /// Image to switch to; defaults to the container's current `repo:tag`
+    image: Option<String>,
+}
+
+async fn redeploy_container_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    payload: Option<Json<RedeployRequest>>,
+) -> Response {
+    if !state.docker.is_connected() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Docker integration is not available").into_response();
+    }
+    let image = payload.and_then(|Json(p)| p.image).filter(|i| !i.trim().is_empty());
+    let guard = match state.deployment.try_start(&uuid::Uuid::new_v4().to_string(), false) {
+        Ok(guard) => guard,
+        Err(running) => return deployment::already_running(&running),
+    };
+
+    let deployer = state.deployer.clone();
+    tokio::spawn(async move {
+        if let Err(e) = deployer.redeploy(&guard, &id, image).await {
+            tracing::error!("Redeploy of {} failed: {}", id, e);
         }
-    } else {
-        (StatusCode::SERVICE_UNAVAILABLE, "Docker integration is not available").into_response()
+    });
+    (StatusCode::OK, "Redeploy triggered. Connect to stream for logs.").into_response()
+}
+
+async fn pipeline_webhook_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: header::HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    match state.pipelines.handle_webhook(&name, &headers, &body).await {
+        Ok(id) => (StatusCode::ACCEPTED, Json(serde_json::json!({ "deployment_id": id }))).into_response(),
+        Err(pipelines::WebhookError::NotFound) => (StatusCode::NOT_FOUND, "Pipeline not found").into_response(),
+        Err(pipelines::WebhookError::Unauthorized(e)) => {
+            tracing::warn!("Rejected webhook for pipeline {}: {}", name, e);
+            (StatusCode::UNAUTHORIZED, e).into_response()
+        }
+        Err(pipelines::WebhookError::Ignored(reason)) => (StatusCode::OK, reason).into_response(),
+        Err(pipelines::WebhookError::Busy(running)) => deployment::already_running(&running),
+    }
+}
+
+fn job_error(e: scheduler::JobError) -> Response {
+    let status = match e {
+        scheduler::JobError::NotFound => StatusCode::NOT_FOUND,
+        scheduler::JobError::Invalid(_) => StatusCode::BAD_REQUEST,
+        scheduler::JobError::Running => StatusCode::CONFLICT,
+        scheduler::JobError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string()).into_response()
+}
+
+async fn list_jobs_handler(State(state): State<AppState>) -> Response {
+    match state.jobs.list_jobs().await {
+        Ok(jobs) => Json(jobs).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn list_job_commands_handler(State(state): State<AppState>) -> Json<Vec<String>> {
+    Json(state.jobs.command_names())
+}
+
+async fn create_job_handler(State(state): State<AppState>, Json(input): Json<scheduler::JobInput>) -> Response {
+    match state.jobs.create_job(input).await {
+        Ok(job) => (StatusCode::CREATED, Json(job)).into_response(),
+        Err(e) => job_error(e),
+    }
+}
+
+async fn get_job_handler(State(state): State<AppState>, Path(id): Path<i64>) -> Response {
+    match state.jobs.get_job(id).await {
+        Ok(Some(job)) => Json(job).into_response(),
+        Ok(None) => job_error(scheduler::JobError::NotFound),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn update_job_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(input): Json<scheduler::JobInput>,
+) -> Response {
+    match state.jobs.update_job(id, input).await {
+        Ok(job) => Json(job).into_response(),
+        Err(e) => job_error(e),
+    }
+}
+
+async fn delete_job_handler(State(state): State<AppState>, Path(id): Path<i64>) -> Response {
+    match state.jobs.delete_job(id).await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => job_error(e),
+    }
+}
+
+/// Starts a job now; the run shows up in `/api/jobs/{id}/runs` as `running` until it finishes.
+async fn run_job_handler(State(state): State<AppState>, Path(id): Path<i64>) -> Response {
+    match state.jobs.run_now(id).await {
+        Ok(run_id) => (StatusCode::ACCEPTED, Json(serde_json::json!({ "run_id": run_id }))).into_response(),
+        Err(e) => job_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct JobRunsQuery {
+    limit: Option<i64>,
+}
+
+async fn list_job_runs_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<JobRunsQuery>,
+) -> Response {
+    match state.jobs.get_runs(id, query.limit.unwrap_or(20)).await {
+        Ok(runs) => Json(runs).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
 
@@ -419,11 +861,6 @@ async fn docker_logs_sse_handler(
 
     tracing::info!("SSE Stream requested for container: {}", id);
 
-    let docker = match &state.docker {
-        Some(d) => d.clone(),
-        None => return (StatusCode::SERVICE_UNAVAILABLE, "Docker not available").into_response(),
-    };
-
     let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(100);
     let container_id = id.clone();
 
@@ -440,8 +877,12 @@ async fn docker_logs_sse_handler(
         None => Some("100".to_string()),
     };
 
+    let mut stream = match state.docker.logs_stream(&container_id, params.since, safe_tail) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, e).into_response(),
+    };
+
     tokio::spawn(async move {
-        let mut stream = docker.logs_stream(&container_id, params.since, safe_tail);
         while let Some(result) = stream.next().await {
             let event = match result {
                 Ok(line) => Event::default().data(line),
@@ -461,30 +902,158 @@ async fn docker_logs_sse_handler(
         .into_response()
 }
 
-async fn get_disk_usage_handler() -> Json<DiskUsageBreakdown> {
-    Json(DiskOps::get_usage("."))
+#[derive(Deserialize)]
+struct DiskUsageQuery {
+    /// Rescan now instead of answering from the cache
+    #[serde(default)]
+    refresh: bool,
+}
+
+async fn get_disk_usage_handler(
+    State(state): State<AppState>,
+    Query(query): Query<DiskUsageQuery>,
+) -> Json<DiskUsageBreakdown> {
+    Json(state.disk_usage.usage(query.refresh).await)
+}
+
+#[derive(Deserialize)]
+struct DiskTreeQuery {
+    path: Option<String>,
+    depth: Option<usize>,
+    limit: Option<usize>,
+}
+
+/// Largest directories below `path`, from the cached scan.
+async fn get_disk_tree_handler(State(state): State<AppState>, Query(query): Query<DiskTreeQuery>) -> Response {
+    let depth = query.depth.unwrap_or(1).clamp(1, 5);
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+    match state.disk_usage.largest_dirs(query.path.as_deref(), depth, limit).await {
+        Some(tree) => Json(tree).into_response(),
+        None => (StatusCode::NOT_FOUND, "Path is not inside a scanned root").into_response(),
+    }
+}
+
+/// Fill rate and expected time until full for `/` and the other mounts.
+async fn get_disk_forecast_handler(State(state): State<AppState>) -> Response {
+    match state.disk_forecast.forecasts().await {
+        Ok(forecasts) => Json(forecasts).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
 }
 
 #[derive(serde::Deserialize)]
 struct CleanRequest {
     target: String,
+    /// Only report what would be removed
+    #[serde(default)]
+    dry_run: bool,
 }
 
-async fn clean_disk_handler(Json(payload): Json<CleanRequest>) -> Response {
-    let result = match payload.target.as_str() {
-        "target" => DiskOps::clean_target(".").await,
-        "node_modules" => DiskOps::clean_node_modules(".").await,
-        "docker" => DiskOps::clean_docker().await,
-        "logs" => DiskOps::clean_logs().await,
-        _ => Err("Invalid target".to_string()),
-    };
+async fn clean_disk_handler(State(state): State<AppState>, Json(payload): Json<CleanRequest>) -> Response {
+    if !CLEAN_TARGETS.contains(&payload.target.as_str()) {
+        return (StatusCode::BAD_REQUEST, "Invalid target").into_response();
+    }
+    let result = DiskOps::clean(&payload.target, ".", &state.docker, &state.retention.journald, payload.dry_run).await;
+    if !payload.dry_run {
+        state.disk_cleanups.record(&payload.target, "manual", &result).await;
+        state.disk_usage.mark_stale();
+    }
+    match result {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// The journald policy and the log file policies from `RETENTION_FILE`.
+async fn get_retention_handler(State(state): State<AppState>) -> Json<retention::RetentionConfig> {
+    Json(state.retention.as_ref().clone())
+}
 
+#[derive(Deserialize)]
+struct ApplyRetentionRequest {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn apply_retention_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<ApplyRetentionRequest>,
+) -> Response {
+    let Some(policy) = state.retention.policy(&name) else {
+        return (StatusCode::NOT_FOUND, "Retention policy not found").into_response();
+    };
+    let result = retention::apply(policy, payload.dry_run).await;
+    if !payload.dry_run {
+        state.disk_cleanups.record(&format!("retention:{}", name), "manual", &result).await;
+        state.disk_usage.mark_stale();
+    }
     match result {
-        Ok(msg) => (StatusCode::OK, msg).into_response(),
+        Ok(report) => Json(report).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
 
+#[derive(Deserialize)]
+struct CleanupsQuery {
+    limit: Option<i64>,
+}
+
+/// Past cleaner runs and the space each one freed.
+async fn get_cleanups_handler(State(state): State<AppState>, Query(query): Query<CleanupsQuery>) -> Response {
+    match state.disk_cleanups.list(query.limit.unwrap_or(100)).await {
+        Ok(records) => Json(records).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct LargeFilesQuery {
+    limit: Option<usize>,
+    older_than_days: Option<u64>,
+}
+
+/// Largest files under the scan roots and old files under the log directories.
+async fn get_large_files_handler(
+    State(state): State<AppState>,
+    Query(query): Query<LargeFilesQuery>,
+) -> Json<LargeFiles> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let older_than_days = query.older_than_days.unwrap_or(30).clamp(1, 3650);
+    Json(state.disk_usage.large_files(limit, older_than_days).await)
+}
+
+#[derive(Deserialize)]
+struct DeleteFilesRequest {
+    paths: Vec<String>,
+}
+
+/// Deletes files inside `DISK_DELETE_ROOTS`; every attempt is audited.
+async fn delete_files_handler(State(state): State<AppState>, Json(payload): Json<DeleteFilesRequest>) -> Response {
+    if payload.paths.is_empty() || payload.paths.len() > 1000 {
+        return (StatusCode::BAD_REQUEST, "Between 1 and 1000 paths are required").into_response();
+    }
+    let roots = state.disk_usage.delete_roots().to_vec();
+    let results = tokio::task::spawn_blocking(move || DiskOps::delete_files(&payload.paths, &roots))
+        .await
+        .unwrap_or_default();
+    state.disk_audit.record(&results).await;
+    state.disk_usage.mark_stale();
+    Json(results).into_response()
+}
+
+#[derive(Deserialize)]
+struct DeletionsQuery {
+    limit: Option<i64>,
+}
+
+async fn get_deletions_handler(State(state): State<AppState>, Query(query): Query<DeletionsQuery>) -> Response {
+    match state.disk_audit.list(query.limit.unwrap_or(100)).await {
+        Ok(records) => Json(records).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 async fn index_handler() -> impl IntoResponse {
     serve_file("index.html")
 }
@@ -511,9 +1080,119 @@ fn serve_file(path: &str) -> Response {
     (StatusCode::NOT_FOUND, "index.html not found").into_response()
 }
 
-async fn get_security_audit_handler(headers: header::HeaderMap) -> Json<Vec<SecurityCheck>> {
+#[derive(Deserialize)]
+struct AuditQuery {
+    /// Only checks in this category, e.g. `users`
+    category: Option<String>,
+}
+
+async fn get_security_audit_handler(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+    headers: header::HeaderMap,
+) -> Json<Vec<SecurityCheck>> {
     let lang = i18n::Lang::from_headers(&headers);
-    Json(SecurityAuditor::run_audit(&lang).await)
+    let mut checks = state.security.run_audit(&lang).await;
+    if let Some(category) = query.category {
+        checks.retain(|c| c.categories.contains(&category));
+    }
+    Json(checks)
+}
+
+/// Listening ports with owner, exposure and allowlist status. Containers are
+/// named from their cgroup id, or from the published port for `docker-proxy`.
+async fn get_open_ports_handler(State(state): State<AppState>) -> Response {
+    let allowlist = state.ports.clone();
+    let mut ports = match tokio::task::spawn_blocking(move || allowlist.scan()).await {
+        Ok(ports) => ports,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if let Ok(containers) = state.docker.list_containers().await {
+        for port in &mut ports {
+            port.container = containers.iter()
+                .find(|c| match &port.container_id {
+                    Some(id) => *id == c.id,
+                    None => port.process.as_deref() == Some("docker-proxy")
+                        && c.ports.split(", ").any(|p| p.split(':').next() == Some(&port.port.to_string())),
+                })
+                .map(|c| c.name.clone());
+        }
+    }
+    Json(ports).into_response()
+}
+
+async fn get_port_allowlist_handler(State(state): State<AppState>) -> Response {
+    Json(state.ports.list()).into_response()
+}
+
+async fn add_allowed_port_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<port_allowlist::NewAllowedPort>,
+) -> Response {
+    if let Err(e) = payload.validate() {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    match state.ports.add(payload).await {
+        Ok(entry) => Json(entry).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn delete_allowed_port_handler(State(state): State<AppState>, Path(id): Path<i64>) -> Response {
+    match state.ports.remove(id).await {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Allowlist entry not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Detected firewall, its default input policy, reachable ports and Docker publishes.
+async fn get_firewall_handler() -> Response {
+    match tokio::task::spawn_blocking(firewall::inspect).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Pending OS updates with the time each was first seen, auto-update and reboot state.
+async fn get_os_updates_handler(State(state): State<AppState>) -> Response {
+    match state.updates.status().await {
+        Ok(status) => Json(status).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Watched paths, baseline size and the files that differ from the baseline.
+async fn get_integrity_handler(State(state): State<AppState>) -> Response {
+    Json(state.integrity.report().await).into_response()
+}
+
+/// Accounts, privileged users, sudo rules and writable PATH entries, with
+/// when each privilege was first and last seen.
+async fn get_users_handler(State(state): State<AppState>) -> Response {
+    match state.privileges.report().await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct RebaselineRequest {
+    /// Only these paths; all changed files when absent
+    #[serde(default)]
+    paths: Option<Vec<String>>,
+}
+
+/// Accepts the current state of changed files as the new baseline.
+async fn rebaseline_integrity_handler(
+    State(state): State<AppState>,
+    payload: Option<Json<RebaselineRequest>>,
+) -> Response {
+    let paths = payload.map(|Json(p)| p).unwrap_or_default().paths;
+    match state.integrity.rebaseline(paths).await {
+        Ok(updated) => Json(serde_json::json!({ "updated": updated })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
 }
 
 async fn get_version_handler() -> &'static str {
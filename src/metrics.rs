@@ -12,6 +12,17 @@ pub struct SystemStats {
     pub timestamp: i64,
 }
 
+/// Space on one mounted filesystem.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MountUsage {
+    pub mount: String,
+    pub used: u64,
+    pub total: u64,
+}
+
+/// Filesystems that do not hold data worth forecasting.
+const VIRTUAL_FILESYSTEMS: &[&str] = &["tmpfs", "devtmpfs", "overlay", "squashfs", "ramfs", "proc", "sysfs", "nsfs", "efivarfs"];
+
 pub struct MetricsState {
     pub sys: Mutex<System>,
     pub disks: Mutex<Disks>,
@@ -84,6 +95,31 @@ impl MetricsState {
     pub fn get_current(&self) -> SystemStats {
         self.current.lock().unwrap().clone()
     }
+
+    /// Real filesystems as of the last refresh. A device mounted more than once
+    /// (bind mounts, container volumes) is listed under its shortest mount point.
+    pub fn mounts(&self) -> Vec<MountUsage> {
+        let disks = self.disks.lock().unwrap();
+        let mut mounts: Vec<(String, MountUsage)> = Vec::new();
+        for disk in disks.iter() {
+            let fs = disk.file_system().to_string_lossy();
+            if VIRTUAL_FILESYSTEMS.contains(&fs.as_ref()) || disk.total_space() == 0 {
+                continue;
+            }
+            let device = disk.name().to_string_lossy().to_string();
+            let usage = MountUsage {
+                mount: disk.mount_point().display().to_string(),
+                used: disk.total_space() - disk.available_space(),
+                total: disk.total_space(),
+            };
+            match mounts.iter_mut().find(|(d, _)| *d == device) {
+                Some((_, existing)) if usage.mount.len() < existing.mount.len() => *existing = usage,
+                Some(_) => {}
+                None => mounts.push((device, usage)),
+            }
+        }
+        mounts.into_iter().map(|(_, usage)| usage).collect()
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,6 @@ use reqwest::Client;
 use std::sync::Mutex;
 use std::collections::HashMap;
 use std::time::{Instant, Duration};
-use hostname;
 
 #[derive(Serialize)]
 struct TelegramMessage {
@@ -52,11 +51,11 @@ impl NotificationService {
         // 1. Deduplication (Cooldown per unique message)
         {
             let mut history = self.alert_history.lock().unwrap();
-            if let Some(last_time) = history.get(message) {
-                if last_time.elapsed() < Duration::from_secs(1800) { // 30 mins
-                    tracing::info!("Skipping duplicate alert (cooldown): {}", message);
-                    return;
-                }
+            if let Some(last_time) = history.get(message)
+                && last_time.elapsed() < Duration::from_secs(1800) // 30 mins
+            {
+                tracing::info!("Skipping duplicate alert (cooldown): {}", message);
+                return;
             }
             history.insert(message.to_string(), Instant::now());
         }
@@ -64,12 +63,12 @@ impl NotificationService {
         // 2. Global Rate Limiting (Throttle)
         {
             let mut last = self.last_sent.lock().unwrap();
-            if let Some(time) = *last {
-                if time.elapsed() < Duration::from_secs(3) { // 3 seconds delay
-                    tracing::info!("Throttling alert to prevent spam");
-                    // In a real app we might queue this, but for now we sleep or skip.
-                    // Sleeping holds the mutex if we aren't careful, so we drop the lock first.
-                }
+            if let Some(time) = *last
+                && time.elapsed() < Duration::from_secs(3) // 3 seconds delay
+            {
+                tracing::info!("Throttling alert to prevent spam");
+                // In a real app we might queue this, but for now we sleep or skip.
+                // Sleeping holds the mutex if we aren't careful, so we drop the lock first.
             }
             *last = Some(Instant::now());
         }
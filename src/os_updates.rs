@@ -0,0 +1,571 @@
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::i18n::{self, Lang};
+use crate::notifications::NotificationService;
+use crate::security::{CachedSource, SecurityAuditor};
+
+/// The package index only changes when apt/dnf refresh it, usually once a day.
+const CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+/// Overdue security updates are reported again after this long.
+const ALERT_COOLDOWN: Duration = Duration::from_secs(24 * 3600);
+/// Packages named in evidence and alerts; the rest are only counted.
+const MAX_LISTED: usize = 10;
+const REBOOT_REQUIRED: &str = "/var/run/reboot-required";
+const REBOOT_REQUIRED_PKGS: &str = "/var/run/reboot-required.pkgs";
+const DNF_AUTOMATIC_CONF: &str = "/etc/dnf/automatic.conf";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Apt,
+    /// Also yum, which takes the same arguments
+    Dnf,
+    Apk,
+}
+
+impl PackageManager {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Apk => "apk",
+        }
+    }
+
+    fn detect() -> Option<(Self, PathBuf)> {
+        [
+            (PackageManager::Apt, "apt"),
+            (PackageManager::Dnf, "dnf"),
+            (PackageManager::Dnf, "yum"),
+            (PackageManager::Apk, "apk"),
+        ]
+        .into_iter()
+        .find_map(|(manager, binary)| SecurityAuditor::find_system_binary(binary).map(|path| (manager, path)))
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PendingUpdate {
+    pub name: String,
+    /// Version the package would be upgraded to
+    pub version: String,
+    pub security: bool,
+    /// Unix timestamp (seconds) the agent first saw the update pending
+    pub first_seen: Option<i64>,
+}
+
+impl PendingUpdate {
+    fn new(name: &str, version: &str) -> Self {
+        Self { name: name.to_string(), version: version.to_string(), security: false, first_seen: None }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct UpdateStatus {
+    pub manager: Option<PackageManager>,
+    /// `None` when the pending updates could not be listed
+    pub pending: Option<Vec<PendingUpdate>>,
+    /// `false` when the package manager does not mark security updates (apk)
+    pub security_known: bool,
+    /// `None` when it could not be determined
+    pub auto_updates: Option<bool>,
+    pub reboot_required: bool,
+    /// Packages that asked for the reboot, where the system records them
+    pub reboot_packages: Vec<String>,
+    pub errors: Vec<String>,
+    /// Unix timestamp (seconds)
+    pub checked_at: i64,
+}
+
+impl UpdateStatus {
+    pub fn security_updates(&self) -> Vec<&PendingUpdate> {
+        self.pending.iter().flatten().filter(|p| p.security).collect()
+    }
+
+    /// One line for evidence, e.g. "apt: 12 pending, 2 security (openssl, libssl3)".
+    pub fn summary(&self) -> String {
+        let Some(manager) = self.manager else {
+            return "no supported package manager".to_string();
+        };
+        let Some(pending) = &self.pending else {
+            return format!("{}: pending updates unknown", manager.name());
+        };
+        let mut summary = format!("{}: {} pending", manager.name(), pending.len());
+        let security = self.security_updates();
+        if self.security_known {
+            summary.push_str(&format!(", {} security", security.len()));
+        }
+        if !security.is_empty() {
+            summary.push_str(&format!(" ({})", list_names(&security)));
+        }
+        summary
+    }
+}
+
+fn list_names(updates: &[&PendingUpdate]) -> String {
+    let mut names: Vec<String> = updates.iter().take(MAX_LISTED).map(|p| p.name.clone()).collect();
+    if updates.len() > MAX_LISTED {
+        names.push(format!("+{} more", updates.len() - MAX_LISTED));
+    }
+    names.join(", ")
+}
+
+/// Lists pending updates from the local package index. Nothing is downloaded:
+/// the index is as fresh as the distribution's own refresh timer left it.
+pub fn inspect() -> UpdateStatus {
+    let mut status = UpdateStatus { checked_at: chrono::Utc::now().timestamp(), ..Default::default() };
+    if let Some((manager, path)) = PackageManager::detect() {
+        status.manager = Some(manager);
+        match manager {
+            PackageManager::Apt => inspect_apt(&path, &mut status),
+            PackageManager::Dnf => inspect_dnf(&path, &mut status),
+            PackageManager::Apk => inspect_apk(&path, &mut status),
+        }
+    }
+    if !status.reboot_required {
+        status.reboot_required = running_kernel_removed(Path::new("/lib/modules"));
+    }
+    status
+}
+
+/// Runs a command with the C locale; exit codes other than `ok` are errors.
+fn run(path: &Path, args: &[&str], ok: &[i32]) -> Result<(i32, String), String> {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let output = Command::new(path)
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("{}: {}", name, e))?;
+    let code = output.status.code().unwrap_or(-1);
+    if ok.contains(&code) {
+        Ok((code, String::from_utf8_lossy(&output.stdout).into_owned()))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{} {}: {}", name, args.join(" "), stderr.trim()))
+    }
+}
+
+fn inspect_apt(apt: &Path, status: &mut UpdateStatus) {
+    status.security_known = true;
+    match run(apt, &["list", "--upgradable"], &[0]) {
+        Ok((_, out)) => status.pending = Some(parse_apt_upgradable(&out)),
+        Err(e) => status.errors.push(e),
+    }
+    status.auto_updates = SecurityAuditor::find_system_binary("apt-config")
+        .and_then(|apt_config| run(&apt_config, &["dump"], &[0]).ok())
+        .map(|(_, dump)| apt_periodic_upgrades(&dump) && Path::new("/usr/bin/unattended-upgrade").exists());
+    status.reboot_required = Path::new(REBOOT_REQUIRED).exists();
+    if let Ok(pkgs) = std::fs::read_to_string(REBOOT_REQUIRED_PKGS) {
+        for pkg in pkgs.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if !status.reboot_packages.iter().any(|p| p == pkg) {
+                status.reboot_packages.push(pkg.to_string());
+            }
+        }
+    }
+}
+
+fn inspect_dnf(dnf: &Path, status: &mut UpdateStatus) {
+    // -C: use the cached metadata instead of refreshing it over the network
+    match run(dnf, &["-q", "-C", "check-update"], &[0, 100]) {
+        Ok((_, out)) => status.pending = Some(parse_dnf_check_update(&out)),
+        Err(e) => status.errors.push(e),
+    }
+    match run(dnf, &["-q", "-C", "updateinfo", "list", "--security"], &[0]) {
+        Ok((_, out)) => {
+            status.security_known = true;
+            let security = parse_dnf_advisories(&out);
+            for update in status.pending.iter_mut().flatten() {
+                update.security = security.contains(&update.name);
+            }
+        }
+        Err(e) => status.errors.push(e),
+    }
+    status.auto_updates = dnf_automatic();
+    if let Some(needs_restarting) = SecurityAuditor::find_system_binary("needs-restarting")
+        && let Ok((code, _)) = run(&needs_restarting, &["-r"], &[0, 1])
+    {
+        status.reboot_required = code == 1;
+    }
+}
+
+fn inspect_apk(apk: &Path, status: &mut UpdateStatus) {
+    match run(apk, &["version", "-l", "<"], &[0]) {
+        Ok((_, out)) => status.pending = Some(parse_apk_version(&out)),
+        Err(e) => status.errors.push(e),
+    }
+}
+
+/// `apt list --upgradable` lines: `openssl/jammy-updates,jammy-security 3.0.2-0ubuntu1.15 amd64 [upgradable from: ...]`.
+/// An update is a security one when any suite it comes from is a security suite.
+fn parse_apt_upgradable(text: &str) -> Vec<PendingUpdate> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once('/')?;
+            let mut fields = rest.split_whitespace();
+            let suites = fields.next()?;
+            let version = fields.next()?;
+            let mut update = PendingUpdate::new(name, version);
+            update.security = suites.split(',').any(|s| s.contains("security"));
+            Some(update)
+        })
+        .collect()
+}
+
+/// Whether `apt-config dump` schedules unattended upgrades (`APT::Periodic::Unattended-Upgrade`
+/// set to something other than "0", with `APT::Periodic::Enable` not turned off).
+fn apt_periodic_upgrades(dump: &str) -> bool {
+    let value = |key: &str| {
+        dump.lines()
+            .filter_map(|l| l.trim().strip_prefix(key)?.trim().strip_suffix(';'))
+            .map(|v| v.trim().trim_matches('"').to_string())
+            .next_back()
+    };
+    let scheduled = value("APT::Periodic::Unattended-Upgrade ").is_some_and(|v| !v.is_empty() && v != "0");
+    scheduled && value("APT::Periodic::Enable ").as_deref() != Some("0")
+}
+
+/// `dnf check-update` prints `name.arch version repo`, wrapping long names onto
+/// the next line, so the columns are read as a stream of triples.
+fn parse_dnf_check_update(text: &str) -> Vec<PendingUpdate> {
+    let tokens: Vec<&str> = text.lines()
+        .take_while(|l| !l.starts_with("Obsoleting"))
+        .filter(|l| !l.starts_with("Last metadata") && !l.starts_with("Security:"))
+        .flat_map(str::split_whitespace)
+        .collect();
+    tokens.chunks_exact(3)
+        .map(|c| PendingUpdate::new(c[0].rsplit_once('.').map_or(c[0], |(name, _arch)| name), c[1]))
+        .collect()
+}
+
+/// Package names from `dnf updateinfo list --security`. The package column is
+/// an NEVRA such as `openssl-libs-1:3.0.7-25.el9.x86_64`: the only field with
+/// both dashes and dots (advisory ids and dates lack dots).
+fn parse_dnf_advisories(text: &str) -> HashSet<String> {
+    text.lines()
+        .filter_map(|line| {
+            let nevra = line.split_whitespace().rfind(|t| t.contains('-') && t.contains('.'))?;
+            let (nevr, _arch) = nevra.rsplit_once('.')?;
+            nevr.rsplitn(3, '-').nth(2).map(str::to_string)
+        })
+        .collect()
+}
+
+/// `apk version -l '<'` lines: `musl-1.2.4-r1   < 1.2.4-r2`. Alpine versions
+/// always end in `-r<N>`, so the name is everything before the last two dashes.
+fn parse_apk_version(text: &str) -> Vec<PendingUpdate> {
+    text.lines()
+        .filter_map(|line| {
+            let (installed, available) = line.split_once(" < ")?;
+            let name = installed.trim().rsplitn(3, '-').nth(2)?;
+            Some(PendingUpdate::new(name, available.trim()))
+        })
+        .collect()
+}
+
+/// dnf-automatic installs updates when its install timer runs, or when the
+/// plain timer runs with `apply_updates = yes`.
+fn dnf_automatic() -> Option<bool> {
+    let systemctl = SecurityAuditor::find_system_binary("systemctl")?;
+    let enabled = |unit: &str| {
+        Command::new(&systemctl).args(["is-enabled", "--quiet", unit]).status().is_ok_and(|s| s.success())
+    };
+    if enabled("dnf-automatic-install.timer") {
+        return Some(true);
+    }
+    if enabled("dnf-automatic.timer") || enabled("dnf5-automatic.timer") {
+        let conf = std::fs::read_to_string(DNF_AUTOMATIC_CONF).unwrap_or_default();
+        return Some(automatic_applies_updates(&conf));
+    }
+    Some(false)
+}
+
+fn automatic_applies_updates(conf: &str) -> bool {
+    conf.lines()
+        .filter_map(|l| l.split_once('='))
+        .filter(|(key, _)| key.trim() == "apply_updates")
+        .map(|(_, value)| matches!(value.trim().to_lowercase().as_str(), "yes" | "true" | "1" | "on"))
+        .next_back()
+        .unwrap_or(false)
+}
+
+/// An upgrade removed the modules of the running kernel, so it is outdated.
+/// Without any modules directory (containers) nothing can be told.
+fn running_kernel_removed(modules: &Path) -> bool {
+    let Ok(release) = std::fs::read_to_string("/proc/sys/kernel/osrelease") else {
+        return false;
+    };
+    let has_kernels = std::fs::read_dir(modules).is_ok_and(|mut d| d.next().is_some());
+    has_kernels && !modules.join(release.trim()).exists()
+}
+
+/// Inspection shared by the update checks and the tracker, refreshed after `CACHE_TTL`.
+pub fn source() -> CachedSource<UpdateStatus> {
+    CachedSource::new(CACHE_TTL, || Ok(inspect()))
+}
+
+/// Remembers since when each update is pending and alerts when security
+/// updates stay uninstalled for `alert_days`.
+pub struct UpdateTracker {
+    db: SqlitePool,
+    source: Arc<CachedSource<UpdateStatus>>,
+    notifier: Arc<NotificationService>,
+    /// 0 disables the alert
+    alert_days: u64,
+    alerted: Mutex<Option<Instant>>,
+}
+
+impl UpdateTracker {
+    pub fn new(db: SqlitePool, source: Arc<CachedSource<UpdateStatus>>, notifier: Arc<NotificationService>, alert_days: u64) -> Self {
+        Self { db, source, notifier, alert_days, alerted: Mutex::new(None) }
+    }
+
+    /// `SECURITY_UPDATES_ALERT_DAYS`, default 7.
+    pub fn alert_days_from_env() -> u64 {
+        std::env::var("SECURITY_UPDATES_ALERT_DAYS").ok().and_then(|s| s.parse().ok()).unwrap_or(7)
+    }
+
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS pending_updates (
+                package TEXT PRIMARY KEY,
+                version TEXT NOT NULL,
+                security INTEGER NOT NULL,
+                first_seen INTEGER NOT NULL
+            )"
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Current status, with `first_seen` filled in for every pending update.
+    pub async fn status(&self) -> Result<UpdateStatus, String> {
+        let source = self.source.clone();
+        let status = tokio::task::spawn_blocking(move || source.get())
+            .await
+            .map_err(|e| e.to_string())??;
+        let mut status = status.as_ref().clone();
+        self.record(&status, status.checked_at).await.map_err(|e| e.to_string())?;
+        let first_seen = self.first_seen().await.map_err(|e| e.to_string())?;
+        for update in status.pending.iter_mut().flatten() {
+            update.first_seen = first_seen.get(&update.name).copied();
+        }
+        Ok(status)
+    }
+
+    /// Stores newly pending updates and forgets installed ones. A package keeps
+    /// its first sighting when a newer version replaces the pending one.
+    async fn record(&self, status: &UpdateStatus, now: i64) -> Result<(), sqlx::Error> {
+        // A failed listing says nothing about what was installed
+        let Some(pending) = &status.pending else {
+            return Ok(());
+        };
+        for update in pending {
+            sqlx::query(
+                "INSERT INTO pending_updates (package, version, security, first_seen) VALUES (?, ?, ?, ?)
+                 ON CONFLICT(package) DO UPDATE SET version = excluded.version, security = excluded.security"
+            )
+            .bind(&update.name)
+            .bind(&update.version)
+            .bind(update.security)
+            .bind(now)
+            .execute(&self.db)
+            .await?;
+        }
+        let names: HashSet<&str> = pending.iter().map(|p| p.name.as_str()).collect();
+        for package in self.first_seen().await?.into_keys() {
+            if !names.contains(package.as_str()) {
+                sqlx::query("DELETE FROM pending_updates WHERE package = ?")
+                    .bind(&package)
+                    .execute(&self.db)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn first_seen(&self) -> Result<HashMap<String, i64>, sqlx::Error> {
+        let rows = sqlx::query("SELECT package, first_seen FROM pending_updates")
+            .fetch_all(&self.db)
+            .await?;
+        Ok(rows.iter().map(|r| (r.get("package"), r.get("first_seen"))).collect())
+    }
+
+    /// Security updates pending for at least `alert_days`.
+    fn overdue<'a>(&self, status: &'a UpdateStatus, now: i64) -> Vec<&'a PendingUpdate> {
+        let deadline = now - self.alert_days as i64 * 86400;
+        status.security_updates()
+            .into_iter()
+            .filter(|p| p.first_seen.is_some_and(|seen| seen <= deadline))
+            .collect()
+    }
+
+    async fn check_once(&self) {
+        let status = match self.status().await {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("Pending updates check failed: {}", e);
+                return;
+            }
+        };
+        if self.alert_days == 0 {
+            return;
+        }
+        let overdue = self.overdue(&status, chrono::Utc::now().timestamp());
+        {
+            let mut alerted = self.alerted.lock().unwrap();
+            if overdue.is_empty() {
+                *alerted = None;
+                return;
+            }
+            if alerted.is_some_and(|at| at.elapsed() < ALERT_COOLDOWN) {
+                return;
+            }
+            *alerted = Some(Instant::now());
+        }
+        let lang = Lang::from_headers(&i18n::HeaderMap::new());
+        let mut lines: Vec<String> = overdue.iter()
+            .take(MAX_LISTED)
+            .map(|p| format!("• {} {}", p.name, p.version))
+            .collect();
+        if overdue.len() > MAX_LISTED {
+            lines.push(format!("+{} more", overdue.len() - MAX_LISTED));
+        }
+        let title = i18n::t_val("alert.updates_overdue", &lang, &self.alert_days.to_string());
+        self.notifier.send_alert(&format!("{}\n\n{}", title, lines.join("\n"))).await;
+    }
+
+    /// Records pending updates every hour, so their age is known even when
+    /// nobody opens the panel.
+    pub async fn run_loop(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            self.check_once().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_apt() {
+        let out = "\
+Listing...
+libssl3/jammy-updates,jammy-security 3.0.2-0ubuntu1.15 amd64 [upgradable from: 3.0.2-0ubuntu1.14]
+tzdata/jammy-updates 2024a-0ubuntu0.22.04 all [upgradable from: 2023c-0ubuntu0.22.04.2]
+openssl/stable-security 3.0.11-1~deb12u2 amd64 [upgradable from: 3.0.11-1~deb12u1]
+";
+        let updates = parse_apt_upgradable(out);
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[0], PendingUpdate { security: true, ..PendingUpdate::new("libssl3", "3.0.2-0ubuntu1.15") });
+        assert!(!updates[1].security);
+        assert!(updates[2].security);
+
+        let dump = "APT::Periodic::Update-Package-Lists \"1\";\nAPT::Periodic::Unattended-Upgrade \"1\";\n";
+        assert!(apt_periodic_upgrades(dump));
+        assert!(!apt_periodic_upgrades(&format!("{}APT::Periodic::Enable \"0\";\n", dump)));
+        assert!(!apt_periodic_upgrades("APT::Periodic::Unattended-Upgrade \"0\";\n"));
+        assert!(!apt_periodic_upgrades("APT::Periodic::Update-Package-Lists \"1\";\n"));
+    }
+
+    #[test]
+    fn test_parse_dnf() {
+        let out = "
+Last metadata expiration check: 1:02:03 ago on Mon 01 Jan 2024.
+
+openssl-libs.x86_64                 1:3.0.7-25.el9_3                 baseos
+python3-a-really-long-package-name.noarch
+                                    2.1-3.el9                        appstream
+tzdata.noarch                       2024a-1.el9                      baseos
+Obsoleting Packages
+grub2-tools.x86_64                  1:2.06-70.el9                    baseos
+";
+        let updates = parse_dnf_check_update(out);
+        let names: Vec<&str> = updates.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, ["openssl-libs", "python3-a-really-long-package-name", "tzdata"]);
+        assert_eq!(updates[1].version, "2.1-3.el9");
+
+        let advisories = "\
+RHSA-2024:1234 Important/Sec.  openssl-libs-1:3.0.7-25.el9_3.x86_64
+FEDORA-2024-0a1b2c3d4e security Critical openssl-libs-3.1.1-4.fc39.x86_64 2024-01-02 10:00:00
+";
+        assert_eq!(parse_dnf_advisories(advisories), HashSet::from(["openssl-libs".to_string()]));
+
+        let conf = "[commands]\nupgrade_type = security\napply_updates = yes\n";
+        assert!(automatic_applies_updates(conf));
+        assert!(!automatic_applies_updates("[commands]\napply_updates = no\n"));
+        assert!(!automatic_applies_updates(""));
+    }
+
+    #[test]
+    fn test_parse_apk() {
+        let out = "\
+Installed:                                Available:
+musl-1.2.4-r1                           < 1.2.4-r2
+py3-setuptools-68.0.0-r0                < 68.1.0-r0
+";
+        let updates = parse_apk_version(out);
+        assert_eq!(updates, vec![PendingUpdate::new("musl", "1.2.4-r2"), PendingUpdate::new("py3-setuptools", "68.1.0-r0")]);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut openssl = PendingUpdate::new("openssl", "3.0.2");
+        openssl.security = true;
+        let status = UpdateStatus {
+            manager: Some(PackageManager::Apt),
+            pending: Some(vec![openssl, PendingUpdate::new("tzdata", "2024a")]),
+            security_known: true,
+            ..Default::default()
+        };
+        assert_eq!(status.summary(), "apt: 2 pending, 1 security (openssl)");
+        assert_eq!(UpdateStatus::default().summary(), "no supported package manager");
+    }
+
+    #[tokio::test]
+    async fn test_tracker_ages() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let tracker = UpdateTracker::new(pool, Arc::new(source()), Arc::new(NotificationService::new()), 7);
+        tracker.migrate().await.unwrap();
+        let day = 86400;
+
+        let mut openssl = PendingUpdate::new("openssl", "3.0.2-1");
+        openssl.security = true;
+        let mut status = UpdateStatus {
+            manager: Some(PackageManager::Apt),
+            pending: Some(vec![openssl.clone(), PendingUpdate::new("tzdata", "2024a")]),
+            ..Default::default()
+        };
+        tracker.record(&status, 0).await.unwrap();
+
+        // A newer version of the same package keeps the first sighting
+        openssl.version = "3.0.2-2".into();
+        status.pending = Some(vec![openssl]);
+        tracker.record(&status, 5 * day).await.unwrap();
+        let seen = tracker.first_seen().await.unwrap();
+        assert_eq!(seen, HashMap::from([("openssl".to_string(), 0)]));
+
+        // Listing failures do not clear the history
+        tracker.record(&UpdateStatus::default(), 6 * day).await.unwrap();
+        assert_eq!(tracker.first_seen().await.unwrap().len(), 1);
+
+        for update in status.pending.iter_mut().flatten() {
+            update.first_seen = seen.get(&update.name).copied();
+        }
+        assert!(tracker.overdue(&status, 6 * day).is_empty());
+        assert_eq!(tracker.overdue(&status, 7 * day).len(), 1);
+
+        status.pending = Some(Vec::new());
+        tracker.record(&status, 8 * day).await.unwrap();
+        assert!(tracker.first_seen().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::auth::constant_time_eq;
+use crate::deployment::{DeploymentGuard, DeploymentService, Step};
+use crate::history::{DeploymentRecord, HistoryManager};
+use crate::util::{glob_match, load_json_config};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How far `webhook-timestamp` may be from now, so a captured delivery cannot be replayed later.
+const WEBHOOK_TOLERANCE_SECS: i64 = 300;
+
+/// Deploy pipeline for a user application, started by a signed push webhook.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Pipeline {
+    pub name: String,
+    /// Webhook secret; `secret_env` names an environment variable holding it instead
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub secret_env: Option<String>,
+    pub working_dir: PathBuf,
+    /// Each command is an argv list, run without a shell
+    pub commands: Vec<Vec<String>>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Limit for the whole pipeline
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Branch name patterns (`*` wildcard); empty with `tags` also empty means every push
+    #[serde(default)]
+    pub branches: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_timeout_secs() -> u64 { 900 }
+
+impl Pipeline {
+    fn secret(&self) -> Option<String> {
+        match &self.secret_env {
+            Some(var) => std::env::var(var).ok(),
+            None => self.secret.clone(),
+        }
+        .filter(|s| !s.is_empty())
+    }
+
+    /// Applies the branch/tag filters to a git ref such as `refs/heads/main`.
+    pub fn accepts_ref(&self, git_ref: Option<&str>) -> bool {
+        if self.branches.is_empty() && self.tags.is_empty() {
+            return true;
+        }
+        let Some(git_ref) = git_ref else {
+            return false;
+        };
+        if let Some(branch) = git_ref.strip_prefix("refs/heads/") {
+            self.branches.iter().any(|p| glob_match(p.as_bytes(), branch.as_bytes()))
+        } else if let Some(tag) = git_ref.strip_prefix("refs/tags/") {
+            self.tags.iter().any(|p| glob_match(p.as_bytes(), tag.as_bytes()))
+        } else {
+            false
+        }
+    }
+
+    fn steps(&self) -> Vec<Step> {
+        self.commands.iter().map(|argv| Step {
+            name: argv.join(" "),
+            program: argv[0].clone(),
+            args: argv[1..].to_vec(),
+            env: self.env.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            working_dir: Some(self.working_dir.clone()),
+            timeout: Duration::from_secs(self.timeout_secs),
+        }).collect()
+    }
+}
+
+/// Pipelines from a JSON array, checked for names, secrets and commands.
+pub fn load_pipelines(path: &str) -> Result<Vec<Pipeline>, String> {
+    let pipelines: Vec<Pipeline> = load_json_config(path, "pipelines", Vec::new())?;
+
+    for p in &pipelines {
+        if p.name.is_empty() || !p.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Pipeline name '{}' may only contain letters, digits, '-' and '_'", p.name));
+        }
+        if p.secret.is_none() && p.secret_env.is_none() {
+            return Err(format!("Pipeline '{}' needs `secret` or `secret_env`", p.name));
+        }
+        if p.commands.is_empty() || p.commands.iter().any(|c| c.is_empty()) {
+            return Err(format!("Pipeline '{}' needs at least one non-empty command", p.name));
+        }
+        if pipelines.iter().filter(|o| o.name == p.name).count() > 1 {
+            return Err(format!("Pipeline '{}' is defined twice", p.name));
+        }
+    }
+    Ok(pipelines)
+}
+
+/// Checks the webhook against `secret` in the format of whichever forge sent it:
+/// GitHub `X-Hub-Signature-256`, Gitea/Gogs `X-Gitea-Signature`, GitLab signing token
+/// (`webhook-signature`) or GitLab secret token (`X-Gitlab-Token`).
+pub fn verify_signature(secret: &str, headers: &HeaderMap, body: &[u8]) -> Result<(), String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(sig) = header("x-hub-signature-256") {
+        let hex_sig = sig.strip_prefix("sha256=").ok_or("Malformed X-Hub-Signature-256")?;
+        return verify_hmac(secret, body, &hex::decode(hex_sig).map_err(|_| "Malformed X-Hub-Signature-256")?);
+    }
+    if let Some(sig) = header("x-gitea-signature").or_else(|| header("x-gogs-signature")) {
+        return verify_hmac(secret, body, &hex::decode(sig).map_err(|_| "Malformed X-Gitea-Signature")?);
+    }
+    if let Some(sigs) = header("webhook-signature") {
+        use base64::Engine;
+        let id = header("webhook-id").ok_or("Missing webhook-id")?;
+        let timestamp = header("webhook-timestamp").ok_or("Missing webhook-timestamp")?;
+        let sent: i64 = timestamp.parse().map_err(|_| "Malformed webhook-timestamp")?;
+        if (chrono::Utc::now().timestamp() - sent).abs() > WEBHOOK_TOLERANCE_SECS {
+            return Err("webhook-timestamp is more than 5 minutes off".to_string());
+        }
+        let mut signed = format!("{}.{}.", id, timestamp).into_bytes();
+        signed.extend_from_slice(body);
+
+        // Space-separated list of `v1,<base64>`; any valid one is enough
+        for sig in sigs.split_whitespace() {
+            if let Some(b64) = sig.strip_prefix("v1,")
+                && let Ok(raw) = base64::engine::general_purpose::STANDARD.decode(b64)
+                && verify_hmac(secret, &signed, &raw).is_ok()
+            {
+                return Ok(());
+            }
+        }
+        return Err("Invalid signature".to_string());
+    }
+    if let Some(token) = header("x-gitlab-token") {
+        return if constant_time_eq(token, secret) { Ok(()) } else { Err("Invalid token".to_string()) };
+    }
+    Err("Missing signature header".to_string())
+}
+
+/// Event name when the forge says this is something other than a push (ping, pull request, ...).
+/// Callers that send no event header are treated as pushes.
+fn non_push_event(headers: &HeaderMap) -> Option<String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let (event, is_push) = if let Some(e) = header("x-github-event").or_else(|| header("x-gitea-event")) {
+        (e, e == "push")
+    } else if let Some(e) = header("x-gitlab-event") {
+        (e, e == "Push Hook" || e == "Tag Push Hook")
+    } else {
+        return None;
+    };
+    (!is_push).then(|| event.to_string())
+}
+
+fn verify_hmac(secret: &str, message: &[u8], signature: &[u8]) -> Result<(), String> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).map_err(|e| e.to_string())?;
+    mac.update(message);
+    mac.verify_slice(signature).map_err(|_| "Invalid signature".to_string())
+}
+
+/// Why a webhook did not start a run.
+pub enum WebhookError {
+    NotFound,
+    Unauthorized(String),
+    Ignored(String),
+    Busy(String),
+}
+
+pub struct PipelineRunner {
+    pipelines: HashMap<String, Pipeline>,
+    deployment: Arc<DeploymentService>,
+    history: Arc<HistoryManager>,
+}
+
+impl PipelineRunner {
+    pub fn new(pipelines: Vec<Pipeline>, deployment: Arc<DeploymentService>, history: Arc<HistoryManager>) -> Self {
+        Self {
+            pipelines: pipelines.into_iter().map(|p| (p.name.clone(), p)).collect(),
+            deployment,
+            history,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    /// Verifies a webhook for pipeline `name` and starts it in the background.
+    /// Returns the deployment id.
+    pub async fn handle_webhook(&self, name: &str, headers: &HeaderMap, body: &[u8]) -> Result<String, WebhookError> {
+        let pipeline = self.pipelines.get(name).ok_or(WebhookError::NotFound)?;
+        let secret = pipeline.secret().ok_or_else(|| WebhookError::Unauthorized("Pipeline secret is not set".to_string()))?;
+        verify_signature(&secret, headers, body).map_err(WebhookError::Unauthorized)?;
+
+        if let Some(event) = non_push_event(headers) {
+            return Err(WebhookError::Ignored(format!("Event '{}' is not a push", event)));
+        }
+
+        let payload: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+        let git_ref = payload.get("ref").and_then(|r| r.as_str());
+        if !pipeline.accepts_ref(git_ref) {
+            return Err(WebhookError::Ignored(format!("Ref {} does not match the filters", git_ref.unwrap_or("(none)"))));
+        }
+
+        let deployment_id = uuid::Uuid::new_v4().to_string();
+        let guard = self.deployment.try_start(&deployment_id, true).map_err(WebhookError::Busy)?;
+
+        // GitHub/Gitea send `after`, GitLab `checkout_sha`
+        let commit = payload.get("after").or_else(|| payload.get("checkout_sha")).and_then(|c| c.as_str());
+        let details = match (git_ref, commit) {
+            (Some(r), Some(c)) => format!("Push to {} ({})", r, c.chars().take(12).collect::<String>()),
+            (Some(r), None) => format!("Push to {}", r),
+            _ => "Webhook".to_string(),
+        };
+        self.history.add_record(DeploymentRecord {
+            id: deployment_id.clone(),
+            timestamp: chrono::Utc::now(),
+            action: "pipeline".to_string(),
+            details,
+            status: "in_progress".to_string(),
+            image_id: None,
+            previous_image_id: None,
+            container_name: Some(pipeline.name.clone()),
+            rollback_of: None,
+            exit_code: None,
+            duration_ms: None,
+        }).await;
+
+        let pipeline = pipeline.clone();
+        let deployment = self.deployment.clone();
+        let history = self.history.clone();
+        tokio::spawn(async move {
+            run(pipeline, guard, deployment, history).await;
+        });
+        Ok(deployment_id)
+    }
+}
+
+async fn run(pipeline: Pipeline, guard: DeploymentGuard, deployment: Arc<DeploymentService>, history: Arc<HistoryManager>) {
+    let started = Instant::now();
+    deployment.send(format!("🚀 Running pipeline {} in {}", pipeline.name, pipeline.working_dir.display()));
+
+    let deadline = started + Duration::from_secs(pipeline.timeout_secs);
+    let outcome = deployment.run_steps(&pipeline.steps(), &guard, Some(deadline)).await;
+    if outcome.status() == "success" {
+        deployment.send(format!("✅ Pipeline {} finished", pipeline.name));
+    }
+    history.finish(&guard.id, outcome.status(), outcome.exit_code(), started.elapsed().as_millis() as i64).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline(branches: &[&str], tags: &[&str]) -> Pipeline {
+        Pipeline {
+            name: "blog".to_string(),
+            secret: Some("s3cret".to_string()),
+            secret_env: None,
+            working_dir: PathBuf::from("/srv/blog"),
+            commands: vec![vec!["git".to_string(), "pull".to_string()]],
+            env: HashMap::new(),
+            timeout_secs: 60,
+            branches: branches.iter().map(|s| s.to_string()).collect(),
+            tags: tags.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn sign(secret: &str, message: &[u8]) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(message);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        for (k, v) in pairs {
+            h.insert(*k, v.parse().unwrap());
+        }
+        h
+    }
+
+    #[test]
+    fn test_github_and_gitea_signatures() {
+        let body = br#"{"ref":"refs/heads/main"}"#;
+        let sig = hex::encode(sign("s3cret", body));
+
+        let github = headers(&[("x-hub-signature-256", format!("sha256={}", sig))]);
+        assert!(verify_signature("s3cret", &github, body).is_ok());
+        assert!(verify_signature("other", &github, body).is_err());
+        assert!(verify_signature("s3cret", &github, b"tampered").is_err());
+
+        let gitea = headers(&[("x-gitea-signature", sig)]);
+        assert!(verify_signature("s3cret", &gitea, body).is_ok());
+
+        assert!(verify_signature("s3cret", &HeaderMap::new(), body).is_err());
+    }
+
+    #[test]
+    fn test_gitlab_token_and_signing_token() {
+        use base64::Engine;
+        let body = br#"{"ref":"refs/tags/v1.0"}"#;
+
+        assert!(verify_signature("s3cret", &headers(&[("x-gitlab-token", "s3cret".to_string())]), body).is_ok());
+        assert!(verify_signature("s3cret", &headers(&[("x-gitlab-token", "nope".to_string())]), body).is_err());
+
+        let signing = |timestamp: i64| {
+            let signed = [format!("msg_1.{}.", timestamp).as_bytes(), body].concat();
+            let sig = base64::engine::general_purpose::STANDARD.encode(sign("s3cret", &signed));
+            headers(&[
+                ("webhook-id", "msg_1".to_string()),
+                ("webhook-timestamp", timestamp.to_string()),
+                ("webhook-signature", format!("v1,AAAA v1,{}", sig)),
+            ])
+        };
+        let now = chrono::Utc::now().timestamp();
+        assert!(verify_signature("s3cret", &signing(now), body).is_ok());
+        assert!(verify_signature("other", &signing(now), body).is_err());
+        // A validly signed but old delivery is a replay
+        assert!(verify_signature("s3cret", &signing(now - 600), body).is_err());
+        assert!(verify_signature("s3cret", &signing(now + 600), body).is_err());
+    }
+
+    #[test]
+    fn test_only_push_events_run() {
+        assert_eq!(non_push_event(&HeaderMap::new()), None);
+        assert_eq!(non_push_event(&headers(&[("x-github-event", "push".to_string())])), None);
+        assert_eq!(non_push_event(&headers(&[("x-github-event", "ping".to_string())])), Some("ping".to_string()));
+        assert_eq!(non_push_event(&headers(&[("x-gitlab-event", "Tag Push Hook".to_string())])), None);
+        assert!(non_push_event(&headers(&[("x-gitlab-event", "Merge Request Hook".to_string())])).is_some());
+    }
+
+    #[test]
+    fn test_ref_filters() {
+        assert!(pipeline(&[], &[]).accepts_ref(None));
+
+        let p = pipeline(&["main", "release/*"], &["v*"]);
+        assert!(p.accepts_ref(Some("refs/heads/main")));
+        assert!(p.accepts_ref(Some("refs/heads/release/1.2")));
+        assert!(!p.accepts_ref(Some("refs/heads/feature")));
+        assert!(p.accepts_ref(Some("refs/tags/v1.0")));
+        assert!(!p.accepts_ref(Some("refs/tags/nightly")));
+        assert!(!p.accepts_ref(None));
+
+        // Only branches configured: tag pushes are ignored
+        assert!(!pipeline(&["main"], &[]).accepts_ref(Some("refs/tags/v1.0")));
+    }
+
+    #[test]
+    fn test_load_pipelines_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pipelines.json");
+        let path_str = path.to_str().unwrap();
+
+        std::fs::write(&path, r#"[{"name": "blog", "working_dir": "/srv", "commands": [["make"]]}]"#).unwrap();
+        assert!(load_pipelines(path_str).unwrap_err().contains("secret"));
+
+        std::fs::write(&path, r#"[{"name": "a b", "secret": "x", "working_dir": "/srv", "commands": [["make"]]}]"#).unwrap();
+        assert!(load_pipelines(path_str).is_err());
+
+        std::fs::write(&path, r#"[{"name": "blog", "secret": "x", "working_dir": "/srv", "commands": [[]]}]"#).unwrap();
+        assert!(load_pipelines(path_str).is_err());
+
+        std::fs::write(&path, r#"[{"name": "blog", "secret": "x", "working_dir": "/srv", "commands": [["make", "deploy"]]}]"#).unwrap();
+        let pipelines = load_pipelines(path_str).unwrap();
+        assert_eq!(pipelines[0].timeout_secs, 900);
+        let steps = pipelines[0].steps();
+        assert_eq!((steps[0].program.as_str(), steps[0].args.clone()), ("make", vec!["deploy".to_string()]));
+
+        assert!(load_pipelines("/nonexistent/pipelines.json").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::sync::{Arc, RwLock};
+
+use crate::i18n::{self, Lang};
+use crate::ports::{self, Exposure, OpenPort, Protocol};
+use crate::security::{CheckSpec, CheckStatus, SecurityCheck, SecurityCheckProvider, Severity, CHECK_LISTENING_PORTS};
+
+/// The kernel truncates process names (`/proc/<pid>/comm`) to 15 bytes.
+const MAX_PROCESS_NAME: usize = 15;
+
+const LISTENING_PORTS: CheckSpec = CheckSpec {
+    id: CHECK_LISTENING_PORTS,
+    key: "ports",
+    severity: Severity::Low,
+    categories: &["network"],
+};
+
+/// A port that may listen on a public address.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AllowedPort {
+    pub id: i64,
+    pub port: u16,
+    /// `None` allows both TCP and UDP.
+    pub protocol: Option<Protocol>,
+    /// Only allowed while this process owns the socket.
+    pub process: Option<String>,
+    pub note: Option<String>,
+    /// Unix timestamp (seconds)
+    pub added_at: i64,
+}
+
+impl AllowedPort {
+    pub fn matches(&self, open: &OpenPort) -> bool {
+        self.port == open.port
+            && self.protocol.is_none_or(|p| p == open.protocol)
+            && self.process.as_ref().is_none_or(|p| open.process.as_ref() == Some(p))
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NewAllowedPort {
+    pub port: u16,
+    #[serde(default)]
+    pub protocol: Option<Protocol>,
+    #[serde(default)]
+    pub process: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl NewAllowedPort {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.port == 0 {
+            return Err("Port must be between 1 and 65535".to_string());
+        }
+        if let Some(process) = &self.process
+            && (process.is_empty() || process.len() > MAX_PROCESS_NAME)
+        {
+            return Err(format!("Process name must be 1-{} characters, as in /proc/<pid>/comm", MAX_PROCESS_NAME));
+        }
+        Ok(())
+    }
+}
+
+/// Ports allowed to listen on public addresses. Stored in SQLite and cached,
+/// since the audit reads it from a blocking thread.
+pub struct PortAllowlist {
+    db: SqlitePool,
+    entries: RwLock<Vec<AllowedPort>>,
+}
+
+impl PortAllowlist {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db, entries: RwLock::new(Vec::new()) }
+    }
+
+    /// Creates the table and loads the entries. A new table starts with the
+    /// ports the agent always expected: SSH, HTTP(S), its own and the deploy nginx.
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'port_allowlist'")
+            .fetch_optional(&self.db)
+            .await?
+            .is_some();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS port_allowlist (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                port INTEGER NOT NULL,
+                protocol TEXT,
+                process TEXT,
+                note TEXT,
+                added_at INTEGER NOT NULL
+            )"
+        )
+        .execute(&self.db)
+        .await?;
+        if !exists {
+            for (port, note) in default_ports() {
+                self.insert(&NewAllowedPort {
+                    port,
+                    protocol: Some(Protocol::Tcp),
+                    process: None,
+                    note: Some(note.to_string()),
+                })
+                .await?;
+            }
+        }
+        self.reload().await
+    }
+
+    async fn reload(&self) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query("SELECT id, port, protocol, process, note, added_at FROM port_allowlist ORDER BY port, id")
+            .fetch_all(&self.db)
+            .await?;
+        let entries = rows.iter()
+            .map(|row| AllowedPort {
+                id: row.get("id"),
+                port: row.get::<i64, _>("port") as u16,
+                protocol: row.get::<Option<String>, _>("protocol").and_then(|p| Protocol::parse(&p)),
+                process: row.get("process"),
+                note: row.get("note"),
+                added_at: row.get("added_at"),
+            })
+            .collect();
+        *self.entries.write().unwrap() = entries;
+        Ok(())
+    }
+
+    async fn insert(&self, entry: &NewAllowedPort) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query("INSERT INTO port_allowlist (port, protocol, process, note, added_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(entry.port as i64)
+            .bind(entry.protocol.map(|p| p.as_str()))
+            .bind(&entry.process)
+            .bind(&entry.note)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&self.db)
+            .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub fn list(&self) -> Vec<AllowedPort> {
+        self.entries.read().unwrap().clone()
+    }
+
+    /// Adds an entry; an identical one is returned instead of duplicated.
+    pub async fn add(&self, entry: NewAllowedPort) -> Result<AllowedPort, String> {
+        entry.validate()?;
+        let existing = self.list().into_iter().find(|e| {
+            e.port == entry.port && e.protocol == entry.protocol && e.process == entry.process
+        });
+        if let Some(existing) = existing {
+            return Ok(existing);
+        }
+        let id = self.insert(&entry).await.map_err(|e| e.to_string())?;
+        self.reload().await.map_err(|e| e.to_string())?;
+        self.list().into_iter().find(|e| e.id == id).ok_or_else(|| "Allowlist entry vanished".to_string())
+    }
+
+    /// Returns `false` when there was no such entry.
+    pub async fn remove(&self, id: i64) -> Result<bool, String> {
+        let result = sqlx::query("DELETE FROM port_allowlist WHERE id = ?")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        self.reload().await.map_err(|e| e.to_string())?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub fn is_allowed(&self, port: &OpenPort) -> bool {
+        self.entries.read().unwrap().iter().any(|e| e.matches(port))
+    }
+
+    /// Listening ports with `allowed` set. Reads `/proc`, so it blocks.
+    pub fn scan(&self) -> Vec<OpenPort> {
+        let mut open = ports::scan();
+        for port in &mut open {
+            port.allowed = self.is_allowed(port);
+        }
+        open
+    }
+}
+
+fn default_ports() -> Vec<(u16, &'static str)> {
+    let env_port = |name: &str, default: u16| {
+        std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+    };
+    let mut defaults = vec![
+        (22, "SSH"),
+        (80, "HTTP"),
+        (443, "HTTPS"),
+        (env_port("APP_PORT", 3000), "Mini-Ops"),
+        (env_port("DEPLOY_NGINX_PORT", 8090), "Deploy nginx"),
+    ];
+    defaults.dedup_by_key(|(port, _)| *port);
+    defaults
+}
+
+/// Warns about public ports no allowlist entry covers. Loopback ports are
+/// never reported.
+pub struct ListeningPortsCheck {
+    allowlist: Arc<PortAllowlist>,
+}
+
+impl ListeningPortsCheck {
+    pub fn new(allowlist: Arc<PortAllowlist>) -> Self {
+        Self { allowlist }
+    }
+}
+
+impl SecurityCheckProvider for ListeningPortsCheck {
+    fn id(&self) -> &str {
+        LISTENING_PORTS.id
+    }
+
+    fn run(&self, lang: &Lang) -> SecurityCheck {
+        check_ports(&self.allowlist.scan(), lang)
+    }
+}
+
+fn check_ports(ports: &[OpenPort], lang: &Lang) -> SecurityCheck {
+    // The same service usually listens on IPv4 and IPv6
+    let describe = |allowed: Option<bool>| {
+        let mut described: Vec<String> = ports.iter()
+            .filter(|p| p.exposure == Exposure::Public && allowed.is_none_or(|a| p.allowed == a))
+            .map(|p| p.describe())
+            .collect();
+        described.dedup();
+        described.join(", ")
+    };
+    let unexpected = describe(Some(false));
+    if unexpected.is_empty() {
+        let check = LISTENING_PORTS.pass("audit.ports.pass", lang);
+        let public = describe(None);
+        return if public.is_empty() { check } else { check.with_evidence(public) };
+    }
+    let message = i18n::t_val("audit.ports.warn", lang, &unexpected);
+    LISTENING_PORTS.result(CheckStatus::Warn, message, lang).with_evidence(unexpected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(port: u16, address: &str, process: Option<&str>) -> OpenPort {
+        let address: std::net::IpAddr = address.parse().unwrap();
+        OpenPort {
+            protocol: Protocol::Tcp,
+            address,
+            port,
+            exposure: if address.is_loopback() { Exposure::Loopback } else { Exposure::Public },
+            pid: None,
+            process: process.map(str::to_string),
+            container_id: None,
+            container: None,
+            allowed: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowlist() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let allowlist = PortAllowlist::new(pool);
+        allowlist.migrate().await.unwrap();
+        let seeded = allowlist.list();
+        assert!(seeded.iter().any(|e| e.port == 22 && e.protocol == Some(Protocol::Tcp)));
+        assert!(allowlist.is_allowed(&open(443, "0.0.0.0", Some("nginx"))));
+
+        let redis = allowlist.add(NewAllowedPort {
+            port: 6379,
+            protocol: None,
+            process: Some("redis-server".into()),
+            note: Some("cache".into()),
+        }).await.unwrap();
+        assert!(allowlist.is_allowed(&open(6379, "::", Some("redis-server"))));
+        assert!(!allowlist.is_allowed(&open(6379, "::", Some("nc"))));
+        // Adding the same entry again returns the existing one
+        let again = allowlist.add(NewAllowedPort { port: 6379, protocol: None, process: Some("redis-server".into()), note: None });
+        assert_eq!(again.await.unwrap().id, redis.id);
+
+        let invalid = NewAllowedPort { port: 0, protocol: None, process: None, note: None };
+        assert!(allowlist.add(invalid).await.is_err());
+        let long_name = NewAllowedPort { port: 9000, protocol: None, process: Some("a-very-long-process-name".into()), note: None };
+        assert!(allowlist.add(long_name).await.is_err());
+
+        assert!(allowlist.remove(redis.id).await.unwrap());
+        assert!(!allowlist.remove(redis.id).await.unwrap());
+        assert!(!allowlist.is_allowed(&open(6379, "::", Some("redis-server"))));
+
+        // Entries removed by the user are not seeded again
+        let removed = seeded.iter().find(|e| e.port == 80).unwrap();
+        allowlist.remove(removed.id).await.unwrap();
+        allowlist.migrate().await.unwrap();
+        assert!(!allowlist.list().iter().any(|e| e.port == 80));
+    }
+
+    #[test]
+    fn test_check_ports() {
+        let mut ssh_v4 = open(22, "0.0.0.0", Some("sshd"));
+        ssh_v4.allowed = true;
+        let mut ssh_v6 = open(22, "::", Some("sshd"));
+        ssh_v6.allowed = true;
+        let ports = vec![ssh_v4, ssh_v6, open(5432, "127.0.0.1", Some("postgres"))];
+        let check = check_ports(&ports, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert_eq!(check.evidence.as_deref(), Some("22/tcp (sshd)"));
+
+        let mut ports = ports;
+        ports.push(open(3306, "0.0.0.0", Some("mysqld")));
+        ports.push(open(3306, "::", Some("mysqld")));
+        let check = check_ports(&ports, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.message.contains("3306/tcp (mysqld)"));
+        assert_eq!(check.evidence.as_deref(), Some("3306/tcp (mysqld)"));
+    }
+}
@@ -0,0 +1,363 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+/// TCP state `LISTEN` in `/proc/net/tcp*`.
+const TCP_LISTEN: &str = "0A";
+/// UDP sockets that are bound but not connected show as `CLOSE`.
+const UDP_UNCONNECTED: &str = "07";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl Protocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tcp" => Some(Protocol::Tcp),
+            "udp" => Some(Protocol::Udp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Exposure {
+    /// Bound to a loopback address; only local processes can connect.
+    Loopback,
+    /// Bound to a wildcard or external address.
+    Public,
+}
+
+/// A socket accepting connections (TCP) or datagrams (UDP).
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ListeningSocket {
+    pub protocol: Protocol,
+    pub address: IpAddr,
+    pub port: u16,
+    pub inode: u64,
+}
+
+impl ListeningSocket {
+    /// Bound to a loopback address, so only local processes can reach it.
+    pub fn is_loopback(&self) -> bool {
+        match self.address {
+            IpAddr::V4(v4) => v4.is_loopback(),
+            IpAddr::V6(v6) => v6.is_loopback() || v6.to_ipv4_mapped().is_some_and(|v4| v4.is_loopback()),
+        }
+    }
+}
+
+/// A listening socket and the process that owns it.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OpenPort {
+    pub protocol: Protocol,
+    pub address: IpAddr,
+    pub port: u16,
+    pub exposure: Exposure,
+    /// Unknown when the agent may not read the owner's `/proc/<pid>/fd`.
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+    /// Full id of the container the process runs in.
+    pub container_id: Option<String>,
+    /// Container name; filled in by callers that can ask Docker.
+    pub container: Option<String>,
+    /// Matches an allowlist entry; set by the allowlist.
+    pub allowed: bool,
+}
+
+impl OpenPort {
+    /// `3306/tcp (mysqld, container 1a2b3c4d5e6f)` for messages and evidence.
+    pub fn describe(&self) -> String {
+        let mut owner = Vec::new();
+        if let Some(process) = &self.process {
+            owner.push(process.clone());
+        }
+        match (&self.container, &self.container_id) {
+            (Some(name), _) => owner.push(format!("container {}", name)),
+            (None, Some(id)) => owner.push(format!("container {}", &id[..id.len().min(12)])),
+            _ => {}
+        }
+        if owner.is_empty() {
+            format!("{}/{}", self.port, self.protocol.as_str())
+        } else {
+            format!("{}/{} ({})", self.port, self.protocol.as_str(), owner.join(", "))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Owner {
+    pid: u32,
+    process: String,
+    container_id: Option<String>,
+}
+
+/// Listening sockets with their owners, sorted by protocol, port and address.
+pub fn scan() -> Vec<OpenPort> {
+    let owners = socket_owners(Path::new("/proc"));
+    let mut ports: Vec<OpenPort> = listening_sockets()
+        .into_iter()
+        .map(|socket| {
+            let owner = owners.get(&socket.inode);
+            OpenPort {
+                protocol: socket.protocol,
+                address: socket.address,
+                port: socket.port,
+                exposure: if socket.is_loopback() { Exposure::Loopback } else { Exposure::Public },
+                pid: owner.map(|o| o.pid),
+                process: owner.map(|o| o.process.clone()),
+                container_id: owner.and_then(|o| o.container_id.clone()),
+                container: None,
+                allowed: false,
+            }
+        })
+        .collect();
+    ports.sort_by_key(|p| (p.protocol, p.port, p.address));
+    ports.dedup_by(|a, b| a.protocol == b.protocol && a.port == b.port && a.address == b.address);
+    ports
+}
+
+/// Socket inode to owning process, from the `socket:[inode]` links in
+/// `<proc>/<pid>/fd`. A socket shared by a process tree (e.g. nginx workers)
+/// goes to the lowest pid, usually the parent.
+fn socket_owners(proc_root: &Path) -> HashMap<u64, Owner> {
+    let mut inodes: HashMap<u64, u32> = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(proc_root) else {
+        return HashMap::new();
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        // Processes of other users are unreadable without root
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            if let Some(inode) = target.to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok())
+            {
+                let owner = inodes.entry(inode).or_insert(pid);
+                *owner = (*owner).min(pid);
+            }
+        }
+    }
+
+    let mut processes: HashMap<u32, Owner> = HashMap::new();
+    inodes.into_iter()
+        .map(|(inode, pid)| {
+            let owner = processes.entry(pid).or_insert_with(|| {
+                let dir = proc_root.join(pid.to_string());
+                Owner {
+                    pid,
+                    process: std::fs::read_to_string(dir.join("comm")).unwrap_or_default().trim().to_string(),
+                    container_id: std::fs::read_to_string(dir.join("cgroup")).ok().and_then(|c| container_id(&c)),
+                }
+            });
+            (inode, owner.clone())
+        })
+        .collect()
+}
+
+/// Container id from `/proc/<pid>/cgroup`: `/docker/<id>` (cgroup v1),
+/// `docker-<id>.scope` (systemd driver), `libpod-<id>.scope` (Podman) or
+/// `cri-containerd-<id>.scope`.
+fn container_id(cgroup: &str) -> Option<String> {
+    cgroup.lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .flat_map(|path| path.split('/'))
+        .map(|segment| {
+            let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+            segment.rsplit('-').next().unwrap_or(segment)
+        })
+        .find(|id| id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_string)
+}
+
+/// Listening sockets from `/proc/net/{tcp,tcp6,udp,udp6}`; missing files are skipped.
+pub fn listening_sockets() -> Vec<ListeningSocket> {
+    let mut sockets = Vec::new();
+    for (file, protocol) in [
+        ("/proc/net/tcp", Protocol::Tcp),
+        ("/proc/net/tcp6", Protocol::Tcp),
+        ("/proc/net/udp", Protocol::Udp),
+        ("/proc/net/udp6", Protocol::Udp),
+    ] {
+        if let Ok(content) = std::fs::read_to_string(file) {
+            sockets.extend(parse_proc_net(&content, protocol));
+        }
+    }
+    sockets
+}
+
+/// Parses one `/proc/net` socket table, keeping listening sockets only.
+pub fn parse_proc_net(content: &str, protocol: Protocol) -> Vec<ListeningSocket> {
+    let listen_state = match protocol {
+        Protocol::Tcp => TCP_LISTEN,
+        Protocol::Udp => UDP_UNCONNECTED,
+    };
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != listen_state {
+                return None;
+            }
+            let (address, port) = parse_endpoint(fields[1])?;
+            // UDP sockets with a peer are client sockets
+            if protocol == Protocol::Udp && parse_endpoint(fields[2])?.1 != 0 {
+                return None;
+            }
+            Some(ListeningSocket { protocol, address, port, inode: fields[9].parse().ok()? })
+        })
+        .collect()
+}
+
+/// `0100007F:0016` or a 32-digit IPv6 address; the kernel prints each 32-bit
+/// word in host byte order.
+fn parse_endpoint(field: &str) -> Option<(IpAddr, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let words = (0..address.len() / 8)
+        .map(|i| u32::from_str_radix(&address[i * 8..i * 8 + 8], 16))
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let address = match words.as_slice() {
+        [word] => IpAddr::V4(Ipv4Addr::from(word.to_ne_bytes())),
+        [a, b, c, d] => {
+            let mut bytes = [0u8; 16];
+            for (i, word) in [a, b, c, d].into_iter().enumerate() {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word.to_ne_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(bytes))
+        }
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: &str = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 21512 1 0000000000000000 100 0 0 10 0
+   1: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 18831 1 0000000000000000 100 0 0 10 0
+   2: 0F02000A:0016 0202000A:C350 01 00000000:00000000 02:000A4C3A 00000000     0        0 40170 4 0000000000000000 20 4 31 10 20
+";
+
+    const TCP6: &str = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:0050 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 23001 1 0000000000000000 100 0 0 10 0
+   1: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 23002 1 0000000000000000 100 0 0 10 0
+   2: 0000000000000000FFFF00000100007F:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 23003 1 0000000000000000 100 0 0 10 0
+";
+
+    const UDP: &str = "\
+   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  0: 3500007F:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 17052 2 0000000000000000 0
+  1: 0F02000A:A1B2 08080808:0035 01 00000000:00000000 00:00000000 00000000  1000        0 40200 2 0000000000000000 0
+";
+
+    #[test]
+    fn test_parse_tcp() {
+        let sockets = parse_proc_net(TCP, Protocol::Tcp);
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].address, "127.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[0].port, 3306);
+        assert!(sockets[0].is_loopback());
+        assert_eq!(sockets[1].address, "0.0.0.0".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[1].port, 22);
+        assert_eq!(sockets[1].inode, 18831);
+        assert!(!sockets[1].is_loopback());
+    }
+
+    #[test]
+    fn test_parse_tcp6() {
+        let sockets = parse_proc_net(TCP6, Protocol::Tcp);
+        assert_eq!(sockets.len(), 3);
+        assert_eq!(sockets[0].address, "::".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[0].port, 80);
+        assert_eq!(sockets[1].address, "::1".parse::<IpAddr>().unwrap());
+        assert!(sockets[1].is_loopback());
+        assert_eq!(sockets[2].address, "::ffff:127.0.0.1".parse::<IpAddr>().unwrap());
+        assert!(sockets[2].is_loopback());
+    }
+
+    #[test]
+    fn test_container_id() {
+        let id = "4f1e2c3d5b6a79808f1e2c3d5b6a79808f1e2c3d5b6a79808f1e2c3d5b6a7980";
+        assert_eq!(container_id(&format!("0::/system.slice/docker-{}.scope\n", id)).as_deref(), Some(id));
+        assert_eq!(container_id(&format!("12:memory:/docker/{}\n1:name=systemd:/docker/{}\n", id, id)).as_deref(), Some(id));
+        assert_eq!(container_id(&format!("0::/machine.slice/libpod-{}.scope/container\n", id)).as_deref(), Some(id));
+        assert_eq!(container_id("0::/user.slice/user-1000.slice/session-3.scope\n"), None);
+    }
+
+    #[test]
+    fn test_socket_owners() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for (pid, inode, comm) in [(812, 18831, "sshd"), (1201, 23001, "nginx"), (1202, 23001, "nginx")] {
+            let dir = root.join(pid.to_string());
+            std::fs::create_dir_all(dir.join("fd")).unwrap();
+            std::os::unix::fs::symlink(format!("socket:[{}]", inode), dir.join("fd").join("3")).unwrap();
+            std::os::unix::fs::symlink("/dev/null", dir.join("fd").join("0")).unwrap();
+            std::fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+            std::fs::write(dir.join("cgroup"), "0::/system.slice/ssh.service\n").unwrap();
+        }
+        std::fs::create_dir_all(root.join("self")).unwrap();
+
+        let owners = socket_owners(root);
+        assert_eq!(owners.len(), 2);
+        assert_eq!(owners[&18831], Owner { pid: 812, process: "sshd".into(), container_id: None });
+        assert_eq!(owners[&23001].pid, 1201);
+    }
+
+    #[test]
+    fn test_describe() {
+        let mut port = OpenPort {
+            protocol: Protocol::Tcp,
+            address: "0.0.0.0".parse().unwrap(),
+            port: 3306,
+            exposure: Exposure::Public,
+            pid: None,
+            process: None,
+            container_id: None,
+            container: None,
+            allowed: false,
+        };
+        assert_eq!(port.describe(), "3306/tcp");
+        port.process = Some("mysqld".into());
+        port.container_id = Some("1a2b3c4d5e6f7a8b9c0d".into());
+        assert_eq!(port.describe(), "3306/tcp (mysqld, container 1a2b3c4d5e6f)");
+        port.container = Some("db".into());
+        assert_eq!(port.describe(), "3306/tcp (mysqld, container db)");
+    }
+
+    #[test]
+    fn test_parse_udp() {
+        let sockets = parse_proc_net(UDP, Protocol::Udp);
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].address, "127.0.0.53".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[0].port, 53);
+    }
+}
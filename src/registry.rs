@@ -0,0 +1,355 @@
+use base64::Engine;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const DOCKER_HUB: &str = "registry-1.docker.io";
+
+/// Manifest media types we accept; multi-arch indexes first so the digest matches
+/// what `docker pull <tag>` records in `RepoDigests`.
+const MANIFEST_ACCEPT: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.v2+json";
+
+/// `registry/repository:tag` split out of a container's image reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRef {
+    pub registry: String,
+    pub repository: String,
+    pub tag: String,
+}
+
+impl ImageRef {
+    /// Parses `nginx`, `nginx:1.27`, `ghcr.io/org/app:v2`, `localhost:5000/app`.
+    ///
+    /// Returns `None` for digest-pinned references (`app@sha256:...`) and bare image ids:
+    /// there is no tag that could move.
+    pub fn parse(image: &str) -> Option<Self> {
+        if image.contains('@') || image.starts_with("sha256:") || image.is_empty() {
+            return None;
+        }
+
+        let (registry, rest) = match image.split_once('/') {
+            Some((first, rest)) if first.contains('.') || first.contains(':') || first == "localhost" => {
+                (registry_host(first).to_string(), rest.to_string())
+            }
+            _ => (DOCKER_HUB.to_string(), image.to_string()),
+        };
+
+        // The tag separator is the last ':' after the last '/'
+        let (repository, tag) = match rest.rsplit_once(':') {
+            Some((repo, tag)) if !tag.contains('/') => (repo.to_string(), tag.to_string()),
+            _ => (rest.clone(), "latest".to_string()),
+        };
+
+        let repository = if registry == DOCKER_HUB && !repository.contains('/') {
+            format!("library/{}", repository)
+        } else {
+            repository
+        };
+
+        Some(Self { registry, repository, tag })
+    }
+
+    /// Repository name as it appears in `RepoDigests` (`nginx`, `ghcr.io/org/app`).
+    pub fn digest_repository(&self) -> String {
+        if self.registry == DOCKER_HUB {
+            self.repository.trim_start_matches("library/").to_string()
+        } else {
+            format!("{}/{}", self.registry, self.repository)
+        }
+    }
+}
+
+/// Docker Hub's aliases (`docker.io`, `index.docker.io`) resolve to its API host.
+fn registry_host(host: &str) -> &str {
+    match host {
+        "docker.io" | "index.docker.io" => DOCKER_HUB,
+        _ => host,
+    }
+}
+
+/// Minimal Docker Registry HTTP API v2 client: resolves the manifest digest of a tag.
+pub struct RegistryClient {
+    client: reqwest::Client,
+    /// registry host -> (username, password)
+    credentials: HashMap<String, (String, String)>,
+    insecure: Vec<String>,
+}
+
+impl RegistryClient {
+    pub fn new(credentials: HashMap<String, (String, String)>, insecure: Vec<String>) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(20))
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { client, credentials, insecure })
+    }
+
+    /// Credentials from the Docker client config (`REGISTRY_AUTH_FILE`, default
+    /// `$DOCKER_CONFIG/config.json` or `~/.docker/config.json`). Plain-HTTP registries
+    /// come from `REGISTRY_INSECURE` (comma-separated hosts).
+    pub fn from_env() -> Result<Self, String> {
+        let path = std::env::var("REGISTRY_AUTH_FILE").ok()
+            .or_else(|| std::env::var("DOCKER_CONFIG").ok().map(|d| format!("{}/config.json", d)))
+            .or_else(|| std::env::var("HOME").ok().map(|h| format!("{}/.docker/config.json", h)));
+
+        let credentials = match path.map(std::fs::read_to_string) {
+            Some(Ok(content)) => parse_docker_config(&content)?,
+            _ => HashMap::new(),
+        };
+        let insecure = std::env::var("REGISTRY_INSECURE")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        Self::new(credentials, insecure)
+    }
+
+    /// Credentials for pulling `image` through the Docker daemon, if any are configured.
+    pub fn docker_credentials(&self, image: &str) -> Option<bollard::auth::DockerCredentials> {
+        let image = ImageRef::parse(image)?;
+        let (user, pass) = self.credentials.get(&image.registry)?;
+        Some(bollard::auth::DockerCredentials {
+            username: Some(user.clone()),
+            password: Some(pass.clone()),
+            serveraddress: Some(image.registry),
+            ..Default::default()
+        })
+    }
+
+    fn base_url(&self, registry: &str) -> String {
+        let host = registry.split(':').next().unwrap_or(registry);
+        let plain_http = host == "localhost" || host == "127.0.0.1" || self.insecure.iter().any(|r| r == registry);
+        format!("{}://{}", if plain_http { "http" } else { "https" }, registry)
+    }
+
+    /// Returns the `Docker-Content-Digest` the registry reports for `image.tag`.
+    pub async fn manifest_digest(&self, image: &ImageRef) -> Result<String, String> {
+        let url = format!("{}/v2/{}/manifests/{}", self.base_url(&image.registry), image.repository, image.tag);
+        let creds = self.credentials.get(&image.registry);
+
+        let mut resp = self.client.head(&url)
+            .header(reqwest::header::ACCEPT, MANIFEST_ACCEPT)
+            .send()
+            .await
+            .map_err(|e| format!("Registry request failed: {}", e))?;
+
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            let challenge = resp.headers()
+                .get(reqwest::header::WWW_AUTHENTICATE)
+                .and_then(|h| h.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+
+            let request = self.client.head(&url).header(reqwest::header::ACCEPT, MANIFEST_ACCEPT);
+            let request = if let Some(params) = challenge.strip_prefix("Bearer ") {
+                let token = self.fetch_token(&parse_challenge(params), creds).await?;
+                request.bearer_auth(token)
+            } else if let Some((user, pass)) = creds {
+                request.basic_auth(user, Some(pass))
+            } else {
+                return Err(format!("Registry {} requires credentials", image.registry));
+            };
+
+            resp = request.send().await.map_err(|e| format!("Registry request failed: {}", e))?;
+        }
+
+        if !resp.status().is_success() {
+            return Err(format!("Registry returned {} for {}:{}", resp.status(), image.repository, image.tag));
+        }
+
+        resp.headers()
+            .get("docker-content-digest")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string())
+            .ok_or_else(|| "Registry response has no Docker-Content-Digest header".to_string())
+    }
+
+    async fn fetch_token(
+        &self,
+        challenge: &HashMap<String, String>,
+        creds: Option<&(String, String)>,
+    ) -> Result<String, String> {
+        let realm = challenge.get("realm").ok_or("Bearer challenge without realm")?;
+        let query: Vec<(&str, &String)> = ["service", "scope"].iter()
+            .filter_map(|k| challenge.get(*k).map(|v| (*k, v)))
+            .collect();
+
+        let mut request = self.client.get(realm).query(&query);
+        if let Some((user, pass)) = creds {
+            request = request.basic_auth(user, Some(pass));
+        }
+
+        let body: serde_json::Value = request.send().await
+            .map_err(|e| format!("Token request failed: {}", e))?
+            .error_for_status()
+            .map_err(|e| format!("Token request failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid token response: {}", e))?;
+
+        body.get("token")
+            .or_else(|| body.get("access_token"))
+            .and_then(|t| t.as_str())
+            .map(|t| t.to_string())
+            .ok_or_else(|| "Token response has no token".to_string())
+    }
+}
+
+/// Parses `realm="...",service="...",scope="..."` from a `WWW-Authenticate` header.
+fn parse_challenge(params: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut rest = params.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.split_once('"') {
+                Some((v, r)) => (v, r),
+                None => (quoted, ""),
+            }
+        } else {
+            after.split_once(',').unwrap_or((after, ""))
+        };
+        result.insert(key, value.to_string());
+        rest = remaining.trim_start_matches(',').trim();
+    }
+    result
+}
+
+/// Reads `auths` from a Docker client `config.json`.
+fn parse_docker_config(content: &str) -> Result<HashMap<String, (String, String)>, String> {
+    let config: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| format!("Invalid registry auth file: {}", e))?;
+
+    let mut credentials = HashMap::new();
+    let Some(auths) = config.get("auths").and_then(|a| a.as_object()) else {
+        return Ok(credentials);
+    };
+
+    for (host, entry) in auths {
+        let Some(encoded) = entry.get("auth").and_then(|a| a.as_str()) else {
+            continue;
+        };
+        let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(encoded) else {
+            tracing::warn!("Skipping malformed registry credentials for {}", host);
+            continue;
+        };
+        let decoded = String::from_utf8_lossy(&decoded).to_string();
+        if let Some((user, pass)) = decoded.split_once(':') {
+            let host = host.trim_start_matches("https://").trim_start_matches("http://");
+            let host = host.split('/').next().unwrap_or(host);
+            // Docker Hub credentials are stored under the legacy index URL
+            credentials.insert(registry_host(host).to_string(), (user.to_string(), pass.to_string()));
+        }
+    }
+    Ok(credentials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{HeaderMap, StatusCode, header},
+        response::IntoResponse,
+        routing::get,
+        Router,
+    };
+
+    #[test]
+    fn test_parse_image_refs() {
+        assert_eq!(ImageRef::parse("nginx"), Some(ImageRef {
+            registry: DOCKER_HUB.to_string(),
+            repository: "library/nginx".to_string(),
+            tag: "latest".to_string(),
+        }));
+        assert_eq!(ImageRef::parse("grafana/grafana:11.0.0").unwrap().repository, "grafana/grafana");
+
+        let ghcr = ImageRef::parse("ghcr.io/org/app:v2").unwrap();
+        assert_eq!((ghcr.registry.as_str(), ghcr.repository.as_str(), ghcr.tag.as_str()), ("ghcr.io", "org/app", "v2"));
+        assert_eq!(ghcr.digest_repository(), "ghcr.io/org/app");
+
+        let local = ImageRef::parse("localhost:5000/app").unwrap();
+        assert_eq!((local.registry.as_str(), local.tag.as_str()), ("localhost:5000", "latest"));
+
+        assert_eq!(ImageRef::parse("nginx:1.27").unwrap().digest_repository(), "nginx");
+        assert!(ImageRef::parse("nginx@sha256:abcdef").is_none());
+
+        // Fully-qualified Hub references are the same images as the short forms
+        for image in ["docker.io/library/nginx:1.27", "index.docker.io/library/nginx:1.27", "docker.io/nginx:1.27"] {
+            assert_eq!(ImageRef::parse(image), ImageRef::parse("nginx:1.27"), "{}", image);
+        }
+        let hub = ImageRef::parse("docker.io/grafana/grafana:11.0.0").unwrap();
+        assert_eq!(hub.registry, DOCKER_HUB);
+        assert_eq!(hub.digest_repository(), "grafana/grafana");
+        assert!(ImageRef::parse("sha256:abcdef").is_none());
+    }
+
+    #[test]
+    fn test_parse_challenge() {
+        let c = parse_challenge(r#"realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/nginx:pull""#);
+        assert_eq!(c["realm"], "https://auth.docker.io/token");
+        assert_eq!(c["service"], "registry.docker.io");
+        assert_eq!(c["scope"], "repository:library/nginx:pull");
+    }
+
+    #[test]
+    fn test_parse_docker_config() {
+        let encoded = base64::engine::general_purpose::STANDARD.encode("bot:s3cret");
+        let config = format!(r#"{{"auths": {{"https://index.docker.io/v1/": {{"auth": "{0}"}}, "ghcr.io": {{"auth": "{0}"}}}}}}"#, encoded);
+        let creds = parse_docker_config(&config).unwrap();
+        assert_eq!(creds[DOCKER_HUB], ("bot".to_string(), "s3cret".to_string()));
+        assert!(creds.contains_key("ghcr.io"));
+    }
+
+    /// Local stand-in for a registry with token auth, mirroring Docker Hub's flow.
+    async fn spawn_registry(require_basic: bool) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let realm = format!("http://{}/token", addr);
+
+        let app = Router::new()
+            .route("/token", get(move |headers: HeaderMap| async move {
+                let basic = headers.get(header::AUTHORIZATION).and_then(|h| h.to_str().ok()).unwrap_or("");
+                let expected = format!("Basic {}", base64::engine::general_purpose::STANDARD.encode("bot:s3cret"));
+                if require_basic && basic != expected {
+                    return StatusCode::UNAUTHORIZED.into_response();
+                }
+                axum::Json(serde_json::json!({ "token": "test-token" })).into_response()
+            }))
+            .route("/v2/library/app/manifests/{tag}", get(move |headers: HeaderMap| async move {
+                let auth = headers.get(header::AUTHORIZATION).and_then(|h| h.to_str().ok()).unwrap_or("");
+                if auth != "Bearer test-token" {
+                    let challenge = format!(r#"Bearer realm="{}",service="test",scope="repository:library/app:pull""#, realm);
+                    return (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, challenge)]).into_response();
+                }
+                (StatusCode::OK, [("docker-content-digest", "sha256:feedface")]).into_response()
+            }));
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("127.0.0.1:{}", addr.port())
+    }
+
+    #[tokio::test]
+    async fn test_manifest_digest_anonymous_token() {
+        let registry = spawn_registry(false).await;
+        let client = RegistryClient::new(HashMap::new(), Vec::new()).unwrap();
+        let image = ImageRef::parse(&format!("{}/library/app:1.0", registry)).unwrap();
+        assert_eq!(client.manifest_digest(&image).await.unwrap(), "sha256:feedface");
+    }
+
+    #[tokio::test]
+    async fn test_manifest_digest_with_credentials() {
+        let registry = spawn_registry(true).await;
+        let image = ImageRef::parse(&format!("{}/library/app:1.0", registry)).unwrap();
+
+        let anonymous = RegistryClient::new(HashMap::new(), Vec::new()).unwrap();
+        assert!(anonymous.manifest_digest(&image).await.is_err());
+
+        let creds = HashMap::from([(registry.clone(), ("bot".to_string(), "s3cret".to_string()))]);
+        let client = RegistryClient::new(creds, Vec::new()).unwrap();
+        assert_eq!(client.manifest_digest(&image).await.unwrap(), "sha256:feedface");
+    }
+}
@@ -0,0 +1,448 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use crate::disk_ops::{format_bytes, CleanItem, CleanReport};
+use crate::util::{glob_match, load_json_config, Visit, Walker};
+
+/// Share of a text log `gzip` is assumed to save when estimating a compress run.
+const COMPRESS_SAVING_PERCENT: u64 = 90;
+/// How many files a report lists.
+const REPORT_ITEMS: usize = 100;
+
+/// `journalctl --vacuum-time` / `--vacuum-size` limits used by the `logs` cleaner.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournaldPolicy {
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    #[serde(default)]
+    pub max_size_bytes: Option<u64>,
+}
+
+impl Default for JournaldPolicy {
+    /// What the cleaner always did before policies existed: keep one day.
+    fn default() -> Self {
+        Self { max_age_days: Some(1), max_size_bytes: None }
+    }
+}
+
+impl JournaldPolicy {
+    pub fn vacuum_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(days) = self.max_age_days {
+            args.push(format!("--vacuum-time={}d", days));
+        }
+        if let Some(bytes) = self.max_size_bytes {
+            args.push(format!("--vacuum-size={}", bytes));
+        }
+        args
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogAction {
+    /// `gzip` the file. Already compressed files, and files a process still has
+    /// open (e.g. the live `access.log`), are left alone.
+    Compress,
+    /// Cut the file to zero bytes; safe for logs a process still writes to
+    Truncate,
+    Delete,
+}
+
+/// Retention for plain log files, e.g. `/var/log/nginx/*.log`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogPolicy {
+    pub name: String,
+    /// Absolute paths; `*` and `?` are allowed in the file name only
+    pub paths: Vec<String>,
+    pub action: LogAction,
+    /// Files not modified for this long are acted on
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// Then the oldest files are acted on until all matching files fit in this
+    #[serde(default)]
+    pub max_total_bytes: Option<u64>,
+}
+
+/// Contents of `RETENTION_FILE`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RetentionConfig {
+    #[serde(default)]
+    pub journald: JournaldPolicy,
+    #[serde(default)]
+    pub policies: Vec<LogPolicy>,
+}
+
+impl RetentionConfig {
+    pub fn policy(&self, name: &str) -> Option<&LogPolicy> {
+        self.policies.iter().find(|p| p.name == name)
+    }
+}
+
+/// Without the file only the default journald policy applies.
+pub fn load_config(path: &str) -> Result<RetentionConfig, String> {
+    let config: RetentionConfig = load_json_config(path, "retention policies", RetentionConfig::default())?;
+    validate(&config)?;
+    Ok(config)
+}
+
+fn validate(config: &RetentionConfig) -> Result<(), String> {
+    if config.journald.max_age_days.is_none() && config.journald.max_size_bytes.is_none() {
+        return Err("The journald policy needs `max_age_days` or `max_size_bytes`".to_string());
+    }
+    for p in &config.policies {
+        if p.name.trim().is_empty() {
+            return Err("Retention policy names must not be empty".to_string());
+        }
+        if config.policies.iter().filter(|o| o.name == p.name).count() > 1 {
+            return Err(format!("Retention policy '{}' is defined twice", p.name));
+        }
+        if p.max_age_days.is_none() && p.max_total_bytes.is_none() {
+            return Err(format!("Retention policy '{}' needs `max_age_days` or `max_total_bytes`", p.name));
+        }
+        if p.paths.is_empty() {
+            return Err(format!("Retention policy '{}' needs at least one path", p.name));
+        }
+        for pattern in &p.paths {
+            let path = Path::new(pattern);
+            let dir_has_wildcard = path.parent().is_some_and(|d| d.to_string_lossy().contains(['*', '?']));
+            if !path.is_absolute()
+                || dir_has_wildcard
+                || path.components().any(|c| matches!(c, Component::ParentDir | Component::CurDir))
+            {
+                return Err(format!(
+                    "Retention policy '{}': '{}' must be absolute, without '..', with wildcards in the file name only",
+                    p.name, pattern
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A regular file a policy matched.
+#[derive(Debug, Clone)]
+struct LogFile {
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+    /// (device, inode)
+    id: (u64, u64),
+}
+
+/// Regular files matching any of `patterns`, each once. Symlinks are not followed.
+fn matching_files(patterns: &[String]) -> Vec<LogFile> {
+    let mut files: Vec<LogFile> = Vec::new();
+    let mut walker = Walker::default().max_depth(1);
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            continue;
+        };
+        walker.walk(dir, &mut |visit| {
+            let Visit::File(path, meta) = visit else {
+                return;
+            };
+            let matches = path.file_name().is_some_and(|f| glob_match(name.as_encoded_bytes(), f.as_encoded_bytes()));
+            if matches && meta.is_file() && !files.iter().any(|f| f.path == path) {
+                files.push(LogFile {
+                    path: path.to_path_buf(),
+                    bytes: meta.blocks() * 512,
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    id: (meta.dev(), meta.ino()),
+                });
+            }
+        });
+    }
+    files
+}
+
+/// (device, inode) of every file a process has open, from `/proc/*/fd`. Without
+/// root only the agent's own processes are visible.
+fn open_files() -> HashSet<(u64, u64)> {
+    let mut open = HashSet::new();
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return open;
+    };
+    for proc in procs.flatten() {
+        let Ok(fds) = std::fs::read_dir(proc.path().join("fd")) else {
+            continue;
+        };
+        // metadata() follows the fd link to the open file
+        for fd in fds.flatten() {
+            if let Ok(meta) = std::fs::metadata(fd.path())
+                && meta.is_file()
+            {
+                open.insert((meta.dev(), meta.ino()));
+            }
+        }
+    }
+    open
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ["gz", "xz", "zst", "bz2"].iter().any(|c| ext == *c))
+}
+
+/// Space acting on `file` is expected to free.
+fn estimated_saving(action: LogAction, file: &LogFile) -> u64 {
+    match action {
+        LogAction::Compress => file.bytes * COMPRESS_SAVING_PERCENT / 100,
+        LogAction::Truncate | LogAction::Delete => file.bytes,
+    }
+}
+
+/// Files the policy acts on, oldest first: those older than `max_age_days`, then more of the
+/// oldest until the matching files are expected to fit in `max_total_bytes`.
+///
+/// Files in `open` are not compressed: gzip would unlink a file its writer keeps
+/// appending to, so no space is freed and later lines are lost.
+fn plan(policy: &LogPolicy, mut files: Vec<LogFile>, open: &HashSet<(u64, u64)>, now: SystemTime) -> Vec<LogFile> {
+    let mut total: u64 = files.iter().map(|f| f.bytes).sum();
+    files.sort_by_key(|f| f.modified);
+    let cutoff = policy.max_age_days.map(|days| now - Duration::from_secs(days as u64 * 86400));
+
+    let mut planned = Vec::new();
+    for file in files {
+        let candidate = match policy.action {
+            LogAction::Compress => !is_compressed(&file.path) && !open.contains(&file.id),
+            LogAction::Truncate => file.bytes > 0,
+            LogAction::Delete => true,
+        };
+        if !candidate {
+            continue;
+        }
+        let too_old = cutoff.is_some_and(|cutoff| file.modified < cutoff);
+        let over_size = policy.max_total_bytes.is_some_and(|max| total > max);
+        if too_old || over_size {
+            total = total.saturating_sub(estimated_saving(policy.action, &file));
+            planned.push(file);
+        }
+    }
+    planned
+}
+
+/// Acts on one file and returns the space freed.
+fn apply_file(action: LogAction, file: &LogFile) -> Result<u64, String> {
+    // The file may have been replaced by a symlink since it was matched
+    let meta = std::fs::symlink_metadata(&file.path).map_err(|e| e.to_string())?;
+    if !meta.is_file() {
+        return Err("no longer a regular file".to_string());
+    }
+    let before = meta.blocks() * 512;
+    match action {
+        LogAction::Delete => {
+            std::fs::remove_file(&file.path).map_err(|e| e.to_string())?;
+            Ok(before)
+        }
+        LogAction::Truncate => {
+            std::fs::OpenOptions::new().write(true).open(&file.path)
+                .and_then(|f| f.set_len(0))
+                .map_err(|e| e.to_string())?;
+            Ok(before)
+        }
+        LogAction::Compress => {
+            let output = Command::new("gzip")
+                .arg("-f")
+                .arg("--")
+                .arg(&file.path)
+                .output()
+                .map_err(|e| format!("Failed to run gzip: {}", e))?;
+            if !output.status.success() {
+                return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+            }
+            let mut compressed = file.path.clone().into_os_string();
+            compressed.push(".gz");
+            let after = std::fs::symlink_metadata(&compressed).map(|m| m.blocks() * 512).unwrap_or(0);
+            Ok(before.saturating_sub(after))
+        }
+    }
+}
+
+fn action_name(action: LogAction) -> &'static str {
+    match action {
+        LogAction::Compress => "compress",
+        LogAction::Truncate => "truncate",
+        LogAction::Delete => "delete",
+    }
+}
+
+fn run_policy(policy: &LogPolicy, dry_run: bool) -> Result<CleanReport, String> {
+    let open = if policy.action == LogAction::Compress { open_files() } else { HashSet::new() };
+    let planned = plan(policy, matching_files(&policy.paths), &open, SystemTime::now());
+    let reclaimable: u64 = planned.iter().map(|f| estimated_saving(policy.action, f)).sum();
+    let mut items: Vec<CleanItem> = planned.iter()
+        .map(|f| CleanItem { name: f.path.display().to_string(), bytes: f.bytes })
+        .collect();
+    items.sort_by_key(|i| std::cmp::Reverse(i.bytes));
+    items.truncate(REPORT_ITEMS);
+    let target = format!("retention:{}", policy.name);
+    let action = action_name(policy.action);
+
+    if dry_run {
+        return Ok(CleanReport {
+            target,
+            dry_run: true,
+            items,
+            reclaimable_bytes: reclaimable,
+            freed_bytes: None,
+            message: format!("Would {} {} file(s), freeing about {}", action, planned.len(), format_bytes(reclaimable)),
+        });
+    }
+
+    let mut freed = 0;
+    let mut errors = Vec::new();
+    for file in &planned {
+        match apply_file(policy.action, file) {
+            Ok(bytes) => freed += bytes,
+            Err(e) => errors.push(format!("{}: {}", file.path.display(), e)),
+        }
+    }
+    if !planned.is_empty() && errors.len() == planned.len() {
+        return Err(errors.join("\n"));
+    }
+    let mut message = format!(
+        "{}: {} {} file(s), freed {}.",
+        policy.name, action, planned.len() - errors.len(), format_bytes(freed),
+    );
+    for e in &errors {
+        message.push('\n');
+        message.push_str(e);
+    }
+    Ok(CleanReport { target, dry_run: false, items, reclaimable_bytes: reclaimable, freed_bytes: Some(freed), message })
+}
+
+/// Applies a log file policy, or with `dry_run` only reports which files it would act on.
+pub async fn apply(policy: &LogPolicy, dry_run: bool) -> Result<CleanReport, String> {
+    let policy = policy.clone();
+    tokio::task::spawn_blocking(move || run_policy(&policy, dry_run))
+        .await
+        .map_err(|e| format!("Retention task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> tempfile::TempDir {
+        tempfile::tempdir().unwrap()
+    }
+
+    fn write(path: &Path, bytes: usize, age_days: u64) {
+        std::fs::write(path, "log line\n".repeat(bytes / 9)).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 86400);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    fn policy(dir: &Path, action: LogAction, max_age_days: Option<u32>, max_total_bytes: Option<u64>) -> LogPolicy {
+        LogPolicy {
+            name: "app".to_string(),
+            paths: vec![format!("{}/*.log*", dir.display())],
+            action,
+            max_age_days,
+            max_total_bytes,
+        }
+    }
+
+    #[test]
+    fn test_load_config_validates() {
+        assert_eq!(load_config("/definitely/missing.json").unwrap(), RetentionConfig::default());
+
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        let file = dir.join("retention.json");
+        let cases = [
+            (r#"{"journald": {"max_age_days": 7, "max_size_bytes": 500000000}, "policies": [
+                {"name": "nginx", "paths": ["/var/log/nginx/*.log"], "action": "compress", "max_age_days": 3}]}"#, true),
+            (r#"{"journald": {}}"#, false),
+            (r#"{"policies": [{"name": "x", "paths": ["/var/log/*.log"], "action": "delete"}]}"#, false),
+            (r#"{"policies": [{"name": "x", "paths": ["/var/*/app.log"], "action": "delete", "max_age_days": 1}]}"#, false),
+            (r#"{"policies": [{"name": "x", "paths": ["logs/*.log"], "action": "delete", "max_age_days": 1}]}"#, false),
+            (r#"{"policies": [{"name": "x", "paths": ["/var/log/*.log"], "action": "shred", "max_age_days": 1}]}"#, false),
+        ];
+        for (json, ok) in cases {
+            std::fs::write(&file, json).unwrap();
+            assert_eq!(load_config(file.to_str().unwrap()).is_ok(), ok, "{}", json);
+        }
+        std::fs::write(&file, cases[0].0).unwrap();
+        let config = load_config(file.to_str().unwrap()).unwrap();
+        assert_eq!(config.journald.vacuum_args(), ["--vacuum-time=7d", "--vacuum-size=500000000"]);
+        assert_eq!(config.policy("nginx").unwrap().action, LogAction::Compress);
+    }
+
+    #[test]
+    fn test_plan_by_age_and_total_size() {
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        write(&dir.join("a.log.3"), 40_000, 30);
+        write(&dir.join("a.log.2"), 40_000, 20);
+        write(&dir.join("a.log.1"), 40_000, 2);
+        write(&dir.join("a.log"), 40_000, 0);
+        std::os::unix::fs::symlink("/etc/passwd", dir.join("b.log")).unwrap();
+
+        let names = |files: Vec<LogFile>| -> Vec<String> {
+            files.iter().map(|f| f.path.file_name().unwrap().to_string_lossy().to_string()).collect()
+        };
+        let files = || matching_files(&policy(dir, LogAction::Delete, None, None).paths);
+        assert_eq!(files().len(), 4);
+
+        let by_age = policy(dir, LogAction::Delete, Some(10), None);
+        let none = HashSet::new();
+        assert_eq!(names(plan(&by_age, files(), &none, SystemTime::now())), ["a.log.3", "a.log.2"]);
+
+        // Four files of ~40 KB with room for two
+        let by_size = policy(dir, LogAction::Delete, None, Some(90_000));
+        assert_eq!(names(plan(&by_size, files(), &none, SystemTime::now())), ["a.log.3", "a.log.2"]);
+        let both = policy(dir, LogAction::Delete, Some(25), Some(130_000));
+        assert_eq!(names(plan(&both, files(), &none, SystemTime::now())), ["a.log.3"]);
+    }
+
+    #[test]
+    fn test_open_files_are_not_compressed() {
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        write(&dir.join("app.log"), 40_000, 30);
+        write(&dir.join("app.log.1"), 40_000, 30);
+        // Held open like a process still writing its log
+        let _writer = std::fs::OpenOptions::new().append(true).open(dir.join("app.log")).unwrap();
+
+        let compress = policy(dir, LogAction::Compress, Some(10), None);
+        let planned = plan(&compress, matching_files(&compress.paths), &open_files(), SystemTime::now());
+        let names: Vec<String> = planned.iter().map(|f| f.path.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, ["app.log.1"]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_actions() {
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        write(&dir.join("old.log"), 50_000, 10);
+        write(&dir.join("new.log"), 50_000, 0);
+
+        let compress = policy(dir, LogAction::Compress, Some(5), None);
+        let preview = apply(&compress, true).await.unwrap();
+        assert_eq!(preview.items.len(), 1);
+        assert!(preview.reclaimable_bytes > 0);
+        assert!(dir.join("old.log").exists());
+
+        let report = apply(&compress, false).await.unwrap();
+        assert!(report.freed_bytes.unwrap() > 0);
+        assert!(!dir.join("old.log").exists());
+        assert!(dir.join("old.log.gz").exists());
+        // Compressed files are not compressed again
+        assert!(apply(&compress, true).await.unwrap().items.is_empty());
+
+        let delete = policy(dir, LogAction::Delete, Some(5), None);
+        apply(&delete, false).await.unwrap();
+        assert!(!dir.join("old.log.gz").exists());
+        assert!(dir.join("new.log").exists());
+
+        let truncate = policy(dir, LogAction::Truncate, None, Some(1_000));
+        apply(&truncate, false).await.unwrap();
+        assert_eq!(std::fs::metadata(dir.join("new.log")).unwrap().len(), 0);
+    }
+}
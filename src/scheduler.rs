@@ -0,0 +1,948 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::deployment::kill_process_group;
+use crate::disk_ops::{CleanupHistory, DiskOps, CLEAN_TARGETS};
+use crate::docker::DockerService;
+use crate::i18n::{self, Lang};
+use crate::notifications::NotificationService;
+use crate::retention::{self, RetentionConfig};
+use crate::security::{CheckStatus, SecurityAuditor};
+use crate::util::load_json_config;
+
+/// Output kept per run; the rest is dropped with a note.
+const MAX_OUTPUT: usize = 64 * 1024;
+/// Runs kept per job, older ones are deleted after each run.
+const RUNS_KEPT: i64 = 100;
+
+const MONTH_NAMES: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Standard five-field cron expression: `minute hour day-of-month month day-of-week`.
+///
+/// Supports `*`, lists, ranges, steps, month/weekday names and the `@daily`-style aliases.
+/// As in cron, when both day fields are restricted a day matching either one fires.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("Expected 5 fields (minute hour day month weekday), got {}", fields.len()));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, WEEKDAY_NAMES).map_err(|e| format!("Day of week: {}", e))?;
+        // 7 is Sunday as well
+        if bit(weekdays, 7) {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[]).map_err(|e| format!("Minute: {}", e))?,
+            hours: parse_field(hour, 0, 23, &[]).map_err(|e| format!("Hour: {}", e))?,
+            days: parse_field(day, 1, 31, &[]).map_err(|e| format!("Day of month: {}", e))?,
+            months: parse_field(month, 1, 12, MONTH_NAMES).map_err(|e| format!("Month: {}", e))?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule fires in the minute of `t`.
+    pub fn matches(&self, t: &NaiveDateTime) -> bool {
+        bit(self.minutes, t.minute())
+            && bit(self.hours, t.hour())
+            && bit(self.months, t.month())
+            && self.day_matches(t.date())
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.any_day || self.any_weekday { day && weekday } else { day || weekday }
+    }
+
+    /// First time after `after` the schedule fires, looking up to five years ahead.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let start = after.naive_local();
+        let limit = start + chrono::Duration::days(5 * 366);
+        let mut t = start.date().and_hms_opt(start.hour(), start.minute(), 0)? + chrono::Duration::minutes(1);
+
+        while t < limit {
+            if !bit(self.months, t.month()) {
+                let (year, month) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, t.hour()) {
+                t = t.date().and_hms_opt(t.hour(), 0, 0)? + chrono::Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t += chrono::Duration::minutes(1);
+            } else {
+                // Local times skipped by a DST change do not exist; try the next minute
+                if let Some(time) = tz.from_local_datetime(&t).earliest()
+                    && time > *after
+                {
+                    return Some(time);
+                }
+                t += chrono::Duration::minutes(1);
+            }
+        }
+        None
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses one cron field into a bit set of allowed values.
+fn parse_field(spec: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_ascii_lowercase();
+        match names.iter().position(|n| *n == lower) {
+            Some(i) => Ok(min + i as u32),
+            None => s.parse().map_err(|_| format!("'{}' is not a number", s)),
+        }
+    };
+
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step.parse::<u32>().ok().filter(|s| *s > 0)
+                    .ok_or_else(|| format!("Invalid step in '{}'", part))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (value(a)?, value(b)?)
+        } else {
+            let v = value(range)?;
+            // `5/15` means every 15th value starting at 5
+            (v, if step > 1 { max } else { v })
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(format!("'{}' is out of range {}-{}", part, min, max));
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+/// What a job does. Stored as JSON in `jobs.action`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobAction {
+    /// One of the `/api/disk/clean` targets
+    DiskClean { target: String },
+    /// A log file retention policy, referenced by name
+    LogRetention { policy: String },
+    ContainerRestart { container: String },
+    /// A command from the job commands file, referenced by name
+    Command { command: String },
+    /// Runs the security audit; the run fails when any check fails
+    SecurityAudit,
+}
+
+/// Command that jobs may run, defined in `JOB_COMMANDS_FILE` rather than through the API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobCommand {
+    pub name: String,
+    /// argv list, run without a shell
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 { 300 }
+
+/// Job commands from a JSON array; names must be unique.
+pub fn load_commands(path: &str) -> Result<Vec<JobCommand>, String> {
+    let commands: Vec<JobCommand> = load_json_config(path, "job commands", Vec::new())?;
+
+    for c in &commands {
+        if c.name.trim().is_empty() {
+            return Err("Job command names must not be empty".to_string());
+        }
+        if c.command.is_empty() {
+            return Err(format!("Job command '{}' needs a non-empty `command`", c.name));
+        }
+        if commands.iter().filter(|o| o.name == c.name).count() > 1 {
+            return Err(format!("Job command '{}' is defined twice", c.name));
+        }
+    }
+    Ok(commands)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: i64,
+    pub name: String,
+    pub schedule: String,
+    pub action: JobAction,
+    pub enabled: bool,
+    pub notify_on_failure: bool,
+    /// Unix timestamp (seconds)
+    pub created_at: i64,
+    /// Next scheduled run (Unix seconds, server time zone); `None` while disabled
+    pub next_run: Option<i64>,
+    pub last_status: Option<String>,
+    pub last_run_at: Option<i64>,
+}
+
+/// Body of `POST /api/jobs` and `PUT /api/jobs/{id}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobInput {
+    pub name: String,
+    pub schedule: String,
+    pub action: JobAction,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub notify_on_failure: bool,
+}
+
+fn default_true() -> bool { true }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobRun {
+    pub id: i64,
+    pub job_id: i64,
+    /// "schedule" or "manual"
+    pub triggered_by: String,
+    /// Unix timestamp (seconds)
+    pub started_at: i64,
+    pub duration_ms: Option<i64>,
+    /// "running", "success", "failed", "timeout" or "interrupted"
+    pub status: String,
+    pub output: String,
+}
+
+#[derive(Debug)]
+pub enum JobError {
+    NotFound,
+    Invalid(String),
+    /// The previous run of the job has not finished
+    Running,
+    Db(sqlx::Error),
+}
+
+impl From<sqlx::Error> for JobError {
+    fn from(e: sqlx::Error) -> Self {
+        JobError::Db(e)
+    }
+}
+
+impl std::fmt::Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobError::NotFound => write!(f, "Job not found"),
+            JobError::Invalid(e) => write!(f, "{}", e),
+            JobError::Running => write!(f, "Job is already running"),
+            JobError::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+const JOB_SQL: &str = "SELECT j.id, j.name, j.schedule, j.action, j.enabled, j.notify_on_failure, j.created_at,
+        r.status AS last_status, r.started_at AS last_run_at
+    FROM jobs j
+    LEFT JOIN job_runs r ON r.id = (SELECT MAX(id) FROM job_runs WHERE job_id = j.id)";
+
+/// Runs cron-scheduled maintenance jobs stored in the `jobs` table and records every run.
+pub struct Scheduler {
+    db: SqlitePool,
+    docker: Arc<DockerService>,
+    notifier: Arc<NotificationService>,
+    cleanups: Arc<CleanupHistory>,
+    retention: Arc<RetentionConfig>,
+    auditor: Arc<SecurityAuditor>,
+    commands: Vec<JobCommand>,
+    running: Mutex<HashSet<i64>>,
+}
+
+/// Marks a job as running until dropped, so a run that panics does not block the job forever.
+struct RunningJob {
+    scheduler: Arc<Scheduler>,
+    job_id: i64,
+}
+
+impl Drop for RunningJob {
+    fn drop(&mut self) {
+        self.scheduler.running.lock().unwrap().remove(&self.job_id);
+    }
+}
+
+impl Scheduler {
+    pub fn new(
+        db: SqlitePool,
+        docker: Arc<DockerService>,
+        notifier: Arc<NotificationService>,
+        cleanups: Arc<CleanupHistory>,
+        retention: Arc<RetentionConfig>,
+        auditor: Arc<SecurityAuditor>,
+        commands: Vec<JobCommand>,
+    ) -> Self {
+        Self {
+            db,
+            docker,
+            notifier,
+            cleanups,
+            retention,
+            auditor,
+            commands,
+            running: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Creates the tables and marks runs cut short by a restart as `interrupted`.
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                schedule TEXT NOT NULL,
+                action TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                notify_on_failure BOOLEAN NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS job_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job_id INTEGER NOT NULL,
+                triggered_by TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                duration_ms INTEGER,
+                status TEXT NOT NULL,
+                output TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job_id, id);"
+        )
+        .execute(&self.db)
+        .await?;
+
+        sqlx::query("UPDATE job_runs SET status = 'interrupted' WHERE status = 'running'")
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Names of the commands `command` jobs can reference.
+    pub fn command_names(&self) -> Vec<String> {
+        self.commands.iter().map(|c| c.name.clone()).collect()
+    }
+
+    fn validate(&self, input: &JobInput) -> Result<(), JobError> {
+        if input.name.trim().is_empty() {
+            return Err(JobError::Invalid("Job name must not be empty".to_string()));
+        }
+        CronSchedule::parse(&input.schedule)
+            .map_err(|e| JobError::Invalid(format!("Invalid schedule '{}': {}", input.schedule, e)))?;
+
+        match &input.action {
+            JobAction::DiskClean { target } if !CLEAN_TARGETS.contains(&target.as_str()) => Err(JobError::Invalid(
+                format!("Unknown disk target '{}', expected one of: {}", target, CLEAN_TARGETS.join(", ")),
+            )),
+            JobAction::LogRetention { policy } if self.retention.policy(policy).is_none() => Err(JobError::Invalid(
+                format!("Retention policy '{}' is not defined in the retention file", policy),
+            )),
+            JobAction::ContainerRestart { container } if container.trim().is_empty() => {
+                Err(JobError::Invalid("Container must not be empty".to_string()))
+            }
+            JobAction::Command { command } if !self.commands.iter().any(|c| &c.name == command) => Err(
+                JobError::Invalid(format!("Command '{}' is not defined in the job commands file", command)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn list_jobs(&self) -> Result<Vec<Job>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{} ORDER BY j.id", JOB_SQL))
+            .fetch_all(&self.db)
+            .await?;
+        rows.iter().map(job_from_row).collect()
+    }
+
+    pub async fn get_job(&self, id: i64) -> Result<Option<Job>, sqlx::Error> {
+        let row = sqlx::query(&format!("{} WHERE j.id = ?", JOB_SQL))
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+        row.as_ref().map(job_from_row).transpose()
+    }
+
+    pub async fn create_job(&self, input: JobInput) -> Result<Job, JobError> {
+        self.validate(&input)?;
+        let id = sqlx::query(
+            "INSERT INTO jobs (name, schedule, action, enabled, notify_on_failure, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(input.name.trim())
+        .bind(input.schedule.trim())
+        .bind(serde_json::to_string(&input.action).unwrap_or_default())
+        .bind(input.enabled)
+        .bind(input.notify_on_failure)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&self.db)
+        .await?
+        .last_insert_rowid();
+
+        self.get_job(id).await?.ok_or(JobError::NotFound)
+    }
+
+    pub async fn update_job(&self, id: i64, input: JobInput) -> Result<Job, JobError> {
+        self.validate(&input)?;
+        let updated = sqlx::query(
+            "UPDATE jobs SET name = ?, schedule = ?, action = ?, enabled = ?, notify_on_failure = ? WHERE id = ?"
+        )
+        .bind(input.name.trim())
+        .bind(input.schedule.trim())
+        .bind(serde_json::to_string(&input.action).unwrap_or_default())
+        .bind(input.enabled)
+        .bind(input.notify_on_failure)
+        .bind(id)
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            return Err(JobError::NotFound);
+        }
+        self.get_job(id).await?.ok_or(JobError::NotFound)
+    }
+
+    /// Deletes the job and its run history.
+    pub async fn delete_job(&self, id: i64) -> Result<(), JobError> {
+        let mut tx = self.db.begin().await?;
+        let deleted = sqlx::query("DELETE FROM jobs WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        sqlx::query("DELETE FROM job_runs WHERE job_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        if deleted == 0 { Err(JobError::NotFound) } else { Ok(()) }
+    }
+
+    /// Newest runs of a job first.
+    pub async fn get_runs(&self, job_id: i64, limit: i64) -> Result<Vec<JobRun>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, job_id, triggered_by, started_at, duration_ms, status, output
+             FROM job_runs WHERE job_id = ? ORDER BY id DESC LIMIT ?"
+        )
+        .bind(job_id)
+        .bind(limit.clamp(1, RUNS_KEPT))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.iter().map(|row| JobRun {
+            id: row.get("id"),
+            job_id: row.get("job_id"),
+            triggered_by: row.get("triggered_by"),
+            started_at: row.get("started_at"),
+            duration_ms: row.get("duration_ms"),
+            status: row.get("status"),
+            output: row.get("output"),
+        }).collect())
+    }
+
+    /// Starts a job outside its schedule and returns the id of the new run.
+    pub async fn run_now(self: &Arc<Self>, id: i64) -> Result<i64, JobError> {
+        let job = self.get_job(id).await?.ok_or(JobError::NotFound)?;
+        self.start(job, "manual").await
+    }
+
+    async fn start(self: &Arc<Self>, job: Job, triggered_by: &str) -> Result<i64, JobError> {
+        if !self.running.lock().unwrap().insert(job.id) {
+            return Err(JobError::Running);
+        }
+        let running = RunningJob { scheduler: self.clone(), job_id: job.id };
+
+        let run_id = sqlx::query(
+            "INSERT INTO job_runs (job_id, triggered_by, started_at, status, output) VALUES (?, ?, ?, 'running', '')"
+        )
+        .bind(job.id)
+        .bind(triggered_by)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&self.db)
+        .await?
+        .last_insert_rowid();
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            let _running = running;
+            let started = Instant::now();
+            let (status, output) = this.execute(&job.action).await;
+            let output = truncate_output(output);
+            this.finish_run(job.id, run_id, status, &output, started.elapsed().as_millis() as i64).await;
+
+            if status != "success" {
+                tracing::warn!("Job '{}' finished with status {}", job.name, status);
+                if job.notify_on_failure {
+                    this.notify_failure(&job, status, &output).await;
+                }
+            }
+        });
+        Ok(run_id)
+    }
+
+    async fn finish_run(&self, job_id: i64, run_id: i64, status: &str, output: &str, duration_ms: i64) {
+        let result = sqlx::query("UPDATE job_runs SET status = ?, output = ?, duration_ms = ? WHERE id = ?")
+            .bind(status)
+            .bind(output)
+            .bind(duration_ms)
+            .bind(run_id)
+            .execute(&self.db)
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to save job run {}: {}", run_id, e);
+        }
+
+        let pruned = sqlx::query(
+            "DELETE FROM job_runs WHERE job_id = ?1
+             AND id NOT IN (SELECT id FROM job_runs WHERE job_id = ?1 ORDER BY id DESC LIMIT ?2)"
+        )
+        .bind(job_id)
+        .bind(RUNS_KEPT)
+        .execute(&self.db)
+        .await;
+        if let Err(e) = pruned {
+            tracing::error!("Failed to prune runs of job {}: {}", job_id, e);
+        }
+    }
+
+    async fn notify_failure(&self, job: &Job, status: &str, output: &str) {
+        let lang = Lang::from_headers(&i18n::HeaderMap::new());
+        let tail: String = output.chars().rev().take(500).collect::<Vec<_>>().into_iter().rev().collect();
+        let msg = format!(
+            "{}\n\n```\n{}\n```",
+            i18n::t_val("jobs.failed", &lang, &format!("{} ({})", job.name, status)),
+            tail.trim().replace('`', "'"),
+        );
+        self.notifier.send_alert(&msg).await;
+    }
+
+    /// Runs the action and returns the run status and its output.
+    async fn execute(&self, action: &JobAction) -> (&'static str, String) {
+        match action {
+            JobAction::DiskClean { target } => {
+                let result = DiskOps::clean(target, ".", &self.docker, &self.retention.journald, false).await;
+                self.cleanups.record(target, "job", &result).await;
+                outcome(result.map(|report| report.message))
+            }
+            JobAction::LogRetention { policy } => match self.retention.policy(policy) {
+                Some(p) => {
+                    let result = retention::apply(p, false).await;
+                    self.cleanups.record(&format!("retention:{}", policy), "job", &result).await;
+                    outcome(result.map(|report| report.message))
+                }
+                None => ("failed", format!("Retention policy '{}' is not defined in the retention file", policy)),
+            },
+            JobAction::ContainerRestart { container } => outcome(
+                self.docker.restart_container(container).await
+                    .map(|_| format!("Container {} restarted", container)),
+            ),
+            JobAction::Command { command } => match self.commands.iter().find(|c| &c.name == command) {
+                Some(command) => run_command(command).await,
+                None => ("failed", format!("Command '{}' is not defined in the job commands file", command)),
+            },
+            JobAction::SecurityAudit => {
+                let lang = Lang::from_headers(&i18n::HeaderMap::new());
+                let checks = self.auditor.run_audit(&lang).await;
+                let output = checks.iter()
+                    .map(|c| format!("[{}] {}: {}", c.status.as_str(), c.name, c.message))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let status = if checks.iter().any(|c| c.status == CheckStatus::Fail) { "failed" } else { "success" };
+                (status, output)
+            }
+        }
+    }
+
+    /// Checks the enabled jobs once a minute, right after the minute starts.
+    pub async fn run_loop(self: Arc<Self>) {
+        tracing::info!("Starting job scheduler...");
+        // The current minute may have run before a restart already
+        let mut last = current_minute();
+        loop {
+            let now = Local::now();
+            let into_minute = Duration::from_secs(now.second() as u64)
+                + Duration::from_nanos(now.nanosecond().min(999_999_999) as u64);
+            tokio::time::sleep(Duration::from_secs(60).saturating_sub(into_minute)).await;
+
+            let minute = current_minute();
+            if minute == last {
+                continue;
+            }
+            last = minute;
+            self.tick(minute).await;
+        }
+    }
+
+    async fn tick(self: &Arc<Self>, minute: NaiveDateTime) {
+        let jobs = match self.list_jobs().await {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::error!("Failed to load jobs: {}", e);
+                return;
+            }
+        };
+
+        for job in jobs {
+            let due = job.enabled && CronSchedule::parse(&job.schedule).is_ok_and(|s| s.matches(&minute));
+            if !due {
+                continue;
+            }
+            let name = job.name.clone();
+            match self.start(job, "schedule").await {
+                Ok(_) => {}
+                Err(JobError::Running) => tracing::warn!("Skipping job '{}': the previous run is still going", name),
+                Err(e) => tracing::error!("Failed to start job '{}': {}", name, e),
+            }
+        }
+    }
+}
+
+fn job_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Job, sqlx::Error> {
+    let schedule: String = row.get("schedule");
+    let enabled: bool = row.get("enabled");
+    let action: String = row.get("action");
+    let next_run = if enabled {
+        CronSchedule::parse(&schedule).ok()
+            .and_then(|s| s.next_after(&Local::now()))
+            .map(|t| t.timestamp())
+    } else {
+        None
+    };
+
+    Ok(Job {
+        id: row.get("id"),
+        name: row.get("name"),
+        action: serde_json::from_str(&action).map_err(|e| sqlx::Error::Decode(e.into()))?,
+        schedule,
+        enabled,
+        notify_on_failure: row.get("notify_on_failure"),
+        created_at: row.get("created_at"),
+        next_run,
+        last_status: row.get("last_status"),
+        last_run_at: row.get("last_run_at"),
+    })
+}
+
+fn current_minute() -> NaiveDateTime {
+    let now = Local::now().naive_local();
+    now.date().and_hms_opt(now.hour(), now.minute(), 0).unwrap_or(now)
+}
+
+fn outcome(result: Result<String, String>) -> (&'static str, String) {
+    match result {
+        Ok(output) => ("success", output),
+        Err(e) => ("failed", e),
+    }
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() > MAX_OUTPUT {
+        let mut cut = MAX_OUTPUT;
+        while !output.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        output.truncate(cut);
+        output.push_str("\n... output truncated");
+    }
+    output
+}
+
+/// Reads the whole stream but keeps only the first `MAX_OUTPUT` bytes, so a chatty
+/// command neither blocks on a full pipe nor fills memory.
+async fn read_capped(mut reader: impl tokio::io::AsyncRead + Unpin) -> Vec<u8> {
+    use tokio::io::AsyncReadExt;
+    let mut kept = Vec::new();
+    let mut chunk = [0u8; 8192];
+    while let Ok(n) = reader.read(&mut chunk).await {
+        if n == 0 {
+            break;
+        }
+        let room = MAX_OUTPUT.saturating_sub(kept.len());
+        kept.extend_from_slice(&chunk[..n.min(room)]);
+    }
+    kept
+}
+
+async fn run_command(command: &JobCommand) -> (&'static str, String) {
+    use std::process::Stdio;
+
+    let mut cmd = tokio::process::Command::new(&command.command[0]);
+    cmd.args(&command.command[1..])
+        .envs(&command.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Own process group, so a timeout also stops whatever the command started
+        .process_group(0)
+        .kill_on_drop(true);
+    if let Some(dir) = &command.working_dir {
+        cmd.current_dir(dir);
+    }
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return ("failed", format!("Failed to start {}: {}", command.command[0], e)),
+    };
+    let pgid = child.id().map(|id| id as i32);
+    let stdout = child.stdout.take().expect("Failed to open stdout");
+    let stderr = child.stderr.take().expect("Failed to open stderr");
+
+    let output = tokio::spawn(async move {
+        let (stdout, stderr) = tokio::join!(read_capped(stdout), read_capped(stderr));
+        let mut output = String::from_utf8_lossy(&stdout).to_string();
+        if !stderr.is_empty() {
+            if !output.is_empty() && !output.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str(&String::from_utf8_lossy(&stderr));
+        }
+        output
+    });
+
+    let timeout = Duration::from_secs(command.timeout_secs);
+    let waited = tokio::time::timeout(timeout, child.wait()).await;
+    if waited.is_err()
+        && let Some(pgid) = pgid
+    {
+        kill_process_group(pgid, &mut child).await;
+    }
+    // Pipes close once the process group is gone; a background child holding them open is not waited for
+    let mut output = tokio::time::timeout(Duration::from_secs(5), output).await
+        .ok()
+        .and_then(|r| r.ok())
+        .unwrap_or_default();
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+
+    match waited {
+        Ok(Ok(status)) if status.success() => ("success", output.trim_end().to_string()),
+        Ok(Ok(status)) => ("failed", format!("{}Command failed: {}", output, status)),
+        Ok(Err(e)) => ("failed", format!("{}Failed to wait for the command: {}", output, e)),
+        Err(_) => ("timeout", format!("{}Timed out after {}s", output, command.timeout_secs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(expr: &str, after: &str) -> Option<String> {
+        CronSchedule::parse(expr).unwrap().next_after(&at(after)).map(|t| t.to_rfc3339())
+    }
+
+    #[test]
+    fn test_parse_cron_fields() {
+        let s = CronSchedule::parse("*/15 9-17 * * mon-fri").unwrap();
+        let monday_noon = at("2024-09-02T12:30:00Z").naive_utc();
+        let monday_late = at("2024-09-02T18:00:00Z").naive_utc();
+        let sunday_noon = at("2024-09-01T12:30:00Z").naive_utc();
+        assert!(s.matches(&monday_noon));
+        assert!(!s.matches(&monday_late));
+        assert!(!s.matches(&sunday_noon));
+
+        assert_eq!(CronSchedule::parse("@daily").unwrap(), CronSchedule::parse("0 0 * * *").unwrap());
+        // 7 and SUN are both Sunday
+        assert_eq!(CronSchedule::parse("0 0 * * 7").unwrap(), CronSchedule::parse("0 0 * * SUN").unwrap());
+        assert_eq!(CronSchedule::parse("5/20 * * * *").unwrap(), CronSchedule::parse("5,25,45 * * * *").unwrap());
+
+        for bad in ["60 * * * *", "* * *", "*/0 * * * *", "x * * * *", "* * 0 * *", "* 5-3 * * *", "* * * * * *"] {
+            assert!(CronSchedule::parse(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_next_run() {
+        assert_eq!(next("30 2 * * *", "2024-01-01T03:00:00Z").unwrap(), "2024-01-02T02:30:00+00:00");
+        assert_eq!(next("0 * * * *", "2024-01-01T03:00:00Z").unwrap(), "2024-01-01T04:00:00+00:00");
+        assert_eq!(next("0 0 1 1 *", "2024-12-31T23:59:30Z").unwrap(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(next("0 0 29 2 *", "2025-03-01T00:00:00Z").unwrap(), "2028-02-29T00:00:00+00:00");
+        // Both day fields restricted: the 13th or any Friday
+        assert_eq!(next("0 0 13 * 5", "2024-09-01T00:00:00Z").unwrap(), "2024-09-06T00:00:00+00:00");
+        assert_eq!(next("0 0 30 2 *", "2024-01-01T00:00:00Z"), None);
+    }
+
+    async fn scheduler(commands: Vec<JobCommand>) -> Arc<Scheduler> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let scheduler = Scheduler::new(
+            pool.clone(),
+            Arc::new(DockerService::new()),
+            Arc::new(NotificationService::new()),
+            Arc::new(CleanupHistory::new(pool)),
+            Arc::new(RetentionConfig::default()),
+            Arc::new(SecurityAuditor::default()),
+            commands,
+        );
+        scheduler.migrate().await.unwrap();
+        Arc::new(scheduler)
+    }
+
+    fn command(name: &str, argv: &[&str], timeout_secs: u64) -> JobCommand {
+        JobCommand {
+            name: name.to_string(),
+            command: argv.iter().map(|s| s.to_string()).collect(),
+            working_dir: None,
+            env: HashMap::new(),
+            timeout_secs,
+        }
+    }
+
+    fn input(name: &str, action: JobAction) -> JobInput {
+        JobInput {
+            name: name.to_string(),
+            schedule: "0 3 * * *".to_string(),
+            action,
+            enabled: true,
+            notify_on_failure: false,
+        }
+    }
+
+    async fn wait_for_run(scheduler: &Scheduler, job_id: i64) -> JobRun {
+        for _ in 0..500 {
+            let runs = scheduler.get_runs(job_id, 1).await.unwrap();
+            let idle = !scheduler.running.lock().unwrap().contains(&job_id);
+            if let Some(run) = runs.into_iter().next().filter(|r| idle && r.status != "running") {
+                return run;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} did not finish", job_id);
+    }
+
+    #[tokio::test]
+    async fn test_job_crud_and_validation() {
+        let s = scheduler(vec![command("hello", &["echo", "hello"], 10)]).await;
+
+        let job = s.create_job(input("nightly", JobAction::DiskClean { target: "logs".to_string() })).await.unwrap();
+        assert!(job.next_run.is_some());
+        assert_eq!(job.last_status, None);
+
+        let mut changed = input("nightly docker", JobAction::Command { command: "hello".to_string() });
+        changed.enabled = false;
+        let job = s.update_job(job.id, changed).await.unwrap();
+        assert_eq!(job.name, "nightly docker");
+        assert_eq!(job.action, JobAction::Command { command: "hello".to_string() });
+        assert_eq!(job.next_run, None);
+        assert_eq!(s.list_jobs().await.unwrap().len(), 1);
+
+        let invalid = [
+            JobInput { schedule: "every day".to_string(), ..input("x", JobAction::SecurityAudit) },
+            input(" ", JobAction::SecurityAudit),
+            input("x", JobAction::DiskClean { target: "/".to_string() }),
+            input("x", JobAction::LogRetention { policy: "nginx".to_string() }),
+            input("x", JobAction::Command { command: "rm-rf".to_string() }),
+        ];
+        for i in invalid {
+            assert!(matches!(s.create_job(i).await, Err(JobError::Invalid(_))));
+        }
+
+        s.delete_job(job.id).await.unwrap();
+        assert!(matches!(s.delete_job(job.id).await, Err(JobError::NotFound)));
+        assert!(matches!(s.run_now(job.id).await, Err(JobError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_command_runs_are_recorded() {
+        let s = scheduler(vec![
+            command("hello", &["echo", "hello"], 10),
+            command("broken", &["sh", "-c", "echo oops >&2; exit 3"], 10),
+            command("slow", &["sleep", "30"], 1),
+        ]).await;
+
+        let hello = s.create_job(input("hello", JobAction::Command { command: "hello".to_string() })).await.unwrap();
+        s.run_now(hello.id).await.unwrap();
+        let run = wait_for_run(&s, hello.id).await;
+        assert_eq!((run.status.as_str(), run.output.as_str(), run.triggered_by.as_str()), ("success", "hello", "manual"));
+        assert_eq!(s.get_job(hello.id).await.unwrap().unwrap().last_status.as_deref(), Some("success"));
+
+        let broken = s.create_job(input("broken", JobAction::Command { command: "broken".to_string() })).await.unwrap();
+        s.run_now(broken.id).await.unwrap();
+        let run = wait_for_run(&s, broken.id).await;
+        assert_eq!(run.status, "failed");
+        assert!(run.output.starts_with("oops\nCommand failed"), "{}", run.output);
+
+        let slow = s.create_job(input("slow", JobAction::Command { command: "slow".to_string() })).await.unwrap();
+        s.run_now(slow.id).await.unwrap();
+        // One run per job at a time
+        assert!(matches!(s.run_now(slow.id).await, Err(JobError::Running)));
+        let started = Instant::now();
+        let run = wait_for_run(&s, slow.id).await;
+        assert_eq!(run.status, "timeout");
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert!(run.duration_ms.unwrap() >= 1000);
+    }
+
+    #[tokio::test]
+    async fn test_tick_runs_due_jobs_only() {
+        let s = scheduler(vec![command("hello", &["echo", "hello"], 10)]).await;
+        let due = s.create_job(input("due", JobAction::Command { command: "hello".to_string() })).await.unwrap();
+        let later = s.create_job(JobInput {
+            schedule: "0 4 * * *".to_string(),
+            ..input("later", JobAction::Command { command: "hello".to_string() })
+        }).await.unwrap();
+
+        s.tick(at("2024-01-01T03:00:00Z").naive_utc()).await;
+        assert_eq!(wait_for_run(&s, due.id).await.triggered_by, "schedule");
+        assert!(s.get_runs(later.id, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_old_runs_are_pruned() {
+        let s = scheduler(vec![command("hello", &["true"], 10)]).await;
+        let job = s.create_job(input("hello", JobAction::Command { command: "hello".to_string() })).await.unwrap();
+        for _ in 0..RUNS_KEPT + 3 {
+            s.run_now(job.id).await.unwrap();
+            wait_for_run(&s, job.id).await;
+        }
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM job_runs").fetch_one(&s.db).await.unwrap();
+        assert_eq!(count, RUNS_KEPT);
+    }
+}
@@ -1,39 +1,353 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::process::Command;
 use std::os::unix::fs::PermissionsExt;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use crate::accounts::AccountsReport;
+use crate::accounts_audit::accounts_checks;
 use crate::notifications::NotificationService;
 use crate::i18n::Lang;
-use std::time::Duration;
+use crate::file_integrity::{FileIntegrity, FileIntegrityCheck};
+use crate::firewall;
+use crate::firewall_audit::firewall_checks;
+use crate::os_updates::UpdateStatus;
+use crate::port_allowlist::{ListeningPortsCheck, PortAllowlist};
+use crate::security_checks::{CustomCheck, SecurityChecksConfig};
+use crate::ssh_audit::ssh_checks;
+use crate::sshd_config::{self, SSHD_CONFIG};
+use crate::updates_audit::updates_checks;
+use std::time::{Duration, Instant};
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
+}
+
+/// How much a failing check weakens the server.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// Result of one audit check. `id`, `status`, `severity` and `categories` are
+/// stable and meant for machines; `name`, `message` and `remediation` are
+/// translated for display only.
+#[derive(Serialize, Clone, Debug)]
 pub struct SecurityCheck {
+    pub id: String,
+    pub status: CheckStatus,
+    pub severity: Severity,
+    pub categories: Vec<String>,
     pub name: String,
-    pub status: String, // "PASS", "FAIL", "WARN"
     pub message: String,
+    /// How to fix the finding; omitted when the check passes.
+    pub remediation: Option<String>,
+    /// What the verdict is based on, e.g. the matching config line.
+    pub evidence: Option<String>,
+}
+
+impl SecurityCheck {
+    pub fn with_evidence(mut self, evidence: impl Into<String>) -> Self {
+        self.evidence = Some(evidence.into());
+        self
+    }
+}
+
+pub const CHECK_SSH_ROOT_LOGIN: &str = "ssh.root_login";
+pub const CHECK_SSH_PASSWORD_AUTH: &str = "ssh.password_auth";
+pub const CHECK_FIREWALL: &str = "firewall.active";
+pub const CHECK_FIREWALL_DOCKER_BYPASS: &str = "firewall.docker_bypass";
+pub const CHECK_DOCKER_SOCKET: &str = "docker.socket_permissions";
+pub const CHECK_DISK_ENCRYPTION: &str = "disk.encryption";
+pub const CHECK_FAIL2BAN: &str = "intrusion.fail2ban";
+pub const CHECK_LISTENING_PORTS: &str = "network.listening_ports";
+pub const CHECK_UPDATES_SECURITY: &str = "updates.security";
+pub const CHECK_UPDATES_AUTO: &str = "updates.automatic";
+pub const CHECK_REBOOT_REQUIRED: &str = "updates.reboot_required";
+pub const CHECK_FILE_INTEGRITY: &str = "integrity.files";
+pub const CHECK_USERS_UID0: &str = "users.uid0";
+pub const CHECK_USERS_EMPTY_PASSWORDS: &str = "users.empty_passwords";
+pub const CHECK_USERS_LOGIN_SHELLS: &str = "users.unlocked_shells";
+pub const CHECK_USERS_PRIVILEGED_GROUPS: &str = "users.privileged_groups";
+pub const CHECK_USERS_NOPASSWD: &str = "users.sudo_nopasswd";
+pub const CHECK_USERS_PATH_WRITABLE: &str = "users.path_writable";
+
+/// Former ids still accepted in `disabled`, with the id that replaced them.
+const RENAMED_CHECKS: &[(&str, &str)] = &[("firewall.ufw", CHECK_FIREWALL)];
+
+fn current_check_id(id: &str) -> &str {
+    RENAMED_CHECKS.iter().find(|(old, _)| *old == id).map_or(id, |(_, new)| new)
 }
 
-pub struct SecurityAuditor;
+/// Static description of a built-in check. Texts live in i18n under
+/// `audit.<key>.*`: `name`, one key per outcome, and `fix` for remediation.
+pub(crate) struct CheckSpec {
+    pub(crate) id: &'static str,
+    pub(crate) key: &'static str,
+    pub(crate) severity: Severity,
+    pub(crate) categories: &'static [&'static str],
+}
+
+impl CheckSpec {
+    pub(crate) fn result(&self, status: CheckStatus, message: String, lang: &Lang) -> SecurityCheck {
+        let remediation = (status != CheckStatus::Pass)
+            .then(|| crate::i18n::t(&format!("audit.{}.fix", self.key), lang));
+        SecurityCheck {
+            id: self.id.to_string(),
+            status,
+            severity: self.severity,
+            categories: self.categories.iter().map(|c| c.to_string()).collect(),
+            name: crate::i18n::t(&format!("audit.{}.name", self.key), lang),
+            message,
+            remediation,
+            evidence: None,
+        }
+    }
+
+    pub(crate) fn pass(&self, message_key: &str, lang: &Lang) -> SecurityCheck {
+        self.result(CheckStatus::Pass, crate::i18n::t(message_key, lang), lang)
+    }
+
+    pub(crate) fn warn(&self, message_key: &str, lang: &Lang) -> SecurityCheck {
+        self.result(CheckStatus::Warn, crate::i18n::t(message_key, lang), lang)
+    }
+
+    pub(crate) fn fail(&self, message_key: &str, lang: &Lang) -> SecurityCheck {
+        self.result(CheckStatus::Fail, crate::i18n::t(message_key, lang), lang)
+    }
+}
+
+const DOCKER_SOCKET: CheckSpec = CheckSpec {
+    id: CHECK_DOCKER_SOCKET,
+    key: "docker_sock",
+    severity: Severity::Critical,
+    categories: &["docker", "permissions"],
+};
+
+const DISK_ENCRYPTION: CheckSpec = CheckSpec {
+    id: CHECK_DISK_ENCRYPTION,
+    key: "disk_enc",
+    severity: Severity::Info,
+    categories: &["disk", "encryption"],
+};
+
+const FAIL2BAN: CheckSpec = CheckSpec {
+    id: CHECK_FAIL2BAN,
+    key: "fail2ban",
+    severity: Severity::Medium,
+    categories: &["intrusion", "ssh"],
+};
+
+/// One check an audit can run. Implementations may block (read files, run
+/// commands); the auditor calls them on a blocking thread.
+pub trait SecurityCheckProvider: Send + Sync {
+    /// Stable id, also used in the config to disable the check.
+    fn id(&self) -> &str;
+    fn run(&self, lang: &Lang) -> SecurityCheck;
+}
+
+/// One inspection of the system (firewall rules, sshd config, ...) kept for `ttl`, so
+/// every check of an audit and the trackers reading the same data share it.
+/// Failed loads are not cached.
+pub struct CachedSource<T> {
+    ttl: Duration,
+    load: Box<dyn Fn() -> Result<T, String> + Send + Sync>,
+    cached: Mutex<Option<(Instant, Arc<T>)>>,
+}
+
+impl<T> CachedSource<T> {
+    pub fn new(ttl: Duration, load: impl Fn() -> Result<T, String> + Send + Sync + 'static) -> Self {
+        Self { ttl, load: Box::new(load), cached: Mutex::new(None) }
+    }
+
+    pub fn get(&self) -> Result<Arc<T>, String> {
+        // Held while loading, so concurrent checks wait for one inspection instead of starting their own
+        let mut cached = self.cached.lock().unwrap();
+        if let Some((at, value)) = cached.as_ref()
+            && at.elapsed() < self.ttl
+        {
+            return Ok(value.clone());
+        }
+        let value = Arc::new((self.load)()?);
+        *cached = Some((Instant::now(), value.clone()));
+        Ok(value)
+    }
+}
+
+pub(crate) type SourcedCheckFn<T> = fn(&T, &Lang) -> SecurityCheck;
+
+/// A check that reads a `CachedSource`. Modules declare theirs as `(spec, fn)` pairs.
+pub struct SourcedCheck<T> {
+    spec: &'static CheckSpec,
+    source: Arc<CachedSource<T>>,
+    run: SourcedCheckFn<T>,
+}
+
+impl<T> SourcedCheck<T> {
+    /// One check per `(spec, fn)`, all reading `source`.
+    pub(crate) fn all(
+        source: Arc<CachedSource<T>>,
+        checks: impl IntoIterator<Item = (&'static CheckSpec, SourcedCheckFn<T>)>,
+    ) -> Vec<Self> {
+        checks.into_iter()
+            .map(|(spec, run)| SourcedCheck { spec, source: source.clone(), run })
+            .collect()
+    }
+}
+
+impl<T: Send + Sync> SecurityCheckProvider for SourcedCheck<T> {
+    fn id(&self) -> &str {
+        self.spec.id
+    }
+
+    fn run(&self, lang: &Lang) -> SecurityCheck {
+        match self.source.get() {
+            Ok(value) => (self.run)(&value, lang),
+            Err(e) => self.spec.warn("audit.source.warn", lang).with_evidence(e),
+        }
+    }
+}
+
+/// A check shipped with the agent.
+struct BuiltinCheck {
+    id: &'static str,
+    run: fn(&Lang) -> SecurityCheck,
+}
+
+impl SecurityCheckProvider for BuiltinCheck {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn run(&self, lang: &Lang) -> SecurityCheck {
+        (self.run)(lang)
+    }
+}
+
+/// State the built-in checks share with the rest of the agent.
+#[derive(Clone)]
+pub struct CheckSources {
+    /// Allowlist the listening ports check compares against
+    pub ports: Arc<PortAllowlist>,
+    /// Also read by the pending updates tracker
+    pub updates: Arc<CachedSource<UpdateStatus>>,
+    /// Baseline of the watched files
+    pub integrity: Arc<FileIntegrity>,
+    /// Also read by the privileged users tracker
+    pub accounts: Arc<CachedSource<AccountsReport>>,
+}
+
+fn builtin_checks(sources: &CheckSources) -> Vec<Arc<dyn SecurityCheckProvider>> {
+    let sshd = Arc::new(sshd_config::source(SSHD_CONFIG, true));
+    let mut checks: Vec<Arc<dyn SecurityCheckProvider>> = Vec::new();
+    for check in ssh_checks(sshd) {
+        checks.push(Arc::new(check));
+    }
+    for check in firewall_checks(Arc::new(firewall::source())) {
+        checks.push(Arc::new(check));
+    }
+    for (id, run) in [
+        (CHECK_DOCKER_SOCKET, SecurityAuditor::check_docker_socket as fn(&Lang) -> SecurityCheck),
+        (CHECK_DISK_ENCRYPTION, SecurityAuditor::check_disk_encryption),
+        (CHECK_FAIL2BAN, SecurityAuditor::check_fail2ban_status),
+    ] {
+        checks.push(Arc::new(BuiltinCheck { id, run }));
+    }
+    checks.push(Arc::new(ListeningPortsCheck::new(sources.ports.clone())));
+    for check in updates_checks(sources.updates.clone()) {
+        checks.push(Arc::new(check));
+    }
+    checks.push(Arc::new(FileIntegrityCheck::new(sources.integrity.clone())));
+    for check in accounts_checks(sources.accounts.clone()) {
+        checks.push(Arc::new(check));
+    }
+    checks
+}
+
+/// Share of passed checks, 0-100, over whatever checks ran.
+pub fn hardening_score(checks: &[SecurityCheck]) -> u32 {
+    if checks.is_empty() {
+        return 0;
+    }
+    let passed = checks.iter().filter(|c| c.status == CheckStatus::Pass).count();
+    (passed * 100 / checks.len()) as u32
+}
+
+/// Registry of the checks an audit runs, in registration order.
+#[derive(Clone, Default)]
+pub struct SecurityAuditor {
+    checks: Vec<Arc<dyn SecurityCheckProvider>>,
+}
 
 impl SecurityAuditor {
-    pub async fn run_audit(lang: &Lang) -> Vec<SecurityCheck> {
-        let mut checks = Vec::new();
+    /// Built-in checks plus the custom ones from the config, minus the disabled ids.
+    pub fn from_config(config: &SecurityChecksConfig, sources: &CheckSources) -> Result<Self, String> {
+        let mut candidates = builtin_checks(sources);
+        for custom in &config.custom {
+            candidates.push(Arc::new(CustomCheck::new(custom.clone())?));
+        }
+
+        let disabled: Vec<&str> = config.disabled.iter().map(|id| current_check_id(id)).collect();
+        for id in &disabled {
+            if !candidates.iter().any(|c| c.id() == *id) {
+                return Err(format!("Cannot disable unknown security check '{}'", id));
+            }
+        }
 
-        checks.push(Self::check_ssh_root_login(lang));
-        checks.push(Self::check_ufw_status(lang));
-        checks.push(Self::check_docker_socket(lang));
-        checks.push(Self::check_disk_encryption(lang));
-        checks.push(Self::check_fail2ban_status(lang));
-        checks.push(Self::check_ssh_password_auth(lang));
-        checks.push(Self::check_listening_ports(lang));
+        let mut auditor = Self::default();
+        for check in candidates {
+            if !disabled.contains(&check.id()) {
+                auditor.register(check)?;
+            }
+        }
+        Ok(auditor)
+    }
 
-        checks
+    pub fn register(&mut self, check: Arc<dyn SecurityCheckProvider>) -> Result<(), String> {
+        if self.checks.iter().any(|c| c.id() == check.id()) {
+            return Err(format!("Security check '{}' is registered twice", check.id()));
+        }
+        self.checks.push(check);
+        Ok(())
     }
 
-    fn find_system_binary(name: &str) -> Option<PathBuf> {
+    pub fn ids(&self) -> Vec<&str> {
+        self.checks.iter().map(|c| c.id()).collect()
+    }
+
+    pub async fn run_audit(&self, lang: &Lang) -> Vec<SecurityCheck> {
+        let checks = self.checks.clone();
+        let lang = *lang;
+        // Checks read files and run commands; keep that off the async workers
+        tokio::task::spawn_blocking(move || checks.iter().map(|c| c.run(&lang)).collect())
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Security audit failed: {}", e);
+                Vec::new()
+            })
+    }
+
+    pub(crate) fn find_system_binary(name: &str) -> Option<PathBuf> {
         let standard_paths = [
             format!("/usr/sbin/{}", name),
             format!("/usr/bin/{}", name),
@@ -43,14 +357,14 @@ impl SecurityAuditor {
         
         for path_str in &standard_paths {
             let path = Path::new(path_str);
-            if path.exists() && path.is_file() {
-                if let Ok(metadata) = fs::metadata(path) {
-                    #[cfg(unix)]
-                    {
-                        if metadata.permissions().mode() & 0o111 != 0 {
-                            tracing::debug!("Found {} at {}", name, path_str);
-                            return Some(path.to_path_buf());
-                        }
+            if path.exists() && path.is_file()
+                && let Ok(metadata) = fs::metadata(path)
+            {
+                #[cfg(unix)]
+                {
+                    if metadata.permissions().mode() & 0o111 != 0 {
+                        tracing::debug!("Found {} at {}", name, path_str);
+                        return Some(path.to_path_buf());
                     }
                 }
             }
@@ -60,102 +374,18 @@ impl SecurityAuditor {
         None
     }
 
-    fn check_ssh_root_login(lang: &Lang) -> SecurityCheck {
-        let path = "/etc/ssh/sshd_config";
-        if let Ok(content) = fs::read_to_string(path) {
-            let permit_root = content.lines()
-                .find(|l| l.trim().starts_with("PermitRootLogin"));
-            
-            if let Some(line) = permit_root {
-                if line.contains("yes") {
-                    return SecurityCheck {
-                        name: crate::i18n::t("audit.ssh_root.name", lang),
-                        status: "FAIL".to_string(),
-                        message: crate::i18n::t("audit.ssh_root.fail", lang),
-                    };
-                }
-            }
-            SecurityCheck {
-                name: crate::i18n::t("audit.ssh_root.name", lang),
-                status: "PASS".to_string(),
-                message: crate::i18n::t("audit.ssh_root.pass", lang),
-            }
-        } else {
-            SecurityCheck {
-                name: crate::i18n::t("audit.ssh_root.name", lang),
-                status: "WARN".to_string(),
-                message: crate::i18n::t("audit.ssh_config.warn", lang),
-            }
-        }
-    }
-
-    fn check_ufw_status(lang: &Lang) -> SecurityCheck {
-        // Try to find ufw binary
-        let ufw_path = Self::find_system_binary("ufw")
-            .unwrap_or_else(|| PathBuf::from("ufw")); // Fallback to PATH
-        
-        match Command::new(&ufw_path).arg("status").output() {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                
-                if output.status.success() {
-                    if stdout.contains("Status: active") {
-                        SecurityCheck {
-                            name: crate::i18n::t("audit.ufw.name", lang),
-                            status: "PASS".to_string(),
-                            message: crate::i18n::t("audit.ufw.pass", lang),
-                        }
-                    } else {
-                        SecurityCheck {
-                            name: crate::i18n::t("audit.ufw.name", lang),
-                            status: "FAIL".to_string(),
-                            message: crate::i18n::t("audit.ufw.fail", lang),
-                        }
-                    }
-                } else {
-                    // UFW found but command failed (e.g., permission denied)
-                    tracing::warn!("UFW command failed: {}", String::from_utf8_lossy(&output.stderr));
-                    SecurityCheck {
-                        name: crate::i18n::t("audit.ufw.name", lang),
-                        status: "WARN".to_string(),
-                        message: crate::i18n::t("audit.ufw.error", lang),
-                    }
-                }
-            }
-            Err(e) => {
-                tracing::warn!("Failed to execute UFW: {}", e);
-                SecurityCheck {
-                    name: crate::i18n::t("audit.ufw.name", lang),
-                    status: "WARN".to_string(),
-                    message: crate::i18n::t("audit.ufw.warn", lang),
-                }
-            }
-        }
-    }
-
     fn check_docker_socket(lang: &Lang) -> SecurityCheck {
         let path = "/var/run/docker.sock";
         if let Ok(metadata) = fs::metadata(path) {
             let mode = metadata.permissions().mode();
+            let evidence = format!("{} mode {:o}", path, mode & 0o7777);
             // Check if world writable (o+w is the last bit of the last octal: 0o002)
             if mode & 0o002 != 0 {
-                 return SecurityCheck {
-                    name: crate::i18n::t("audit.docker_sock.name", lang),
-                    status: "FAIL".to_string(),
-                    message: crate::i18n::t("audit.docker_sock.fail", lang),
-                };
-            }
-            SecurityCheck {
-                name: crate::i18n::t("audit.docker_sock.name", lang),
-                status: "PASS".to_string(),
-                message: crate::i18n::t("audit.docker_sock.pass", lang),
+                return DOCKER_SOCKET.fail("audit.docker_sock.fail", lang).with_evidence(evidence);
             }
+            DOCKER_SOCKET.pass("audit.docker_sock.pass", lang).with_evidence(evidence)
         } else {
-            SecurityCheck {
-                name: crate::i18n::t("audit.docker_sock.name", lang),
-                status: "WARN".to_string(),
-                message: crate::i18n::t("audit.docker_sock.warn", lang),
-            }
+            DOCKER_SOCKET.warn("audit.docker_sock.warn", lang)
         }
     }
     
@@ -164,27 +394,15 @@ impl SecurityAuditor {
         let lsblk_path = Self::find_system_binary("lsblk")
             .unwrap_or_else(|| PathBuf::from("lsblk"));
             
-        if let Ok(output) = Command::new(&lsblk_path).args(&["-o", "TYPE"]).output() {
+        if let Ok(output) = Command::new(&lsblk_path).args(["-o", "TYPE"]).output() {
             let stdout = String::from_utf8_lossy(&output.stdout);
             if stdout.contains("crypt") {
-                SecurityCheck {
-                    name: crate::i18n::t("audit.disk_enc.name", lang),
-                    status: "PASS".to_string(),
-                    message: crate::i18n::t("audit.disk_enc.pass", lang),
-                }
+                DISK_ENCRYPTION.pass("audit.disk_enc.pass", lang)
             } else {
-                SecurityCheck {
-                    name: crate::i18n::t("audit.disk_enc.name", lang),
-                    status: "WARN".to_string(),
-                    message: crate::i18n::t("audit.disk_enc.warn", lang),
-                }
+                DISK_ENCRYPTION.warn("audit.disk_enc.warn", lang)
             }
         } else {
-            SecurityCheck {
-                name: crate::i18n::t("audit.disk_enc.name", lang),
-                status: "WARN".to_string(),
-                message: crate::i18n::t("audit.disk_enc.error", lang),
-            }
+            DISK_ENCRYPTION.warn("audit.disk_enc.error", lang)
         }
     }
 
@@ -193,128 +411,49 @@ impl SecurityAuditor {
         let systemctl_path = Self::find_system_binary("systemctl")
             .unwrap_or_else(|| PathBuf::from("systemctl"));
         
-        match Command::new(&systemctl_path).args(&["is-active", "fail2ban"]).output() {
+        match Command::new(&systemctl_path).args(["is-active", "fail2ban"]).output() {
             Ok(output) => {
-                if output.status.success() {
-                    SecurityCheck {
-                        name: crate::i18n::t("audit.fail2ban.name", lang),
-                        status: "PASS".to_string(),
-                        message: crate::i18n::t("audit.fail2ban.pass", lang),
-                    }
+                let state = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                let mut check = if output.status.success() {
+                    FAIL2BAN.pass("audit.fail2ban.pass", lang)
                 } else {
-                    SecurityCheck {
-                        name: crate::i18n::t("audit.fail2ban.name", lang),
-                        status: "WARN".to_string(),
-                        message: crate::i18n::t("audit.fail2ban.warn", lang),
-                    }
-                }
-            }
-            Err(_) => {
-                SecurityCheck {
-                    name: crate::i18n::t("audit.fail2ban.name", lang),
-                    status: "WARN".to_string(),
-                    message: crate::i18n::t("audit.fail2ban.missing", lang),
-                }
-            }
-        }
-    }
-
-    /// Проверяет, отключена ли аутентификация по паролю в SSH.
-    fn check_ssh_password_auth(lang: &Lang) -> SecurityCheck {
-        if let Ok(content) = fs::read_to_string("/etc/ssh/sshd_config") {
-            let password_auth = content.lines()
-                .find(|l| {
-                    let trim = l.trim();
-                    trim.starts_with("PasswordAuthentication") && !trim.starts_with("#")
-                });
-            
-            if let Some(line) = password_auth {
-                if line.contains("no") {
-                    return SecurityCheck {
-                        name: crate::i18n::t("audit.ssh_passwd.name", lang),
-                        status: "PASS".to_string(),
-                        message: crate::i18n::t("audit.ssh_passwd.pass", lang),
-                    };
+                    FAIL2BAN.warn("audit.fail2ban.warn", lang)
+                };
+                if !state.is_empty() {
+                    check = check.with_evidence(format!("systemctl is-active fail2ban: {}", state));
                 }
+                check
             }
-            SecurityCheck {
-                name: crate::i18n::t("audit.ssh_passwd.name", lang),
-                status: "FAIL".to_string(),
-                message: crate::i18n::t("audit.ssh_passwd.fail", lang),
-            }
-        } else {
-            SecurityCheck {
-                name: crate::i18n::t("audit.ssh_passwd.name", lang),
-                status: "WARN".to_string(),
-                message: crate::i18n::t("audit.ssh_config.warn", lang),
-            }
-        }
-    }
-
-    /// Сканирует открытые порты и ищет подозрительные (не входящие в белый список).
-    fn check_listening_ports(lang: &Lang) -> SecurityCheck {
-        let ss_path = Self::find_system_binary("ss")
-            .unwrap_or_else(|| PathBuf::from("ss"));
-        
-        if let Ok(output) = Command::new(&ss_path).args(&["-tulpn"]).output() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let nginx_port = std::env::var("DEPLOY_NGINX_PORT").unwrap_or_else(|_| "8090".to_string());
-            let app_port = std::env::var("APP_PORT").unwrap_or_else(|_| "3000".to_string());
-            let allowed_ports = vec!["22".to_string(), "80".to_string(), "443".to_string(), app_port, nginx_port];
-            
-            let mut suspicious = Vec::new();
-            for line in stdout.lines() {
-                if line.contains("LISTEN") {
-                    // Extract port using simple split
-                    if let Some(local_part) = line.split_whitespace().nth(4) {
-                        if let Some(port) = local_part.split(':').last() {
-                            if !allowed_ports.contains(&port.to_string()) {
-                                suspicious.push(port.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-            
-            suspicious.sort();
-            suspicious.dedup();
-
-            if suspicious.is_empty() {
-                SecurityCheck {
-                    name: crate::i18n::t("audit.ports.name", lang),
-                    status: "PASS".to_string(),
-                    message: crate::i18n::t("audit.ports.pass", lang),
-                }
-            } else {
-                SecurityCheck {
-                    name: crate::i18n::t("audit.ports.name", lang),
-                    status: "WARN".to_string(),
-                    message: format!("{}: {}", crate::i18n::t("audit.ports.warn", lang), suspicious.join(", ")),
-                }
-            }
-        } else {
-            SecurityCheck {
-                name: crate::i18n::t("audit.ports.name", lang),
-                status: "WARN".to_string(),
-                message: crate::i18n::t("audit.ports.error", lang),
-            }
+            Err(_) => FAIL2BAN.warn("audit.fail2ban.missing", lang),
         }
     }
 }
 
 pub struct SecurityMonitor {
     notifier: Arc<NotificationService>,
-    last_states: Mutex<HashMap<String, String>>,
+    auditor: Arc<SecurityAuditor>,
+    /// Last seen status per check id.
+    last_states: Mutex<HashMap<String, CheckStatus>>,
+    /// Watched files re-scanned each round
+    integrity: Option<Arc<FileIntegrity>>,
 }
 
 impl SecurityMonitor {
-    pub fn new(notifier: Arc<NotificationService>) -> Self {
+    pub fn new(notifier: Arc<NotificationService>, auditor: Arc<SecurityAuditor>) -> Self {
         Self {
             notifier,
+            auditor,
             last_states: Mutex::new(HashMap::new()),
+            integrity: None,
         }
     }
 
+    /// Also alerts when watched files change.
+    pub fn with_integrity(mut self, integrity: Arc<FileIntegrity>) -> Self {
+        self.integrity = Some(integrity);
+        self
+    }
+
     pub async fn run_loop(self: Arc<Self>) {
         tracing::info!("Starting Security Monitor Loop...");
         let mut interval = tokio::time::interval(Duration::from_secs(60)); 
@@ -328,41 +467,72 @@ impl SecurityMonitor {
     async fn check_once(&self) {
         // Background loop uses default language from env
         let default_lang = Lang::from_headers(&crate::i18n::HeaderMap::new());
-        let checks = SecurityAuditor::run_audit(&default_lang).await;
+        let checks = self.auditor.run_audit(&default_lang).await;
         
         // 1. Calculate alerts to send (synchronous part)
-        let mut alerts = Vec::new();
-        {
-            let mut states = self.last_states.lock().unwrap();
-            for check in &checks {
-                let old_status = states.get(&check.name).cloned().unwrap_or_else(|| "UNKNOWN".to_string());
-                
-                if check.status == "FAIL" && old_status != "FAIL" {
-                     alerts.push(format!("{}\n\n{}: {}\n{}: {}", 
-                        crate::i18n::t("security.detected", &default_lang),
-                        crate::i18n::t("security.check", &default_lang), check.name,
-                        crate::i18n::t("security.message", &default_lang), check.message));
-                } else if check.status == "PASS" && old_status == "FAIL" {
-                     alerts.push(format!("{}\n\n{}: {}", 
-                        crate::i18n::t("security.resolved", &default_lang),
-                        crate::i18n::t("security.check", &default_lang), check.name));
-                }
-                
-                states.insert(check.name.clone(), check.status.clone());
-            }
-        } // Drop mutex guard here
+        let alerts = self.transitions(&checks, &default_lang);
 
         // 2. Send alerts (async part)
         for num_alert in alerts {
             self.notifier.send_alert(&num_alert).await;
         }
+
+        // 3. Watched files; the audit above usually scanned them already
+        if let Some(integrity) = &self.integrity {
+            let scanner = integrity.clone();
+            let changes = tokio::task::spawn_blocking(move || scanner.changes()).await.unwrap_or_default();
+            let new = integrity.unreported(&changes);
+            if !new.is_empty() {
+                self.notifier.send_alert(&FileIntegrity::alert(&new, &default_lang)).await;
+            }
+        }
+    }
+
+    /// Records the new statuses and returns alerts for checks that started or
+    /// stopped failing. Keyed by check id, so the alert language does not matter.
+    fn transitions(&self, checks: &[SecurityCheck], lang: &Lang) -> Vec<String> {
+        let mut alerts = Vec::new();
+        let mut states = self.last_states.lock().unwrap();
+        for check in checks {
+            let old_status = states.insert(check.id.clone(), check.status);
+
+            if check.status == CheckStatus::Fail && old_status != Some(CheckStatus::Fail) {
+                let mut alert = format!("{}\n\n{}: {}\n{}: {}",
+                    crate::i18n::t("security.detected", lang),
+                    crate::i18n::t("security.check", lang), check.name,
+                    crate::i18n::t("security.message", lang), check.message);
+                if let Some(evidence) = &check.evidence {
+                    alert.push_str(&format!("\n{}: {}", crate::i18n::t("security.evidence", lang), evidence));
+                }
+                if let Some(remediation) = &check.remediation {
+                    alert.push_str(&format!("\n{}: {}", crate::i18n::t("security.remediation", lang), remediation));
+                }
+                alerts.push(alert);
+            } else if check.status == CheckStatus::Pass && old_status == Some(CheckStatus::Fail) {
+                alerts.push(format!("{}\n\n{}: {}",
+                    crate::i18n::t("security.resolved", lang),
+                    crate::i18n::t("security.check", lang), check.name));
+            }
+        }
+        alerts
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use crate::firewall_audit::FIREWALL;
+    use crate::ssh_audit::SSH_ROOT_LOGIN;
+
+    fn sources() -> CheckSources {
+        let db = sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        CheckSources {
+            ports: Arc::new(PortAllowlist::new(db.clone())),
+            updates: Arc::new(crate::os_updates::source()),
+            integrity: Arc::new(FileIntegrity::new(db, Vec::new())),
+            accounts: Arc::new(crate::accounts::source()),
+        }
+    }
 
     #[test]
     fn test_find_system_binary_existing() {
@@ -380,4 +550,107 @@ mod tests {
         let result = SecurityAuditor::find_system_binary("nonexistent_binary_xyz123");
         assert!(result.is_none(), "Nonexistent binary should not be found");
     }
+
+    #[test]
+    fn test_check_serialization() {
+        let check = SSH_ROOT_LOGIN
+            .fail("audit.ssh_root.fail", &Lang::EN)
+            .with_evidence("PermitRootLogin yes");
+        let json = serde_json::to_value(&check).unwrap();
+        assert_eq!(json["id"], "ssh.root_login");
+        assert_eq!(json["status"], "FAIL");
+        assert_eq!(json["severity"], "high");
+        assert_eq!(json["categories"], serde_json::json!(["ssh", "access"]));
+        assert_eq!(json["evidence"], "PermitRootLogin yes");
+        assert!(json["remediation"].as_str().unwrap().contains("PermitRootLogin no"));
+
+        let passed = SSH_ROOT_LOGIN.pass("audit.ssh_root.pass", &Lang::EN);
+        assert!(passed.remediation.is_none());
+    }
+
+    #[test]
+    fn test_monitor_keys_on_id() {
+        let monitor = SecurityMonitor::new(Arc::new(NotificationService::new()), Arc::new(SecurityAuditor::default()));
+
+        let failing = [FIREWALL.fail("audit.firewall.fail", &Lang::EN)];
+        let alerts = monitor.transitions(&failing, &Lang::EN);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].contains("ufw enable"));
+
+        // Same check in another language is not a new failure
+        let failing_ru = [FIREWALL.fail("audit.firewall.fail", &Lang::RU)];
+        assert!(monitor.transitions(&failing_ru, &Lang::RU).is_empty());
+
+        let resolved = [FIREWALL.result(CheckStatus::Pass, "UFW".into(), &Lang::RU)];
+        let alerts = monitor.transitions(&resolved, &Lang::EN);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].contains("Resolved"));
+    }
+
+    #[tokio::test]
+    async fn test_registry_from_config() {
+        let config: SecurityChecksConfig = serde_json::from_str(r#"{
+            "disabled": ["disk.encryption", "custom.off"],
+            "custom": [
+                {"id": "custom.true", "name": "True", "type": "command", "command": ["true"]},
+                {"id": "custom.off", "name": "Off", "type": "command", "command": ["false"]}
+            ]
+        }"#).unwrap();
+        let auditor = SecurityAuditor::from_config(&config, &sources()).unwrap();
+        let ids = auditor.ids();
+        assert_eq!(ids.len(), 28);
+        assert!(!ids.contains(&CHECK_DISK_ENCRYPTION));
+        assert!(!ids.contains(&"custom.off"));
+        assert_eq!(ids.last(), Some(&"custom.true"));
+
+        let renamed: SecurityChecksConfig = serde_json::from_str(r#"{"disabled": ["firewall.ufw"]}"#).unwrap();
+        let auditor = SecurityAuditor::from_config(&renamed, &sources()).unwrap();
+        assert!(!auditor.ids().contains(&CHECK_FIREWALL));
+
+        let unknown: SecurityChecksConfig = serde_json::from_str(r#"{"disabled": ["ssh.typo"]}"#).unwrap();
+        assert!(SecurityAuditor::from_config(&unknown, &sources()).is_err());
+
+        let clash: SecurityChecksConfig = serde_json::from_str(
+            r#"{"custom": [{"id": "firewall.active", "name": "Mine", "type": "command", "command": ["true"]}]}"#
+        ).unwrap();
+        assert!(SecurityAuditor::from_config(&clash, &sources()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_score_follows_registered_checks() {
+        let config: SecurityChecksConfig = serde_json::from_str(r#"{
+            "custom": [
+                {"id": "a", "name": "A", "type": "command", "command": ["true"]},
+                {"id": "b", "name": "B", "type": "command", "command": ["true"]},
+                {"id": "c", "name": "C", "type": "command", "command": ["false"]}
+            ]
+        }"#).unwrap();
+        let mut auditor = SecurityAuditor::default();
+        for custom in &config.custom {
+            auditor.register(Arc::new(CustomCheck::new(custom.clone()).unwrap())).unwrap();
+        }
+        let checks = auditor.run_audit(&Lang::EN).await;
+        assert_eq!(checks.len(), 3);
+        assert_eq!(hardening_score(&checks), 66);
+        assert_eq!(hardening_score(&[]), 0);
+    }
+
+    #[test]
+    fn test_cached_source_reuses_results_but_not_failures() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let loads = Arc::new(AtomicUsize::new(0));
+        let counter = loads.clone();
+        let source = CachedSource::new(Duration::from_secs(60), move || {
+            match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => Err("not yet".to_string()),
+                n => Ok(n),
+            }
+        });
+
+        assert_eq!(source.get().unwrap_err(), "not yet");
+        assert_eq!(*source.get().unwrap(), 1);
+        assert_eq!(*source.get().unwrap(), 1);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
 }
@@ -0,0 +1,309 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::i18n::{self, Lang};
+use crate::security::{CheckStatus, SecurityCheck, SecurityCheckProvider, Severity};
+use crate::util::load_json_config;
+
+/// Longest evidence string a custom check reports.
+const MAX_EVIDENCE_CHARS: usize = 300;
+
+/// Contents of `SECURITY_CHECKS_FILE`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SecurityChecksConfig {
+    /// Ids of built-in or custom checks that should not run
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    #[serde(default)]
+    pub custom: Vec<CustomCheckConfig>,
+    /// Files and directories the integrity monitor watches instead of the defaults
+    #[serde(default)]
+    pub integrity_paths: Option<Vec<String>>,
+}
+
+impl SecurityChecksConfig {
+    pub fn integrity_paths(&self) -> Vec<String> {
+        self.integrity_paths.clone()
+            .unwrap_or_else(|| crate::file_integrity::DEFAULT_PATHS.iter().map(|p| p.to_string()).collect())
+    }
+}
+
+/// User-defined check: a command or a file line that must look a certain way.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomCheckConfig {
+    pub id: String,
+    /// Shown as is; custom checks are not translated
+    pub name: String,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub remediation: Option<String>,
+    /// Status reported when the condition is not met
+    #[serde(default = "default_on_failure")]
+    pub on_failure: CheckStatus,
+    #[serde(flatten)]
+    pub probe: Probe,
+}
+
+fn default_severity() -> Severity { Severity::Medium }
+fn default_on_failure() -> CheckStatus { CheckStatus::Fail }
+fn default_timeout_secs() -> u64 { 10 }
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Probe {
+    /// Runs an argv list without a shell. Passes when the exit code and the
+    /// stdout pattern match; with neither given, exit code 0 is expected.
+    Command {
+        command: Vec<String>,
+        #[serde(default)]
+        expect_exit: Option<i32>,
+        #[serde(default)]
+        output_regex: Option<String>,
+        #[serde(default = "default_timeout_secs")]
+        timeout_secs: u64,
+    },
+    /// Passes when the file has the line; whitespace runs compare as one space.
+    FileLine {
+        path: PathBuf,
+        line: String,
+    },
+}
+
+/// Without the file only the built-in checks run.
+pub fn load_config(path: &str) -> Result<SecurityChecksConfig, String> {
+    load_json_config(path, "security checks", SecurityChecksConfig::default())
+}
+
+pub struct CustomCheck {
+    config: CustomCheckConfig,
+    regex: Option<Regex>,
+}
+
+impl CustomCheck {
+    pub fn new(config: CustomCheckConfig) -> Result<Self, String> {
+        if config.id.trim().is_empty() {
+            return Err("Custom security check ids must not be empty".to_string());
+        }
+        if config.on_failure == CheckStatus::Pass {
+            return Err(format!("Custom security check '{}' cannot use PASS as `on_failure`", config.id));
+        }
+        let regex = match &config.probe {
+            Probe::Command { command, output_regex, .. } => {
+                if command.is_empty() {
+                    return Err(format!("Custom security check '{}' needs a non-empty `command`", config.id));
+                }
+                output_regex.as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| format!("Custom security check '{}' has an invalid `output_regex`: {}", config.id, e))?
+            }
+            Probe::FileLine { path, line } => {
+                if !path.is_absolute() {
+                    return Err(format!("Custom security check '{}' needs an absolute `path`", config.id));
+                }
+                if line.trim().is_empty() {
+                    return Err(format!("Custom security check '{}' needs a non-empty `line`", config.id));
+                }
+                None
+            }
+        };
+        Ok(Self { config, regex })
+    }
+
+    fn result(&self, status: CheckStatus, message: String, evidence: Option<String>) -> SecurityCheck {
+        SecurityCheck {
+            id: self.config.id.clone(),
+            status,
+            severity: self.config.severity,
+            categories: self.config.categories.clone(),
+            name: self.config.name.clone(),
+            message,
+            remediation: (status != CheckStatus::Pass).then(|| self.config.remediation.clone()).flatten(),
+            evidence: evidence.map(|e| truncate(e.trim())),
+        }
+    }
+
+    fn run_command(&self, command: &[String], expect_exit: Option<i32>, timeout: Duration, lang: &Lang) -> SecurityCheck {
+        let (code, stdout) = match run_with_timeout(command, timeout) {
+            Ok(output) => output,
+            Err(e) => return self.result(CheckStatus::Warn, i18n::t_val("audit.custom.error", lang, &e), None),
+        };
+
+        // With only a pattern configured the exit code does not matter
+        let expected_code = expect_exit.or(if self.regex.is_none() { Some(0) } else { None });
+        if let Some(expected) = expected_code
+            && code != Some(expected)
+        {
+            let code = code.map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string());
+            let message = i18n::t_val("audit.custom.exit_code", lang, &code);
+            return self.result(self.config.on_failure, message, Some(stdout));
+        }
+
+        match &self.regex {
+            Some(regex) => match stdout.lines().find(|l| regex.is_match(l)) {
+                Some(line) => self.result(CheckStatus::Pass, i18n::t("audit.custom.pass", lang), Some(line.to_string())),
+                None => self.result(self.config.on_failure, i18n::t("audit.custom.no_match", lang), Some(stdout)),
+            },
+            None => self.result(CheckStatus::Pass, i18n::t("audit.custom.pass", lang), Some(stdout)),
+        }
+    }
+
+    fn run_file_line(&self, path: &std::path::Path, line: &str, lang: &Lang) -> SecurityCheck {
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) => {
+                let error = format!("{}: {}", path.display(), e);
+                return self.result(CheckStatus::Warn, i18n::t_val("audit.custom.error", lang, &error), None);
+            }
+        };
+        let wanted = normalize(line);
+        match content.lines().find(|l| normalize(l) == wanted) {
+            Some(found) => {
+                let evidence = format!("{}: {}", path.display(), found.trim());
+                self.result(CheckStatus::Pass, i18n::t("audit.custom.pass", lang), Some(evidence))
+            }
+            None => {
+                let message = i18n::t_val("audit.custom.missing_line", lang, &wanted);
+                self.result(self.config.on_failure, message, Some(path.display().to_string()))
+            }
+        }
+    }
+}
+
+impl SecurityCheckProvider for CustomCheck {
+    fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    fn run(&self, lang: &Lang) -> SecurityCheck {
+        match &self.config.probe {
+            Probe::Command { command, expect_exit, timeout_secs, .. } => {
+                self.run_command(command, *expect_exit, Duration::from_secs(*timeout_secs), lang)
+            }
+            Probe::FileLine { path, line } => self.run_file_line(path, line, lang),
+        }
+    }
+}
+
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_EVIDENCE_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Runs the command and returns its exit code (None if killed by a signal)
+/// and stdout. Called from the auditor's blocking thread.
+fn run_with_timeout(command: &[String], timeout: Duration) -> Result<(Option<i32>, String), String> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", command[0], e))?;
+
+    // Read in the background so a chatty command cannot fill the pipe and stall
+    let mut stdout = child.stdout.take().expect("Failed to open stdout");
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        let _ = tx.send(String::from_utf8_lossy(&buf).into_owned());
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{} timed out after {}s", command[0], timeout.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(format!("{}: {}", command[0], e)),
+        }
+    };
+    // A background process the command left behind can keep the pipe open
+    // past the command itself; the read is bound by the same deadline
+    let stdout = rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .map_err(|_| format!("{} timed out after {}s", command[0], timeout.as_secs()))?;
+    Ok((status.code(), stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(json: &str) -> Result<CustomCheck, String> {
+        let config: CustomCheckConfig = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        CustomCheck::new(config)
+    }
+
+    #[test]
+    fn test_command_check() {
+        let exit = check(r#"{"id": "c.exit", "name": "Exit", "type": "command", "command": ["sh", "-c", "exit 3"], "expect_exit": 3}"#).unwrap();
+        assert_eq!(exit.run(&Lang::EN).status, CheckStatus::Pass);
+
+        let failing = check(r#"{"id": "c.false", "name": "False", "type": "command", "command": ["false"], "on_failure": "WARN", "remediation": "Fix it"}"#).unwrap();
+        let result = failing.run(&Lang::EN);
+        assert_eq!(result.status, CheckStatus::Warn);
+        assert_eq!(result.remediation.as_deref(), Some("Fix it"));
+
+        let pattern = check(r#"{"id": "c.re", "name": "Re", "type": "command", "command": ["printf", "a\nmode=strict\n"], "output_regex": "^mode=(strict|paranoid)$"}"#).unwrap();
+        let result = pattern.run(&Lang::EN);
+        assert_eq!(result.status, CheckStatus::Pass);
+        assert_eq!(result.evidence.as_deref(), Some("mode=strict"));
+        assert!(result.remediation.is_none());
+
+        let slow = check(r#"{"id": "c.slow", "name": "Slow", "type": "command", "command": ["sleep", "5"], "timeout_secs": 0}"#).unwrap();
+        assert_eq!(slow.run(&Lang::EN).status, CheckStatus::Warn);
+
+        // The shell exits at once, but its background child keeps stdout open
+        let started = Instant::now();
+        let background = check(r#"{"id": "c.bg", "name": "Bg", "type": "command", "command": ["sh", "-c", "sleep 5 & echo ok"], "timeout_secs": 1}"#).unwrap();
+        assert_eq!(background.run(&Lang::EN).status, CheckStatus::Warn);
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        let missing = check(r#"{"id": "c.missing", "name": "Missing", "type": "command", "command": ["/nonexistent/binary"]}"#).unwrap();
+        assert_eq!(missing.run(&Lang::EN).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_file_line_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("sysctl.conf");
+        std::fs::write(&file, "# comment\nnet.ipv4.tcp_syncookies   =  1\n").unwrap();
+
+        let json = |line: &str| format!(
+            r#"{{"id": "c.file", "name": "File", "type": "file_line", "path": "{}", "line": "{}"}}"#,
+            file.display(), line,
+        );
+        assert_eq!(check(&json("net.ipv4.tcp_syncookies = 1")).unwrap().run(&Lang::EN).status, CheckStatus::Pass);
+        assert_eq!(check(&json("net.ipv4.ip_forward = 0")).unwrap().run(&Lang::EN).status, CheckStatus::Fail);
+
+        dir.close().unwrap();
+        assert_eq!(check(&json("net.ipv4.tcp_syncookies = 1")).unwrap().run(&Lang::EN).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_invalid_checks() {
+        assert!(check(r#"{"id": "c", "name": "C", "type": "command", "command": []}"#).is_err());
+        assert!(check(r#"{"id": "c", "name": "C", "type": "command", "command": ["true"], "output_regex": "("}"#).is_err());
+        assert!(check(r#"{"id": "c", "name": "C", "type": "command", "command": ["true"], "on_failure": "PASS"}"#).is_err());
+        assert!(check(r#"{"id": "c", "name": "C", "type": "file_line", "path": "relative", "line": "x"}"#).is_err());
+        assert!(check(r#"{"id": " ", "name": "C", "type": "file_line", "path": "/etc/hosts", "line": "x"}"#).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::time::Duration;
+
+/// How `/api/deploy/webhook` updates the agent (`UPDATE_MODE`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateMode {
+    /// `scripts/update.sh`: git pull + npm + cargo on the server
+    Source,
+    /// Signed release binary downloaded from `UPDATE_RELEASE_URL`
+    Release,
+}
+
+impl UpdateMode {
+    pub fn from_env() -> Self {
+        match std::env::var("UPDATE_MODE").as_deref() {
+            Ok("release") => UpdateMode::Release,
+            _ => UpdateMode::Source,
+        }
+    }
+}
+
+/// Version of the running agent; only releases newer than this are installed.
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Release binary plus its `.version`, `.sha256` and `.sig` files, all next to each other.
+///
+/// `{os}` and `{arch}` in the URL are replaced with the agent's platform
+/// (`linux`, `x86_64`/`aarch64`), so one setting serves every server.
+pub struct ReleaseSource {
+    url: String,
+    /// Raw 32-byte Ed25519 public key
+    public_key: Vec<u8>,
+    client: reqwest::Client,
+}
+
+impl ReleaseSource {
+    pub fn new(url: &str, public_key: Vec<u8>) -> Result<Self, String> {
+        if public_key.len() != 32 {
+            return Err("Release public key must be 32 bytes (Ed25519)".to_string());
+        }
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(600))
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            url: url.replace("{os}", std::env::consts::OS).replace("{arch}", std::env::consts::ARCH),
+            public_key,
+            client,
+        })
+    }
+
+    /// Reads `UPDATE_RELEASE_URL` and the base64 `UPDATE_PUBLIC_KEY`; both are required.
+    pub fn from_env() -> Result<Self, String> {
+        let url = std::env::var("UPDATE_RELEASE_URL")
+            .ok()
+            .filter(|u| !u.is_empty())
+            .ok_or("UPDATE_RELEASE_URL is not set")?;
+        let key = std::env::var("UPDATE_PUBLIC_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .ok_or("UPDATE_PUBLIC_KEY is not set; unsigned releases are not installed")?;
+        let key = base64::engine::general_purpose::STANDARD
+            .decode(key.trim())
+            .map_err(|e| format!("UPDATE_PUBLIC_KEY is not valid base64: {}", e))?;
+        Self::new(&url, key)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Version from `<url>.version` and SHA-256 (lowercase hex) from `<url>.sha256`,
+    /// in `sha256sum` format or bare. Neither is trusted before `download_verified`.
+    pub async fn release(&self) -> Result<Release, String> {
+        let text = String::from_utf8_lossy(&self.get(&format!("{}.sha256", self.url)).await?).to_string();
+        let sha256 = text.split_whitespace().next().unwrap_or("").to_lowercase();
+        if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Malformed checksum file: {:?}", text.trim()));
+        }
+
+        let text = String::from_utf8_lossy(&self.get(&format!("{}.version", self.url)).await?).to_string();
+        let version = text.trim().trim_start_matches('v').to_string();
+        if parse_version(&version).is_none() {
+            return Err(format!("Malformed version file: {:?}", text.trim()));
+        }
+        Ok(Release { version, sha256 })
+    }
+
+    /// Checks `<url>.sig` over the release's version and checksum, refuses
+    /// anything not newer than the running agent, then downloads the binary
+    /// and checks it against the checksum.
+    pub async fn download_verified(&self, release: &Release) -> Result<Vec<u8>, String> {
+        let signature = self.get(&format!("{}.sig", self.url)).await?;
+        verify_signature(&self.public_key, release.signed_message().as_bytes(), &signature)?;
+        // A validly signed old release is still a downgrade to its vulnerabilities
+        if parse_version(&release.version) <= parse_version(CURRENT_VERSION) {
+            return Err(format!(
+                "Release {} is not newer than the running {}; refusing to downgrade",
+                release.version, CURRENT_VERSION
+            ));
+        }
+
+        let binary = self.get(&self.url).await?;
+        let actual = sha256_hex(&binary);
+        if actual != release.sha256 {
+            return Err(format!("Checksum mismatch: expected {}, got {}", release.sha256, actual));
+        }
+        Ok(binary)
+    }
+
+    async fn get(&self, url: &str) -> Result<Vec<u8>, String> {
+        let resp = self.client.get(url).send().await
+            .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+        if !resp.status().is_success() {
+            return Err(format!("Failed to download {}: HTTP {}", url, resp.status()));
+        }
+        resp.bytes().await
+            .map(|b| b.to_vec())
+            .map_err(|e| format!("Failed to download {}: {}", url, e))
+    }
+}
+
+/// What the release host announces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    /// e.g. `1.2.0`
+    pub version: String,
+    pub sha256: String,
+}
+
+impl Release {
+    /// The text `<url>.sig` signs: `mini-ops <version> <sha256>`, without a newline.
+    fn signed_message(&self) -> String {
+        format!("mini-ops {} {}", self.version, self.sha256)
+    }
+}
+
+/// `1.2.0` as `[1, 2, 0]`; anything but dot-separated numbers is `None`.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Ed25519 signature over `message`, either raw 64 bytes or base64.
+fn verify_signature(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+    let signature = if signature.len() == 64 {
+        signature.to_vec()
+    } else {
+        base64::engine::general_purpose::STANDARD
+            .decode(String::from_utf8_lossy(signature).trim())
+            .map_err(|_| "Malformed signature file".to_string())?
+    };
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(message, &signature)
+        .map_err(|_| "Signature verification failed".to_string())
+}
+
+/// Replaces `exe` with `binary`: written next to it, fsynced and renamed over it,
+/// so a crash leaves either the old or the new file, never half of one.
+pub fn install_binary(binary: &[u8], exe: &Path) -> Result<(), String> {
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = exe.with_extension("download");
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(binary)?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o755))?;
+        file.sync_all()
+    };
+    if let Err(e) = write() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("Failed to write {}: {}", tmp.display(), e));
+    }
+    std::fs::rename(&tmp, exe).map_err(|e| format!("Failed to replace {}: {}", exe.display(), e))
+}
+
+/// Under systemd (`INVOCATION_ID` is set) exiting lets `Restart=always` start the new binary.
+pub fn running_under_systemd() -> bool {
+    std::env::var_os("INVOCATION_ID").is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use ring::signature::KeyPair;
+
+    fn keypair() -> ring::signature::Ed25519KeyPair {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn signature(key: &ring::signature::Ed25519KeyPair, release: &Release) -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD.encode(key.sign(release.signed_message().as_bytes()).as_ref()).into_bytes()
+    }
+
+    /// Serves `/mini-ops-linux-x86_64` with its version, checksum and signature files.
+    async fn spawn_release_host(binary: &'static [u8], release: Release, signature: Vec<u8>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let Release { version, sha256 } = release;
+        let app = Router::new()
+            .route("/mini-ops-linux-x86_64", get(move || async move { binary }))
+            .route("/mini-ops-linux-x86_64.version", get(move || async move { format!("{}\n", version) }))
+            .route("/mini-ops-linux-x86_64.sha256", get(move || async move {
+                format!("{}  mini-ops-linux-x86_64\n", sha256)
+            }))
+            .route("/mini-ops-linux-x86_64.sig", get(move || async move { signature }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}/mini-ops-{{os}}-x86_64", addr)
+    }
+
+    fn release(version: &str, binary: &[u8]) -> Release {
+        Release { version: version.to_string(), sha256: sha256_hex(binary) }
+    }
+
+    #[tokio::test]
+    async fn test_download_verifies_checksum_and_signature() {
+        let binary: &'static [u8] = b"\x7fELF new mini-ops";
+        let key = keypair();
+        let newer = release("999.0.0", binary);
+        let url = spawn_release_host(binary, newer.clone(), signature(&key, &newer)).await;
+
+        let source = ReleaseSource::new(&url, key.public_key().as_ref().to_vec()).unwrap();
+        assert!(source.url().contains("/mini-ops-linux-x86_64"));
+        let announced = source.release().await.unwrap();
+        assert_eq!(announced, newer);
+        assert_eq!(source.download_verified(&announced).await.unwrap(), binary);
+
+        // Signed by someone else
+        let other = ReleaseSource::new(&url, keypair().public_key().as_ref().to_vec()).unwrap();
+        assert_eq!(other.download_verified(&announced).await.unwrap_err(), "Signature verification failed");
+    }
+
+    #[tokio::test]
+    async fn test_download_rejects_checksum_mismatch() {
+        let binary: &'static [u8] = b"tampered";
+        let key = keypair();
+        let original = release("999.0.0", b"original");
+        let url = spawn_release_host(binary, original.clone(), signature(&key, &original)).await;
+
+        let source = ReleaseSource::new(&url, key.public_key().as_ref().to_vec()).unwrap();
+        let announced = source.release().await.unwrap();
+        assert!(source.download_verified(&announced).await.unwrap_err().starts_with("Checksum mismatch"));
+    }
+
+    #[tokio::test]
+    async fn test_download_refuses_downgrades() {
+        let binary: &'static [u8] = b"\x7fELF old mini-ops";
+        let key = keypair();
+        for version in ["0.1.0", CURRENT_VERSION] {
+            let old = release(version, binary);
+            let url = spawn_release_host(binary, old.clone(), signature(&key, &old)).await;
+            let source = ReleaseSource::new(&url, key.public_key().as_ref().to_vec()).unwrap();
+            let err = source.download_verified(&source.release().await.unwrap()).await.unwrap_err();
+            assert!(err.contains("refusing to downgrade"), "{}", err);
+        }
+
+        // The version is signed, so an old release cannot be relabelled as new
+        let old = release("0.1.0", binary);
+        let url = spawn_release_host(binary, release("999.0.0", binary), signature(&key, &old)).await;
+        let source = ReleaseSource::new(&url, key.public_key().as_ref().to_vec()).unwrap();
+        let announced = source.release().await.unwrap();
+        assert_eq!(source.download_verified(&announced).await.unwrap_err(), "Signature verification failed");
+    }
+
+    #[test]
+    fn test_install_binary_replaces_atomically() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("mini-ops");
+        std::fs::write(&exe, "old").unwrap();
+
+        install_binary(b"new", &exe).unwrap();
+        assert_eq!(std::fs::read(&exe).unwrap(), b"new");
+        assert_eq!(std::fs::metadata(&exe).unwrap().permissions().mode() & 0o777, 0o755);
+        assert!(!exe.with_extension("download").exists());
+    }
+
+    #[test]
+    fn test_public_key_length_is_checked() {
+        assert!(ReleaseSource::new("http://localhost/bin", vec![0; 31]).is_err());
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.10.0"), Some(vec![1, 10, 0]));
+        assert!(parse_version("1.10.0") > parse_version("1.9.3"));
+        assert_eq!(parse_version("1.2.0-rc1"), None);
+        assert_eq!(parse_version(""), None);
+    }
+}
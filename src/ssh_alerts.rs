@@ -88,11 +88,11 @@ impl SshAlertsService {
         // Rate limiting (10s per IP)
         {
             let mut limiter = self.rate_limiter.lock().unwrap();
-            if let Some(last) = limiter.get(&event.ip) {
-                if last.elapsed() < Duration::from_secs(10) {
-                     tracing::info!("Rate limiting SSH alert for IP: {}", event.ip);
-                     return Ok(()); // Silently skip
-                }
+            if let Some(last) = limiter.get(&event.ip)
+                && last.elapsed() < Duration::from_secs(10)
+            {
+                tracing::info!("Rate limiting SSH alert for IP: {}", event.ip);
+                return Ok(()); // Silently skip
             }
             limiter.insert(event.ip.clone(), Instant::now());
         }
@@ -171,7 +171,7 @@ impl SshAlertsService {
 
     pub async fn add_trusted_ip(&self, ip: String, description: Option<String>) -> Result<(), sqlx::Error> {
         // Validate IP before storing
-        validate_ip(&ip).map_err(|e| sqlx::Error::Protocol(e))?;
+        validate_ip(&ip).map_err(sqlx::Error::Protocol)?;
 
         sqlx::query("INSERT INTO trusted_ips (ip, description, added_at) VALUES (?, ?, ?)")
             .bind(ip)
@@ -0,0 +1,647 @@
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::i18n::{self, Lang};
+use crate::security::{
+    CachedSource, CheckSpec, CheckStatus, SecurityCheck, Severity, SourcedCheck, SourcedCheckFn, CHECK_SSH_PASSWORD_AUTH,
+    CHECK_SSH_ROOT_LOGIN,
+};
+use crate::sshd_config::{Directive, SshdConfig};
+
+/// `MaxAuthTries` above this gives brute force too many guesses per connection.
+const MAX_AUTH_TRIES_LIMIT: u32 = 4;
+/// RSA keys shorter than this are weak.
+const MIN_RSA_BITS: usize = 2048;
+
+const WEAK_KEX: &[&str] = &[
+    "diffie-hellman-group1-sha1",
+    "diffie-hellman-group14-sha1",
+    "diffie-hellman-group-exchange-sha1",
+    "gss-gex-sha1-",
+    "gss-group1-sha1-",
+    "gss-group14-sha1-",
+];
+const WEAK_CIPHERS: &[&str] = &[
+    "3des-cbc",
+    "aes128-cbc",
+    "aes192-cbc",
+    "aes256-cbc",
+    "blowfish-cbc",
+    "cast128-cbc",
+    "arcfour",
+    "arcfour128",
+    "arcfour256",
+    "rijndael-cbc@lysator.liu.se",
+];
+const WEAK_MACS: &[&str] = &[
+    "hmac-md5",
+    "hmac-md5-96",
+    "hmac-md5-etm@openssh.com",
+    "hmac-md5-96-etm@openssh.com",
+    "hmac-sha1-96",
+    "hmac-sha1-96-etm@openssh.com",
+    "hmac-ripemd160",
+    "hmac-ripemd160@openssh.com",
+    "hmac-ripemd160-etm@openssh.com",
+    "umac-64@openssh.com",
+    "umac-64-etm@openssh.com",
+];
+
+pub(crate) const SSH_ROOT_LOGIN: CheckSpec = CheckSpec {
+    id: CHECK_SSH_ROOT_LOGIN,
+    key: "ssh_root",
+    severity: Severity::High,
+    categories: &["ssh", "access"],
+};
+
+pub(crate) const SSH_PASSWORD_AUTH: CheckSpec = CheckSpec {
+    id: CHECK_SSH_PASSWORD_AUTH,
+    key: "ssh_passwd",
+    severity: Severity::High,
+    categories: &["ssh", "authentication"],
+};
+
+const SSH_PUBKEY_AUTH: CheckSpec = CheckSpec {
+    id: "ssh.pubkey_auth",
+    key: "ssh_pubkey",
+    severity: Severity::Medium,
+    categories: &["ssh", "authentication"],
+};
+
+const SSH_EMPTY_PASSWORDS: CheckSpec = CheckSpec {
+    id: "ssh.empty_passwords",
+    key: "ssh_empty",
+    severity: Severity::Critical,
+    categories: &["ssh", "authentication"],
+};
+
+const SSH_MAX_AUTH_TRIES: CheckSpec = CheckSpec {
+    id: "ssh.max_auth_tries",
+    key: "ssh_auth_tries",
+    severity: Severity::Low,
+    categories: &["ssh", "authentication"],
+};
+
+const SSH_X11_FORWARDING: CheckSpec = CheckSpec {
+    id: "ssh.x11_forwarding",
+    key: "ssh_x11",
+    severity: Severity::Low,
+    categories: &["ssh", "forwarding"],
+};
+
+const SSH_TCP_FORWARDING: CheckSpec = CheckSpec {
+    id: "ssh.tcp_forwarding",
+    key: "ssh_tcp_fwd",
+    severity: Severity::Low,
+    categories: &["ssh", "forwarding"],
+};
+
+const SSH_CLIENT_ALIVE: CheckSpec = CheckSpec {
+    id: "ssh.client_alive",
+    key: "ssh_idle",
+    severity: Severity::Low,
+    categories: &["ssh", "session"],
+};
+
+const SSH_WEAK_CRYPTO: CheckSpec = CheckSpec {
+    id: "ssh.weak_crypto",
+    key: "ssh_crypto",
+    severity: Severity::Medium,
+    categories: &["ssh", "crypto"],
+};
+
+const SSH_PORT: CheckSpec = CheckSpec {
+    id: "ssh.port",
+    key: "ssh_port",
+    severity: Severity::Info,
+    categories: &["ssh", "network"],
+};
+
+const SSH_ALLOW_USERS: CheckSpec = CheckSpec {
+    id: "ssh.allow_users",
+    key: "ssh_allow",
+    severity: Severity::Low,
+    categories: &["ssh", "access"],
+};
+
+const SSH_AUTHORIZED_KEYS: CheckSpec = CheckSpec {
+    id: "ssh.authorized_keys",
+    key: "ssh_keys",
+    severity: Severity::High,
+    categories: &["ssh", "authentication", "permissions"],
+};
+
+/// Every SSH check, in the order the audit lists them.
+pub fn ssh_checks(source: Arc<CachedSource<SshdConfig>>) -> Vec<SourcedCheck<SshdConfig>> {
+    let checks: [(&'static CheckSpec, SourcedCheckFn<SshdConfig>); 12] = [
+        (&SSH_ROOT_LOGIN, check_root_login),
+        (&SSH_PASSWORD_AUTH, check_password_auth),
+        (&SSH_PUBKEY_AUTH, check_pubkey_auth),
+        (&SSH_EMPTY_PASSWORDS, check_empty_passwords),
+        (&SSH_MAX_AUTH_TRIES, check_max_auth_tries),
+        (&SSH_X11_FORWARDING, check_x11_forwarding),
+        (&SSH_TCP_FORWARDING, check_tcp_forwarding),
+        (&SSH_CLIENT_ALIVE, check_client_alive),
+        (&SSH_WEAK_CRYPTO, check_weak_crypto),
+        (&SSH_PORT, check_port),
+        (&SSH_ALLOW_USERS, check_allow_users),
+        (&SSH_AUTHORIZED_KEYS, check_authorized_keys),
+    ];
+    SourcedCheck::all(source, checks)
+}
+
+/// First `Match` block line that sets the keyword to a weaker value.
+fn weaker_override<'a>(config: &'a SshdConfig, keyword: &str, is_weak: impl Fn(&str) -> bool) -> Option<&'a Directive> {
+    config.match_overrides(keyword)
+        .into_iter()
+        .find(|d| is_weak(&d.value().to_lowercase()))
+}
+
+fn check_root_login(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("PermitRootLogin", "prohibit-password");
+    let message = match setting.value.to_lowercase().as_str() {
+        "no" => "audit.ssh_root.pass",
+        "prohibit-password" | "without-password" => "audit.ssh_root.keys_only",
+        "forced-commands-only" => "audit.ssh_root.forced_commands",
+        _ => return SSH_ROOT_LOGIN.fail("audit.ssh_root.fail", lang).with_evidence(setting.evidence()),
+    };
+    if let Some(line) = weaker_override(config, "PermitRootLogin", |v| v == "yes") {
+        return SSH_ROOT_LOGIN.warn("audit.ssh_root.match", lang).with_evidence(line.location());
+    }
+    SSH_ROOT_LOGIN.pass(message, lang).with_evidence(setting.evidence())
+}
+
+fn check_password_auth(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("PasswordAuthentication", "yes");
+    if !setting.value.eq_ignore_ascii_case("no") {
+        return SSH_PASSWORD_AUTH.fail("audit.ssh_passwd.fail", lang).with_evidence(setting.evidence());
+    }
+    if let Some(line) = weaker_override(config, "PasswordAuthentication", |v| v == "yes") {
+        return SSH_PASSWORD_AUTH.warn("audit.ssh_passwd.match", lang).with_evidence(line.location());
+    }
+    SSH_PASSWORD_AUTH.pass("audit.ssh_passwd.pass", lang).with_evidence(setting.evidence())
+}
+
+fn check_pubkey_auth(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("PubkeyAuthentication", "yes");
+    if setting.value.eq_ignore_ascii_case("no") {
+        return SSH_PUBKEY_AUTH.warn("audit.ssh_pubkey.warn", lang).with_evidence(setting.evidence());
+    }
+    SSH_PUBKEY_AUTH.pass("audit.ssh_pubkey.pass", lang).with_evidence(setting.evidence())
+}
+
+fn check_empty_passwords(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("PermitEmptyPasswords", "no");
+    if !setting.value.eq_ignore_ascii_case("no") {
+        return SSH_EMPTY_PASSWORDS.fail("audit.ssh_empty.fail", lang).with_evidence(setting.evidence());
+    }
+    if let Some(line) = weaker_override(config, "PermitEmptyPasswords", |v| v == "yes") {
+        return SSH_EMPTY_PASSWORDS.warn("audit.ssh_empty.match", lang).with_evidence(line.location());
+    }
+    SSH_EMPTY_PASSWORDS.pass("audit.ssh_empty.pass", lang).with_evidence(setting.evidence())
+}
+
+fn check_max_auth_tries(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("MaxAuthTries", "6");
+    let message = |key: &str| i18n::t_val(key, lang, &setting.value);
+    match setting.value.parse::<u32>() {
+        Ok(tries) if tries <= MAX_AUTH_TRIES_LIMIT => SSH_MAX_AUTH_TRIES
+            .result(CheckStatus::Pass, message("audit.ssh_auth_tries.pass"), lang)
+            .with_evidence(setting.evidence()),
+        _ => SSH_MAX_AUTH_TRIES
+            .result(CheckStatus::Warn, message("audit.ssh_auth_tries.warn"), lang)
+            .with_evidence(setting.evidence()),
+    }
+}
+
+fn check_x11_forwarding(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("X11Forwarding", "no");
+    if setting.value.eq_ignore_ascii_case("no") {
+        return SSH_X11_FORWARDING.pass("audit.ssh_x11.pass", lang).with_evidence(setting.evidence());
+    }
+    SSH_X11_FORWARDING.warn("audit.ssh_x11.warn", lang).with_evidence(setting.evidence())
+}
+
+fn check_tcp_forwarding(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("AllowTcpForwarding", "yes");
+    if setting.value.eq_ignore_ascii_case("no") {
+        return SSH_TCP_FORWARDING.pass("audit.ssh_tcp_fwd.pass", lang).with_evidence(setting.evidence());
+    }
+    let message = i18n::t_val("audit.ssh_tcp_fwd.warn", lang, &setting.value);
+    SSH_TCP_FORWARDING.result(CheckStatus::Warn, message, lang).with_evidence(setting.evidence())
+}
+
+fn check_client_alive(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let setting = config.setting("ClientAliveInterval", "0");
+    if parse_sshd_time(&setting.value).unwrap_or(0) == 0 {
+        return SSH_CLIENT_ALIVE.warn("audit.ssh_idle.warn", lang).with_evidence(setting.evidence());
+    }
+    let message = i18n::t_val("audit.ssh_idle.pass", lang, &setting.value);
+    SSH_CLIENT_ALIVE.result(CheckStatus::Pass, message, lang).with_evidence(setting.evidence())
+}
+
+/// Seconds in an sshd time value: a bare number or units like `1h30m`.
+fn parse_sshd_time(value: &str) -> Option<u64> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return None;
+    }
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            _ => return None,
+        };
+        total += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        total += number.parse::<u64>().ok()?;
+    }
+    Some(total)
+}
+
+/// Weak algorithms a `Ciphers`-style list enables. `-` entries remove
+/// algorithms, `+` and `^` add them to the defaults.
+fn weak_algorithms(list: &str, weak: &[&str]) -> Vec<String> {
+    let list = list.trim();
+    if list.starts_with('-') {
+        return Vec::new();
+    }
+    list.trim_start_matches(['+', '^'])
+        .split([',', ' '])
+        .map(|a| a.trim().to_lowercase())
+        .filter(|a| weak.iter().any(|w| if w.ends_with('-') { a.starts_with(w) } else { a == w }))
+        .collect()
+}
+
+fn check_weak_crypto(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let mut found = Vec::new();
+    let mut evidence = Vec::new();
+    for (keyword, weak) in [("KexAlgorithms", WEAK_KEX), ("Ciphers", WEAK_CIPHERS), ("MACs", WEAK_MACS)] {
+        // Only lists set in the files are judged. Without one OpenSSH's defaults
+        // apply, and `sshd -T` prints those in full, umac-64 included.
+        let Some(directive) = config.setting(keyword, "").directive else {
+            continue;
+        };
+        let algorithms = weak_algorithms(&directive.value(), weak);
+        if !algorithms.is_empty() {
+            evidence.push(format!("{} {}: {}", keyword, algorithms.join(","), directive.location()));
+            found.extend(algorithms);
+        }
+    }
+    if found.is_empty() {
+        return SSH_WEAK_CRYPTO.pass("audit.ssh_crypto.pass", lang);
+    }
+    let message = i18n::t_val("audit.ssh_crypto.fail", lang, &found.join(", "));
+    SSH_WEAK_CRYPTO.result(CheckStatus::Fail, message, lang).with_evidence(evidence.join("; "))
+}
+
+fn check_port(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let mut ports = config.values("Port");
+    if ports.is_empty() {
+        ports.push("22".to_string());
+    }
+    let evidence = format!("Port {}", ports.join(" "));
+    if ports.iter().any(|p| p == "22") {
+        return SSH_PORT.warn("audit.ssh_port.warn", lang).with_evidence(evidence);
+    }
+    let message = i18n::t_val("audit.ssh_port.pass", lang, &ports.join(", "));
+    SSH_PORT.result(CheckStatus::Pass, message, lang).with_evidence(evidence)
+}
+
+fn check_allow_users(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let users = config.values("AllowUsers");
+    let groups = config.values("AllowGroups");
+    if users.is_empty() && groups.is_empty() {
+        return SSH_ALLOW_USERS.warn("audit.ssh_allow.warn", lang);
+    }
+    let mut allowed = Vec::new();
+    if !users.is_empty() {
+        allowed.push(format!("AllowUsers {}", users.join(" ")));
+    }
+    if !groups.is_empty() {
+        allowed.push(format!("AllowGroups {}", groups.join(" ")));
+    }
+    let allowed = allowed.join("; ");
+    let message = i18n::t_val("audit.ssh_allow.pass", lang, &allowed);
+    SSH_ALLOW_USERS.result(CheckStatus::Pass, message, lang).with_evidence(allowed)
+}
+
+/// Account from `/etc/passwd` that may have authorized keys.
+struct Account {
+    name: String,
+    uid: u32,
+    home: PathBuf,
+}
+
+fn passwd_accounts(passwd: &str) -> Vec<Account> {
+    passwd.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 7 || fields[0].starts_with('#') {
+                return None;
+            }
+            Some(Account {
+                name: fields[0].to_string(),
+                uid: fields[2].parse().ok()?,
+                home: PathBuf::from(fields[5]),
+            })
+        })
+        .filter(|a| a.home.is_absolute() && a.home != Path::new("/"))
+        .collect()
+}
+
+/// `AuthorizedKeysFile` entries for one account, with `%h`, `%u` and `%U` expanded.
+fn authorized_keys_paths(patterns: &str, account: &Account) -> Vec<PathBuf> {
+    patterns.split_whitespace()
+        .filter(|p| !p.eq_ignore_ascii_case("none"))
+        .map(|p| {
+            let expanded = p.replace("%h", &account.home.to_string_lossy())
+                .replace("%u", &account.name)
+                .replace("%U", &account.uid.to_string())
+                .replace("%%", "%");
+            let path = PathBuf::from(&expanded);
+            if path.is_absolute() { path } else { account.home.join(path) }
+        })
+        .collect()
+}
+
+/// Why a key is weak, if it is: DSA, or RSA shorter than `MIN_RSA_BITS`.
+fn weak_key(line: &str) -> Option<String> {
+    use base64::Engine;
+
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    // Options like `command="..."` may come before the key type
+    let index = tokens.iter().position(|t| t.starts_with("ssh-") || t.starts_with("ecdsa-") || t.starts_with("sk-"))?;
+    match tokens[index] {
+        "ssh-dss" => Some("ssh-dss".to_string()),
+        "ssh-rsa" => {
+            let blob = base64::engine::general_purpose::STANDARD.decode(tokens.get(index + 1)?).ok()?;
+            let bits = rsa_modulus_bits(&blob)?;
+            (bits < MIN_RSA_BITS).then(|| format!("ssh-rsa {} bits", bits))
+        }
+        _ => None,
+    }
+}
+
+/// Modulus size of an `ssh-rsa` public key blob: string type, mpint e, mpint n.
+fn rsa_modulus_bits(blob: &[u8]) -> Option<usize> {
+    let mut rest = blob;
+    let mut fields = Vec::new();
+    for _ in 0..3 {
+        let len = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        fields.push(rest.get(4..4 + len)?);
+        rest = &rest[4 + len..];
+    }
+    let modulus = fields[2];
+    let first = modulus.iter().position(|&b| b != 0)?;
+    Some((modulus.len() - first) * 8 - modulus[first].leading_zeros() as usize)
+}
+
+/// Problems with one authorized_keys file: unsafe ownership or mode of the
+/// file or its directory, and weak keys.
+fn authorized_keys_findings(path: &Path, account: &Account) -> (usize, Vec<String>) {
+    let mut findings = Vec::new();
+    let Ok(content) = std::fs::read_to_string(path) else { return (0, findings) };
+
+    for target in [path, path.parent().unwrap_or(path)] {
+        if let Ok(meta) = std::fs::metadata(target) {
+            let mode = meta.permissions().mode() & 0o7777;
+            if mode & 0o022 != 0 {
+                findings.push(format!("{} mode {:o}", target.display(), mode));
+            }
+            if meta.uid() != account.uid && meta.uid() != 0 {
+                findings.push(format!("{} owned by uid {}", target.display(), meta.uid()));
+            }
+        }
+    }
+
+    let mut keys = 0;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        keys += 1;
+        if let Some(reason) = weak_key(line) {
+            findings.push(format!("{}:{}: {}", path.display(), index + 1, reason));
+        }
+    }
+    (keys, findings)
+}
+
+fn check_authorized_keys(config: &SshdConfig, lang: &Lang) -> SecurityCheck {
+    let passwd = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+    authorized_keys_result(config, &passwd, lang)
+}
+
+fn authorized_keys_result(config: &SshdConfig, passwd: &str, lang: &Lang) -> SecurityCheck {
+    let patterns = config.setting("AuthorizedKeysFile", ".ssh/authorized_keys .ssh/authorized_keys2").value;
+    let mut keys = 0;
+    let mut findings = Vec::new();
+    for account in passwd_accounts(passwd) {
+        for path in authorized_keys_paths(&patterns, &account) {
+            let (count, problems) = authorized_keys_findings(&path, &account);
+            keys += count;
+            findings.extend(problems);
+        }
+    }
+
+    if !findings.is_empty() {
+        return SSH_AUTHORIZED_KEYS.fail("audit.ssh_keys.fail", lang).with_evidence(findings.join("; "));
+    }
+    if keys == 0 {
+        return SSH_AUTHORIZED_KEYS.pass("audit.ssh_keys.none", lang);
+    }
+    let message = i18n::t_val("audit.ssh_keys.pass", lang, &keys.to_string());
+    SSH_AUTHORIZED_KEYS.result(CheckStatus::Pass, message, lang)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{CheckStatus, SecurityCheckProvider};
+    use std::path::PathBuf;
+
+    fn config(content: &str) -> (PathBuf, SshdConfig) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        std::fs::write(&path, content).unwrap();
+        let config = SshdConfig::load(&path).unwrap();
+        (path, config)
+    }
+
+    #[test]
+    fn test_root_login() {
+        let (_, commented) = config("#PermitRootLogin yes\n");
+        let check = check_root_login(&commented, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert_eq!(check.message, crate::i18n::t("audit.ssh_root.keys_only", &Lang::EN));
+        assert_eq!(check.evidence.as_deref(), Some("default prohibit-password"));
+
+        let (path, enabled) = config("permitrootlogin YES\nPermitRootLogin no\n");
+        let check = check_root_login(&enabled, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert_eq!(check.evidence, Some(format!("{}:1: permitrootlogin YES", path.display())));
+
+        let (_, matched) = config("PermitRootLogin no\nMatch Address 10.0.0.0/8\n  PermitRootLogin yes\n");
+        assert_eq!(check_root_login(&matched, &Lang::EN).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_password_auth() {
+        let (_, unset) = config("Port 22\n");
+        assert_eq!(check_password_auth(&unset, &Lang::EN).status, CheckStatus::Fail);
+
+        let (_, disabled) = config("PasswordAuthentication = no\n");
+        assert_eq!(check_password_auth(&disabled, &Lang::EN).status, CheckStatus::Pass);
+
+        let (_, matched) = config("PasswordAuthentication no\nMatch User deploy\nPasswordAuthentication yes\n");
+        assert_eq!(check_password_auth(&matched, &Lang::EN).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_unreadable_config() {
+        let source = Arc::new(crate::sshd_config::source("/nonexistent/sshd_config", false));
+        let checks = ssh_checks(source);
+        assert_eq!(checks.len(), 12);
+        let check = checks[0].run(&Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.evidence.unwrap().contains("/nonexistent/sshd_config"));
+    }
+
+    #[test]
+    fn test_config_checks() {
+        let (_, defaults) = config("Port 22\n");
+        let status = |f: SourcedCheckFn<SshdConfig>| f(&defaults, &Lang::EN).status;
+        assert_eq!(status(check_pubkey_auth), CheckStatus::Pass);
+        assert_eq!(status(check_empty_passwords), CheckStatus::Pass);
+        assert_eq!(status(check_max_auth_tries), CheckStatus::Warn);
+        assert_eq!(status(check_x11_forwarding), CheckStatus::Pass);
+        assert_eq!(status(check_tcp_forwarding), CheckStatus::Warn);
+        assert_eq!(status(check_client_alive), CheckStatus::Warn);
+        assert_eq!(status(check_weak_crypto), CheckStatus::Pass);
+        assert_eq!(status(check_port), CheckStatus::Warn);
+        assert_eq!(status(check_allow_users), CheckStatus::Warn);
+
+        let (_, hardened) = config("\
+PubkeyAuthentication no
+PermitEmptyPasswords yes
+MaxAuthTries 3
+X11Forwarding yes
+AllowTcpForwarding no
+ClientAliveInterval 5m
+Port 2222
+AllowGroups sshusers
+");
+        let status = |f: SourcedCheckFn<SshdConfig>| f(&hardened, &Lang::EN).status;
+        assert_eq!(status(check_pubkey_auth), CheckStatus::Warn);
+        assert_eq!(status(check_empty_passwords), CheckStatus::Fail);
+        assert_eq!(status(check_max_auth_tries), CheckStatus::Pass);
+        assert_eq!(status(check_x11_forwarding), CheckStatus::Warn);
+        assert_eq!(status(check_tcp_forwarding), CheckStatus::Pass);
+        assert_eq!(status(check_client_alive), CheckStatus::Pass);
+        assert_eq!(status(check_port), CheckStatus::Pass);
+        let allow = check_allow_users(&hardened, &Lang::EN);
+        assert_eq!(allow.status, CheckStatus::Pass);
+        assert_eq!(allow.evidence.as_deref(), Some("AllowGroups sshusers"));
+
+        let (_, empty_in_match) = config("Match User guest\nPermitEmptyPasswords yes\n");
+        assert_eq!(check_empty_passwords(&empty_in_match, &Lang::EN).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_weak_crypto() {
+        assert_eq!(weak_algorithms("+aes128-cbc,aes256-gcm@openssh.com", WEAK_CIPHERS), vec!["aes128-cbc"]);
+        assert!(weak_algorithms("-aes128-cbc", WEAK_CIPHERS).is_empty());
+        assert_eq!(weak_algorithms("gss-gex-sha1-toWM5Slw5Ew8Mqkay+al2g==", WEAK_KEX).len(), 1);
+        assert!(weak_algorithms("hmac-sha2-256-etm@openssh.com,hmac-sha1", WEAK_MACS).is_empty());
+
+        let (_, weak) = config("Ciphers aes256-ctr,3des-cbc\nMACs hmac-md5\n");
+        let check = check_weak_crypto(&weak, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.message.ends_with("3des-cbc, hmac-md5"));
+
+        // Stock OpenSSH 9.2 `sshd -T`: the default MACs include umac-64
+        let (_, mut stock) = config("Port 22\n");
+        stock.runtime = Some(crate::sshd_config::parse_test_output("\
+port 22
+ciphers chacha20-poly1305@openssh.com,aes128-ctr,aes192-ctr,aes256-ctr,aes128-gcm@openssh.com,aes256-gcm@openssh.com
+macs umac-64-etm@openssh.com,umac-128-etm@openssh.com,hmac-sha2-256-etm@openssh.com,hmac-sha2-512-etm@openssh.com,hmac-sha1-etm@openssh.com,umac-64@openssh.com,umac-128@openssh.com,hmac-sha2-256,hmac-sha2-512,hmac-sha1
+kexalgorithms sntrup761x25519-sha512@openssh.com,curve25519-sha256,curve25519-sha256@libssh.org,ecdh-sha2-nistp256,ecdh-sha2-nistp384,ecdh-sha2-nistp521,diffie-hellman-group-exchange-sha256,diffie-hellman-group16-sha512,diffie-hellman-group18-sha512,diffie-hellman-group14-sha256
+"));
+        assert_eq!(check_weak_crypto(&stock, &Lang::EN).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_client_alive_time() {
+        assert_eq!(parse_sshd_time("300"), Some(300));
+        assert_eq!(parse_sshd_time("5m"), Some(300));
+        assert_eq!(parse_sshd_time("1h30m"), Some(5400));
+        assert_eq!(parse_sshd_time("0s"), Some(0));
+        assert_eq!(parse_sshd_time("5x"), None);
+
+        let (_, zero) = config("ClientAliveInterval 0s\n");
+        assert_eq!(check_client_alive(&zero, &Lang::EN).status, CheckStatus::Warn);
+    }
+
+    fn rsa_key(bits: usize) -> String {
+        use base64::Engine;
+
+        let mut blob = Vec::new();
+        let mut field = |bytes: &[u8]| {
+            blob.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            blob.extend_from_slice(bytes);
+        };
+        field(b"ssh-rsa");
+        field(&[1, 0, 1]);
+        let mut modulus = vec![0u8; bits / 8 + 1];
+        modulus[1] = 0x80;
+        field(&modulus);
+        format!("ssh-rsa {} user@host", base64::engine::general_purpose::STANDARD.encode(blob))
+    }
+
+    #[test]
+    fn test_authorized_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = tmp.path();
+        let ssh_dir = home.join(".ssh");
+        std::fs::create_dir_all(&ssh_dir).unwrap();
+        std::fs::set_permissions(&ssh_dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        let keys = ssh_dir.join("authorized_keys");
+        std::fs::write(&keys, format!("# admin\n{}\nssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIFake user@host\n", rsa_key(4096))).unwrap();
+        std::fs::set_permissions(&keys, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        let uid = std::fs::metadata(home).unwrap().uid();
+        let passwd = format!("tester:x:{}:{}::{}:/bin/bash\nnobody:x:65534:65534::/nonexistent:/usr/sbin/nologin\n", uid, uid, home.display());
+        let (_, sshd) = config("Port 22\n");
+
+        let check = authorized_keys_result(&sshd, &passwd, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert_eq!(check.message, "2 keys checked, no problems");
+
+        std::fs::write(&keys, format!("no-pty,command=\"/bin/true\" {}\nssh-dss AAAAB3NzaC1kc3M user@host\n", rsa_key(1024))).unwrap();
+        std::fs::set_permissions(&keys, std::fs::Permissions::from_mode(0o666)).unwrap();
+        let check = authorized_keys_result(&sshd, &passwd, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Fail);
+        let evidence = check.evidence.unwrap();
+        assert!(evidence.contains("authorized_keys mode 666"));
+        assert!(evidence.contains("authorized_keys:1: ssh-rsa 1024 bits"));
+        assert!(evidence.contains("authorized_keys:2: ssh-dss"));
+
+        tmp.close().unwrap();
+        let check = authorized_keys_result(&sshd, &passwd, &Lang::EN);
+        assert_eq!(check.message, "No authorized_keys files found");
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::util::glob_match;
+use crate::security::CachedSource;
+
+pub const SSHD_CONFIG: &str = "/etc/ssh/sshd_config";
+/// Same nesting limit as sshd.
+const MAX_INCLUDE_DEPTH: usize = 16;
+/// How long a parsed config is reused, so one audit reads the files once.
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// One `Keyword value` line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+    /// Lowercase, as keywords are case-insensitive
+    pub keyword: String,
+    pub args: Vec<String>,
+    pub file: PathBuf,
+    pub line: usize,
+    /// `Match` criteria the line is under; None for the global section
+    pub match_criteria: Option<String>,
+}
+
+impl Directive {
+    pub fn value(&self) -> String {
+        self.args.join(" ")
+    }
+
+    /// `file:line: Keyword value`, for check evidence.
+    pub fn location(&self) -> String {
+        let mut location = format!("{}:{}:", self.file.display(), self.line);
+        if let Some(criteria) = &self.match_criteria {
+            location.push_str(&format!(" [Match {}]", criteria));
+        }
+        format!("{} {} {}", location, self.keyword, self.value())
+    }
+}
+
+/// Effective value of a keyword and where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    /// As written; enum-like values need a case-insensitive compare
+    pub value: String,
+    /// First global line that set the keyword
+    pub directive: Option<Directive>,
+    /// Value reported by `sshd -T`, when it could run
+    pub runtime: Option<String>,
+}
+
+impl Setting {
+    pub fn evidence(&self) -> String {
+        let source = match &self.directive {
+            Some(d) => d.location(),
+            None => format!("default {}", self.value),
+        };
+        match &self.runtime {
+            Some(runtime) if self.directive.as_ref().is_some_and(|d| !d.value().eq_ignore_ascii_case(runtime)) => {
+                format!("{} (sshd -T: {})", source, runtime)
+            }
+            _ => source,
+        }
+    }
+}
+
+/// Parsed sshd configuration: every directive of the main file and its
+/// includes in the order sshd reads them.
+#[derive(Debug, Clone, Default)]
+pub struct SshdConfig {
+    pub directives: Vec<Directive>,
+    pub files: Vec<PathBuf>,
+    /// `sshd -T` output, keyword to values in print order
+    pub runtime: Option<HashMap<String, Vec<String>>>,
+}
+
+impl SshdConfig {
+    /// Parses `path` and its includes. Relative includes resolve against the
+    /// directory of `path` (`/etc/ssh` for the system config).
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let base = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let mut config = Self::default();
+        config.parse(&content, path, &base, None, 0);
+        Ok(config)
+    }
+
+    /// Adds what `sshd -T -f path` reports, if sshd is installed and can run.
+    pub fn with_runtime(mut self, path: &Path) -> Self {
+        self.runtime = sshd_test_output(path);
+        self
+    }
+
+    fn parse(&mut self, content: &str, file: &Path, base: &Path, context: Option<String>, depth: usize) {
+        self.files.push(file.to_path_buf());
+        // A Match inside a file lasts until the next Match or the end of that file
+        let mut current = context.clone();
+
+        for (index, raw) in content.lines().enumerate() {
+            let Some((keyword, args)) = split_line(raw) else { continue };
+
+            match keyword.as_str() {
+                "match" => {
+                    let criteria = args.join(" ");
+                    current = if criteria.eq_ignore_ascii_case("all") && context.is_none() {
+                        None
+                    } else {
+                        Some(criteria)
+                    };
+                }
+                "include" if depth < MAX_INCLUDE_DEPTH => {
+                    for pattern in &args {
+                        for included in expand_include(pattern, base) {
+                            if let Ok(content) = std::fs::read_to_string(&included) {
+                                self.parse(&content, &included, base, current.clone(), depth + 1);
+                            }
+                        }
+                    }
+                }
+                "include" => {
+                    tracing::warn!("{}:{}: Include nested too deeply, ignored", file.display(), index + 1);
+                }
+                _ => self.directives.push(Directive {
+                    keyword,
+                    args,
+                    file: file.to_path_buf(),
+                    line: index + 1,
+                    match_criteria: current.clone(),
+                }),
+            }
+        }
+    }
+
+    /// First global line for the keyword; later lines are ignored by sshd.
+    pub fn first(&self, keyword: &str) -> Option<&Directive> {
+        let keyword = keyword.to_lowercase();
+        self.directives.iter().find(|d| d.match_criteria.is_none() && d.keyword == keyword)
+    }
+
+    /// Lines inside `Match` blocks that set the keyword for some connections.
+    pub fn match_overrides(&self, keyword: &str) -> Vec<&Directive> {
+        let keyword = keyword.to_lowercase();
+        self.directives.iter().filter(|d| d.match_criteria.is_some() && d.keyword == keyword).collect()
+    }
+
+    /// Effective global value: `sshd -T` when available, then the first line
+    /// in the files, then `default`.
+    pub fn setting(&self, keyword: &str, default: &str) -> Setting {
+        let directive = self.first(keyword).cloned();
+        let runtime = self.runtime.as_ref()
+            .and_then(|r| r.get(&keyword.to_lowercase()))
+            .map(|values| values.join(" "));
+        let value = runtime.clone()
+            .or_else(|| directive.as_ref().map(|d| d.value()))
+            .unwrap_or_else(|| default.to_string());
+        Setting { value, directive, runtime }
+    }
+
+    /// All arguments of a keyword that accumulates, like `Port` or
+    /// `AllowUsers`: from `sshd -T` when available, else every global line.
+    pub fn values(&self, keyword: &str) -> Vec<String> {
+        let keyword = keyword.to_lowercase();
+        match &self.runtime {
+            Some(runtime) => runtime.get(&keyword)
+                .map(|lines| lines.iter().flat_map(|l| l.split_whitespace().map(str::to_string)).collect())
+                .unwrap_or_default(),
+            None => self.directives.iter()
+                .filter(|d| d.match_criteria.is_none() && d.keyword == keyword)
+                .flat_map(|d| d.args.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Splits a line into its lowercase keyword and arguments. Accepts
+/// `Keyword value`, `Keyword=value` and double-quoted arguments; an unquoted
+/// `#` starts a comment.
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let keyword = line[..end].to_lowercase();
+    let mut rest = line[end..].trim_start();
+    if let Some(stripped) = rest.strip_prefix('=') {
+        rest = stripped.trim_start();
+    }
+
+    let mut args = Vec::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '#' {
+            break;
+        } else if c == '"' {
+            chars.next();
+            args.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut arg = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                arg.push(c);
+                chars.next();
+            }
+            args.push(arg);
+        }
+    }
+    Some((keyword, args))
+}
+
+/// Files an `Include` pattern names, sorted like sshd's glob. Wildcards are
+/// supported in the file name only.
+fn expand_include(pattern: &str, base: &Path) -> Vec<PathBuf> {
+    let path = if Path::new(pattern).is_absolute() { PathBuf::from(pattern) } else { base.join(pattern) };
+    let Some(name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(dir) = path.parent() else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| e.file_name().to_str().is_some_and(|n| glob_match(name.as_bytes(), n.as_bytes())))
+        .filter(|e| e.path().is_file())
+        .map(|e| e.path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Parses `sshd -T`; None when sshd is missing or refuses (e.g. not root).
+fn sshd_test_output(path: &Path) -> Option<HashMap<String, Vec<String>>> {
+    let sshd = ["/usr/sbin/sshd", "/usr/bin/sshd", "/sbin/sshd"]
+        .into_iter()
+        .map(Path::new)
+        .find(|p| p.is_file())?;
+    let output = Command::new(sshd).arg("-T").arg("-f").arg(path).output().ok()?;
+    if !output.status.success() {
+        tracing::debug!("sshd -T failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        return None;
+    }
+    Some(parse_test_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+pub(crate) fn parse_test_output(stdout: &str) -> HashMap<String, Vec<String>> {
+    let mut values: HashMap<String, Vec<String>> = HashMap::new();
+    for line in stdout.lines() {
+        if let Some((keyword, value)) = line.trim().split_once(' ') {
+            values.entry(keyword.to_lowercase()).or_default().push(value.trim().to_string());
+        }
+    }
+    values
+}
+
+/// Shares one parse of the config between the SSH checks of an audit.
+/// `runtime` enables the `sshd -T` cross-check.
+pub fn source(path: impl Into<PathBuf>, runtime: bool) -> CachedSource<SshdConfig> {
+    let path = path.into();
+    CachedSource::new(CACHE_TTL, move || {
+        let config = SshdConfig::load(&path)?;
+        Ok(if runtime { config.with_runtime(&path) } else { config })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sshd_config.d")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(split_line("  # PermitRootLogin yes"), None);
+        assert_eq!(split_line(""), None);
+        assert_eq!(split_line("permitrootlogin  No"), Some(("permitrootlogin".into(), vec!["No".into()])));
+        assert_eq!(split_line("PasswordAuthentication=no"), Some(("passwordauthentication".into(), vec!["no".into()])));
+        assert_eq!(split_line("Port = 2222 # custom"), Some(("port".into(), vec!["2222".into()])));
+        assert_eq!(
+            split_line(r#"Banner "/etc/my banner""#),
+            Some(("banner".into(), vec!["/etc/my banner".into()])),
+        );
+    }
+
+    #[test]
+    fn test_includes_and_match_blocks() {
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        let main = dir.join("sshd_config");
+        std::fs::write(&main, "\
+Include sshd_config.d/*.conf
+#PermitRootLogin yes
+PermitRootLogin no
+PasswordAuthentication no
+AllowUsers alice
+AllowUsers bob carol
+Match User deploy
+    PasswordAuthentication yes
+    Include extra.conf
+").unwrap();
+        // Included first, so it wins over the main file
+        std::fs::write(dir.join("sshd_config.d/50-cloud-init.conf"), "passwordauthentication YES\n").unwrap();
+        std::fs::write(dir.join("sshd_config.d/ignored.txt"), "PermitRootLogin yes\n").unwrap();
+        std::fs::write(dir.join("extra.conf"), "X11Forwarding yes\n").unwrap();
+
+        let config = SshdConfig::load(&main).unwrap();
+        assert_eq!(config.files.len(), 3);
+
+        let root = config.setting("PermitRootLogin", "prohibit-password");
+        assert_eq!(root.value, "no");
+        assert_eq!(root.directive.as_ref().unwrap().line, 3);
+
+        let password = config.setting("passwordauthentication", "yes");
+        assert_eq!(password.value, "YES");
+        assert!(password.evidence().contains("50-cloud-init.conf:1:"));
+
+        let overrides = config.match_overrides("PasswordAuthentication");
+        assert_eq!(overrides.len(), 1);
+        assert!(overrides[0].location().contains("[Match User deploy]"));
+        // Included inside the Match block, so it is not global
+        assert!(config.first("x11forwarding").is_none());
+        assert_eq!(config.match_overrides("x11forwarding").len(), 1);
+
+        assert_eq!(config.values("AllowUsers"), vec!["alice", "bob", "carol"]);
+        assert!(config.values("AllowGroups").is_empty());
+        assert_eq!(config.setting("MaxAuthTries", "6").value, "6");
+        assert_eq!(config.setting("MaxAuthTries", "6").evidence(), "default 6");
+    }
+
+    #[test]
+    fn test_match_ends_with_included_file() {
+        let tmp = temp_dir();
+        let dir = tmp.path();
+        let main = dir.join("sshd_config");
+        std::fs::write(&main, "Include sshd_config.d/*.conf\nPermitRootLogin no\n").unwrap();
+        std::fs::write(dir.join("sshd_config.d/10-admins.conf"), "Match Group admins\nPermitRootLogin yes\n").unwrap();
+
+        let config = SshdConfig::load(&main).unwrap();
+        assert_eq!(config.setting("PermitRootLogin", "prohibit-password").value, "no");
+        assert_eq!(config.match_overrides("PermitRootLogin").len(), 1);
+    }
+
+    #[test]
+    fn test_runtime_cross_check() {
+        let mut config = SshdConfig {
+            directives: vec![Directive {
+                keyword: "permitrootlogin".into(),
+                args: vec!["yes".into()],
+                file: PathBuf::from(SSHD_CONFIG),
+                line: 7,
+                match_criteria: None,
+            }],
+            ..Default::default()
+        };
+        config.runtime = Some(parse_test_output("port 22\npermitrootlogin no\nallowusers alice\nallowusers bob\n"));
+
+        let root = config.setting("PermitRootLogin", "prohibit-password");
+        assert_eq!(root.value, "no");
+        assert_eq!(root.evidence(), "/etc/ssh/sshd_config:7: permitrootlogin yes (sshd -T: no)");
+        assert_eq!(config.values("AllowUsers"), vec!["alice", "bob"]);
+        assert!(config.values("AllowGroups").is_empty());
+    }
+}
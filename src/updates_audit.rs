@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use crate::i18n::{self, Lang};
+use crate::os_updates::UpdateStatus;
+use crate::security::{
+    CachedSource, CheckSpec, CheckStatus, SecurityCheck, Severity, SourcedCheck, SourcedCheckFn, CHECK_REBOOT_REQUIRED,
+    CHECK_UPDATES_AUTO, CHECK_UPDATES_SECURITY,
+};
+
+const SECURITY_UPDATES: CheckSpec = CheckSpec {
+    id: CHECK_UPDATES_SECURITY,
+    key: "updates",
+    severity: Severity::High,
+    categories: &["updates"],
+};
+
+const AUTO_UPDATES: CheckSpec = CheckSpec {
+    id: CHECK_UPDATES_AUTO,
+    key: "auto_updates",
+    severity: Severity::Medium,
+    categories: &["updates"],
+};
+
+const REBOOT_REQUIRED: CheckSpec = CheckSpec {
+    id: CHECK_REBOOT_REQUIRED,
+    key: "reboot",
+    severity: Severity::Medium,
+    categories: &["updates"],
+};
+
+pub fn updates_checks(source: Arc<CachedSource<UpdateStatus>>) -> Vec<SourcedCheck<UpdateStatus>> {
+    let checks: [(&'static CheckSpec, SourcedCheckFn<UpdateStatus>); 3] = [
+        (&SECURITY_UPDATES, check_security_updates),
+        (&AUTO_UPDATES, check_auto_updates),
+        (&REBOOT_REQUIRED, check_reboot_required),
+    ];
+    SourcedCheck::all(source, checks)
+}
+
+/// Pending security updates are a warning: fresh ones are normal until the next
+/// maintenance window. The tracker alerts when they stay pending too long.
+fn check_security_updates(status: &UpdateStatus, lang: &Lang) -> SecurityCheck {
+    if status.manager.is_none() {
+        return SECURITY_UPDATES.warn("audit.updates.unsupported", lang);
+    }
+    let Some(pending) = &status.pending else {
+        return SECURITY_UPDATES.warn("audit.updates.error", lang).with_evidence(status.errors.join("; "));
+    };
+    let evidence = status.summary();
+    if !status.security_known && !pending.is_empty() {
+        let message = i18n::t_val("audit.updates.unmarked", lang, &pending.len().to_string());
+        return SECURITY_UPDATES.result(CheckStatus::Warn, message, lang).with_evidence(evidence);
+    }
+    let security = status.security_updates();
+    if security.is_empty() {
+        return SECURITY_UPDATES.pass("audit.updates.pass", lang).with_evidence(evidence);
+    }
+    let message = i18n::t_val("audit.updates.warn", lang, &security.len().to_string());
+    SECURITY_UPDATES.result(CheckStatus::Warn, message, lang).with_evidence(evidence)
+}
+
+fn check_auto_updates(status: &UpdateStatus, lang: &Lang) -> SecurityCheck {
+    let Some(manager) = status.manager else {
+        return AUTO_UPDATES.warn("audit.auto_updates.unknown", lang);
+    };
+    match status.auto_updates {
+        Some(true) => AUTO_UPDATES.pass("audit.auto_updates.pass", lang).with_evidence(manager.name()),
+        Some(false) => AUTO_UPDATES.warn("audit.auto_updates.warn", lang).with_evidence(manager.name()),
+        None => AUTO_UPDATES.warn("audit.auto_updates.unknown", lang).with_evidence(manager.name()),
+    }
+}
+
+fn check_reboot_required(status: &UpdateStatus, lang: &Lang) -> SecurityCheck {
+    if !status.reboot_required {
+        return REBOOT_REQUIRED.pass("audit.reboot.pass", lang);
+    }
+    let check = REBOOT_REQUIRED.warn("audit.reboot.warn", lang);
+    if status.reboot_packages.is_empty() {
+        check
+    } else {
+        check.with_evidence(status.reboot_packages.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os_updates::{PackageManager, PendingUpdate};
+
+    fn update(name: &str, security: bool) -> PendingUpdate {
+        PendingUpdate { name: name.into(), version: "1.0".into(), security, first_seen: None }
+    }
+
+    #[test]
+    fn test_security_updates() {
+        let mut status = UpdateStatus {
+            manager: Some(PackageManager::Apt),
+            pending: Some(vec![update("tzdata", false)]),
+            security_known: true,
+            ..Default::default()
+        };
+        assert_eq!(check_security_updates(&status, &Lang::EN).status, CheckStatus::Pass);
+
+        status.pending = Some(vec![update("tzdata", false), update("openssl", true)]);
+        let check = check_security_updates(&status, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.message.contains('1'));
+        assert_eq!(check.evidence.as_deref(), Some("apt: 2 pending, 1 security (openssl)"));
+
+        // apk does not mark security updates
+        status.manager = Some(PackageManager::Apk);
+        status.security_known = false;
+        status.pending = Some(vec![update("musl", false)]);
+        assert_eq!(check_security_updates(&status, &Lang::EN).status, CheckStatus::Warn);
+        status.pending = Some(Vec::new());
+        assert_eq!(check_security_updates(&status, &Lang::EN).status, CheckStatus::Pass);
+
+        status.pending = None;
+        status.errors = vec!["apk version: failed".into()];
+        let check = check_security_updates(&status, &Lang::EN);
+        assert_eq!(check.evidence.as_deref(), Some("apk version: failed"));
+    }
+
+    #[test]
+    fn test_reboot_and_auto_updates() {
+        let mut status = UpdateStatus { manager: Some(PackageManager::Apt), auto_updates: Some(false), ..Default::default() };
+        assert_eq!(check_auto_updates(&status, &Lang::EN).status, CheckStatus::Warn);
+        status.auto_updates = Some(true);
+        assert_eq!(check_auto_updates(&status, &Lang::EN).status, CheckStatus::Pass);
+        assert_eq!(check_reboot_required(&status, &Lang::EN).status, CheckStatus::Pass);
+
+        status.reboot_required = true;
+        status.reboot_packages = vec!["linux-image-6.8.0-45-generic".into(), "libc6".into()];
+        let check = check_reboot_required(&status, &Lang::EN);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert_eq!(check.evidence.as_deref(), Some("linux-image-6.8.0-45-generic, libc6"));
+    }
+}
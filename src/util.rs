@@ -0,0 +1,180 @@
+//! Small helpers shared by several modules.
+
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// `*` matches any run of characters and `?` a single one. Backtracks only to
+/// the last `*`, so patterns with many stars stay linear.
+pub(crate) fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // Let the last star swallow one more character
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Parses the JSON file at `path`, or returns `default` when there is no such file.
+/// `what` names the contents in the parse error, e.g. "healing rules".
+pub(crate) fn load_json_config<T: DeserializeOwned>(path: &str, what: &str, default: T) -> Result<T, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(default),
+        Err(e) => return Err(format!("Failed to read {}: {}", path, e)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("Invalid {} in {}: {}", what, path, e))
+}
+
+/// What `Walker::walk` reports, in walk order.
+pub(crate) enum Visit<'a> {
+    /// A directory, before its entries
+    Enter(&'a Path, &'a Metadata),
+    /// Anything that is not a directory
+    File(&'a Path, &'a Metadata),
+    /// After the entries of the directory entered last
+    Leave,
+}
+
+/// Walks directory trees without following symlinks or crossing into other filesystems.
+/// Files with several hard links are reported once per walker, however many trees it walks.
+pub(crate) struct Walker {
+    max_depth: usize,
+    sorted: bool,
+    seen: HashSet<(u64, u64)>,
+    /// Entries that could not be read, usually for lack of permissions
+    pub errors: u64,
+}
+
+impl Default for Walker {
+    fn default() -> Self {
+        Self { max_depth: usize::MAX, sorted: false, seen: HashSet::new(), errors: 0 }
+    }
+}
+
+impl Walker {
+    /// Entries more than `depth` levels below the root are skipped; 1 lists the root's own entries.
+    pub(crate) fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Entries of each directory are reported in path order.
+    pub(crate) fn sorted(mut self) -> Self {
+        self.sorted = true;
+        self
+    }
+
+    /// Reports `root` and everything below it to `f`. A missing root reports nothing.
+    pub(crate) fn walk(&mut self, root: &Path, f: &mut impl FnMut(Visit<'_>)) {
+        match std::fs::symlink_metadata(root) {
+            Ok(meta) => self.visit(root, &meta, meta.dev(), 0, f),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(_) => self.errors += 1,
+        }
+    }
+
+    fn visit(&mut self, path: &Path, meta: &Metadata, dev: u64, depth: usize, f: &mut impl FnMut(Visit<'_>)) {
+        if !meta.is_dir() {
+            if meta.nlink() <= 1 || self.seen.insert((meta.dev(), meta.ino())) {
+                f(Visit::File(path, meta));
+            }
+            return;
+        }
+        // Mount points such as /proc or a separate /home are left out
+        if meta.dev() != dev {
+            return;
+        }
+        f(Visit::Enter(path, meta));
+        if depth < self.max_depth {
+            for (child, meta) in self.entries(path) {
+                self.visit(&child, &meta, dev, depth + 1, f);
+            }
+        }
+        f(Visit::Leave);
+    }
+
+    fn entries(&mut self, dir: &Path) -> Vec<(PathBuf, Metadata)> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            self.errors += 1;
+            return Vec::new();
+        };
+        let mut children = Vec::new();
+        for entry in entries {
+            // DirEntry::metadata does not follow symlinks
+            match entry.and_then(|e| e.metadata().map(|m| (e.path(), m))) {
+                Ok(child) => children.push(child),
+                Err(_) => self.errors += 1,
+            }
+        }
+        if self.sorted {
+            children.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*.log", b"access.log"));
+        assert!(glob_match(b"*.log*", b"access.log.2.gz"));
+        assert!(glob_match(b"app-?.log", b"app-1.log"));
+        assert!(!glob_match(b"*.log", b"access.log.1"));
+        assert!(!glob_match(b"app-?.log", b"app-10.log"));
+        assert!(glob_match(b"authorized_keys*", b"authorized_keys"));
+        assert!(glob_match(b"a*b*c", b"aXXbYYbc"));
+        assert!(!glob_match(b"file?", b"file"));
+        assert!(glob_match(b"*", b"anything"));
+        assert!(glob_match(b"v*.*", b"v1.2"));
+        assert!(!glob_match(b"v*.*", b"v12"));
+        assert!(!glob_match(b"main", b"main2"));
+        // Would take exponential time with naive backtracking
+        let name = [b'a'; 200];
+        assert!(!glob_match(b"*a*a*a*a*a*a*a*a*a*a*a*a*b", &name));
+    }
+
+    #[test]
+    fn test_walker_dedups_hard_links_and_limits_depth() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("a/b/c")).unwrap();
+        std::fs::write(root.join("top"), "x").unwrap();
+        std::fs::write(root.join("a/b/deep"), "x").unwrap();
+        std::fs::write(root.join("a/b/c/deeper"), "x").unwrap();
+        std::fs::hard_link(root.join("top"), root.join("a/link")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("a/loop")).unwrap();
+
+        let walk = |mut walker: Walker| {
+            let mut seen = Vec::new();
+            walker.walk(root, &mut |visit| match visit {
+                Visit::Enter(path, _) => seen.push(format!("{}/", path.strip_prefix(root).unwrap().display())),
+                Visit::File(path, _) => seen.push(path.strip_prefix(root).unwrap().display().to_string()),
+                Visit::Leave => seen.push("..".to_string()),
+            });
+            seen
+        };
+        // `top` is only reported under the name found first; the symlink is not followed
+        assert_eq!(walk(Walker::default().sorted()), ["/", "a/", "a/b/", "a/b/c/", "a/b/c/deeper", "..", "a/b/deep", "..", "a/link", "a/loop", "..", ".."]);
+        assert_eq!(walk(Walker::default().max_depth(2).sorted()), ["/", "a/", "a/b/", "..", "a/link", "a/loop", "..", ".."]);
+        assert_eq!(walk(Walker::default().max_depth(1).sorted()), ["/", "a/", "..", "top", ".."]);
+    }
+}